@@ -0,0 +1,63 @@
+//! Checks that the crate builds and its tests pass under every combination of its features, so that
+//! a missing `#[cfg]` on a feature gated module or import is caught before it reaches a minimal build.
+//!
+//! Run with `cargo run --example feature_matrix`. Pass `--check-only` to skip running the tests.
+
+use std::process::{Command, ExitCode};
+
+/// The additive features of the crate. `cli` is left out since it only combines the others.
+const FEATURES: [&str; 7] = ["async-http", "blocking-http", "bridge", "keystore", "metrics", "testing", "tracing"];
+
+fn main() -> ExitCode {
+    let check_only: bool = std::env::args().any(|x| x == "--check-only");
+
+    let mut combinations: Vec<Vec<&str>> = (0..1 << FEATURES.len())
+        .map(|mask: usize| {
+            FEATURES
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .map(|(_, feature)| *feature)
+                .collect()
+        })
+        .collect();
+    combinations.push(vec!["cli"]);
+
+    let mut failures: Vec<String> = Vec::new();
+    for features in combinations.iter() {
+        let features: String = features.join(",");
+        let mut subcommands: Vec<&str> = vec!["check", "clippy"];
+        if !check_only {
+            subcommands.push("test");
+        }
+
+        for subcommand in subcommands {
+            println!("cargo {} --no-default-features --features \"{}\"", subcommand, features);
+            let mut command: Command = Command::new(env!("CARGO"));
+            command
+                .arg(subcommand)
+                .arg("--no-default-features")
+                .arg("--features")
+                .arg(&features);
+            if subcommand == "clippy" {
+                command.args(["--all-targets", "--", "-D", "warnings"]);
+            }
+
+            match command.status() {
+                Ok(status) if status.success() => {}
+                _ => failures.push(format!("cargo {} --features \"{}\"", subcommand, features)),
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        println!("All {} feature combinations passed", combinations.len());
+        ExitCode::SUCCESS
+    } else {
+        println!("{} invocations failed:", failures.len());
+        for failure in failures {
+            println!("  {}", failure);
+        }
+        ExitCode::FAILURE
+    }
+}
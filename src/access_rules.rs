@@ -0,0 +1,57 @@
+//! Decoding the access rules in the `authorization` of the `/component` envelope. The PTE renders the
+//! rules as JSON whose shape varies by rule, so they are only decoded as far as finding the rule of a
+//! method and the badges it requires.
+
+use scrypto::prelude::*;
+
+use serde_json::Value;
+
+/// The virtual badge of a public key: the non-fungible of the ECDSA token whose ID is the compressed
+/// public key. Accounts created for a key require this badge to withdraw.
+pub fn virtual_badge(public_key: &EcdsaPublicKey) -> NonFungibleAddress {
+    NonFungibleAddress::new(ECDSA_TOKEN, NonFungibleId::from_bytes(public_key.to_vec()))
+}
+
+/// Finds the rule protecting the method: the rule of the method itself when one of the access rules
+/// of the component names it, otherwise the default rule of the first access rules which have one.
+pub fn method_rule<'a>(authorization: &'a [Value], method: &str) -> Option<&'a Value> {
+    authorization
+        .iter()
+        .find_map(|rules| rules.get("method_auth").and_then(|x| x.get(method)))
+        .or_else(|| authorization.iter().find_map(|rules| rules.get("default_auth")))
+}
+
+/// The non-fungible addresses which appear anywhere in the rule, in the order they appear.
+pub fn required_badges(rule: &Value) -> Vec<NonFungibleAddress> {
+    let mut badges: Vec<NonFungibleAddress> = Vec::new();
+    collect_badges(rule, &mut badges);
+    badges
+}
+
+/// Whether the rule requires the given badge somewhere. A rule naming the badge as separate resource
+/// and ID strings, rather than as a non-fungible address, also counts.
+pub fn rule_requires(rule: &Value, badge: &NonFungibleAddress) -> bool {
+    if required_badges(rule).contains(badge) {
+        return true;
+    }
+    let mut strings: Vec<&str> = Vec::new();
+    collect_strings(rule, &mut strings);
+    let resource: String = badge.resource_address().to_string();
+    let id: String = badge.non_fungible_id().to_string();
+    strings.contains(&resource.as_str()) && strings.contains(&id.as_str())
+}
+
+fn collect_badges(value: &Value, badges: &mut Vec<NonFungibleAddress>) {
+    let mut strings: Vec<&str> = Vec::new();
+    collect_strings(value, &mut strings);
+    badges.extend(strings.into_iter().filter_map(|x| NonFungibleAddress::from_str(x).ok()));
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(string) => strings.push(string),
+        Value::Array(values) => values.iter().for_each(|x| collect_strings(x, strings)),
+        Value::Object(map) => map.values().for_each(|x| collect_strings(x, strings)),
+        _ => {}
+    }
+}
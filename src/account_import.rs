@@ -0,0 +1,147 @@
+//! Importing accounts created through the PTE browser extension. The extension exports either the
+//! hex of the private key or a JSON object holding it along with the address of the account. Before
+//! anything is stored, the key is checked to actually control the account: the virtual badge of its
+//! public key must be required by the withdraw rule of the account on the ledger.
+
+use scrypto::prelude::*;
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::access_rules::{method_rule, rule_requires, virtual_badge};
+use crate::address_book::AddressBook;
+use crate::client::PteClient;
+use crate::component::ComponentInfo;
+use crate::keystore::Keystore;
+use crate::PteApiError;
+
+/// The JSON export of an account by the browser extension.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionExport {
+    pub private_key: String,
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+impl ExtensionExport {
+    /// Parses the export, which is either the hex of the private key, with or without a `0x` prefix,
+    /// or the JSON export of the extension.
+    pub fn parse(text: &str) -> Result<Self, ImportError> {
+        let text: &str = text.trim();
+        if text.starts_with('{') {
+            return serde_json::from_str(text).map_err(|error| ImportError::InvalidExport(error.to_string()));
+        }
+        Ok(Self {
+            private_key: text.to_string(),
+            address: None,
+        })
+    }
+
+    pub fn key_bytes(&self) -> Result<Vec<u8>, ImportError> {
+        let key: &str = self.private_key.trim();
+        let bytes: Vec<u8> = hex::decode(key.strip_prefix("0x").unwrap_or(key))
+            .map_err(|_| ImportError::InvalidExport("the private key is not hex encoded".to_string()))?;
+        EcdsaPrivateKey::from_bytes(&bytes)
+            .map_err(|_| ImportError::InvalidExport("the private key is not a valid key".to_string()))?;
+        Ok(bytes)
+    }
+}
+
+/// An account imported into the keystore and the address book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedAccount {
+    pub label: String,
+    pub address: ComponentAddress,
+    pub public_key: EcdsaPublicKey,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    InvalidExport(String),
+    /// The export names a different account than the one being imported.
+    AddressMismatch { exported: String, given: String },
+    /// No address was given and the export doesn't name one either.
+    MissingAddress,
+    /// The label is already used in the keystore or the address book for something else.
+    LabelTaken(String),
+    /// The account has no rule protecting `withdraw` in its authorization.
+    NoWithdrawRule(String),
+    /// The withdraw rule of the account doesn't require the virtual badge of the key.
+    KeyDoesNotControlAccount { derived_badge: String, on_ledger_rule: String },
+    ApiError(PteApiError),
+    IoError(std::io::Error),
+}
+
+impl From<PteApiError> for ImportError {
+    fn from(error: PteApiError) -> ImportError {
+        ImportError::ApiError(error)
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(error: std::io::Error) -> ImportError {
+        ImportError::IoError(error)
+    }
+}
+
+/// Checks that the key controls the account on the ledger, returning the public key.
+pub fn verify_control(info: &ComponentInfo, private_key: &EcdsaPrivateKey) -> Result<EcdsaPublicKey, ImportError> {
+    let public_key: EcdsaPublicKey = private_key.public_key();
+    let rule = method_rule(&info.authorization, "withdraw").ok_or_else(|| ImportError::NoWithdrawRule(info.address.clone()))?;
+    let badge: NonFungibleAddress = virtual_badge(&public_key);
+    if !rule_requires(rule, &badge) {
+        return Err(ImportError::KeyDoesNotControlAccount {
+            derived_badge: badge.to_string(),
+            on_ledger_rule: rule.to_string(),
+        });
+    }
+    Ok(public_key)
+}
+
+/// Imports an account exported by the browser extension under the label. The address is taken from
+/// the export when `account` is `None`. The key is stored in the keystore and the address in the
+/// address book only after it is verified to control the account. Importing the same account under
+/// the same label again succeeds without changes.
+pub fn import_account(
+    client: &PteClient,
+    export: &ExtensionExport,
+    account: Option<ComponentAddress>,
+    label: &str,
+    keystore: &Keystore,
+    address_book: &Path,
+) -> Result<ImportedAccount, ImportError> {
+    let address: ComponentAddress = match (account, export.address.as_deref()) {
+        (Some(given), Some(exported)) if given.to_string() != exported => {
+            return Err(ImportError::AddressMismatch {
+                exported: exported.to_string(),
+                given: given.to_string(),
+            })
+        }
+        (Some(given), _) => given,
+        (None, Some(exported)) => ComponentAddress::from_str(exported)
+            .map_err(|_| ImportError::InvalidExport(format!("invalid account address {}", exported)))?,
+        (None, None) => return Err(ImportError::MissingAddress),
+    };
+    let key_bytes: Vec<u8> = export.key_bytes()?;
+    let private_key: EcdsaPrivateKey = EcdsaPrivateKey::from_bytes(&key_bytes).unwrap();
+
+    let mut book: AddressBook = AddressBook::load(address_book)?;
+    if book.get(label).is_some_and(|x| x != address.to_string()) {
+        return Err(ImportError::LabelTaken(label.to_string()));
+    }
+    if keystore.contains(label) && keystore.load(label)?.public_key() != private_key.public_key() {
+        return Err(ImportError::LabelTaken(label.to_string()));
+    }
+
+    let public_key: EcdsaPublicKey = verify_control(&client.get_component(address)?, &private_key)?;
+
+    keystore.insert(label, &key_bytes)?;
+    book.insert(label, &address.to_string());
+    book.save(address_book)?;
+    Ok(ImportedAccount {
+        label: label.to_string(),
+        address,
+        public_key,
+    })
+}
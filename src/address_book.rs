@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "blocking-http")]
+use scrypto::prelude::*;
+
+use crate::labeled::abbreviate;
+use crate::{Receipt, TransactionBody};
+
+#[cfg(feature = "blocking-http")]
+use crate::client::PteClient;
+#[cfg(feature = "blocking-http")]
+use crate::labeled::{AddressSource, LabeledAddress};
+#[cfg(feature = "blocking-http")]
+use crate::PteApiError;
+
+/// A local book of human readable labels and the addresses they refer to.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AddressBook {
+    entries: BTreeMap<String, String>,
+}
+
+impl AddressBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the address book from a JSON file, returning an empty book if the file does not exist.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+
+    pub fn insert(&mut self, label: &str, address: &str) {
+        self.entries.insert(label.to_string(), address.to_string());
+    }
+
+    pub fn get(&self, label: &str) -> Option<&str> {
+        self.entries.get(label).map(String::as_str)
+    }
+
+    /// Finds the label of the given address, if there is one.
+    pub fn label_of(&self, address: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, x)| x.as_str() == address)
+            .map(|(label, _)| label.as_str())
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(label, address)| (label.as_str(), address.as_str()))
+    }
+}
+
+/// Resolves labels into component addresses by first looking them up in the local address book and
+/// then, if a registry component is configured, in the on-ledger name registry.
+#[cfg(feature = "blocking-http")]
+pub struct AddressResolver<'a> {
+    book: &'a AddressBook,
+    registry: Option<(&'a PteClient, ComponentAddress)>,
+}
+
+#[cfg(feature = "blocking-http")]
+impl<'a> AddressResolver<'a> {
+    pub fn new(book: &'a AddressBook) -> Self {
+        Self { book, registry: None }
+    }
+
+    pub fn with_registry(self, client: &'a PteClient, registry: ComponentAddress) -> Self {
+        Self {
+            registry: Some((client, registry)),
+            ..self
+        }
+    }
+
+    /// Resolves the given label or address string into a component address, remembering the label
+    /// and where the address was found.
+    pub fn resolve_component(&self, label: &str) -> Result<Option<LabeledAddress<ComponentAddress>>, PteApiError> {
+        if let Ok(address) = ComponentAddress::from_str(label) {
+            return Ok(Some(address.into()));
+        }
+        if let Some(address) = self.book.get(label).and_then(|x| ComponentAddress::from_str(x).ok()) {
+            return Ok(Some(LabeledAddress::new(address, label, AddressSource::AddressBook { path: None })));
+        }
+        match self.registry {
+            Some((client, registry)) => Ok(client.resolve_name(registry, label)?.map(|address| {
+                LabeledAddress::new(address, label, AddressSource::Registry { registry: registry.to_string() })
+            })),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Replaces the addresses in the manifest text which have a label in the address book with
+/// `label (abbreviated address)`, for display only. Addresses are only replaced when a whole token
+/// of the text matches them, so an address which happens to be a prefix of a longer token (or of
+/// another address) is left alone. An address with several labels is shown with the first of them
+/// in alphabetical order.
+pub fn annotate_manifest(text: &str, book: &AddressBook) -> String {
+    let mut annotated: String = String::with_capacity(text.len());
+    let mut rest: &str = text;
+    while let Some(start) = rest.find(|x: char| x.is_ascii_alphanumeric()) {
+        annotated.push_str(&rest[..start]);
+        let length: usize = rest[start..]
+            .find(|x: char| !x.is_ascii_alphanumeric())
+            .unwrap_or(rest.len() - start);
+        let token: &str = &rest[start..start + length];
+        match book.label_of(token) {
+            Some(label) => annotated.push_str(&format!("{} ({})", label, abbreviate(token))),
+            None => annotated.push_str(token),
+        }
+        rest = &rest[start + length..];
+    }
+    annotated.push_str(rest);
+    annotated
+}
+
+impl TransactionBody {
+    /// The manifest with the labels of known addresses inlined, for display only. The body itself,
+    /// and so what is submitted, hashed and stored, is left unchanged.
+    pub fn annotated_manifest(&self, book: &AddressBook) -> String {
+        annotate_manifest(self.manifest(), book)
+    }
+}
+
+impl Receipt {
+    /// A human readable rendering of the receipt with the labels of known addresses inlined.
+    pub fn to_annotated_string(&self, book: &AddressBook) -> String {
+        let mut lines: Vec<String> = vec![
+            format!("Transaction {}: {}", self.transaction_hash, self.status),
+        ];
+        for (kind, addresses) in [
+            ("package", &self.new_packages),
+            ("component", &self.new_components),
+            ("resource", &self.new_resources),
+        ] {
+            lines.extend(addresses.iter().map(|x| format!("New {}: {}", kind, annotate_manifest(x, book))));
+        }
+        lines.extend(self.outputs.iter().map(|x| format!("Output: {}", annotate_manifest(x, book))));
+        lines.extend(self.logs.iter().map(|x| format!("Log: {}", annotate_manifest(x, book))));
+        if let Some(ref cost) = self.cost {
+            lines.push(format!("Cost: {}", cost));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173";
+    /// An address which starts with `ACCOUNT`, so that a naive search and replace would match it.
+    const LONGER: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a17300";
+
+    fn book(entries: &[(&str, &str)]) -> AddressBook {
+        let mut book: AddressBook = AddressBook::new();
+        for (label, address) in entries {
+            book.insert(label, address);
+        }
+        book
+    }
+
+    fn call(address: &str) -> String {
+        format!("CALL_METHOD_WITH_ALL_RESOURCES ComponentAddress(\"{}\") \"deposit_batch\";\n", address)
+    }
+
+    #[test]
+    fn known_addresses_are_labelled_inline() {
+        assert_eq!(
+            annotate_manifest(&call(ACCOUNT), &book(&[("alice", ACCOUNT)])),
+            call("alice (02c1d7…)")
+        );
+        assert_eq!(annotate_manifest(&call(ACCOUNT), &AddressBook::new()), call(ACCOUNT));
+    }
+
+    #[test]
+    fn addresses_are_only_replaced_as_whole_tokens() {
+        let manifest: String = format!("{}{}", call(ACCOUNT), call(LONGER));
+        assert_eq!(
+            annotate_manifest(&manifest, &book(&[("alice", ACCOUNT)])),
+            format!("{}{}", call("alice (02c1d7…)"), call(LONGER))
+        );
+        assert_eq!(
+            annotate_manifest(&manifest, &book(&[("alice", ACCOUNT), ("bob", LONGER)])),
+            format!("{}{}", call("alice (02c1d7…)"), call("bob (02c1d7…)"))
+        );
+    }
+
+    #[test]
+    fn addresses_with_several_labels_use_the_first_alphabetically() {
+        assert_eq!(
+            annotate_manifest(&call(ACCOUNT), &book(&[("zed", ACCOUNT), ("alice", ACCOUNT)])),
+            call("alice (02c1d7…)")
+        );
+    }
+
+    #[test]
+    fn annotating_a_transaction_body_leaves_it_unchanged() {
+        let body: TransactionBody = serde_json::from_value(serde_json::json!({
+            "manifest": call(ACCOUNT),
+            "nonce": { "value": 1 },
+            "signatures": [],
+        }))
+        .unwrap();
+        let before: String = serde_json::to_string(&body).unwrap();
+
+        assert_eq!(body.annotated_manifest(&book(&[("alice", ACCOUNT)])), call("alice (02c1d7…)"));
+        assert_eq!(body.manifest(), call(ACCOUNT));
+        assert_eq!(serde_json::to_string(&body).unwrap(), before);
+    }
+
+    #[test]
+    fn receipts_are_rendered_with_labels() {
+        let receipt: Receipt = serde_json::from_value(serde_json::json!({
+            "transaction_hash": "ab",
+            "status": "Success",
+            "outputs": [],
+            "logs": [format!("Deposited into {}", ACCOUNT)],
+            "new_packages": [],
+            "new_components": [ACCOUNT],
+            "new_resources": [],
+        }))
+        .unwrap();
+        assert_eq!(
+            receipt.to_annotated_string(&book(&[("alice", ACCOUNT)])),
+            "Transaction ab: Success\nNew component: alice (02c1d7…)\nLog: Deposited into alice (02c1d7…)"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Encoding of the arguments of function and method calls. All of the helpers in this crate encode
+//! their arguments through this module so that a change in how the engine expects arguments to be
+//! encoded only needs to be absorbed here. `encode_args` produces the single SBOR struct which
+//! `to_struct!` produces, and `call_args` the list of per-argument encodings which the call
+//! instructions of the pinned engine take, which is what `args!` produces.
+//!
+//! Buckets and proofs are passed as `BucketRef`s and `ProofRef`s, which are only handed out to the
+//! callbacks of the `TransactionBuilderExt` methods creating them and can't outlive the callback.
+//! They are moved into the arguments which use them and don't expose their ids, so leaking a bucket
+//! out of its scope or passing it twice is a compile error rather than a manifest the engine
+//! rejects. The cases which must not compile are in `tests/compile_fail`.
+
+use sbor::type_id::{TYPE_FIELDS_UNNAMED, TYPE_STRUCT};
+use sbor::Encode;
+use scrypto::buffer::scrypto_encode;
+use scrypto::engine::types::{BucketId, ProofId};
+use scrypto::prelude::*;
+
+use std::marker::PhantomData;
+
+/// Makes the lifetime of a reference invariant, so that a reference of one callback can't be
+/// coerced into the scope of another.
+type Scope<'scope> = PhantomData<fn(&'scope ()) -> &'scope ()>;
+
+/// A bucket created by a builder callback, valid only within that callback.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BucketRef<'scope> {
+    id: BucketId,
+    scope: Scope<'scope>,
+}
+
+impl<'scope> BucketRef<'scope> {
+    pub(crate) fn new(id: BucketId) -> Self {
+        Self { id, scope: PhantomData }
+    }
+
+    pub(crate) fn id(&self) -> BucketId {
+        self.id
+    }
+}
+
+/// A proof created by a builder callback, valid only within that callback.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProofRef<'scope> {
+    id: ProofId,
+    scope: Scope<'scope>,
+}
+
+impl<'scope> ProofRef<'scope> {
+    pub(crate) fn new(id: ProofId) -> Self {
+        Self { id, scope: PhantomData }
+    }
+
+    pub(crate) fn id(&self) -> ProofId {
+        self.id
+    }
+}
+
+/// A builder of the arguments of a function or method call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Args {
+    values: Vec<Vec<u8>>,
+}
+
+impl Args {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_bucket(mut self, bucket: BucketRef) -> Self {
+        self.values.push(scrypto_encode(&scrypto::resource::Bucket(bucket.id())));
+        self
+    }
+
+    pub fn add_proof(mut self, proof: ProofRef) -> Self {
+        self.values.push(scrypto_encode(&scrypto::resource::Proof(proof.id())));
+        self
+    }
+
+    pub fn add_decimal(mut self, decimal: Decimal) -> Self {
+        self.values.push(scrypto_encode(&decimal));
+        self
+    }
+
+    /// Adds a component, package or resource address.
+    pub fn add_address<A: Encode>(mut self, address: A) -> Self {
+        self.values.push(scrypto_encode(&address));
+        self
+    }
+
+    pub fn add_string(mut self, string: &str) -> Self {
+        self.values.push(scrypto_encode(&string.to_string()));
+        self
+    }
+
+    /// Adds any other value which can be SBOR encoded.
+    pub fn add_value<T: Encode>(mut self, value: &T) -> Self {
+        self.values.push(scrypto_encode(value));
+        self
+    }
+
+    /// Adds an argument which was already SBOR encoded.
+    pub fn add_raw(mut self, bytes: Vec<u8>) -> Self {
+        self.values.push(bytes);
+        self
+    }
+}
+
+/// Encodes the arguments as one SBOR struct with an unnamed field per argument, in the same way as
+/// `to_struct!`.
+pub fn encode_args(args: Args) -> Vec<u8> {
+    let mut encoded: Vec<u8> = vec![TYPE_STRUCT, TYPE_FIELDS_UNNAMED];
+    encoded.extend((args.values.len() as u32).to_le_bytes());
+    for value in args.values {
+        encoded.extend(value);
+    }
+    encoded
+}
+
+/// Encodes the arguments into the form the call instructions of the pinned engine take: a list
+/// with the SBOR encoding of each argument, in the same way as `args!`.
+pub fn call_args(args: Args) -> Vec<Vec<u8>> {
+    args.values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173";
+
+    #[derive(TypeId, Encode)]
+    struct Expected(Decimal, ComponentAddress, ResourceAddress, String, scrypto::resource::Bucket, scrypto::resource::Proof);
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::from_str(ACCOUNT).unwrap()
+    }
+
+    fn args() -> Args {
+        Args::new()
+            .add_decimal(dec!("12.5"))
+            .add_address(account())
+            .add_address(RADIX_TOKEN)
+            .add_string("name")
+            .add_bucket(BucketRef::new(512))
+            .add_proof(ProofRef::new(513))
+    }
+
+    #[test]
+    fn call_args_match_the_args_macro() {
+        assert_eq!(
+            call_args(args()),
+            args!(
+                dec!("12.5"),
+                account(),
+                RADIX_TOKEN,
+                "name".to_string(),
+                scrypto::resource::Bucket(512),
+                scrypto::resource::Proof(513)
+            )
+        );
+        assert_eq!(call_args(Args::new()), args!());
+    }
+
+    #[test]
+    fn encoded_args_match_a_struct_of_the_arguments() {
+        let expected: Expected = Expected(
+            dec!("12.5"),
+            account(),
+            RADIX_TOKEN,
+            "name".to_string(),
+            scrypto::resource::Bucket(512),
+            scrypto::resource::Proof(513),
+        );
+        assert_eq!(encode_args(args()), scrypto_encode(&expected));
+    }
+
+    #[test]
+    fn raw_and_typed_arguments_encode_the_same() {
+        let typed: Args = Args::new().add_value(&7u32).add_decimal(Decimal::one());
+        let raw: Args = Args::new().add_raw(scrypto_encode(&7u32)).add_raw(scrypto_encode(&Decimal::one()));
+        assert_eq!(encode_args(typed.clone()), encode_args(raw.clone()));
+        assert_eq!(call_args(typed), call_args(raw));
+    }
+}
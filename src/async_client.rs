@@ -0,0 +1,186 @@
+//! A minimal asynchronous client for applications which already run on a tokio runtime. It only
+//! covers submitting transactions, optionally through a submission queue and with hooks, and reading
+//! components; the mirror, idempotency and the other helpers are only available on the blocking
+//! `PteClient`.
+
+use radix_engine::model::SignedTransaction;
+use scrypto::prelude::*;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::clock::{system_clock, Clock};
+use crate::compatibility::detect_dialect_mismatch;
+use crate::component::{ComponentEnvelope, ComponentInfo};
+use crate::hooks::{Completion, Hooks, TxHashes};
+use crate::labeled::LabeledAddress;
+use crate::pool::PoolConfig;
+use crate::public_key::PublicKeyFormat;
+use crate::queue::{ExecuteOptions, QueueMetrics, SubmissionQueue};
+use crate::{PteApiError, Receipt, TransactionBody, TransactionSubmissionError};
+
+pub struct AsyncPteClient {
+    base_url: String,
+    http_client: reqwest::Client,
+    public_key_format: PublicKeyFormat,
+    queue: Option<SubmissionQueue>,
+    hooks: Arc<Hooks>,
+    clock: Arc<dyn Clock>,
+}
+
+impl AsyncPteClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http_client: PoolConfig::default().build_async_http_client(),
+            public_key_format: PublicKeyFormat::default(),
+            queue: None,
+            hooks: Arc::new(Hooks::new()),
+            clock: system_clock(),
+        }
+    }
+
+    /// Configures the pool of connections kept open to the PTE server.
+    pub fn with_pool_config(self, config: PoolConfig) -> Self {
+        Self {
+            http_client: config.build_async_http_client(),
+            ..self
+        }
+    }
+
+    /// Replaces the format public keys are written in within transaction bodies.
+    pub fn with_public_key_format(self, public_key_format: PublicKeyFormat) -> Self {
+        Self {
+            public_key_format,
+            ..self
+        }
+    }
+
+    /// Routes all of the submissions made through `execute` through a bounded submission queue
+    /// with priorities and per-key fairness.
+    pub fn with_submission_queue(self, queue: SubmissionQueue) -> Self {
+        Self {
+            queue: Some(queue),
+            ..self
+        }
+    }
+
+    /// Registers the hooks run on the lifecycle events of the submissions made by the client. They
+    /// run on the blocking threads of the runtime, so slow hooks don't stall its workers, and the
+    /// submission only returns once they are done.
+    pub fn with_hooks(self, hooks: Hooks) -> Self {
+        Self {
+            hooks: Arc::new(hooks),
+            ..self
+        }
+    }
+
+    /// Replaces the clock used to time submissions.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Submits the transaction to the PTE's `/transaction` endpoint.
+    pub async fn submit_transaction(&self, transaction: &SignedTransaction) -> Result<Receipt, TransactionSubmissionError> {
+        let transaction_body: TransactionBody = TransactionBody::from_signed_with_format(transaction, self.public_key_format)?;
+        let hashes: TxHashes = TxHashes::of(transaction);
+        let (hashes, transaction_body): (TxHashes, TransactionBody) = {
+            let hooks: Arc<Hooks> = self.hooks.clone();
+            spawn_hooks(move || {
+                hooks.submitted(&hashes, &transaction_body);
+                (hashes, transaction_body)
+            })
+            .await
+        };
+
+        let started_at: Instant = self.clock.now();
+        let outcome: Result<Receipt, TransactionSubmissionError> = self.post_transaction(&transaction_body).await;
+        let duration: Duration = self.clock.now().saturating_duration_since(started_at);
+
+        let hooks: Arc<Hooks> = self.hooks.clone();
+        let transaction: SignedTransaction = transaction.clone();
+        spawn_hooks(move || {
+            if let Ok(ref receipt) = outcome {
+                hooks.received(receipt);
+            }
+            hooks.completed(&Completion {
+                hashes: &hashes,
+                transaction: &transaction,
+                duration,
+                outcome: &outcome,
+            });
+            outcome
+        })
+        .await
+    }
+
+    async fn post_transaction(&self, transaction_body: &TransactionBody) -> Result<Receipt, TransactionSubmissionError> {
+        let response = self
+            .http_client
+            .post(format!("{}/transaction", self.base_url))
+            .json(transaction_body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status: u16 = response.status().as_u16();
+            let body: String = response.text().await?;
+            return Err(match detect_dialect_mismatch(transaction_body.manifest(), &body) {
+                Some(mismatch) => TransactionSubmissionError::ManifestDialectMismatch(mismatch),
+                None => TransactionSubmissionError::Rejected { status, body },
+            });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Submits the transaction through the submission queue if one is configured, or directly if
+    /// not. The task waits for its turn in the queue without blocking its thread.
+    pub async fn execute(&self, transaction: &SignedTransaction, options: &ExecuteOptions) -> Result<Receipt, TransactionSubmissionError> {
+        match self.queue {
+            Some(ref queue) => queue.run_async(options, || self.submit_transaction(transaction)).await?,
+            None => self.submit_transaction(transaction).await,
+        }
+    }
+
+    /// The metrics of the submission queue, if one is configured.
+    pub fn queue_metrics(&self) -> Option<QueueMetrics> {
+        self.queue.as_ref().map(SubmissionQueue::metrics)
+    }
+
+    /// Gets the information of the component with the given address from the PTE's `/component`
+    /// endpoint.
+    pub async fn get_component(&self, address: impl Into<LabeledAddress<ComponentAddress>>) -> Result<ComponentInfo, PteApiError> {
+        let labeled_address: LabeledAddress<ComponentAddress> = address.into();
+        let address: ComponentAddress = labeled_address.address;
+        let response = self
+            .http_client
+            .get(format!("{}/component/{}", self.base_url, address))
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(PteApiError::NotFound(labeled_address.to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(PteApiError::UnexpectedStatus {
+                status: response.status().as_u16(),
+                body: response.text().await?,
+            });
+        }
+        Ok(ComponentInfo::from_envelope(
+            address,
+            ComponentEnvelope::parse(&response.text().await?)?,
+        ))
+    }
+}
+
+/// Runs hooks on the blocking threads of the runtime, handing back what they were given. The hooks
+/// catch their own panics, so the task only fails if the runtime is shutting down.
+async fn spawn_hooks<T: Send + 'static>(run: impl FnOnce() -> T + Send + 'static) -> T {
+    match tokio::task::spawn_blocking(run).await {
+        Ok(value) => value,
+        Err(error) => std::panic::resume_unwind(error.into_panic()),
+    }
+}
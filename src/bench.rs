@@ -0,0 +1,228 @@
+//! Throughput and latency measurements of transaction submissions. Pointed at a `MockPte` the
+//! numbers are small enough to sanity check in CI-sized runs, and pointed at a real PTE they give a
+//! rough idea of its capacity. Rebuilding transactions is measured offline.
+
+use radix_engine::model::{Instruction, SignedTransaction, Transaction};
+use scrypto::buffer::scrypto_encode;
+use scrypto::prelude::*;
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::client::PteClient;
+use crate::clock::Clock;
+use crate::progress::{NoProgress, Progress};
+use crate::public_key::PublicKeyFormat;
+use crate::queue::ExecuteOptions;
+use crate::utils::decompile;
+use crate::{TransactionBody, TransactionSubmissionError};
+
+/// The latencies and outcomes of the submissions of a benchmark run.
+#[derive(Debug, Clone, Default)]
+pub struct BenchReport {
+    pub elapsed: Duration,
+    /// The latency of each submission, sorted in ascending order.
+    pub latencies: Vec<Duration>,
+    pub failures: usize,
+}
+
+impl BenchReport {
+    /// The number of submissions per second over the whole run.
+    pub fn throughput(&self) -> f64 {
+        if self.elapsed.is_zero() {
+            0.0
+        } else {
+            self.latencies.len() as f64 / self.elapsed.as_secs_f64()
+        }
+    }
+
+    /// The latency below which the given fraction of the submissions completed, using the nearest
+    /// rank method.
+    pub fn percentile(&self, quantile: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank: usize = (quantile * self.latencies.len() as f64).ceil() as usize;
+        self.latencies[rank.clamp(1, self.latencies.len()) - 1]
+    }
+
+    pub fn p50(&self) -> Duration {
+        self.percentile(0.5)
+    }
+
+    pub fn p99(&self) -> Duration {
+        self.percentile(0.99)
+    }
+}
+
+/// Submits the transactions with up to `concurrency` of them in flight at once and measures the
+/// latency of each submission. Transactions which fail to submit or are not committed successfully
+/// are counted as failures, and their latencies are still recorded.
+pub fn run(client: &PteClient, transactions: &[SignedTransaction], concurrency: usize, options: &ExecuteOptions) -> BenchReport {
+    run_with_progress(client, transactions, concurrency, options, &NoProgress)
+}
+
+/// Same as `run`, reporting a step as each submission completes, whether it succeeded or not.
+pub fn run_with_progress(
+    client: &PteClient,
+    transactions: &[SignedTransaction],
+    concurrency: usize,
+    options: &ExecuteOptions,
+    progress: &dyn Progress,
+) -> BenchReport {
+    let next: Mutex<usize> = Mutex::new(0);
+    let report: Mutex<BenchReport> = Mutex::new(BenchReport::default());
+
+    let started_at: Instant = client.clock().now();
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency.max(1) {
+            scope.spawn(|| loop {
+                let index: usize = {
+                    let mut next = next.lock().unwrap();
+                    *next += 1;
+                    *next - 1
+                };
+                let transaction: &SignedTransaction = match transactions.get(index) {
+                    Some(transaction) => transaction,
+                    None => break,
+                };
+
+                let submitted_at: Instant = client.clock().now();
+                let success: bool = client
+                    .execute(transaction, options)
+                    .map(|receipt| receipt.is_success())
+                    .unwrap_or(false);
+                let latency: Duration = client.clock().now().duration_since(submitted_at);
+
+                let mut report = report.lock().unwrap();
+                report.latencies.push(latency);
+                if !success {
+                    report.failures += 1;
+                }
+                progress.on_step(report.latencies.len(), transactions.len(), "submitted");
+            });
+        }
+    });
+
+    let mut report: BenchReport = report.into_inner().unwrap();
+    report.elapsed = client.clock().now().duration_since(started_at);
+    report.latencies.sort();
+    report
+}
+
+/// The time taken to rebuild the same transaction with fresh nonces, such as when an expired intent
+/// is rebased, with and without reusing its decompiled manifest.
+#[derive(Debug, Clone, Copy)]
+pub struct RebuildComparison {
+    pub rounds: usize,
+    /// Decompiling the manifest for every rebuild.
+    pub decompiling: Duration,
+    /// Decompiling the manifest once and reusing its text for every rebuild.
+    pub cached: Duration,
+}
+
+impl RebuildComparison {
+    /// The number of rebuilds per second with and without reusing the manifest text.
+    pub fn throughputs(&self) -> (f64, f64) {
+        let throughput = |elapsed: Duration| self.rounds as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        (throughput(self.decompiling), throughput(self.cached))
+    }
+}
+
+/// Rebuilds the transaction `rounds` times with a new nonce, re-signing it and creating its body,
+/// first decompiling the manifest for every rebuild and then reusing the manifest decompiled once.
+/// Both ways must produce the same manifest text, which is checked for every rebuild. The rebuilds
+/// are timed on the given clock.
+pub fn compare_rebuilds(
+    transaction: &Transaction,
+    private_key: &EcdsaPrivateKey,
+    rounds: usize,
+    clock: &dyn Clock,
+) -> Result<RebuildComparison, TransactionSubmissionError> {
+    let mut manifests: Vec<String> = Vec::with_capacity(rounds);
+    let started_at: Instant = clock.now();
+    for nonce in 0..rounds as u64 {
+        let (transaction, signatures) = rebuild(transaction, private_key, nonce);
+        manifests.push(TransactionBody::from_parts(&transaction, &signatures)?.manifest().to_string());
+    }
+    let decompiling: Duration = clock.now().duration_since(started_at);
+
+    let started_at: Instant = clock.now();
+    let manifest: String = decompile(transaction)?;
+    for nonce in 0..rounds as u64 {
+        let (transaction, signatures) = rebuild(transaction, private_key, nonce);
+        let transaction_body: TransactionBody =
+            TransactionBody::from_parts_with_manifest(&transaction, &signatures, manifest.clone(), PublicKeyFormat::default())?;
+        assert_eq!(transaction_body.manifest(), manifests[nonce as usize], "the cached manifest text diverged");
+    }
+    let cached: Duration = clock.now().duration_since(started_at);
+
+    Ok(RebuildComparison {
+        rounds,
+        decompiling,
+        cached,
+    })
+}
+
+/// Replaces the nonce of the transaction and signs it again, as rebasing an expired intent does.
+fn rebuild(
+    transaction: &Transaction,
+    private_key: &EcdsaPrivateKey,
+    nonce: u64,
+) -> (Transaction, Vec<(EcdsaPublicKey, EcdsaSignature)>) {
+    let mut transaction: Transaction = transaction.clone();
+    for instruction in transaction.instructions.iter_mut() {
+        if let Instruction::Nonce { .. } = instruction {
+            *instruction = Instruction::Nonce { nonce };
+        }
+    }
+    let signature: EcdsaSignature = private_key.sign(&scrypto_encode(&transaction));
+    (transaction, vec![(private_key.public_key(), signature)])
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    use radix_engine::transaction::TransactionBuilder;
+
+    use crate::clock::TestClock;
+    use crate::fixtures::{private_key, ACCOUNT, OTHER_ACCOUNT};
+
+    fn transfer() -> Transaction {
+        TransactionBuilder::new()
+            .withdraw_from_account_by_amount(dec!("10"), RADIX_TOKEN, ComponentAddress::from_str(ACCOUNT).unwrap())
+            .call_method_with_all_resources(ComponentAddress::from_str(OTHER_ACCOUNT).unwrap(), "deposit_batch")
+            .build(0)
+    }
+
+    /// The instructions of the transaction other than its nonce, encoded.
+    fn encoded_without_nonce(transaction: &Transaction) -> Vec<Vec<u8>> {
+        transaction
+            .instructions
+            .iter()
+            .filter(|x| !matches!(x, Instruction::Nonce { .. }))
+            .map(scrypto_encode)
+            .collect()
+    }
+
+    #[test]
+    fn rebuilt_transactions_keep_the_manifest_bytes_of_the_original() {
+        let original: Transaction = transfer();
+        let (rebuilt, signatures) = rebuild(&original, &private_key(), 42);
+
+        assert_eq!(
+            TransactionBody::from_parts(&rebuilt, &signatures).unwrap().manifest().as_bytes(),
+            decompile(&original).unwrap().as_bytes()
+        );
+        assert_eq!(encoded_without_nonce(&rebuilt), encoded_without_nonce(&original));
+        assert!(rebuilt.instructions.iter().any(|x| matches!(x, Instruction::Nonce { nonce: 42 })));
+        assert_ne!(scrypto_encode(&rebuilt), scrypto_encode(&original));
+    }
+
+    #[test]
+    fn rebuilds_are_compared_over_every_round() {
+        let comparison: RebuildComparison = compare_rebuilds(&transfer(), &private_key(), 3, &TestClock::new()).unwrap();
+        assert_eq!(comparison.rounds, 3);
+    }
+}
@@ -0,0 +1,360 @@
+//! Sets up a complete demo environment on the PTE: a number of funded accounts with their keys saved
+//! to a directory, a sample token distributed between them, and an address book of everything which
+//! was created. Progress is recorded in a session file after every step, so running the bootstrap
+//! again resumes a partially created environment and only verifies a complete one.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::address_book::AddressBook;
+use crate::builder::{Distribution, TransactionBuilderExt};
+use crate::client::PteClient;
+use crate::manifest_spec::ManifestSpec;
+use crate::progress::{NoProgress, Progress};
+use crate::queue::ExecuteOptions;
+use crate::{PteApiError, Receipt, TransactionSubmissionError};
+
+/// The name of the session file in the output directory.
+pub const SESSION_FILE: &str = "session.json";
+
+/// The name of the address book in the output directory.
+pub const ADDRESS_BOOK_FILE: &str = "address-book.json";
+
+/// What to create in the demo environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapConfig {
+    /// The number of accounts, at least one. The first account mints the token.
+    pub accounts: usize,
+    pub token_symbol: String,
+    pub token_supply: Decimal,
+    pub out: PathBuf,
+}
+
+/// An account of the environment. The address is only known once the account was created.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapAccount {
+    pub label: String,
+    pub key_file: String,
+    pub address: Option<String>,
+}
+
+/// The progress of a bootstrap, saved after every step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapSession {
+    pub token_symbol: String,
+    pub token_supply: String,
+    pub accounts: Vec<BootstrapAccount>,
+    pub token: Option<String>,
+    pub distributed: bool,
+    /// The error of the step which failed last, if the bootstrap didn't complete.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum BootstrapError {
+    IoError(std::io::Error),
+    /// The existing session was created with different settings than the ones given.
+    ConfigMismatch(String),
+    SubmissionFailed { step: String, error: TransactionSubmissionError },
+    /// The transaction of a step was committed but didn't succeed or didn't create what it should.
+    StepFailed { step: String, receipt: Box<Receipt> },
+    /// An entity recorded in a complete session could not be found on the PTE.
+    VerificationFailed { label: String, error: PteApiError },
+}
+
+impl From<std::io::Error> for BootstrapError {
+    fn from(error: std::io::Error) -> BootstrapError {
+        BootstrapError::IoError(error)
+    }
+}
+
+impl BootstrapSession {
+    fn new(config: &BootstrapConfig) -> Self {
+        Self {
+            token_symbol: config.token_symbol.clone(),
+            token_supply: config.token_supply.to_string(),
+            accounts: (1..=config.accounts)
+                .map(|index| BootstrapAccount {
+                    label: format!("account-{}", index),
+                    key_file: format!("keys/account-{}.key", index),
+                    address: None,
+                })
+                .collect(),
+            token: None,
+            distributed: false,
+            last_error: None,
+        }
+    }
+
+    /// Loads the session from the output directory, returning `None` if there is none yet.
+    pub fn load(out: &Path) -> std::io::Result<Option<Self>> {
+        match fs::read_to_string(out.join(SESSION_FILE)) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, out: &Path) -> std::io::Result<()> {
+        let temporary_path: PathBuf = out.join(SESSION_FILE).with_extension("tmp");
+        fs::write(&temporary_path, serde_json::to_string_pretty(self).unwrap())?;
+        fs::rename(&temporary_path, out.join(SESSION_FILE))
+    }
+
+    /// The number of steps of the bootstrap: creating each account, creating the token and
+    /// distributing it.
+    pub fn total_steps(&self) -> usize {
+        self.accounts.len() + 2
+    }
+
+    /// The number of steps already completed.
+    pub fn completed_steps(&self) -> usize {
+        self.accounts.iter().filter(|x| x.address.is_some()).count()
+            + self.token.is_some() as usize
+            + self.distributed as usize
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.accounts.iter().all(|x| x.address.is_some()) && self.token.is_some() && self.distributed
+    }
+
+    /// The address book of the accounts and the token created so far.
+    pub fn address_book(&self) -> AddressBook {
+        let mut book: AddressBook = AddressBook::new();
+        for account in self.accounts.iter() {
+            if let Some(ref address) = account.address {
+                book.insert(&account.label, address);
+            }
+        }
+        if let Some(ref token) = self.token {
+            book.insert(&self.token_symbol, token);
+        }
+        book
+    }
+
+    /// A table of the labels and addresses of the environment and where its files are.
+    pub fn summary_table(&self, out: &Path) -> String {
+        let mut rows: Vec<(String, String, String)> = self
+            .accounts
+            .iter()
+            .map(|x| {
+                (
+                    x.label.clone(),
+                    x.address.clone().unwrap_or_else(|| "(not created)".to_string()),
+                    out.join(&x.key_file).display().to_string(),
+                )
+            })
+            .collect();
+        rows.push((
+            self.token_symbol.clone(),
+            self.token.clone().unwrap_or_else(|| "(not created)".to_string()),
+            format!("supply {}, distributed: {}", self.token_supply, self.distributed),
+        ));
+
+        let widths: (usize, usize) = rows
+            .iter()
+            .fold((5, 7), |(label, address), x| (label.max(x.0.len()), address.max(x.1.len())));
+        let mut table: String = format!("{:<3$}  {:<4$}  {}\n", "Label", "Address", "Details", widths.0, widths.1);
+        for (label, address, details) in rows {
+            table.push_str(&format!("{:<3$}  {:<4$}  {}\n", label, address, details, widths.0, widths.1));
+        }
+        table.push_str(&format!("\nSession: {}\n", out.join(SESSION_FILE).display()));
+        table.push_str(&format!("Address book: {}\n", out.join(ADDRESS_BOOK_FILE).display()));
+        table
+    }
+}
+
+/// Creates the environment described by the config, or resumes it from the session in the output
+/// directory. A complete environment is verified against the PTE instead of being created again.
+/// Accounts are funded through the faucet limiter of the client, if it has one.
+/// The session is saved after every step, including the failed one, so a failed bootstrap can be
+/// resumed by running it again.
+pub fn bootstrap(client: &PteClient, config: &BootstrapConfig) -> Result<BootstrapSession, BootstrapError> {
+    bootstrap_with_progress(client, config, &NoProgress)
+}
+
+/// Same as `bootstrap`, reporting a step as each account, the token and the distribution are
+/// created. A resumed bootstrap starts out with the steps of the session already completed, and
+/// verifying a complete environment reports a step per verified account.
+pub fn bootstrap_with_progress(
+    client: &PteClient,
+    config: &BootstrapConfig,
+    progress: &dyn Progress,
+) -> Result<BootstrapSession, BootstrapError> {
+    if config.accounts == 0 {
+        return Err(BootstrapError::ConfigMismatch("at least one account is needed".to_string()));
+    }
+    fs::create_dir_all(config.out.join("keys"))?;
+    let mut session: BootstrapSession = match BootstrapSession::load(&config.out)? {
+        Some(session) => {
+            check_matches(&session, config)?;
+            session
+        }
+        None => BootstrapSession::new(config),
+    };
+
+    if session.is_complete() {
+        verify(client, &session, progress)?;
+        return Ok(session);
+    }
+
+    if session.completed_steps() > 0 {
+        progress.on_step(session.completed_steps(), session.total_steps(), "resumed");
+    }
+    let outcome: Result<(), BootstrapError> = run_steps(client, config, &mut session, progress);
+    session.last_error = outcome.as_ref().err().map(|error| format!("{:?}", error));
+    session.save(&config.out)?;
+    session.address_book().save(&config.out.join(ADDRESS_BOOK_FILE))?;
+    outcome.map(|_| session)
+}
+
+fn check_matches(session: &BootstrapSession, config: &BootstrapConfig) -> Result<(), BootstrapError> {
+    let mismatch = |what: &str| Err(BootstrapError::ConfigMismatch(format!(
+        "the session in {} was created with a different {}",
+        config.out.display(),
+        what
+    )));
+    if session.accounts.len() != config.accounts {
+        return mismatch("number of accounts");
+    }
+    if session.token_symbol != config.token_symbol || session.token_supply != config.token_supply.to_string() {
+        return mismatch("token");
+    }
+    Ok(())
+}
+
+fn run_steps(
+    client: &PteClient,
+    config: &BootstrapConfig,
+    session: &mut BootstrapSession,
+    progress: &dyn Progress,
+) -> Result<(), BootstrapError> {
+    let options: ExecuteOptions = ExecuteOptions::new().tag("flow", "bootstrap");
+
+    for index in 0..session.accounts.len() {
+        if session.accounts[index].address.is_some() {
+            continue;
+        }
+        let private_key: EcdsaPrivateKey = load_or_create_key(&config.out.join(&session.accounts[index].key_file))?;
+        let step: String = format!("creating {}", session.accounts[index].label);
+        let transaction: SignedTransaction = ManifestSpec::NewAccount
+            .build(&private_key, client.next_nonce())
+            .expect("the new account spec has no fields to be invalid");
+        let receipt: Receipt = submit(client, &step, &transaction, &options)?;
+        match receipt.new_components().first() {
+            Some(address) => session.accounts[index].address = Some(address.to_string()),
+            None => return Err(BootstrapError::StepFailed { step, receipt: Box::new(receipt) }),
+        }
+        session.save(&config.out)?;
+        progress.on_step(session.completed_steps(), session.total_steps(), &step);
+    }
+
+    let treasury: &BootstrapAccount = &session.accounts[0];
+    let treasury_address: String = treasury.address.clone().unwrap();
+    let treasury_key: EcdsaPrivateKey = load_or_create_key(&config.out.join(&treasury.key_file))?;
+
+    if session.token.is_none() {
+        let step: String = format!("creating the {} token", config.token_symbol);
+        let transaction: SignedTransaction = ManifestSpec::NewToken {
+            account: treasury_address.clone(),
+            symbol: config.token_symbol.clone(),
+            supply: config.token_supply.to_string(),
+        }
+        .build(&treasury_key, client.next_nonce())
+        .map_err(|what| BootstrapError::ConfigMismatch(format!("invalid token {}", what)))?;
+        let receipt: Receipt = submit(client, &step, &transaction, &options)?;
+        match receipt.new_resources().first() {
+            Some(address) => session.token = Some(address.to_string()),
+            None => return Err(BootstrapError::StepFailed { step, receipt: Box::new(receipt) }),
+        }
+        session.save(&config.out)?;
+        progress.on_step(session.completed_steps(), session.total_steps(), &step);
+    }
+
+    if !session.distributed {
+        let step: String = format!("distributing the {} token", config.token_symbol);
+        let token: ResourceAddress = ResourceAddress::from_str(session.token.as_ref().unwrap()).unwrap();
+        let recipients: Vec<ComponentAddress> = session
+            .accounts
+            .iter()
+            .map(|x| ComponentAddress::from_str(x.address.as_ref().unwrap()).unwrap())
+            .collect();
+        let transaction: SignedTransaction = TransactionBuilder::new()
+            .airdrop(
+                ComponentAddress::from_str(&treasury_address).unwrap(),
+                token,
+                config.token_supply,
+                &recipients,
+                &Distribution::Equal,
+            )
+            .expect("an equal distribution between at least one account can always be split")
+            .build(client.next_nonce())
+            .sign([&treasury_key]);
+        submit(client, &step, &transaction, &options)?;
+        session.distributed = true;
+        session.save(&config.out)?;
+        progress.on_step(session.completed_steps(), session.total_steps(), &step);
+    }
+
+    Ok(())
+}
+
+fn submit(
+    client: &PteClient,
+    step: &str,
+    transaction: &SignedTransaction,
+    options: &ExecuteOptions,
+) -> Result<Receipt, BootstrapError> {
+    let receipt: Receipt = client
+        .execute(transaction, options)
+        .map_err(|error| BootstrapError::SubmissionFailed { step: step.to_string(), error })?;
+    if !receipt.is_success() {
+        return Err(BootstrapError::StepFailed {
+            step: step.to_string(),
+            receipt: Box::new(receipt),
+        });
+    }
+    Ok(receipt)
+}
+
+/// Checks that all of the accounts of a complete session exist on the PTE.
+fn verify(client: &PteClient, session: &BootstrapSession, progress: &dyn Progress) -> Result<(), BootstrapError> {
+    for (index, account) in session.accounts.iter().enumerate() {
+        let address: ComponentAddress = ComponentAddress::from_str(account.address.as_ref().unwrap()).unwrap();
+        client
+            .get_component(address)
+            .map_err(|error| BootstrapError::VerificationFailed {
+                label: account.label.clone(),
+                error,
+            })?;
+        progress.on_step(index + 1, session.accounts.len(), &format!("verified {}", account.label));
+    }
+    Ok(())
+}
+
+/// Loads the hex encoded private key from the file, generating and saving a new one if the file
+/// doesn't exist yet. Keys are saved before the account is created so that a crash in between never
+/// loses the key of an account.
+fn load_or_create_key(path: &Path) -> std::io::Result<EcdsaPrivateKey> {
+    if let Ok(content) = fs::read_to_string(path) {
+        return hex::decode(content.trim())
+            .ok()
+            .and_then(|bytes| EcdsaPrivateKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid key in {}", path.display())));
+    }
+
+    loop {
+        let bytes: [u8; 32] = rand::random();
+        if let Ok(private_key) = EcdsaPrivateKey::from_bytes(&bytes) {
+            fs::write(path, hex::encode(bytes))?;
+            return Ok(private_key);
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! A small HTTP bridge exposing a configured client to people who don't use Rust. Requests go through
+//! the same client as everything else, so its keys, address book, server selection, queue, hooks and
+//! circuit breaker all apply. Every request must carry the shared secret, and only the operations on
+//! the allowlist are served.
+//!
+//! - `POST /execute` takes a `ManifestSpec` as JSON, builds it, signs it with the default signer of
+//!   the client and answers with the receipt.
+//! - `GET /balances/<account>` answers with the balances of the account per resource, each written
+//!   both in whole units and in attos.
+//! - `GET /receipt/<hash>` answers with the receipt of a transaction executed through the bridge or
+//!   recorded by the client's mirror.
+//!
+//! The bridge is served by axum on a runtime of its own, so callers of the blocking client don't need
+//! one. The client blocks, so every operation runs on the blocking threads of that runtime. Bodies
+//! over `MAX_BODY_BYTES` are refused with `413 Payload Too Large` before they are read in full.
+
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, Request, State};
+use axum::http::StatusCode;
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use radix_engine::model::SignedTransaction;
+use scrypto::prelude::*;
+
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tokio::sync::oneshot;
+
+use crate::address_book::AddressBook;
+use crate::client::PteClient;
+use crate::credentials::Credentials;
+use crate::denomination::DenominatedAmount;
+use crate::manifest_spec::ManifestSpec;
+use crate::queue::ExecuteOptions;
+use crate::Receipt;
+
+/// The header carrying the shared secret.
+pub const SECRET_HEADER: &str = "x-bridge-secret";
+
+/// The largest request body the bridge reads. Manifest specs are a few hundred bytes.
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// The operations the bridge can serve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BridgeOperation {
+    Execute,
+    Balances,
+    Receipt,
+}
+
+impl BridgeOperation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "execute" => Some(BridgeOperation::Execute),
+            "balances" => Some(BridgeOperation::Balances),
+            "receipt" => Some(BridgeOperation::Receipt),
+            _ => None,
+        }
+    }
+}
+
+/// The access control and name resolution of the bridge.
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    pub secret: String,
+    pub allowed: BTreeSet<BridgeOperation>,
+    /// The labels accepted in place of the addresses of the specs and of the balances endpoint.
+    pub address_book: AddressBook,
+}
+
+impl BridgeConfig {
+    /// A configuration allowing only the read-only operations.
+    pub fn new(secret: &str) -> Self {
+        Self {
+            secret: secret.to_string(),
+            allowed: [BridgeOperation::Balances, BridgeOperation::Receipt].into_iter().collect(),
+            address_book: AddressBook::new(),
+        }
+    }
+
+    pub fn allow(mut self, operation: BridgeOperation) -> Self {
+        self.allowed.insert(operation);
+        self
+    }
+
+    pub fn with_address_book(self, address_book: AddressBook) -> Self {
+        Self { address_book, ..self }
+    }
+
+    fn resolve(&self, label: &str) -> String {
+        self.address_book.get(label).unwrap_or(label).to_string()
+    }
+}
+
+struct BridgeState {
+    client: Arc<PteClient>,
+    config: BridgeConfig,
+    receipts: Mutex<HashMap<String, Receipt>>,
+}
+
+/// A running bridge. Dropping it stops the server.
+pub struct Bridge {
+    address: SocketAddr,
+    stop: Option<oneshot::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Bridge {
+    /// Starts serving the client on the given address, such as `127.0.0.1:9050`, or on a free port
+    /// with `127.0.0.1:0`.
+    pub fn start(client: Arc<PteClient>, config: BridgeConfig, listen: &str) -> std::io::Result<Self> {
+        let listener: std::net::TcpListener = std::net::TcpListener::bind(listen)?;
+        let address: SocketAddr = listener.local_addr()?;
+        listener.set_nonblocking(true)?;
+        let runtime: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+        let listener: tokio::net::TcpListener = {
+            let _context = runtime.enter();
+            tokio::net::TcpListener::from_std(listener)?
+        };
+        let state: Arc<BridgeState> = Arc::new(BridgeState {
+            client,
+            config,
+            receipts: Mutex::new(HashMap::new()),
+        });
+        let router: Router = router(state.clone());
+        let (stop, stopped) = oneshot::channel::<()>();
+
+        let handle: JoinHandle<()> = std::thread::spawn(move || {
+            // The blocking client panics when it is dropped within the runtime, so the last
+            // reference to it is held here and only dropped once the runtime has stopped.
+            let _state: Arc<BridgeState> = state;
+            runtime.block_on(async move {
+                axum::serve(listener, router)
+                    .with_graceful_shutdown(async {
+                        stopped.await.ok();
+                    })
+                    .await
+                    .ok();
+            })
+        });
+
+        Ok(Self {
+            address,
+            stop: Some(stop),
+            handle: Some(handle),
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Blocks until the server stops, which only happens when it fails to accept connections.
+    pub fn wait(mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+impl Drop for Bridge {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.send(()).ok();
+        }
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+fn router(state: Arc<BridgeState>) -> Router {
+    Router::new()
+        .route("/execute", post(handle_execute))
+        .route("/balances/:account", get(handle_balances))
+        .route("/receipt/:hash", get(handle_receipt))
+        .fallback(|| async { respond(404, json!({ "error": "not found" })) })
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        // Added last so that it runs first, before the route or the body are looked at.
+        .layer(from_fn_with_state(state.clone(), require_secret))
+        .with_state(state)
+}
+
+async fn require_secret(State(state): State<Arc<BridgeState>>, request: Request, next: Next) -> Response {
+    match request.headers().get(SECRET_HEADER) {
+        Some(secret) if constant_time_eq(secret.as_bytes(), state.config.secret.as_bytes()) => next.run(request).await,
+        _ => respond(401, json!({ "error": format!("missing or wrong {} header", SECRET_HEADER) })),
+    }
+}
+
+async fn handle_execute(State(state): State<Arc<BridgeState>>, body: Bytes) -> Response {
+    serve(state, BridgeOperation::Execute, move |state| execute(state, &body)).await
+}
+
+async fn handle_balances(State(state): State<Arc<BridgeState>>, Path(account): Path<String>) -> Response {
+    serve(state, BridgeOperation::Balances, move |state| balances(state, &account)).await
+}
+
+async fn handle_receipt(State(state): State<Arc<BridgeState>>, Path(hash): Path<String>) -> Response {
+    serve(state, BridgeOperation::Receipt, move |state| receipt(state, &hash)).await
+}
+
+/// Runs the operation on the blocking threads of the runtime, unless the operation isn't allowed.
+async fn serve(
+    state: Arc<BridgeState>,
+    operation: BridgeOperation,
+    run: impl FnOnce(&BridgeState) -> (u16, serde_json::Value) + Send + 'static,
+) -> Response {
+    if !state.config.allowed.contains(&operation) {
+        return respond(403, json!({ "error": format!("{:?} is not allowed by this bridge", operation) }));
+    }
+    let (status, body): (u16, serde_json::Value) = match tokio::task::spawn_blocking(move || run(&state)).await {
+        Ok(outcome) => outcome,
+        Err(_) => (500, json!({ "error": format!("{:?} failed unexpectedly", operation) })),
+    };
+    respond(status, body)
+}
+
+fn respond(status: u16, body: serde_json::Value) -> Response {
+    (
+        StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        Json(body),
+    )
+        .into_response()
+}
+
+fn receipt(state: &BridgeState, hash: &str) -> (u16, serde_json::Value) {
+    let receipt: Option<Receipt> = state
+        .receipts
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(hash)
+        .cloned()
+        .or_else(|| state.client.mirror().and_then(|mirror| mirror.get_receipt(hash)));
+    match receipt {
+        Some(receipt) => (200, serde_json::to_value(receipt).unwrap()),
+        None => (404, json!({ "error": format!("no receipt of {} is known to the bridge", hash) })),
+    }
+}
+
+fn execute(state: &BridgeState, body: &[u8]) -> (u16, serde_json::Value) {
+    let spec: ManifestSpec = match serde_json::from_slice(body) {
+        Ok(spec) => resolve_spec(&state.config, spec),
+        Err(error) => return (400, json!({ "error": format!("invalid manifest spec: {}", error) })),
+    };
+    let private_key: &EcdsaPrivateKey = match state.client.resolve_signer(Credentials::Default) {
+        Ok(private_key) => private_key,
+        Err(error) => return (error.http_status(), json!({ "error": format!("{:?}", error) })),
+    };
+    let transaction: SignedTransaction = match spec.build(private_key, state.client.next_nonce()) {
+        Ok(transaction) => transaction,
+        Err(field) => return (400, json!({ "error": format!("invalid {}", field) })),
+    };
+
+    match state.client.execute(&transaction, &ExecuteOptions::new().tag("flow", "bridge")) {
+        Ok(receipt) => {
+            state
+                .receipts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(receipt.transaction_hash.clone(), receipt.clone());
+            (200, serde_json::to_value(receipt).unwrap())
+        }
+        Err(error) => (error.http_status(), json!({ "error": format!("{:?}", error) })),
+    }
+}
+
+fn balances(state: &BridgeState, account: &str) -> (u16, serde_json::Value) {
+    let address: ComponentAddress = match ComponentAddress::from_str(&state.config.resolve(account)) {
+        Ok(address) => address,
+        Err(_) => return (400, json!({ "error": format!("{} is neither an account address nor a label", account) })),
+    };
+    match state.client.get_balances(address) {
+        Ok(balances) => {
+            let balances: BTreeMap<String, DenominatedAmount> = balances
+                .into_iter()
+                .map(|(resource, amount)| (resource.to_string(), DenominatedAmount::from(amount)))
+                .collect();
+            (200, json!(balances))
+        }
+        Err(error) => (502, json!({ "error": format!("{:?}", error) })),
+    }
+}
+
+/// Replaces the labels of the address book in the address fields of the spec with their addresses.
+fn resolve_spec(config: &BridgeConfig, spec: ManifestSpec) -> ManifestSpec {
+    match spec {
+        ManifestSpec::NewAccount => ManifestSpec::NewAccount,
+        ManifestSpec::Transfer { from, to, resource, amount } => ManifestSpec::Transfer {
+            from: config.resolve(&from),
+            to: config.resolve(&to),
+            resource: config.resolve(&resource),
+            amount,
+        },
+        ManifestSpec::NewToken { account, symbol, supply } => ManifestSpec::NewToken {
+            account: config.resolve(&account),
+            symbol,
+            supply,
+        },
+    }
+}
+
+/// Compares the secrets without returning early on the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |difference, (x, y)| difference | (x ^ y)) == 0
+}
@@ -0,0 +1,557 @@
+//! Helpers on top of the `TransactionBuilder` for common patterns which otherwise require manual
+//! worktop plumbing.
+
+use radix_engine::engine::{IdValidator, ProofKind};
+use radix_engine::model::{Instruction, Transaction};
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+use scrypto::values::ScryptoValue;
+
+use std::collections::HashMap;
+
+use crate::args::{call_args, Args, BucketRef, ProofRef};
+use crate::math::{split_amount, SplitError};
+
+/// Where the resources returned by a method call should be sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteTarget {
+    /// Deposit the resources into an account.
+    Deposit(ComponentAddress),
+
+    /// Pass the resources as the only argument of a method call.
+    Call {
+        component: ComponentAddress,
+        method: String,
+    },
+
+    /// Burn the resources. The pinned engine has no burn instruction, so the bucket is passed to the
+    /// `burn` method of a burner component which burns everything it receives.
+    Burn { burner: ComponentAddress },
+}
+
+/// How the total amount of an airdrop is split between its recipients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Distribution {
+    /// Every recipient gets the same share.
+    Equal,
+    /// Each recipient gets a share proportional to its weight, given in the order of the recipients.
+    Weighted(Vec<Decimal>),
+}
+
+pub trait TransactionBuilderExt {
+    /// Takes all of the resource from the worktop into a bucket which the callback can pass on.
+    fn take_from_worktop_ref<F>(&mut self, resource: ResourceAddress, then: F) -> &mut Self
+    where
+        F: for<'b, 'scope> FnOnce(&'b mut Self, BucketRef<'scope>) -> &'b mut Self;
+
+    /// Takes an amount of the resource from the worktop into a bucket which the callback can pass on.
+    fn take_from_worktop_by_amount_ref<F>(&mut self, amount: Decimal, resource: ResourceAddress, then: F) -> &mut Self
+    where
+        F: for<'b, 'scope> FnOnce(&'b mut Self, BucketRef<'scope>) -> &'b mut Self;
+
+    /// Creates a proof of the resource from the auth zone which the callback can pass on.
+    fn create_proof_from_auth_zone_ref<F>(&mut self, resource: ResourceAddress, then: F) -> &mut Self
+    where
+        F: for<'b, 'scope> FnOnce(&'b mut Self, ProofRef<'scope>) -> &'b mut Self;
+
+    /// Calls a method which returns a bucket of `resource` to the worktop, takes what it returned
+    /// and sends it to the route target. When `expected_amount` is given, the transaction fails
+    /// unless at least that much of the resource was returned, and only that amount is routed.
+    fn call_and_route(
+        &mut self,
+        component: ComponentAddress,
+        method: &str,
+        args: Args,
+        resource: ResourceAddress,
+        expected_amount: Option<Decimal>,
+        route_to: RouteTarget,
+    ) -> &mut Self;
+
+    /// Withdraws the total amount of the resource from the account and deposits it into the
+    /// recipients' accounts according to the distribution. The amounts always sum exactly to the
+    /// total, so no dust is left on the worktop.
+    fn airdrop(
+        &mut self,
+        from: ComponentAddress,
+        resource: ResourceAddress,
+        total: Decimal,
+        recipients: &[ComponentAddress],
+        distribution: &Distribution,
+    ) -> Result<&mut Self, SplitError>;
+
+    /// Withdraws everything the account holds of each of the resources and deposits it all into the
+    /// destination account.
+    fn sweep(&mut self, from: ComponentAddress, resources: &[ResourceAddress], to: ComponentAddress) -> &mut Self;
+}
+
+impl TransactionBuilderExt for TransactionBuilder {
+    fn take_from_worktop_ref<F>(&mut self, resource: ResourceAddress, then: F) -> &mut Self
+    where
+        F: for<'b, 'scope> FnOnce(&'b mut Self, BucketRef<'scope>) -> &'b mut Self,
+    {
+        self.take_from_worktop(resource, |builder, bucket_id| then(builder, BucketRef::new(bucket_id)))
+    }
+
+    fn take_from_worktop_by_amount_ref<F>(&mut self, amount: Decimal, resource: ResourceAddress, then: F) -> &mut Self
+    where
+        F: for<'b, 'scope> FnOnce(&'b mut Self, BucketRef<'scope>) -> &'b mut Self,
+    {
+        self.take_from_worktop_by_amount(amount, resource, |builder, bucket_id| then(builder, BucketRef::new(bucket_id)))
+    }
+
+    fn create_proof_from_auth_zone_ref<F>(&mut self, resource: ResourceAddress, then: F) -> &mut Self
+    where
+        F: for<'b, 'scope> FnOnce(&'b mut Self, ProofRef<'scope>) -> &'b mut Self,
+    {
+        self.create_proof_from_auth_zone(resource, |builder, proof_id| then(builder, ProofRef::new(proof_id)))
+    }
+
+    fn call_and_route(
+        &mut self,
+        component: ComponentAddress,
+        method: &str,
+        args: Args,
+        resource: ResourceAddress,
+        expected_amount: Option<Decimal>,
+        route_to: RouteTarget,
+    ) -> &mut Self {
+        self.call_method(component, method, call_args(args));
+
+        match expected_amount {
+            Some(amount) => self
+                .add_instruction(Instruction::AssertWorktopContainsByAmount {
+                    amount,
+                    resource_address: resource,
+                })
+                .0
+                .take_from_worktop_by_amount_ref(amount, resource, |builder, bucket| route(builder, bucket, &route_to)),
+            None => self.take_from_worktop_ref(resource, |builder, bucket| route(builder, bucket, &route_to)),
+        }
+    }
+
+    fn airdrop(
+        &mut self,
+        from: ComponentAddress,
+        resource: ResourceAddress,
+        total: Decimal,
+        recipients: &[ComponentAddress],
+        distribution: &Distribution,
+    ) -> Result<&mut Self, SplitError> {
+        let weights: Vec<Decimal> = match distribution {
+            Distribution::Equal => vec![dec!("1"); recipients.len()],
+            Distribution::Weighted(weights) if weights.len() == recipients.len() => weights.clone(),
+            Distribution::Weighted(weights) => {
+                return Err(SplitError::WeightCountMismatch {
+                    weights: weights.len(),
+                    recipients: recipients.len(),
+                })
+            }
+        };
+        let amounts: Vec<Decimal> = split_amount(total, &weights)?;
+
+        self.withdraw_from_account_by_amount(total, resource, from);
+        for (recipient, amount) in recipients.iter().zip(amounts) {
+            self.take_from_worktop_by_amount_ref(amount, resource, |builder, bucket| {
+                builder.call_method(*recipient, "deposit", call_args(Args::new().add_bucket(bucket)))
+            });
+        }
+        Ok(self)
+    }
+
+    fn sweep(&mut self, from: ComponentAddress, resources: &[ResourceAddress], to: ComponentAddress) -> &mut Self {
+        for resource in resources {
+            self.withdraw_from_account(*resource, from);
+        }
+        self.call_method_with_all_resources(to, "deposit_batch")
+    }
+}
+
+/// Passes the bucket to the route target.
+fn route<'b>(builder: &'b mut TransactionBuilder, bucket: BucketRef, route_to: &RouteTarget) -> &'b mut TransactionBuilder {
+    let bucket_args: Vec<Vec<u8>> = call_args(Args::new().add_bucket(bucket));
+    match route_to {
+        RouteTarget::Deposit(account) => builder.call_method(*account, "deposit", bucket_args),
+        RouteTarget::Call { component, method } => builder.call_method(*component, method, bucket_args),
+        RouteTarget::Burn { burner } => builder.call_method(*burner, "burn", bucket_args),
+    }
+}
+
+/// Why splicing user supplied instructions into a transaction failed.
+#[derive(Debug)]
+pub enum SpliceError {
+    /// The position is past the end of the instructions of the transaction.
+    PositionOutOfRange { position: usize, len: usize },
+    /// The manifest text could not be compiled.
+    CompileError(transaction_manifest::CompileError),
+    /// The instruction at `index` of the spliced transaction refers to a bucket or proof which
+    /// doesn't exist at that point, or has arguments which can't be decoded.
+    InvalidInstruction { index: usize, instruction: Instruction, reason: String },
+}
+
+/// Splices instructions into an otherwise helper-built transaction: helper-built instructions, whose
+/// buckets and proofs are `BucketRef`s and `ProofRef`s scoped to their callbacks, or raw instructions
+/// the helpers don't cover, such as instructions of new engine features. Positions are indices into the instructions of the
+/// transaction at the time of the splice. The engine allocates bucket and proof ids in the order of
+/// the instructions, so the ids referenced after the position are renumbered to account for the
+/// ids the spliced instructions allocate, and helper-built instructions and compiled manifest text,
+/// which allocate their ids from their own allocators, are renumbered into the ids of the
+/// transaction. The bucket and proof ids of the
+/// whole transaction are validated again when the transaction is built.
+pub struct ManifestEditor {
+    transaction: Transaction,
+    error: Option<SpliceError>,
+}
+
+impl ManifestEditor {
+    pub fn new(transaction: Transaction) -> Self {
+        Self { transaction, error: None }
+    }
+
+    /// Builds instructions with the builder and its helpers and inserts them at the given position.
+    /// The buckets and proofs they use are those created within `build`.
+    pub fn with_instructions<F>(self, position: usize, build: F) -> Self
+    where
+        F: FnOnce(&mut TransactionBuilder) -> &mut TransactionBuilder,
+    {
+        let mut builder: TransactionBuilder = TransactionBuilder::new();
+        build(&mut builder);
+        let instructions: Vec<Instruction> = builder
+            .build(0)
+            .instructions
+            .into_iter()
+            .filter(|x| !matches!(x, Instruction::Nonce { .. }))
+            .collect();
+        self.splice(position, instructions, true)
+    }
+
+    /// Inserts the instruction at the given position. The ids it refers to are those of the
+    /// transaction.
+    pub fn with_raw_instruction(self, position: usize, instruction: Instruction) -> Self {
+        self.splice(position, vec![instruction], false)
+    }
+
+    /// Compiles the manifest text and inserts its instructions at the given position. The ids of
+    /// the buckets and proofs named in the text are its own.
+    pub fn with_raw_manifest_text(self, position: usize, manifest: &str) -> Self {
+        match transaction_manifest::compile(manifest) {
+            Ok(compiled) => {
+                let instructions: Vec<Instruction> = compiled
+                    .instructions
+                    .into_iter()
+                    .filter(|x| !matches!(x, Instruction::Nonce { .. }))
+                    .collect();
+                self.splice(position, instructions, true)
+            }
+            Err(error) => self.fail(SpliceError::CompileError(error)),
+        }
+    }
+
+    fn splice(mut self, position: usize, instructions: Vec<Instruction>, own_ids: bool) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        let len: usize = self.transaction.instructions.len();
+        if position > len {
+            return self.fail(SpliceError::PositionOutOfRange { position, len });
+        }
+        let (host, spliced): (Vec<Instruction>, Vec<Instruction>) =
+            renumber_ids(&self.transaction.instructions, position, instructions, own_ids);
+        self.transaction.instructions = host;
+        self.transaction.instructions.splice(position..position, spliced);
+        self
+    }
+
+    /// Returns the spliced transaction, or the first error of the splices or of validating the ids
+    /// of the spliced transaction.
+    pub fn build(self) -> Result<Transaction, SpliceError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        validate_ids(&self.transaction.instructions)?;
+        Ok(self.transaction)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdKind {
+    Bucket,
+    Proof,
+}
+
+/// The kind of id the instruction allocates, if it allocates one.
+fn allocated_kind(instruction: &Instruction) -> Option<IdKind> {
+    match instruction {
+        Instruction::TakeFromWorktop { .. }
+        | Instruction::TakeFromWorktopByAmount { .. }
+        | Instruction::TakeFromWorktopByIds { .. } => Some(IdKind::Bucket),
+        Instruction::PopFromAuthZone
+        | Instruction::CreateProofFromAuthZone { .. }
+        | Instruction::CreateProofFromAuthZoneByAmount { .. }
+        | Instruction::CreateProofFromAuthZoneByIds { .. }
+        | Instruction::CreateProofFromBucket { .. }
+        | Instruction::CloneProof { .. } => Some(IdKind::Proof),
+        _ => None,
+    }
+}
+
+/// The ids a fresh allocator hands out for allocations of the kinds, in order.
+fn allocate_ids(kinds: &[IdKind]) -> Vec<u32> {
+    let mut id_validator: IdValidator = IdValidator::new();
+    kinds
+        .iter()
+        .map(|kind| match kind {
+            IdKind::Bucket => id_validator.new_bucket().expect("the bucket ids of the transaction are exhausted"),
+            IdKind::Proof => id_validator
+                .new_proof(ProofKind::AuthZoneProof)
+                .expect("the proof ids of the transaction are exhausted"),
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct IdMapping {
+    buckets: HashMap<u32, u32>,
+    proofs: HashMap<u32, u32>,
+}
+
+impl IdMapping {
+    fn insert(&mut self, kind: IdKind, old: u32, new: u32) {
+        match kind {
+            IdKind::Bucket => self.buckets.insert(old, new),
+            IdKind::Proof => self.proofs.insert(old, new),
+        };
+    }
+
+    /// The instruction with the ids it refers to replaced. Ids without a replacement are left alone
+    /// for the validation to report.
+    fn apply(&self, instruction: Instruction) -> Instruction {
+        let bucket = |id: u32| *self.buckets.get(&id).unwrap_or(&id);
+        let proof = |id: u32| *self.proofs.get(&id).unwrap_or(&id);
+        match instruction {
+            Instruction::ReturnToWorktop { bucket_id } => Instruction::ReturnToWorktop { bucket_id: bucket(bucket_id) },
+            Instruction::CreateProofFromBucket { bucket_id } => Instruction::CreateProofFromBucket { bucket_id: bucket(bucket_id) },
+            Instruction::PushToAuthZone { proof_id } => Instruction::PushToAuthZone { proof_id: proof(proof_id) },
+            Instruction::DropProof { proof_id } => Instruction::DropProof { proof_id: proof(proof_id) },
+            Instruction::CloneProof { proof_id } => Instruction::CloneProof { proof_id: proof(proof_id) },
+            Instruction::CallFunction { package_address, blueprint_name, function, args } => Instruction::CallFunction {
+                package_address,
+                blueprint_name,
+                function,
+                args: self.apply_to_args(args),
+            },
+            Instruction::CallMethod { component_address, method, args } => Instruction::CallMethod {
+                component_address,
+                method,
+                args: self.apply_to_args(args),
+            },
+            instruction => instruction,
+        }
+    }
+
+    fn apply_to_args(&self, args: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        args.into_iter()
+            .map(|arg| {
+                let mut value: ScryptoValue = match ScryptoValue::from_slice(&arg) {
+                    Ok(value) => value,
+                    Err(_) => return arg,
+                };
+                match value.replace_ids(&mut self.proofs.clone(), &mut self.buckets.clone()) {
+                    Ok(()) => value.raw,
+                    Err(_) => arg,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Renumbers the ids of the host instructions and of the instructions spliced into them at the
+/// position, into the ids the engine allocates for the instructions once spliced. The ids the
+/// spliced instructions refer to are their own when `own_ids` is set, and those of the host
+/// otherwise. Returns the renumbered host and spliced instructions.
+fn renumber_ids(
+    host: &[Instruction],
+    position: usize,
+    spliced: Vec<Instruction>,
+    own_ids: bool,
+) -> (Vec<Instruction>, Vec<Instruction>) {
+    let host_kinds: Vec<IdKind> = host.iter().filter_map(allocated_kind).collect();
+    let spliced_kinds: Vec<IdKind> = spliced.iter().filter_map(allocated_kind).collect();
+    let before: usize = host[..position].iter().filter_map(allocated_kind).count();
+
+    let combined_kinds: Vec<IdKind> = host_kinds[..before]
+        .iter()
+        .chain(spliced_kinds.iter())
+        .chain(host_kinds[before..].iter())
+        .copied()
+        .collect();
+    let host_ids: Vec<u32> = allocate_ids(&host_kinds);
+    let spliced_ids: Vec<u32> = allocate_ids(&spliced_kinds);
+    let combined_ids: Vec<u32> = allocate_ids(&combined_kinds);
+
+    let mut host_mapping: IdMapping = IdMapping::default();
+    for (index, (kind, old)) in host_kinds.iter().zip(host_ids.iter()).enumerate() {
+        let new_index: usize = if index < before { index } else { index + spliced_kinds.len() };
+        host_mapping.insert(*kind, *old, combined_ids[new_index]);
+    }
+    let mut spliced_mapping: IdMapping = IdMapping::default();
+    for (index, (kind, old)) in spliced_kinds.iter().zip(spliced_ids.iter()).enumerate() {
+        spliced_mapping.insert(*kind, *old, combined_ids[before + index]);
+    }
+
+    let spliced_mapping: &IdMapping = if own_ids { &spliced_mapping } else { &host_mapping };
+    (
+        host.iter().cloned().map(|x| host_mapping.apply(x)).collect(),
+        spliced.into_iter().map(|x| spliced_mapping.apply(x)).collect(),
+    )
+}
+
+/// Replays the bucket and proof ids of the instructions in the same way the `TransactionBuilder` does
+/// when instructions are added to it, reporting the first instruction which refers to an id which
+/// doesn't exist.
+pub fn validate_ids(instructions: &[Instruction]) -> Result<(), SpliceError> {
+    let mut id_validator: IdValidator = IdValidator::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        let outcome: Result<(), String> = match instruction {
+            Instruction::TakeFromWorktop { .. }
+            | Instruction::TakeFromWorktopByAmount { .. }
+            | Instruction::TakeFromWorktopByIds { .. } => id_validator.new_bucket().map(|_| ()).map_err(|x| format!("{:?}", x)),
+            Instruction::ReturnToWorktop { bucket_id } => id_validator.drop_bucket(*bucket_id).map_err(|x| format!("{:?}", x)),
+            Instruction::PopFromAuthZone
+            | Instruction::CreateProofFromAuthZone { .. }
+            | Instruction::CreateProofFromAuthZoneByAmount { .. }
+            | Instruction::CreateProofFromAuthZoneByIds { .. } => id_validator
+                .new_proof(ProofKind::AuthZoneProof)
+                .map(|_| ())
+                .map_err(|x| format!("{:?}", x)),
+            Instruction::CreateProofFromBucket { bucket_id } => id_validator
+                .new_proof(ProofKind::BucketProof(*bucket_id))
+                .map(|_| ())
+                .map_err(|x| format!("{:?}", x)),
+            Instruction::PushToAuthZone { proof_id } | Instruction::DropProof { proof_id } => {
+                id_validator.drop_proof(*proof_id).map_err(|x| format!("{:?}", x))
+            }
+            Instruction::CloneProof { proof_id } => id_validator.clone_proof(*proof_id).map(|_| ()).map_err(|x| format!("{:?}", x)),
+            Instruction::CallFunction { args, .. } | Instruction::CallMethod { args, .. } => args.iter().try_for_each(|arg| {
+                let value: ScryptoValue = ScryptoValue::from_slice(arg).map_err(|x| format!("{:?}", x))?;
+                id_validator.move_resources(&value).map_err(|x| format!("{:?}", x))
+            }),
+            Instruction::CallMethodWithAllResources { .. } => id_validator.move_all_resources().map_err(|x| format!("{:?}", x)),
+            _ => Ok(()),
+        };
+        if let Err(reason) = outcome {
+            return Err(SpliceError::InvalidInstruction {
+                index,
+                instruction: instruction.clone(),
+                reason,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173";
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::from_str(ACCOUNT).unwrap()
+    }
+
+    /// Takes all of the XRD on the worktop and deposits it, in its own ids.
+    fn deposit_text() -> String {
+        format!(
+            "TAKE_FROM_WORKTOP ResourceAddress(\"{}\") Bucket(\"xrd\");\nCALL_METHOD ComponentAddress(\"{}\") \"deposit\" Bucket(\"xrd\");\n",
+            RADIX_TOKEN, ACCOUNT
+        )
+    }
+
+    fn deposit_transaction() -> Transaction {
+        TransactionBuilder::new()
+            .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+            .take_from_worktop_ref(RADIX_TOKEN, |builder, bucket| {
+                builder.call_method(account(), "deposit", call_args(Args::new().add_bucket(bucket)))
+            })
+            .build(0)
+    }
+
+    fn bucket_arg(id: u32) -> Vec<Vec<u8>> {
+        vec![scrypto_encode(&scrypto::resource::Bucket(id))]
+    }
+
+    #[test]
+    fn spliced_text_at_the_end_gets_ids_past_those_of_the_transaction() {
+        let transaction: Transaction = ManifestEditor::new(deposit_transaction())
+            .with_raw_manifest_text(3, &deposit_text())
+            .build()
+            .unwrap();
+        let ids: Vec<u32> = allocate_ids(&[IdKind::Bucket, IdKind::Bucket]);
+        match &transaction.instructions[4] {
+            Instruction::CallMethod { args, .. } => assert_eq!(*args, bucket_arg(ids[1])),
+            instruction => panic!("unexpected instruction {:?}", instruction),
+        }
+    }
+
+    #[test]
+    fn spliced_text_before_the_ids_of_the_transaction_shifts_them() {
+        let transaction: Transaction = ManifestEditor::new(deposit_transaction())
+            .with_raw_manifest_text(1, &deposit_text())
+            .build()
+            .unwrap();
+        let ids: Vec<u32> = allocate_ids(&[IdKind::Bucket, IdKind::Bucket]);
+        let deposits: Vec<&Vec<Vec<u8>>> = transaction
+            .instructions
+            .iter()
+            .filter_map(|x| match x {
+                Instruction::CallMethod { method, args, .. } if method == "deposit" => Some(args),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deposits, vec![&bucket_arg(ids[0]), &bucket_arg(ids[1])]);
+    }
+
+    #[test]
+    fn colliding_ids_without_renumbering_fail_validation() {
+        let mut instructions: Vec<Instruction> = deposit_transaction().instructions;
+        let compiled: Vec<Instruction> = transaction_manifest::compile(&deposit_text()).unwrap().instructions;
+        instructions.splice(1..1, compiled.into_iter().filter(|x| !matches!(x, Instruction::Nonce { .. })));
+        assert!(validate_ids(&instructions).is_err());
+    }
+
+    #[test]
+    fn raw_instructions_refer_to_the_ids_of_the_transaction() {
+        let ids: Vec<u32> = allocate_ids(&[IdKind::Bucket, IdKind::Proof]);
+        let transaction: Transaction = ManifestEditor::new(deposit_transaction())
+            .with_raw_instruction(2, Instruction::CreateProofFromBucket { bucket_id: ids[0] })
+            .with_raw_instruction(3, Instruction::DropProof { proof_id: ids[1] })
+            .build()
+            .unwrap();
+        assert_eq!(transaction.instructions[3], Instruction::DropProof { proof_id: ids[1] });
+    }
+
+    #[test]
+    fn helper_built_instructions_are_renumbered_like_manifest_text() {
+        let from_text: Transaction = ManifestEditor::new(deposit_transaction())
+            .with_raw_manifest_text(1, &deposit_text())
+            .build()
+            .unwrap();
+        let from_helpers: Transaction = ManifestEditor::new(deposit_transaction())
+            .with_instructions(1, |builder| {
+                builder.take_from_worktop_ref(RADIX_TOKEN, |builder, bucket| {
+                    builder.call_method(account(), "deposit", call_args(Args::new().add_bucket(bucket)))
+                })
+            })
+            .build()
+            .unwrap();
+        assert_eq!(from_helpers.instructions, from_text.instructions);
+    }
+
+    #[test]
+    fn splicing_past_the_end_fails() {
+        assert!(matches!(
+            ManifestEditor::new(deposit_transaction()).with_raw_manifest_text(10, &deposit_text()).build(),
+            Err(SpliceError::PositionOutOfRange { position: 10, .. })
+        ));
+    }
+}
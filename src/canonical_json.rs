@@ -0,0 +1,129 @@
+//! A canonical JSON serializer: object keys are sorted, numbers use their shortest form and no
+//! insignificant whitespace is emitted. Two equal values always serialize to the same bytes.
+//!
+//! Keys are sorted by their UTF-8 bytes. Numbers and strings are written by serde_json:
+//! - integers as their decimal digits, with a leading `-` when negative
+//! - floats in the shortest form which reads back as the same float, always with a fraction or an
+//!   exponent, such as `1.0`, `0.1` or `1e21`
+//! - strings escape `"`, `\` and the control characters, using `\n`, `\t` and the other short
+//!   escapes where they exist and `\u00XX` otherwise; everything else, including non-ASCII text,
+//!   is written as UTF-8
+//!
+//! This is serde_json's format without its `arbitrary_precision` feature, which would write numbers
+//! as they were parsed instead. The tests of this module pin the exact bytes, so a dependency
+//! changing the format fails them rather than silently changing the stored hashes.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serializes the value into its canonical JSON bytes.
+pub fn to_canonical_vec<T: Serialize>(value: &T) -> serde_json::Result<Vec<u8>> {
+    Ok(to_canonical_string(value)?.into_bytes())
+}
+
+/// Serializes the value into its canonical JSON string.
+pub fn to_canonical_string<T: Serialize>(value: &T) -> serde_json::Result<String> {
+    let mut buffer: String = String::new();
+    write_value(&serde_json::to_value(value)?, &mut buffer)?;
+    Ok(buffer)
+}
+
+fn write_value(value: &Value, buffer: &mut String) -> serde_json::Result<()> {
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {
+            buffer.push_str(&serde_json::to_string(value)?);
+        }
+        Value::Array(elements) => {
+            buffer.push('[');
+            for (index, element) in elements.iter().enumerate() {
+                if index != 0 {
+                    buffer.push(',');
+                }
+                write_value(element, buffer)?;
+            }
+            buffer.push(']');
+        }
+        Value::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+
+            buffer.push('{');
+            for (index, key) in keys.into_iter().enumerate() {
+                if index != 0 {
+                    buffer.push(',');
+                }
+                buffer.push_str(&serde_json::to_string(key)?);
+                buffer.push(':');
+                write_value(&object[key], buffer)?;
+            }
+            buffer.push('}');
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn keys_are_sorted_by_their_bytes_at_every_level() {
+        let value: Value = json!({ "b": 1, "a": { "z": [3, { "y": 1, "x": 2 }], "B": null }, "é": true, "aa": "" });
+        assert_eq!(
+            to_canonical_string(&value).unwrap(),
+            r#"{"a":{"B":null,"z":[3,{"x":2,"y":1}]},"aa":"","b":1,"é":true}"#
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped_minimally() {
+        let value: Value = json!(["quote\" back\\slash", "new\nline\ttab\r", "bell\u{7}nul\u{0}", "é ✓ /"]);
+        assert_eq!(
+            to_canonical_string(&value).unwrap(),
+            r#"["quote\" back\\slash","new\nline\ttab\r","bell\u0007nul\u0000","é ✓ /"]"#
+        );
+    }
+
+    #[test]
+    fn numbers_use_their_shortest_form() {
+        let value: Value = json!([0, -1, u64::MAX, i64::MIN, 1.5, 1.0, 0.1, -2.5e-7, 1e21]);
+        assert_eq!(
+            to_canonical_string(&value).unwrap(),
+            "[0,-1,18446744073709551615,-9223372036854775808,1.5,1.0,0.1,-2.5e-7,1e21]"
+        );
+    }
+
+    #[test]
+    fn bytes_and_string_agree() {
+        let value: Value = json!({ "b": [1, 2], "a": "é" });
+        assert_eq!(to_canonical_vec(&value).unwrap(), to_canonical_string(&value).unwrap().into_bytes());
+    }
+
+    #[test]
+    fn canonical_json_round_trips() {
+        let value: Value = json!({
+            "nested": { "list": [1, -2, 0.5, "text", null, false], "empty": {} },
+            "escaped": "line\nbreak \"quoted\"",
+            "unicode": "✓",
+        });
+        let canonical: String = to_canonical_string(&value).unwrap();
+
+        let parsed: Value = serde_json::from_str(&canonical).unwrap();
+        assert_eq!(parsed, value);
+        assert_eq!(to_canonical_string(&parsed).unwrap(), canonical);
+    }
+
+    #[test]
+    fn the_order_values_are_built_in_does_not_matter() {
+        let mut first: serde_json::Map<String, Value> = serde_json::Map::new();
+        first.insert("x".to_string(), json!(1));
+        first.insert("y".to_string(), json!(2));
+        let mut second: serde_json::Map<String, Value> = serde_json::Map::new();
+        second.insert("y".to_string(), json!(2));
+        second.insert("x".to_string(), json!(1));
+
+        assert_eq!(to_canonical_vec(&first).unwrap(), to_canonical_vec(&second).unwrap());
+    }
+}
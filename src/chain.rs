@@ -0,0 +1,357 @@
+//! Instantiating a component and calling methods on it right away. The pinned engine can't refer to
+//! a component created earlier in the same manifest, so the instantiation and the follow-up calls
+//! are submitted as separate transactions, the follow-ups once the address of the new component is
+//! known from the receipt of the instantiation. Engines which derive the address of a component
+//! from its instantiation alone let the address be learned from a preview instead, and the
+//! instantiation and the follow-ups be submitted as one transaction.
+
+use radix_engine::model::{SignedTransaction, Transaction};
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use crate::client::PteClient;
+use crate::credentials::Credentials;
+use crate::signer_account::{DepositTarget, SignerAccountError};
+use crate::{PteApiError, Receipt, TransactionBody, TransactionSubmissionError};
+
+/// Whether the engine can call methods on a component created earlier in the same transaction. Once
+/// it can, `instantiate_then_call` submits the instantiation and the follow-ups as one transaction.
+/// The pinned engine derives the address of a component from the hash of the whole transaction, so
+/// the address previewed for the instantiation alone is not the one it gets with the follow-ups.
+pub const SINGLE_TRANSACTION_CHAINING: bool = false;
+
+/// The function call which instantiates the component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instantiation {
+    pub package: PackageAddress,
+    pub blueprint: String,
+    pub function: String,
+    pub args: Vec<Vec<u8>>,
+    /// The account into which the resources returned by the calls, such as admin badges, are
+    /// deposited. Without it, calls which return resources fail with resources left on the worktop.
+    pub deposit_returned_to: Option<DepositTarget>,
+}
+
+impl Instantiation {
+    pub fn new(package: PackageAddress, blueprint: &str, function: &str, args: Vec<Vec<u8>>) -> Self {
+        Self {
+            package,
+            blueprint: blueprint.to_string(),
+            function: function.to_string(),
+            args,
+            deposit_returned_to: None,
+        }
+    }
+
+    /// Deposits the returned resources into the account, or into the account of the signer when
+    /// given `DepositTarget::SignerAccount`.
+    pub fn deposit_returned_to(self, target: impl Into<DepositTarget>) -> Self {
+        Self {
+            deposit_returned_to: Some(target.into()),
+            ..self
+        }
+    }
+}
+
+/// The created component and the receipts of the instantiation followed by those of the follow-up
+/// calls.
+#[derive(Debug)]
+pub struct ChainedCalls {
+    pub component: ComponentAddress,
+    pub receipts: Vec<Receipt>,
+}
+
+#[derive(Debug)]
+pub enum ChainError {
+    /// The instantiation could not be submitted, or there was no signer to sign it with.
+    SubmissionError(TransactionSubmissionError),
+    /// The account of the signer to deposit the returned resources into could not be resolved.
+    DepositTargetError(SignerAccountError),
+    /// The instantiation could not be previewed to learn the address of the component.
+    PreviewError(PteApiError),
+    /// The instantiation was committed but failed. When chained in one transaction, this is also
+    /// how a failing follow-up is reported, as it reverts the instantiation along with it.
+    InstantiationFailed(Box<Receipt>),
+    /// The instantiation succeeded but created no component.
+    NoComponentCreated(Box<Receipt>),
+    /// The instantiation succeeded but the created component did not become visible.
+    ComponentNotVisible {
+        component: ComponentAddress,
+        receipt: Box<Receipt>,
+        error: PteApiError,
+    },
+    /// The follow-up call at `index` failed, either on submission or in its receipt. The component
+    /// was created and the follow-ups before `index` succeeded; `receipts` holds their receipts.
+    FollowupFailed {
+        component: ComponentAddress,
+        index: usize,
+        method: String,
+        receipts: Vec<Receipt>,
+        error: Box<FollowupError>,
+    },
+}
+
+#[derive(Debug)]
+pub enum FollowupError {
+    SubmissionError(TransactionSubmissionError),
+    Failed(Receipt),
+}
+
+impl PteClient {
+    /// Instantiates a component through the function of the blueprint and then calls the follow-up
+    /// methods on it in order, each in its own transaction signed by the signer. Stops at the first
+    /// follow-up which fails, reporting which one it was along with the created component. The
+    /// follow-ups are only submitted once the component is visible, see `with_component_wait`.
+    /// With `SINGLE_TRANSACTION_CHAINING`, everything is submitted as one transaction instead.
+    pub fn instantiate_then_call<'a>(
+        &'a self,
+        instantiation: &Instantiation,
+        followup: Vec<(String, Vec<Vec<u8>>)>,
+        signer: impl Into<Credentials<'a>>,
+    ) -> Result<ChainedCalls, ChainError> {
+        self.chain(instantiation, followup, signer.into(), SINGLE_TRANSACTION_CHAINING)
+    }
+
+    fn chain<'a>(
+        &'a self,
+        instantiation: &Instantiation,
+        followup: Vec<(String, Vec<Vec<u8>>)>,
+        signer: Credentials<'a>,
+        single_transaction: bool,
+    ) -> Result<ChainedCalls, ChainError> {
+        let private_key: &EcdsaPrivateKey = self.resolve_signer(signer).map_err(ChainError::SubmissionError)?;
+        let deposit_account: Option<ComponentAddress> = instantiation
+            .deposit_returned_to
+            .map(|target| self.resolve_deposit_target(&target, &private_key.public_key()))
+            .transpose()
+            .map_err(ChainError::DepositTargetError)?;
+        let deposit = |builder: &mut TransactionBuilder| {
+            if let Some(account) = deposit_account {
+                builder.call_method_with_all_resources(account, "deposit_batch");
+            }
+        };
+
+        let instantiate = || {
+            let mut builder: TransactionBuilder = TransactionBuilder::new();
+            builder.call_function(
+                instantiation.package,
+                &instantiation.blueprint,
+                &instantiation.function,
+                instantiation.args.clone(),
+            );
+            builder
+        };
+
+        let mut builder: TransactionBuilder = instantiate();
+        if single_transaction {
+            let mut preview: TransactionBuilder = instantiate();
+            deposit(&mut preview);
+            let component: ComponentAddress = self.preview_component(&preview.build(self.next_nonce()))?;
+            for (method, args) in followup {
+                builder.call_method(component, &method, args);
+            }
+            deposit(&mut builder);
+            let transaction: SignedTransaction = builder.build(self.next_nonce()).sign([private_key]);
+
+            let receipt: Receipt = self.submit_transaction(&transaction).map_err(ChainError::SubmissionError)?;
+            if !receipt.is_success() {
+                return Err(ChainError::InstantiationFailed(Box::new(receipt)));
+            }
+            if !receipt.new_components().contains(&component) {
+                return Err(ChainError::NoComponentCreated(Box::new(receipt)));
+            }
+            return Ok(ChainedCalls {
+                component,
+                receipts: vec![receipt],
+            });
+        }
+
+        deposit(&mut builder);
+        let transaction: SignedTransaction = builder.build(self.next_nonce()).sign([private_key]);
+
+        let receipt: Receipt = self.submit_transaction(&transaction).map_err(ChainError::SubmissionError)?;
+        if !receipt.is_success() {
+            return Err(ChainError::InstantiationFailed(Box::new(receipt)));
+        }
+        let component: ComponentAddress = match receipt.new_components().first() {
+            Some(component) => *component,
+            None => return Err(ChainError::NoComponentCreated(Box::new(receipt))),
+        };
+        if let Err(error) = self.get_created_component(component) {
+            return Err(ChainError::ComponentNotVisible {
+                component,
+                receipt: Box::new(receipt),
+                error,
+            });
+        }
+
+        let mut receipts: Vec<Receipt> = vec![receipt];
+        for (index, (method, args)) in followup.into_iter().enumerate() {
+            let mut builder: TransactionBuilder = TransactionBuilder::new();
+            builder.call_method(component, &method, args);
+            deposit(&mut builder);
+            let transaction: SignedTransaction = builder.build(self.next_nonce()).sign([private_key]);
+
+            let error: FollowupError = match self.submit_transaction(&transaction) {
+                Ok(receipt) if receipt.is_success() => {
+                    receipts.push(receipt);
+                    continue;
+                }
+                Ok(receipt) => FollowupError::Failed(receipt),
+                Err(error) => FollowupError::SubmissionError(error),
+            };
+            return Err(ChainError::FollowupFailed {
+                component,
+                index,
+                method,
+                receipts,
+                error: Box::new(error),
+            });
+        }
+
+        Ok(ChainedCalls { component, receipts })
+    }
+
+    /// The address of the component the instantiation creates, from a preview of it.
+    fn preview_component(&self, instantiation: &Transaction) -> Result<ComponentAddress, ChainError> {
+        let transaction_body: TransactionBody =
+            TransactionBody::from_parts(instantiation, &[]).map_err(ChainError::SubmissionError)?;
+        let receipt: Receipt = self.preview(&transaction_body).map_err(ChainError::PreviewError)?;
+        if !receipt.is_success() {
+            return Err(ChainError::InstantiationFailed(Box::new(receipt)));
+        }
+        match receipt.new_components().first() {
+            Some(component) => Ok(*component),
+            None => Err(ChainError::NoComponentCreated(Box::new(receipt))),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    use crate::client::ComponentWait;
+    use crate::fixtures::{account_creation_receipt, account_envelope, failed_transfer_receipt, private_key, ACCOUNT, PACKAGE};
+    use crate::mock::{EndpointConfig, MockPte};
+
+    use std::thread;
+    use std::time::Duration;
+
+    fn receipt_endpoint(receipt: &Receipt) -> EndpointConfig {
+        EndpointConfig::new(200, &serde_json::to_string(receipt).unwrap())
+    }
+
+    /// A mock PTE answering submissions and previews with the receipt of a component creation.
+    fn mock() -> MockPte {
+        MockPte::start(0)
+            .unwrap()
+            .with_endpoint("POST", "/transaction", receipt_endpoint(&account_creation_receipt()))
+            .with_endpoint("POST", "/transaction/preview", receipt_endpoint(&account_creation_receipt()))
+    }
+
+    fn instantiation() -> Instantiation {
+        Instantiation::new(PackageAddress::from_str(PACKAGE).unwrap(), "Vendor", "new", vec![])
+    }
+
+    fn followup() -> Vec<(String, Vec<Vec<u8>>)> {
+        vec![("restock".to_string(), vec![]), ("open".to_string(), vec![])]
+    }
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::from_str(ACCOUNT).unwrap()
+    }
+
+    #[test]
+    fn separate_transactions_submit_each_followup_on_its_own() {
+        let mock: MockPte = mock();
+        let client: PteClient = mock.client().with_component_wait(None);
+        let key: EcdsaPrivateKey = private_key();
+
+        let chained: ChainedCalls = client.chain(&instantiation(), followup(), (&key).into(), false).unwrap();
+        assert_eq!(chained.component, account());
+        assert_eq!(chained.receipts.len(), 3);
+        assert_eq!(mock.request_count("POST", "/transaction"), 3);
+        assert_eq!(mock.request_count("POST", "/transaction/preview"), 0);
+
+        let bodies: Vec<String> = mock.request_bodies("POST", "/transaction");
+        assert!(bodies[1].contains("restock") && !bodies[1].contains("open"));
+        assert!(bodies[2].contains("open"));
+    }
+
+    #[test]
+    fn a_single_transaction_calls_the_previewed_component() {
+        let mock: MockPte = mock();
+        let client: PteClient = mock.client().with_component_wait(None);
+        let key: EcdsaPrivateKey = private_key();
+
+        let chained: ChainedCalls = client.chain(&instantiation(), followup(), (&key).into(), true).unwrap();
+        assert_eq!(chained.component, account());
+        assert_eq!(chained.receipts.len(), 1);
+        assert_eq!(mock.request_count("POST", "/transaction/preview"), 1);
+        assert_eq!(mock.request_count("POST", "/transaction"), 1);
+
+        let body: String = mock.request_bodies("POST", "/transaction").remove(0);
+        assert!(body.contains(ACCOUNT) && body.contains("restock") && body.contains("open"));
+        assert!(!mock.request_bodies("POST", "/transaction/preview")[0].contains("restock"));
+    }
+
+    #[test]
+    fn a_failing_single_transaction_reverts_the_instantiation() {
+        let mock: MockPte = mock();
+        mock.set_endpoint("POST", "/transaction", receipt_endpoint(&failed_transfer_receipt()));
+        let client: PteClient = mock.client().with_component_wait(None);
+        let key: EcdsaPrivateKey = private_key();
+
+        assert!(matches!(
+            client.chain(&instantiation(), followup(), (&key).into(), true),
+            Err(ChainError::InstantiationFailed(_))
+        ));
+        assert_eq!(mock.request_count("POST", "/transaction"), 1);
+    }
+
+    #[test]
+    fn a_failed_followup_is_reported_with_the_created_component() {
+        let mock: MockPte = mock().with_endpoint("GET", "/component/", EndpointConfig::new(404, "not found"));
+        let client: PteClient = mock.client().with_component_wait(Some(ComponentWait {
+            timeout: Duration::from_secs(10),
+            poll: Duration::from_millis(20),
+        }));
+        let key: EcdsaPrivateKey = private_key();
+
+        // The instantiation has been submitted once the component is waited for, so the follow-ups
+        // which come after it fail.
+        let outcome: Result<ChainedCalls, ChainError> = thread::scope(|scope| {
+            scope.spawn(|| {
+                while mock.request_count("GET", "/component/") == 0 {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                mock.set_endpoint("POST", "/transaction", receipt_endpoint(&failed_transfer_receipt()));
+                mock.set_endpoint(
+                    "GET",
+                    "/component/",
+                    EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap()),
+                );
+            });
+            client.chain(&instantiation(), followup(), (&key).into(), false)
+        });
+
+        match outcome {
+            Err(ChainError::FollowupFailed {
+                component,
+                index,
+                method,
+                receipts,
+                error,
+            }) => {
+                assert_eq!(component, account());
+                assert_eq!((index, method.as_str()), (0, "restock"));
+                assert_eq!(receipts.len(), 1);
+                assert!(receipts[0].is_success());
+                assert!(matches!(*error, FollowupError::Failed(_)));
+            }
+            other => panic!("expected the first follow-up to fail, got {:?}", other),
+        }
+        assert_eq!(mock.request_count("POST", "/transaction"), 2);
+    }
+}
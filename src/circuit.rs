@@ -0,0 +1,271 @@
+//! A circuit breaker around the transport to the PTE. When the PTE is down every request would
+//! otherwise wait out its full timeout; after enough consecutive transport failures the circuit
+//! opens and requests fail fast until a probe after the cool-down shows that the PTE recovered.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::clock::{system_clock, Clock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitConfig {
+    /// The number of consecutive transport failures which opens the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a probe request is let through.
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go through.
+    Closed,
+    /// Requests fail fast until the cool-down is over.
+    Open,
+    /// A single probe request is in flight to test whether the PTE recovered.
+    HalfOpen,
+}
+
+/// The error returned instead of sending a request while the circuit is open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitOpen {
+    /// How long until a probe request will be let through.
+    pub retry_after: Duration,
+}
+
+/// A snapshot of the metrics of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitMetrics {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub opened: u64,
+    pub half_opened: u64,
+    pub closed: u64,
+    /// The requests which failed fast because the circuit was open.
+    pub rejected: u64,
+}
+
+type TransitionListener = Box<dyn Fn(CircuitState, CircuitState) + Send + Sync>;
+
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    metrics: CircuitMetrics,
+}
+
+/// A circuit breaker which can be shared between clients through an `Arc`, so that all of them see
+/// the same state of the PTE.
+pub struct CircuitBreaker {
+    config: CircuitConfig,
+    clock: Arc<dyn Clock>,
+    state: Mutex<BreakerState>,
+    listeners: Vec<TransitionListener>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitConfig) -> Self {
+        Self {
+            config,
+            clock: system_clock(),
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                metrics: CircuitMetrics {
+                    state: CircuitState::Closed,
+                    consecutive_failures: 0,
+                    opened: 0,
+                    half_opened: 0,
+                    closed: 0,
+                    rejected: 0,
+                },
+            }),
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Replaces the clock used to time the cool-down.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Registers a listener called with the old and the new state on every state transition.
+    pub fn on_transition(mut self, listener: impl Fn(CircuitState, CircuitState) + Send + Sync + 'static) -> Self {
+        self.listeners.push(Box::new(listener));
+        self
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state.lock().unwrap().state
+    }
+
+    pub fn metrics(&self) -> CircuitMetrics {
+        let state = self.state.lock().unwrap();
+        CircuitMetrics {
+            state: state.state,
+            consecutive_failures: state.consecutive_failures,
+            ..state.metrics
+        }
+    }
+
+    /// Checks whether a request may be sent. Once the cool-down of an open circuit is over, the
+    /// first caller is let through as the probe and the circuit becomes half-open.
+    pub fn before_request(&self) -> Result<(), CircuitOpen> {
+        let mut state = self.state.lock().unwrap();
+        match state.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let open_for: Duration = state
+                    .opened_at
+                    .map(|x| self.clock.now().saturating_duration_since(x))
+                    .unwrap_or_default();
+                if open_for >= self.config.cool_down {
+                    self.transition(&mut state, CircuitState::HalfOpen);
+                    Ok(())
+                } else {
+                    state.metrics.rejected += 1;
+                    Err(CircuitOpen {
+                        retry_after: self.config.cool_down - open_for,
+                    })
+                }
+            }
+            CircuitState::HalfOpen => {
+                state.metrics.rejected += 1;
+                Err(CircuitOpen {
+                    retry_after: Duration::ZERO,
+                })
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        if state.state != CircuitState::Closed {
+            self.transition(&mut state, CircuitState::Closed);
+        }
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        let should_open: bool = match state.state {
+            CircuitState::Closed => state.consecutive_failures >= self.config.failure_threshold,
+            // A failed probe opens the circuit for another cool-down.
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if should_open {
+            state.opened_at = Some(self.clock.now());
+            self.transition(&mut state, CircuitState::Open);
+        }
+    }
+
+    fn transition(&self, state: &mut BreakerState, to: CircuitState) {
+        let from: CircuitState = state.state;
+        state.state = to;
+        match to {
+            CircuitState::Open => state.metrics.opened += 1,
+            CircuitState::HalfOpen => state.metrics.half_opened += 1,
+            CircuitState::Closed => state.metrics.closed += 1,
+        }
+        for listener in self.listeners.iter() {
+            listener(from, to);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing", feature = "blocking-http"))]
+mod tests {
+    use super::*;
+
+    use scrypto::prelude::*;
+
+    use crate::client::{PoolConfig, PteClient};
+    use crate::clock::TestClock;
+    use crate::fixtures::{account_envelope, ACCOUNT};
+    use crate::mock::{EndpointConfig, LatencyDistribution, MockPte};
+    use crate::PteApiError;
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::from_str(ACCOUNT).unwrap()
+    }
+
+    fn healthy() -> EndpointConfig {
+        EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap())
+    }
+
+    /// Answers long after the client gave up on the request, which the client sees as a transport
+    /// failure.
+    fn hanging() -> EndpointConfig {
+        healthy().latency(LatencyDistribution::Fixed(Duration::from_secs(2)))
+    }
+
+    #[test]
+    fn the_circuit_opens_half_opens_and_closes_against_a_failing_pte() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let transitions: Arc<Mutex<Vec<(CircuitState, CircuitState)>>> = Arc::new(Mutex::new(Vec::new()));
+        let recorded: Arc<Mutex<Vec<(CircuitState, CircuitState)>>> = transitions.clone();
+        let circuit_breaker: Arc<CircuitBreaker> = Arc::new(
+            CircuitBreaker::new(CircuitConfig {
+                failure_threshold: 2,
+                cool_down: Duration::from_secs(30),
+            })
+            .with_clock(clock.clone())
+            .on_transition(move |from, to| recorded.lock().unwrap().push((from, to))),
+        );
+        let mock: MockPte = MockPte::start(0).unwrap().with_endpoint("GET", "/component", hanging());
+        let client: PteClient = mock
+            .client()
+            .with_pool_config(PoolConfig {
+                request_timeout: Some(Duration::from_millis(200)),
+                ..PoolConfig::default()
+            })
+            .with_circuit_breaker(circuit_breaker.clone());
+
+        // Consecutive transport failures open the circuit, after which requests fail fast
+        for _ in 0..2 {
+            assert!(matches!(client.get_component(account()), Err(PteApiError::HttpRequestError(_))));
+        }
+        assert_eq!(circuit_breaker.state(), CircuitState::Open);
+        assert!(matches!(client.get_component(account()), Err(PteApiError::CircuitOpen(_))));
+        assert_eq!(mock.request_count("GET", "/component"), 2);
+
+        // A probe failing after the cool-down opens the circuit for another cool-down
+        clock.advance(Duration::from_secs(30));
+        assert!(matches!(client.get_component(account()), Err(PteApiError::HttpRequestError(_))));
+        assert_eq!(circuit_breaker.state(), CircuitState::Open);
+        assert!(matches!(client.get_component(account()), Err(PteApiError::CircuitOpen(_))));
+
+        // Once the PTE recovered the probe closes the circuit and requests go through again
+        mock.set_endpoint("GET", "/component", healthy());
+        clock.advance(Duration::from_secs(30));
+        assert!(client.get_component(account()).is_ok());
+        assert_eq!(circuit_breaker.state(), CircuitState::Closed);
+        assert!(client.get_component(account()).is_ok());
+        assert_eq!(mock.request_count("GET", "/component"), 5);
+
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![
+                (CircuitState::Closed, CircuitState::Open),
+                (CircuitState::Open, CircuitState::HalfOpen),
+                (CircuitState::HalfOpen, CircuitState::Open),
+                (CircuitState::Open, CircuitState::HalfOpen),
+                (CircuitState::HalfOpen, CircuitState::Closed),
+            ]
+        );
+        let metrics: CircuitMetrics = circuit_breaker.metrics();
+        assert_eq!((metrics.opened, metrics.half_opened, metrics.closed, metrics.rejected), (2, 2, 1, 2));
+        assert_eq!(metrics.consecutive_failures, 0);
+    }
+}
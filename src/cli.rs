@@ -0,0 +1,817 @@
+//! The commands of the command line interface. Running the binary without a command runs the main
+//! example instead.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::account_import::{import_account, ExtensionExport, ImportError};
+use crate::address_book::{annotate_manifest, AddressBook};
+use crate::bootstrap::{bootstrap_with_progress, BootstrapConfig, BootstrapError, BootstrapSession};
+use crate::bridge::{Bridge, BridgeConfig, BridgeOperation};
+use crate::builder::TransactionBuilderExt;
+use crate::client::{PteClient, DEFAULT_CANDIDATE_URLS, DEFAULT_PTE_URL};
+use crate::confirm::{confirm, ConfirmationPolicy, Operation};
+use crate::credentials::Credentials;
+use crate::denomination::{Denomination, DenominationError};
+use crate::deployment::{verify_deployment, DeploymentReport};
+use crate::epoch_clock::{describe_duration, EpochClock, EpochLengthEstimate};
+use crate::faucet::{FaucetLimiter, FaucetLimiterConfig};
+use crate::hooks::Hooks;
+use crate::intent::{EpochsRemaining, EPOCH_LENGTH};
+use crate::keystore::Keystore;
+use crate::progress::BarProgress;
+use crate::queue::ExecuteOptions;
+use crate::report::{parse_tag, timed, BatchReport, RunReport, TransactionSummary};
+use crate::{conformance, fixtures, test_package, test_vectors, Receipt, TransactionSubmissionError};
+
+/// The arguments of a command split into positional arguments, flags and options with values.
+pub struct CliArgs {
+    pub positional: Vec<String>,
+    pub flags: HashSet<String>,
+    pub options: HashMap<String, Vec<String>>,
+}
+
+impl CliArgs {
+    /// Parses the arguments, treating `--name value` (or `--name=value`) as an option when `name`
+    /// is one of `option_names` and as a flag otherwise.
+    pub fn parse(args: &[String], option_names: &[&str]) -> Self {
+        let mut cli_args: CliArgs = CliArgs {
+            positional: Vec::new(),
+            flags: HashSet::new(),
+            options: HashMap::new(),
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => match name.split_once('=') {
+                    Some((name, value)) => cli_args.options.entry(name.to_string()).or_default().push(value.to_string()),
+                    None if option_names.contains(&name) => {
+                        let value: String = args.next().cloned().unwrap_or_default();
+                        cli_args.options.entry(name.to_string()).or_default().push(value);
+                    }
+                    None => {
+                        cli_args.flags.insert(name.to_string());
+                    }
+                },
+                None => cli_args.positional.push(arg.clone()),
+            }
+        }
+
+        cli_args
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// The last value given to the option, if it was given.
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|x| x.last()).map(String::as_str)
+    }
+
+    /// All of the values given to an option which can be repeated.
+    pub fn options(&self, name: &str) -> Vec<&str> {
+        self.options
+            .get(name)
+            .map(|x| x.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// The address book given through `--address-book`, or `address-book.json` by default. Exits with
+    /// 65 if it is not a valid address book and with 74 if it can't be read.
+    pub fn address_book(&self) -> AddressBook {
+        let path: &str = self.option("address-book").unwrap_or(DEFAULT_ADDRESS_BOOK_FILE);
+        match AddressBook::load(Path::new(path)) {
+            Ok(book) => book,
+            Err(error) => {
+                eprintln!("Failed to load the address book {}: {}", path, error);
+                std::process::exit(match error.kind() {
+                    std::io::ErrorKind::InvalidData => 65,
+                    _ => 74,
+                });
+            }
+        }
+    }
+
+    /// The client for the PTE given through `--url`, or PTE01 by default.
+    pub fn client(&self) -> PteClient {
+        self.configure(PteClient::new(self.option("url").unwrap_or(DEFAULT_PTE_URL)))
+    }
+
+    /// The configuration file given through `--config`, or `pte.json` in the working directory.
+    pub fn config(&self) -> CliConfig {
+        let path: &Path = Path::new(self.option("config").unwrap_or(DEFAULT_CONFIG_FILE));
+        match CliConfig::load(path) {
+            Ok(config) => config,
+            Err(error) => {
+                eprintln!("Failed to load the configuration in {}: {}", path.display(), error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Applies the configuration file to the client, registering its default identity as the
+    /// default signer. The warnings of the client are printed to standard error.
+    fn configure(&self, client: PteClient) -> PteClient {
+        let client: PteClient = client.with_hooks(Hooks::new().on_warning(|warning| eprintln!("Warning: {:?}", warning)));
+        match self.config().default_identity {
+            Some(ref path) => match load_private_key(Path::new(path)) {
+                Ok(private_key) => client.with_default_signer(private_key),
+                Err(error) => {
+                    eprintln!("Failed to load the default identity {}: {}", path, error);
+                    std::process::exit(2);
+                }
+            },
+            None => client,
+        }
+    }
+
+    /// When dangerous operations are confirmed: never with `--yes`, and otherwise in terminals or
+    /// when `--require-confirmation` is given.
+    pub fn confirmation_policy(&self) -> ConfirmationPolicy {
+        ConfirmationPolicy {
+            yes: self.flag("yes"),
+            require_confirmation: self.flag("require-confirmation"),
+            interactive: std::io::stdout().is_terminal(),
+        }
+    }
+
+    /// The denomination given through `--denomination`, or the one of the configuration.
+    pub fn denomination(&self) -> Denomination {
+        let denomination: Result<Denomination, DenominationError> = match self.option("denomination") {
+            Some(denomination) => Denomination::from_str(denomination),
+            None => Ok(self.config().denomination),
+        };
+        denomination.unwrap_or_else(|error| {
+            eprintln!("Invalid denomination: {:?}", error);
+            std::process::exit(2);
+        })
+    }
+
+    /// The key given through `--key`, or the default identity of the configuration.
+    fn signer(&self, client: &PteClient) -> EcdsaPrivateKey {
+        if let Some(path) = self.option("key") {
+            return load_private_key(Path::new(path)).unwrap_or_else(|error| {
+                eprintln!("Failed to load the key {}: {}", path, error);
+                std::process::exit(2);
+            });
+        }
+        match client.resolve_signer(Credentials::Default) {
+            Ok(private_key) => private_key.clone(),
+            Err(_) => {
+                eprintln!("No key to sign with: pass --key or set default_identity in the configuration");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Prompts for the confirmation of the operation when it is dangerous, exiting if it is refused.
+    fn confirm(&self, operation: &Operation) {
+        let confirmed: bool = confirm(
+            operation,
+            &self.confirmation_policy(),
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+        )
+        .unwrap_or(false);
+        if !confirmed {
+            eprintln!("Aborted");
+            std::process::exit(1);
+        }
+    }
+
+    /// The client for the first healthy server among the candidates given through repeated `--url`
+    /// options, or the default candidates. The selected server is persisted in the session file
+    /// given through `--session`, or `DEFAULT_SERVER_SESSION_FILE`, so that later runs stay on it.
+    /// Exits when none of them is healthy. Reads only fail over to the other candidates mid-run when
+    /// `--allow-midrun-failover` is passed, and submissions always stay on the server of the session.
+    pub fn healthy_client(&self) -> PteClient {
+        let mut candidates: Vec<&str> = self.options("url");
+        if candidates.is_empty() {
+            candidates = DEFAULT_CANDIDATE_URLS.to_vec();
+        }
+        let session: &Path = Path::new(self.option("session").unwrap_or(DEFAULT_SERVER_SESSION_FILE));
+        match PteClient::first_healthy_in_session(&candidates, self.flag("allow-midrun-failover"), session) {
+            Ok(Some(client)) => {
+                println!("Using the PTE at {} (recorded in {})", client.base_url(), session.display());
+                self.configure(client)
+            }
+            Ok(None) => {
+                eprintln!("None of the PTE servers are healthy, or the server of {} is down: {:?}", session.display(), candidates);
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("Failed to read or write the session file {}: {}", session.display(), error);
+                std::process::exit(74);
+            }
+        }
+    }
+}
+
+/// The file the server selected by `example` is persisted in when no `--session` is given.
+pub const DEFAULT_SERVER_SESSION_FILE: &str = "pte-session.json";
+
+/// The configuration file read when no `--config` is given.
+pub const DEFAULT_CONFIG_FILE: &str = "pte.json";
+
+/// The address book read when no `--address-book` is given.
+pub const DEFAULT_ADDRESS_BOOK_FILE: &str = "address-book.json";
+
+/// The settings shared by all of the commands, read from a JSON configuration file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CliConfig {
+    /// The file holding the hex encoded private key used as the default signer, such as one of the
+    /// keys written by `bootstrap`.
+    pub default_identity: Option<String>,
+    /// The denomination amounts are written in by the commands, unless they are given another one
+    /// through `--denomination`.
+    #[serde(default)]
+    pub denomination: Denomination,
+}
+
+impl CliConfig {
+    /// Loads the configuration, returning the default configuration if the file does not exist.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Loads a hex encoded private key from a file.
+fn load_private_key(path: &Path) -> std::io::Result<EcdsaPrivateKey> {
+    hex::decode(std::fs::read_to_string(path)?.trim())
+        .ok()
+        .and_then(|bytes| EcdsaPrivateKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "not a hex encoded private key"))
+}
+
+/// Runs the command given as the first argument.
+pub fn run(args: &[String]) {
+    let command: &str = args[0].as_str();
+    let args: &[String] = &args[1..];
+    match command {
+        "beginner" => run_beginner(&CliArgs::parse(args, &["url"])),
+        "bootstrap" => run_bootstrap(&CliArgs::parse(args, &["url", "config", "accounts", "token", "out"])),
+        "bridge" => run_bridge(&CliArgs::parse(args, &["url", "config", "listen", "allow", "address-book"])),
+        "annotate" => run_annotate(&CliArgs::parse(args, &["address-book"])),
+        "conformance" => run_conformance(&CliArgs::parse(args, &[])),
+        "import-account" => run_import_account(&CliArgs::parse(args, &["url", "config", "account", "label", "keystore", "address-book"])),
+        "epoch" => run_epoch(&CliArgs::parse(args, &["url", "config", "clock", "target", "expires-at", "in-minutes"])),
+        "example" => run_example(&CliArgs::parse(args, &["url", "config", "session", "denomination", "report", "report-json"])),
+        "receipt" => run_receipt(&CliArgs::parse(args, &["address-book"])),
+        "receipts" => run_receipts(&CliArgs::parse(args, &["tag", "config", "denomination"])),
+        "sweep" => run_sweep(&CliArgs::parse(args, &["url", "config", "to", "key", "denomination", "report", "report-json"])),
+        "transfer" => run_transfer(&CliArgs::parse(args, &["url", "config", "from", "to", "resource", "amount", "denomination", "key", "report", "report-json"])),
+        "test-vectors" => run_test_vectors(&CliArgs::parse(args, &[])),
+        "verify-deployment" => run_verify_deployment(&CliArgs::parse(args, &["url", "config", "report-json"])),
+        "wait" => run_wait(&CliArgs::parse(args, &["url", "config", "timeout", "poll"])),
+        _ => {
+            eprintln!("Unknown command: {}", command);
+            eprintln!("Usage: pte [annotate <manifest file> [--address-book <file.json>] | beginner [--url <url>] | bootstrap [--accounts <n>] [--token <SYMBOL:supply>] [--out <directory>] | bridge [--listen <address>] [--allow <operation>]... [--address-book <file.json>] | conformance [<directory>] [--check] | epoch [--clock <file.json>] [--target <epoch>] [--expires-at <epoch>] [--in-minutes <minutes>] | example [--url <url>]... [--allow-midrun-failover] [--session <file.json>] [--config <file.json>] [--denomination <whole|attos>] [--report <file.md>] [--report-json <file.json>] | import-account <export file> --label <label> [--account <address>] [--keystore <directory>] [--address-book <file.json>] | receipt <receipt.json> [--annotated] [--address-book <file.json>] | receipts <report.json> [--tag <key=value>]... [--denomination <whole|attos>] | sweep <account> --to <account> [--key <key file>] [--denomination <whole|attos>] [--yes] [--require-confirmation] [--report <file.md>] [--report-json <file.json>] | test-vectors <directory> [--check] | transfer --from <account> --to <account> --resource <address> --amount <amount> [--denomination <whole|attos>] [--key <key file>] [--yes] [--require-confirmation] [--report <file.md>] [--report-json <file.json>] | verify-deployment [--url <url>] [--report-json <file.json>] | wait <address> [--timeout <seconds>] [--poll <seconds>]]");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Runs the main example on the first healthy server of the candidates given through `--url` (PTE01
+/// and then PTE02 by default), writing a report of the run when `--report` or `--report-json` is
+/// given.
+fn run_example(args: &CliArgs) {
+    let client: PteClient = args.healthy_client();
+    match crate::example::run_example(&client) {
+        Ok(batch) => write_reports(args, &RunReport::from_batch(&batch.with_denomination(args.denomination()))),
+        Err(error) => {
+            eprintln!("The example failed ({:?}): {:?}", error.classification(), error);
+            std::process::exit(error.exit_code());
+        }
+    }
+}
+
+/// Runs the beginner example on the server given through `--url`, or PTE01 by default.
+fn run_beginner(args: &CliArgs) {
+    if let Err(error) = crate::example::run_beginner_example(args.option("url").unwrap_or(DEFAULT_PTE_URL)) {
+        eprintln!("The beginner example failed: {:?}", error);
+        std::process::exit(1);
+    }
+}
+
+/// Lists the transactions of the JSON report written through `--report-json` which were submitted
+/// with all of the tags given through repeated `--tag key=value` options.
+fn run_receipts(args: &CliArgs) {
+    let usage = || -> ! {
+        eprintln!("Usage: pte receipts <report.json> [--tag <key=value>]... [--denomination <whole|attos>]");
+        std::process::exit(2);
+    };
+    let path: &str = args.positional.first().map(String::as_str).unwrap_or_else(|| usage());
+    let tags: BTreeMap<String, String> = args
+        .options("tag")
+        .into_iter()
+        .map(|tag| parse_tag(tag).unwrap_or_else(|| usage()))
+        .collect();
+
+    let report: RunReport = match std::fs::read_to_string(path).map(|x| RunReport::from_json(&x)) {
+        Ok(Ok(report)) => report,
+        Ok(Err(error)) => {
+            eprintln!("{} is not a JSON report: {}", path, error);
+            std::process::exit(65);
+        }
+        Err(error) => {
+            eprintln!("Failed to read the report {}: {}", path, error);
+            std::process::exit(74);
+        }
+    };
+    let transactions: Vec<&TransactionSummary> = report.transactions_tagged(&tags);
+    for transaction in transactions.iter() {
+        let tags: Vec<String> = transaction.tags.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        println!(
+            "{} {} {} [{}]",
+            transaction.transaction_hash.as_deref().unwrap_or("-"),
+            transaction.status,
+            transaction.label,
+            tags.join(", ")
+        );
+    }
+    println!("{} of {} transactions", transactions.len(), report.transactions.len());
+    if let Ok(faucet_xrd) = report.faucet_xrd.amount() {
+        let denomination: Denomination = args.denomination();
+        println!("XRD from the faucet: {} ({})", denomination.format(faucet_xrd), denomination);
+    }
+}
+
+/// Prints the receipt in the given JSON file. With `--annotated` the addresses which have a label in
+/// the address book given through `--address-book` are shown as `label (abbreviated address)`; the
+/// file itself is left unchanged.
+fn run_receipt(args: &CliArgs) {
+    let path: &str = match args.positional.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: pte receipt <receipt.json> [--annotated] [--address-book <file.json>]");
+            std::process::exit(2);
+        }
+    };
+    let receipt: Receipt = match std::fs::read_to_string(path).map(|x| serde_json::from_str::<Receipt>(&x)) {
+        Ok(Ok(receipt)) => receipt,
+        Ok(Err(error)) => {
+            eprintln!("{} is not a receipt: {}", path, error);
+            std::process::exit(65);
+        }
+        Err(error) => {
+            eprintln!("Failed to read the receipt {}: {}", path, error);
+            std::process::exit(74);
+        }
+    };
+    let book: AddressBook = match args.flag("annotated") {
+        true => args.address_book(),
+        false => AddressBook::new(),
+    };
+    println!("{}", receipt.to_annotated_string(&book));
+}
+
+/// Writes the Markdown and JSON renderings of the report to the files given through `--report` and
+/// `--report-json`, exiting with 74 if either of them can't be written.
+pub fn write_reports(args: &CliArgs, report: &RunReport) {
+    for (option, what, content) in [
+        ("report", "report", report.to_markdown()),
+        ("report-json", "JSON report", report.to_json()),
+    ] {
+        if let Some(path) = args.option(option) {
+            write_or_exit(path, what, &content);
+        }
+    }
+}
+
+/// Writes the file, exiting with 74 if it can't be written.
+fn write_or_exit(path: &str, what: &str, content: &str) {
+    if let Err(error) = std::fs::write(path, content) {
+        eprintln!("Failed to write the {} to {}: {}", what, path, error);
+        std::process::exit(74);
+    }
+    println!("Wrote the {} to {}", what, path);
+}
+
+/// The environment variable holding the shared secret of `pte bridge`, kept out of the arguments so
+/// that it doesn't show up in the list of processes.
+pub const BRIDGE_SECRET_VARIABLE: &str = "PTE_BRIDGE_SECRET";
+
+/// Serves the client over HTTP on the address given through `--listen` until the process is stopped.
+/// Only the read-only operations are allowed unless more are given through repeated `--allow`
+/// options, and the labels of the address book given through `--address-book` are accepted in place
+/// of addresses.
+fn run_bridge(args: &CliArgs) {
+    let usage = || -> ! {
+        eprintln!("Usage: {}=<secret> pte bridge [--listen <address>] [--allow <execute|balances|receipt>]... [--address-book <file.json>]", BRIDGE_SECRET_VARIABLE);
+        std::process::exit(2);
+    };
+    let secret: String = match std::env::var(BRIDGE_SECRET_VARIABLE) {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => usage(),
+    };
+    let mut config: BridgeConfig = BridgeConfig::new(&secret);
+    for name in args.options("allow") {
+        match BridgeOperation::from_name(name) {
+            Some(operation) => config = config.allow(operation),
+            None => usage(),
+        }
+    }
+    if let Some(path) = args.option("address-book") {
+        match AddressBook::load(Path::new(path)) {
+            Ok(address_book) => config = config.with_address_book(address_book),
+            Err(error) => {
+                eprintln!("Failed to load the address book {}: {}", path, error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let listen: &str = args.option("listen").unwrap_or("127.0.0.1:9050");
+    let allowed: BTreeSet<BridgeOperation> = config.allowed.clone();
+    match Bridge::start(Arc::new(args.client()), config, listen) {
+        Ok(bridge) => {
+            println!("Serving {:?} on {}", allowed, bridge.url());
+            bridge.wait();
+        }
+        Err(error) => {
+            eprintln!("Failed to listen on {}: {}", listen, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Creates a demo environment of funded accounts and a distributed token in the directory given
+/// through `--out`, or resumes or verifies the one already there, and prints a summary of it.
+fn run_bootstrap(args: &CliArgs) {
+    let usage = || -> ! {
+        eprintln!("Usage: pte bootstrap [--accounts <n>] [--token <SYMBOL:supply>] [--out <directory>]");
+        std::process::exit(2);
+    };
+    let accounts: usize = match args.option("accounts").unwrap_or("5").parse() {
+        Ok(accounts) if accounts > 0 => accounts,
+        _ => usage(),
+    };
+    let (token_symbol, token_supply) = match args
+        .option("token")
+        .unwrap_or("DEMO:1000000")
+        .split_once(':')
+        .map(|(symbol, supply)| (symbol, Decimal::from_str(supply)))
+    {
+        Some((symbol, Ok(supply))) if !symbol.is_empty() => (symbol.to_string(), supply),
+        _ => usage(),
+    };
+    let config: BootstrapConfig = BootstrapConfig {
+        accounts,
+        token_symbol,
+        token_supply,
+        out: PathBuf::from(args.option("out").unwrap_or("demo-env")),
+    };
+
+    let client: PteClient = args
+        .client()
+        .with_faucet_limiter(Arc::new(FaucetLimiter::new(FaucetLimiterConfig::default())));
+    let progress: BarProgress = BarProgress::new();
+    let outcome = bootstrap_with_progress(&client, &config, &progress);
+    progress.finish();
+    match outcome {
+        Ok(session) => print!("{}", session.summary_table(&config.out)),
+        Err(error) => {
+            eprintln!("The bootstrap failed: {:?}", error);
+            if let Ok(Some(session)) = BootstrapSession::load(&config.out) {
+                eprint!("{}", session.summary_table(&config.out));
+                eprintln!("\nRun the same command again to resume.");
+            }
+            match error {
+                BootstrapError::SubmissionFailed { error, .. } => std::process::exit(error.exit_code()),
+                _ => std::process::exit(1),
+            }
+        }
+    }
+}
+
+/// Imports an account exported by the browser extension, given as a file holding either the hex of
+/// the private key or the JSON export, into the keystore given through `--keystore` and the address
+/// book given through `--address-book`.
+fn run_import_account(args: &CliArgs) {
+    let (export, label) = match (args.positional.first().map(std::fs::read_to_string), args.option("label")) {
+        (Some(Ok(export)), Some(label)) if !label.is_empty() => (export, label),
+        _ => {
+            eprintln!("Usage: pte import-account <export file> --label <label> [--account <address>] [--keystore <directory>] [--address-book <file.json>]");
+            std::process::exit(2);
+        }
+    };
+    let account: Option<ComponentAddress> = match args.option("account").map(ComponentAddress::from_str) {
+        Some(Ok(account)) => Some(account),
+        Some(Err(_)) => {
+            eprintln!("Invalid account address: {}", args.option("account").unwrap());
+            std::process::exit(2);
+        }
+        None => None,
+    };
+    let keystore: Keystore = Keystore::new(args.option("keystore").unwrap_or("keys"));
+    let address_book: &Path = Path::new(args.option("address-book").unwrap_or(DEFAULT_ADDRESS_BOOK_FILE));
+
+    let outcome = ExtensionExport::parse(&export)
+        .and_then(|export| import_account(&args.client(), &export, account, label, &keystore, address_book));
+    match outcome {
+        Ok(imported) => println!(
+            "Imported {} as {}, key saved to {}",
+            imported.address,
+            imported.label,
+            keystore.path_of(&imported.label).display()
+        ),
+        Err(ImportError::KeyDoesNotControlAccount { derived_badge, on_ledger_rule }) => {
+            eprintln!("The key does not control the account: its virtual badge {} is not required by the withdraw rule {}", derived_badge, on_ledger_rule);
+            std::process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("The import failed: {:?}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Transfers an amount of a resource between two accounts, confirming large amounts first.
+fn run_transfer(args: &CliArgs) {
+    let parsed = (
+        args.option("from").map(ComponentAddress::from_str),
+        args.option("to").map(ComponentAddress::from_str),
+        args.option("resource").map(ResourceAddress::from_str),
+        args.option("amount").map(|amount| args.denomination().parse(amount)),
+    );
+    let (from, to, resource, amount) = match parsed {
+        (Some(Ok(from)), Some(Ok(to)), Some(Ok(resource)), Some(Ok(amount))) => (from, to, resource, amount),
+        _ => {
+            eprintln!("Usage: pte transfer --from <account> --to <account> --resource <address> --amount <amount> [--denomination <whole|attos>] [--key <key file>] [--yes] [--require-confirmation]");
+            std::process::exit(2);
+        }
+    };
+    let client: PteClient = args.client();
+    let private_key: EcdsaPrivateKey = args.signer(&client);
+
+    args.confirm(&Operation {
+        name: "Transfer".to_string(),
+        server: client.base_url().to_string(),
+        accounts: vec![from.to_string()],
+        resources: vec![(resource.to_string(), Some(amount))],
+        target: Some(to.to_string()),
+        denomination: args.denomination(),
+    });
+    let transaction: SignedTransaction = TransactionBuilder::new()
+        .withdraw_from_account_by_amount(amount, resource, from)
+        .call_method_with_all_resources(to, "deposit_batch")
+        .build(client.next_nonce())
+        .sign([&private_key]);
+    submit_and_report(args, &client, "transfer", &transaction);
+}
+
+/// Moves everything the account holds to another account, always confirming first.
+fn run_sweep(args: &CliArgs) {
+    let parsed = (
+        args.positional.first().map(|x| ComponentAddress::from_str(x)),
+        args.option("to").map(ComponentAddress::from_str),
+    );
+    let (from, to) = match parsed {
+        (Some(Ok(from)), Some(Ok(to))) => (from, to),
+        _ => {
+            eprintln!("Usage: pte sweep <account> --to <account> [--key <key file>] [--denomination <whole|attos>] [--yes] [--require-confirmation]");
+            std::process::exit(2);
+        }
+    };
+    let client: PteClient = args.client();
+    let private_key: EcdsaPrivateKey = args.signer(&client);
+    let resources: Vec<ResourceAddress> = match client.get_vaults(from) {
+        Ok(vaults) => vaults
+            .iter()
+            .filter_map(|x| ResourceAddress::from_str(&x.resource).ok())
+            .collect::<BTreeSet<ResourceAddress>>()
+            .into_iter()
+            .collect(),
+        Err(error) => {
+            eprintln!("Failed to list the vaults of {}: {:?}", from, error);
+            std::process::exit(1);
+        }
+    };
+    if resources.is_empty() {
+        println!("{} holds nothing to sweep", from);
+        return;
+    }
+
+    args.confirm(&Operation {
+        name: "Sweep".to_string(),
+        server: client.base_url().to_string(),
+        accounts: vec![from.to_string()],
+        resources: resources.iter().map(|x| (x.to_string(), None)).collect(),
+        target: Some(to.to_string()),
+        denomination: args.denomination(),
+    });
+    let transaction: SignedTransaction = TransactionBuilder::new()
+        .sweep(from, &resources, to)
+        .build(client.next_nonce())
+        .sign([&private_key]);
+    submit_and_report(args, &client, "sweep", &transaction);
+}
+
+/// Submits the transaction, writing a report of it when `--report` or `--report-json` is given.
+fn submit_and_report(args: &CliArgs, client: &PteClient, label: &str, transaction: &SignedTransaction) {
+    let mut batch: BatchReport = BatchReport::with_clock(client.clock().clone()).with_denomination(args.denomination());
+    let options: ExecuteOptions = ExecuteOptions::new().tag("flow", label);
+    let outcome: Result<Receipt, TransactionSubmissionError> = timed(&mut batch, label, &options, transaction, |transaction, _| {
+        client.submit_transaction(transaction)
+    });
+    write_reports(args, &RunReport::from_batch(&batch));
+    match outcome {
+        Ok(receipt) if receipt.is_success() => println!("Committed {}", receipt.transaction_hash),
+        Ok(receipt) => {
+            eprintln!("The transaction failed: {}", receipt.status);
+            std::process::exit(1);
+        }
+        Err(error) => {
+            eprintln!("The submission failed ({:?}): {:?}", error.classification(), error);
+            std::process::exit(error.exit_code());
+        }
+    }
+}
+
+/// Generates the test vectors into the given directory, or checks them against the directory when
+/// `--check` is passed, along with the round-trips of the fixtures and the hash of the test package.
+/// Generating them records the hash of the test package when its WASM was built.
+fn run_test_vectors(args: &CliArgs) {
+    let directory: &Path = Path::new(
+        args.positional
+            .first()
+            .map(String::as_str)
+            .unwrap_or("test-vectors")
+    );
+
+    let vectors = test_vectors::generate_test_vectors(test_vectors::TEST_VECTORS_MODE).unwrap();
+    if args.flag("check") {
+        let mut mismatches: Vec<String> = test_vectors::check_test_vectors(&vectors, directory);
+        mismatches.extend(fixtures::check_round_trips().into_iter().map(|x| format!("{} (fixture round-trip)", x)));
+        mismatches.extend(test_package::check_test_package().into_iter().map(|x| format!("{} (test package)", x)));
+        if !mismatches.is_empty() {
+            eprintln!("Test vectors do not match the checked-in ones: {:?}", mismatches);
+            std::process::exit(1);
+        }
+        println!("All {} test vectors match", vectors.len());
+    } else {
+        test_vectors::write_test_vectors(&vectors, directory).unwrap();
+        println!("Wrote {} test vectors to {}", vectors.len(), directory.display());
+        if let Some(hash) = test_package::record_hash().unwrap() {
+            println!("Recorded the hash {} of the test package", hash);
+        }
+    }
+}
+
+/// Prints the manifest in the given file with the labels of the addresses in the address book given
+/// through `--address-book` inlined. The file itself is left unchanged.
+fn run_annotate(args: &CliArgs) {
+    let path: &str = match args.positional.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: pte annotate <manifest file> [--address-book <file.json>]");
+            std::process::exit(2);
+        }
+    };
+    let manifest: String = match std::fs::read_to_string(path) {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            eprintln!("Failed to read the manifest {}: {}", path, error);
+            std::process::exit(74);
+        }
+    };
+    print!("{}", annotate_manifest(&manifest, &args.address_book()));
+}
+
+/// Regenerates the outputs of the conformance scenarios, or checks them byte for byte against the
+/// checked-in outputs when `--check` is passed.
+fn run_conformance(args: &CliArgs) {
+    let directory: &Path = Path::new(
+        args.positional
+            .first()
+            .map(String::as_str)
+            .unwrap_or("conformance")
+    );
+
+    if args.flag("check") {
+        let mismatches: Vec<String> = conformance::check_outputs(directory).unwrap();
+        if !mismatches.is_empty() {
+            eprintln!("Conformance outputs differ for: {:?}", mismatches);
+            std::process::exit(1);
+        }
+        println!("All conformance outputs match");
+    } else {
+        let count: usize = conformance::write_outputs(directory).unwrap();
+        println!("Regenerated the outputs of {} conformance scenarios", count);
+    }
+}
+
+/// Runs the deployment verification battery against the PTE given through `--url`, printing the
+/// report and writing it as JSON to the file given through `--report-json`. Exits with an error when
+/// any of the steps failed.
+fn run_verify_deployment(args: &CliArgs) {
+    let report: DeploymentReport = verify_deployment(&args.client());
+    println!("{}", report);
+    if let Some(path) = args.option("report-json") {
+        write_or_exit(path, "JSON report", &report.to_json());
+    }
+    if !report.is_success() {
+        std::process::exit(1);
+    }
+}
+
+/// The file the epoch clock of `epoch` is kept in when no `--clock` is given.
+pub const DEFAULT_EPOCH_CLOCK_FILE: &str = "epoch-clock.json";
+
+/// Samples the current epoch into the epoch clock kept in the file given through `--clock`, so that
+/// the estimated epoch length improves with every run, and prints the estimate. `--target` prints
+/// when an epoch starts, `--expires-at` how long until something expiring at an epoch expires, and
+/// `--in-minutes` the epoch to schedule something at so that it happens after that many minutes.
+fn run_epoch(args: &CliArgs) {
+    let path: &Path = Path::new(args.option("clock").unwrap_or(DEFAULT_EPOCH_CLOCK_FILE));
+    let mut epoch_clock: EpochClock = match EpochClock::load(path) {
+        Ok(epoch_clock) => epoch_clock,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => EpochClock::new(EPOCH_LENGTH),
+        Err(error) => {
+            eprintln!("Failed to read the epoch clock {}: {}", path.display(), error);
+            std::process::exit(74);
+        }
+    };
+    let epoch = |name: &str| args.option(name).and_then(|x| x.parse::<u64>().ok());
+
+    let client: PteClient = args.client();
+    let current_epoch: u64 = match client.current_epoch() {
+        Ok(Some(current_epoch)) => current_epoch,
+        Ok(None) => {
+            eprintln!("The PTE at {} has no epoch endpoint", client.base_url());
+            std::process::exit(69);
+        }
+        Err(error) => {
+            eprintln!("Failed to read the current epoch: {:?}", error);
+            std::process::exit(69);
+        }
+    };
+    let now: SystemTime = client.clock().system_time();
+    epoch_clock.observe(current_epoch, now);
+    if let Err(error) = epoch_clock.save(path) {
+        eprintln!("Failed to write the epoch clock {}: {}", path.display(), error);
+        std::process::exit(74);
+    }
+
+    let estimate: EpochLengthEstimate = epoch_clock.estimate();
+    println!(
+        "Epoch {}, epochs last ~{} ({} to {}, from {} transitions)",
+        current_epoch,
+        describe_duration(estimate.mean),
+        describe_duration(estimate.low),
+        describe_duration(estimate.high),
+        estimate.transitions
+    );
+    if let Some(target) = epoch("target") {
+        println!("Time until epoch {}: {}", target, epoch_clock.describe_eta(target, now, current_epoch));
+    }
+    if let Some(expires_at_epoch) = epoch("expires-at") {
+        println!(
+            "Epoch {}: {}",
+            expires_at_epoch,
+            EpochsRemaining(expires_at_epoch.saturating_sub(current_epoch)).describe(&epoch_clock)
+        );
+    }
+    if let Some(minutes) = epoch("in-minutes") {
+        if let Some(at) = now.checked_add(Duration::from_secs(minutes.saturating_mul(60))) {
+            println!("In {} min: schedule at epoch {}", minutes, epoch_clock.schedule_at(at, now, current_epoch));
+        }
+    }
+}
+
+/// Waits for the component with the given address to become visible on the PTE.
+fn run_wait(args: &CliArgs) {
+    let address: ComponentAddress = match args.positional.first().map(|x| ComponentAddress::from_str(x)) {
+        Some(Ok(address)) => address,
+        _ => {
+            eprintln!("Usage: pte wait <component address> [--timeout <seconds>] [--poll <seconds>]");
+            std::process::exit(2);
+        }
+    };
+    let seconds = |name: &str, default: u64| {
+        Duration::from_secs(args.option(name).and_then(|x| x.parse().ok()).unwrap_or(default))
+    };
+
+    match args.client().wait_for_component(address, seconds("timeout", 30), seconds("poll", 1)) {
+        Ok(info) => println!("Component {} exists, blueprint: {:?}", address, info.blueprint),
+        Err(error) => {
+            eprintln!("{:?}", error);
+            std::process::exit(1);
+        }
+    }
+}
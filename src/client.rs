@@ -0,0 +1,859 @@
+use radix_engine::model::{Instruction, SignedTransaction};
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::circuit::{CircuitBreaker, CircuitOpen};
+use crate::clock::{system_clock, Clock};
+use crate::compatibility::detect_dialect_mismatch;
+use crate::component::{ComponentEnvelope, ComponentInfo};
+use crate::deterministic::{DeterministicMode, Randomness};
+use crate::extensions::ReceiptExtension;
+use crate::faucet::{calls_faucet, FaucetLimiter, FaucetPermit};
+use crate::hooks::{Completion, Hooks, TxHashes};
+use crate::idempotency::Idempotency;
+use crate::intent::{PreparedTransaction, DEFAULT_STATIC_VALIDITY_WINDOW};
+use crate::labeled::LabeledAddress;
+use crate::mirror::Mirror;
+use crate::nonce::{NonceManager, NonceOutcome};
+pub use crate::pool::PoolConfig;
+use crate::public_key::PublicKeyFormat;
+use crate::queue::{ExclusiveGuard, ExclusiveLocks, ExclusiveWait, ExecuteOptions, QueueMetrics, SubmissionQueue};
+use crate::scope::verify_manifest_scope;
+use crate::signer_account::SignerAccountResolver;
+use crate::{PteApiError, Receipt, TransactionBody, TransactionSubmissionError};
+
+/// The URL of the PTE01 server which is used when no other URL is given.
+pub const DEFAULT_PTE_URL: &str = "https://pte01.radixdlt.com";
+
+/// The servers tried in order when selecting the first healthy one without being given candidates.
+pub const DEFAULT_CANDIDATE_URLS: [&str; 2] = [DEFAULT_PTE_URL, "https://pte02.radixdlt.com"];
+
+/// The two kinds of requests the client makes. Reads and writes can be routed to different servers,
+/// for example to send reads through a caching proxy while writes go directly to the PTE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Horizon {
+    /// Component, receipt, epoch and resource information requests.
+    Read,
+    /// Transaction submissions.
+    Write,
+}
+
+/// How long to wait for a newly created component to become visible, and how often to poll for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentWait {
+    pub timeout: Duration,
+    pub poll: Duration,
+}
+
+impl Default for ComponentWait {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            poll: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A client used to talk to the API of a PTE server. All of the requests made by the client go
+/// through a single pooled HTTP client so that connections are reused across requests.
+pub struct PteClient {
+    read_urls: Vec<String>,
+    write_urls: Vec<String>,
+    pool_config: PoolConfig,
+    /// Replaced when warming up pins the addresses of the servers.
+    http_client: RwLock<reqwest::blocking::Client>,
+    randomness: Mutex<Randomness>,
+    queue: Option<SubmissionQueue>,
+    mirror: Option<Arc<Mirror>>,
+    nonce_manager: Option<NonceManager>,
+    idempotency: Option<Idempotency>,
+    hooks: Hooks,
+    clock: Arc<dyn Clock>,
+    canonical_wire: bool,
+    public_key_format: PublicKeyFormat,
+    serialize_writes_to: HashSet<ComponentAddress>,
+    exclusive_locks: ExclusiveLocks,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    faucet_limiter: Option<Arc<FaucetLimiter>>,
+    receipt_extensions: Vec<ReceiptExtension>,
+    component_wait: Option<ComponentWait>,
+    signer_account_resolver: Option<Arc<SignerAccountResolver>>,
+    pub(crate) default_signer: Option<Arc<EcdsaPrivateKey>>,
+    pub(crate) default_notary: Option<Arc<EcdsaPrivateKey>>,
+    /// Whether each of the read servers supports previewing transactions, once probed.
+    pub(crate) preview_support: Mutex<HashMap<String, bool>>,
+    /// Whether each of the read servers has an epoch endpoint, once probed.
+    pub(crate) epoch_support: Mutex<HashMap<String, bool>>,
+    /// The window, in epochs, given to intents created while the current epoch is unknown.
+    pub(crate) static_validity_window: u64,
+}
+
+impl PteClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            read_urls: vec![normalize_url(base_url)],
+            write_urls: vec![normalize_url(base_url)],
+            pool_config: PoolConfig::default(),
+            http_client: RwLock::new(PoolConfig::default().build_http_client()),
+            randomness: Mutex::new(Randomness::new(DeterministicMode::Disabled)),
+            queue: None,
+            mirror: None,
+            nonce_manager: None,
+            idempotency: None,
+            hooks: Hooks::new(),
+            clock: system_clock(),
+            canonical_wire: false,
+            public_key_format: PublicKeyFormat::default(),
+            serialize_writes_to: HashSet::new(),
+            exclusive_locks: ExclusiveLocks::new(),
+            circuit_breaker: None,
+            faucet_limiter: None,
+            receipt_extensions: Vec::new(),
+            component_wait: Some(ComponentWait::default()),
+            signer_account_resolver: None,
+            default_signer: None,
+            default_notary: None,
+            preview_support: Mutex::new(HashMap::new()),
+            epoch_support: Mutex::new(HashMap::new()),
+            static_validity_window: DEFAULT_STATIC_VALIDITY_WINDOW,
+        }
+    }
+
+    /// Configures the pool of connections kept open to the PTE servers.
+    pub fn with_pool_config(self, config: PoolConfig) -> Self {
+        Self {
+            pool_config: config,
+            http_client: RwLock::new(config.build_http_client()),
+            ..self
+        }
+    }
+
+    /// Replaces the source of randomness used by the client when generating nonces.
+    pub fn with_deterministic_mode(self, mode: DeterministicMode) -> Self {
+        Self {
+            randomness: Mutex::new(Randomness::new(mode).with_clock(self.clock.clone())),
+            ..self
+        }
+    }
+
+    /// Routes all of the submissions made through `execute` through a bounded submission queue
+    /// with priorities and per-key fairness.
+    pub fn with_submission_queue(self, queue: SubmissionQueue) -> Self {
+        Self {
+            queue: Some(queue),
+            ..self
+        }
+    }
+
+    /// Records all of the receipts, component and resource information the client learns in the
+    /// mirror. Receipts are recorded by the built-in hooks of `Hooks::with_mirror`.
+    pub fn with_mirror(self, mirror: Arc<Mirror>) -> Self {
+        Self {
+            hooks: self.hooks.with_mirror(mirror.clone()),
+            mirror: Some(mirror),
+            ..self
+        }
+    }
+
+    /// Allocates the nonces of the transactions built through `execute_with_nonce` from windows of
+    /// outstanding nonces kept per signing key. Unless it has a seed, the manager starts the counters
+    /// from the time of the client's clock.
+    pub fn with_nonce_manager(self, nonce_manager: NonceManager) -> Self {
+        Self {
+            nonce_manager: Some(nonce_manager.with_clock(self.clock.clone())),
+            ..self
+        }
+    }
+
+    /// Configures the store used by `execute_idempotent` to remember which idempotency keys were
+    /// already executed.
+    pub fn with_idempotency(self, idempotency: Idempotency) -> Self {
+        Self {
+            idempotency: Some(idempotency),
+            ..self
+        }
+    }
+
+    /// Registers the hooks run on the lifecycle events of the submissions made by the client, after
+    /// the hooks already registered, including the built-in ones.
+    pub fn with_hooks(self, hooks: Hooks) -> Self {
+        Self {
+            hooks: self.hooks.extend(hooks),
+            ..self
+        }
+    }
+
+    /// Replaces the clock used by the client when polling and timing requests.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        let randomness: Randomness = self
+            .randomness
+            .into_inner()
+            .unwrap_or_else(|error| error.into_inner())
+            .with_clock(clock.clone());
+        Self {
+            nonce_manager: self.nonce_manager.map(|x| x.with_clock(clock.clone())),
+            clock,
+            randomness: Mutex::new(randomness),
+            ..self
+        }
+    }
+
+    /// Sends transaction bodies to the PTE as canonical JSON, so that the bytes on the wire are the
+    /// same bytes that are hashed and stored locally.
+    pub fn with_canonical_wire(self, canonical_wire: bool) -> Self {
+        Self { canonical_wire, ..self }
+    }
+
+    /// Executes the transactions which call methods of the given components one at a time, while
+    /// everything else stays parallel. Equivalent to passing `exclusive_on` for each of the called
+    /// components in the options of every call to `execute`.
+    pub fn with_serialized_writes(self, serialize_writes_to: HashSet<ComponentAddress>) -> Self {
+        Self {
+            serialize_writes_to,
+            ..self
+        }
+    }
+
+    /// Writes the public keys of the signatures of submitted transactions in the given format, for PTE
+    /// deployments which expect a different one than the default compressed hex.
+    pub fn with_public_key_format(self, public_key_format: PublicKeyFormat) -> Self {
+        Self {
+            public_key_format,
+            ..self
+        }
+    }
+
+    /// Sends all of the requests of the client through the circuit breaker. The same breaker can be
+    /// given to several clients so that they share the state of the PTE.
+    pub fn with_circuit_breaker(self, circuit_breaker: Arc<CircuitBreaker>) -> Self {
+        Self {
+            circuit_breaker: Some(circuit_breaker),
+            ..self
+        }
+    }
+
+    pub fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.circuit_breaker.as_ref()
+    }
+
+    /// Sends the transactions calling the faucet through the limiter. The same limiter can be given
+    /// to several clients so that they stay within one cap.
+    pub fn with_faucet_limiter(self, faucet_limiter: Arc<FaucetLimiter>) -> Self {
+        Self {
+            faucet_limiter: Some(faucet_limiter),
+            ..self
+        }
+    }
+
+    pub fn faucet_limiter(&self) -> Option<&Arc<FaucetLimiter>> {
+        self.faucet_limiter.as_ref()
+    }
+
+    /// Decodes the extra fields of every receipt the client gets with the extension, in addition
+    /// to the extensions already added.
+    pub fn with_receipt_extension(mut self, extension: ReceiptExtension) -> Self {
+        self.receipt_extensions.push(extension);
+        self
+    }
+
+    pub fn receipt_extensions(&self) -> &[ReceiptExtension] {
+        &self.receipt_extensions
+    }
+
+    /// Waits for the components created by instantiations to become visible before using them, or
+    /// reads them right away when given `None`.
+    pub fn with_component_wait(self, component_wait: Option<ComponentWait>) -> Self {
+        Self { component_wait, ..self }
+    }
+
+    pub fn component_wait(&self) -> Option<ComponentWait> {
+        self.component_wait
+    }
+
+    /// Resolves `DepositTarget::SignerAccount` into the account of the signer through the resolver.
+    /// The same resolver can be given to several clients so that they share its cache.
+    pub fn with_signer_account_resolver(self, resolver: Arc<SignerAccountResolver>) -> Self {
+        Self {
+            signer_account_resolver: Some(resolver),
+            ..self
+        }
+    }
+
+    pub fn signer_account_resolver(&self) -> Option<&Arc<SignerAccountResolver>> {
+        self.signer_account_resolver.as_ref()
+    }
+
+    /// Decodes the extra fields of a receipt received by the client with its receipt extensions,
+    /// then reports the warnings of the receipt.
+    pub(crate) fn decode_extensions(&self, receipt: &mut Receipt) {
+        for extension in self.receipt_extensions.iter() {
+            extension.apply(receipt);
+        }
+        self.hooks.received(receipt);
+    }
+
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    pub fn idempotency(&self) -> Option<&Idempotency> {
+        self.idempotency.as_ref()
+    }
+
+    pub fn mirror(&self) -> Option<&Arc<Mirror>> {
+        self.mirror.as_ref()
+    }
+
+    /// Sends read requests to the given servers instead of the base URL. The servers are tried in
+    /// order, failing over to the next one when a request can not be sent. Without any server, reads
+    /// fail with `NoServers`.
+    pub fn with_read_urls(self, urls: &[&str]) -> Self {
+        Self {
+            read_urls: urls.iter().map(|x| normalize_url(x)).collect(),
+            ..self
+        }
+    }
+
+    /// Sends transaction submissions to the given servers instead of the base URL. The servers are
+    /// tried in order, failing over to the next one when a request can not be sent. Without any
+    /// server, submissions fail with `NoServers`.
+    pub fn with_write_urls(self, urls: &[&str]) -> Self {
+        Self {
+            write_urls: urls.iter().map(|x| normalize_url(x)).collect(),
+            ..self
+        }
+    }
+
+    /// The URL of the first server used for transaction submissions, or an empty string when there
+    /// is none.
+    pub fn base_url(&self) -> &str {
+        self.write_urls.first().map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn urls(&self, horizon: Horizon) -> &[String] {
+        match horizon {
+            Horizon::Read => &self.read_urls,
+            Horizon::Write => &self.write_urls,
+        }
+    }
+
+    /// Sends the request built by `request` to the servers of the given horizon in order until one
+    /// of them can be reached. Writes only move on to the next server when the connection could not
+    /// be established, since any later failure may come after the PTE received the transaction, and
+    /// sending it again could submit it twice. Fails fast without sending anything while the circuit breaker, if one
+    /// is configured, is open. Responses in a content encoding the HTTP client can't decode are
+    /// rejected with `UnsupportedEncoding` rather than failing to parse later.
+    pub(crate) fn send(
+        &self,
+        horizon: Horizon,
+        request: impl Fn(&str) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response, TransportError> {
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            circuit_breaker.before_request()?;
+        }
+
+        let urls: &[String] = self.urls(horizon);
+        if urls.is_empty() {
+            return Err(TransportError::NoServers(horizon));
+        }
+        let mut last_error: Option<reqwest::Error> = None;
+        for url in urls {
+            match request(url).send() {
+                Ok(response) => {
+                    if let Some(ref circuit_breaker) = self.circuit_breaker {
+                        circuit_breaker.record_success();
+                    }
+                    // The HTTP client removes the header of the encodings it decoded, so any
+                    // encoding left is one it doesn't support.
+                    return match response.headers().get(reqwest::header::CONTENT_ENCODING) {
+                        Some(encoding) if encoding.as_bytes() != b"identity" => Err(TransportError::UnsupportedEncoding(
+                            String::from_utf8_lossy(encoding.as_bytes()).to_string(),
+                        )),
+                        _ => Ok(response),
+                    };
+                }
+                Err(error) => {
+                    let connected: bool = !error.is_connect();
+                    last_error = Some(error);
+                    if horizon == Horizon::Write && connected {
+                        break;
+                    }
+                }
+            }
+        }
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            circuit_breaker.record_failure();
+        }
+        match last_error {
+            Some(error) => Err(TransportError::HttpRequestError(error)),
+            None => Err(TransportError::NoServers(horizon)),
+        }
+    }
+
+    /// Creates a client on the first of the candidate servers which passes the health probe. The
+    /// client sticks to the selected server so that all of the steps of a session see the same
+    /// ledger. When `allow_midrun_failover` is set, reads fail over to the other candidates if the
+    /// selected server stops responding, while submissions still only go to the selected server.
+    pub fn first_healthy(candidates: &[&str], allow_midrun_failover: bool) -> Option<Self> {
+        let probe: PteClient = Self::new(candidates.first()?);
+        let selected: &str = candidates.iter().find(|url| probe.is_healthy(url))?;
+        Some(Self::on_selected(selected, candidates, allow_midrun_failover))
+    }
+
+    /// Like `first_healthy`, but persists the selected server in the session file so that later
+    /// runs of the session stay on it, even when an earlier candidate becomes healthy again. The
+    /// server of the session stays the write server even once it is no longer healthy: without
+    /// `allow_midrun_failover` no client is returned, and with it reads fail over to the healthy
+    /// candidates while submissions keep going to the server of the session.
+    pub fn first_healthy_in_session(
+        candidates: &[&str],
+        allow_midrun_failover: bool,
+        session: &Path,
+    ) -> std::io::Result<Option<Self>> {
+        if let Some(selection) = ServerSelection::load(session)? {
+            let probe: PteClient = Self::new(&selection.server);
+            if probe.is_healthy(&selection.server) {
+                return Ok(Some(Self::on_selected(&selection.server, candidates, allow_midrun_failover)));
+            }
+            if !allow_midrun_failover || !candidates.iter().any(|url| probe.is_healthy(url)) {
+                return Ok(None);
+            }
+            return Ok(Some(Self::on_selected(&selection.server, candidates, true)));
+        }
+
+        let client: Option<PteClient> = Self::first_healthy(candidates, allow_midrun_failover);
+        if let Some(ref client) = client {
+            ServerSelection {
+                server: client.base_url().to_string(),
+            }
+            .save(session)?;
+        }
+        Ok(client)
+    }
+
+    fn on_selected(selected: &str, candidates: &[&str], allow_midrun_failover: bool) -> Self {
+        let selected: String = normalize_url(selected);
+        let mut read_urls: Vec<&str> = vec![&selected];
+        if allow_midrun_failover {
+            read_urls.extend(candidates.iter().filter(|url| normalize_url(url) != selected));
+        }
+        Self::new(&selected).with_read_urls(&read_urls)
+    }
+
+    /// Probes whether the first server of the horizon has an endpoint, caching the answer per server
+    /// in `support`. The probe goes through the circuit breaker like any other request. Only a
+    /// `404 Not Found` or `405 Method Not Allowed` means the endpoint is missing, while a server
+    /// error or a failure to reach the server is returned without being cached, so that the next
+    /// call probes again instead of remembering a transient failure as a missing endpoint.
+    pub(crate) fn probe_endpoint(
+        &self,
+        support: &Mutex<HashMap<String, bool>>,
+        horizon: Horizon,
+        request: impl Fn(&str) -> reqwest::blocking::RequestBuilder,
+    ) -> Result<bool, PteApiError> {
+        let url: String = self.urls(horizon).first().cloned().ok_or(PteApiError::NoServers)?;
+        if let Some(supported) = support.lock().unwrap().get(&url) {
+            return Ok(*supported);
+        }
+
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            circuit_breaker.before_request()?;
+        }
+        let response = match request(&url).send() {
+            Ok(response) => response,
+            Err(error) => {
+                if let Some(ref circuit_breaker) = self.circuit_breaker {
+                    circuit_breaker.record_failure();
+                }
+                return Err(PteApiError::HttpRequestError(error));
+            }
+        };
+        if let Some(ref circuit_breaker) = self.circuit_breaker {
+            circuit_breaker.record_success();
+        }
+
+        let status: reqwest::StatusCode = response.status();
+        if status.is_server_error() {
+            return Err(PteApiError::UnexpectedStatus {
+                status: status.as_u16(),
+                body: response.text().unwrap_or_default(),
+            });
+        }
+        let supported: bool = !matches!(status, reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED);
+        support.lock().unwrap().insert(url, supported);
+        Ok(supported)
+    }
+
+    /// Whether the server answers its root with a success status. A server answering with an error
+    /// is up but not healthy.
+    fn is_healthy(&self, url: &str) -> bool {
+        self.http_client()
+            .get(url)
+            .send()
+            .is_ok_and(|response| response.status().is_success())
+    }
+
+    /// Resolves the DNS of all of the servers and opens a connection to each of them through a
+    /// lightweight HEAD request so that the first real request does not pay for the handshakes.
+    /// The resolved addresses are pinned in the client, so later requests don't resolve them again
+    /// until the pool is reconfigured through `with_pool_config`. Servers used for both reads and
+    /// writes are warmed up once, and a server which can't be reached doesn't stop the others from
+    /// being warmed up. Returns the servers which couldn't be reached. Meant to be called once when
+    /// a service starts.
+    pub fn warm_up(&self) -> Vec<(String, PteApiError)> {
+        let mut warmed_up: HashSet<&String> = HashSet::new();
+        let urls: Vec<&String> = self
+            .read_urls
+            .iter()
+            .chain(self.write_urls.iter())
+            .filter(|url| warmed_up.insert(*url))
+            .collect();
+
+        let resolved: Vec<(String, SocketAddr)> = urls.iter().filter_map(|url| resolve(url)).collect();
+        if !resolved.is_empty() {
+            let http_client: reqwest::blocking::Client = resolved
+                .iter()
+                .fold(self.pool_config.http_client_builder(), |builder, (domain, address)| {
+                    builder.resolve(domain, *address)
+                })
+                .build()
+                .expect("Failed to initialize the HTTP client");
+            *self.http_client.write().unwrap_or_else(|error| error.into_inner()) = http_client;
+        }
+
+        let http_client: reqwest::blocking::Client = self.http_client();
+        urls.into_iter()
+            .filter_map(|url| match http_client.head(url.as_str()).send() {
+                Ok(_) => None,
+                Err(error) => Some((url.clone(), error.into())),
+            })
+            .collect()
+    }
+
+    /// Checks whether each of the read and write servers can be reached.
+    pub fn health_check(&self) -> Vec<(Horizon, String, bool)> {
+        [Horizon::Read, Horizon::Write]
+            .into_iter()
+            .flat_map(|horizon| {
+                self.urls(horizon).iter().map(move |url| {
+                    let healthy: bool = self.is_healthy(url);
+                    (horizon, url.clone(), healthy)
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    pub(crate) fn http_client(&self) -> reqwest::blocking::Client {
+        self.http_client.read().unwrap_or_else(|error| error.into_inner()).clone()
+    }
+
+    /// Generates a nonce for a new transaction.
+    pub fn next_nonce(&self) -> u64 {
+        self.randomness.lock().unwrap().next_nonce()
+    }
+
+    /// Submits the transaction to the PTE's `/transaction` endpoint.
+    pub fn submit_transaction(&self, transaction: &SignedTransaction) -> Result<Receipt, TransactionSubmissionError> {
+        let transaction_body: TransactionBody = TransactionBody::from_signed_with_format(transaction, self.public_key_format)?;
+        self.submit_body(transaction, &transaction_body)
+    }
+
+    /// Submits a prepared transaction without decompiling its manifest again.
+    pub fn submit_prepared(&self, prepared: &PreparedTransaction) -> Result<Receipt, TransactionSubmissionError> {
+        let transaction_body: TransactionBody = TransactionBody::from_parts_with_manifest(
+            &prepared.transaction.transaction,
+            &prepared.transaction.signatures,
+            prepared.manifest.clone(),
+            self.public_key_format,
+        )?;
+        self.submit_body(&prepared.transaction, &transaction_body)
+    }
+
+    fn submit_body(&self, transaction: &SignedTransaction, transaction_body: &TransactionBody) -> Result<Receipt, TransactionSubmissionError> {
+        let hashes: TxHashes = TxHashes::of(transaction);
+        self.hooks.submitted(&hashes, transaction_body);
+
+        let started_at: Instant = self.clock.now();
+        let outcome: Result<Receipt, TransactionSubmissionError> = self.post_transaction(transaction_body);
+        self.hooks.completed(&Completion {
+            hashes: &hashes,
+            transaction,
+            duration: self.clock.now().saturating_duration_since(started_at),
+            outcome: &outcome,
+        });
+        outcome
+    }
+
+    fn post_transaction(&self, transaction_body: &TransactionBody) -> Result<Receipt, TransactionSubmissionError> {
+        let response = self.send(Horizon::Write, |url| {
+            let request = self.http_client().post(format!("{}/transaction", url));
+            if self.canonical_wire {
+                request
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(transaction_body.to_canonical_json())
+            } else {
+                request.json(transaction_body)
+            }
+        })?;
+        if !response.status().is_success() {
+            let status: u16 = response.status().as_u16();
+            let body: String = response.text()?;
+            return Err(match detect_dialect_mismatch(transaction_body.manifest(), &body) {
+                Some(mismatch) => TransactionSubmissionError::ManifestDialectMismatch(mismatch),
+                None => TransactionSubmissionError::Rejected { status, body },
+            });
+        }
+        let mut receipt: Receipt = response.json()?;
+        self.decode_extensions(&mut receipt);
+        Ok(receipt)
+    }
+
+    /// Submits the transaction through the submission queue if one is configured, or directly if
+    /// not. Transactions outside of the scope of the options are refused without being submitted.
+    pub fn execute(&self, transaction: &SignedTransaction, options: &ExecuteOptions) -> Result<Receipt, TransactionSubmissionError> {
+        self.execute_with(transaction, options, || self.submit_transaction(transaction))
+    }
+
+    /// Same as `execute` for a transaction whose manifest was already decompiled, such as a rebased
+    /// intent being submitted again.
+    pub fn execute_prepared(&self, prepared: &PreparedTransaction, options: &ExecuteOptions) -> Result<Receipt, TransactionSubmissionError> {
+        self.execute_with(&prepared.transaction, options, || self.submit_prepared(prepared))
+    }
+
+    fn execute_with(
+        &self,
+        transaction: &SignedTransaction,
+        options: &ExecuteOptions,
+        submit: impl FnOnce() -> Result<Receipt, TransactionSubmissionError>,
+    ) -> Result<Receipt, TransactionSubmissionError> {
+        if let Some(ref scope) = options.scope {
+            verify_manifest_scope(&transaction.transaction, scope)
+                .map_err(|violation| TransactionSubmissionError::ScopeViolation(Box::new(violation)))?;
+        }
+        let mut exclusive_on: BTreeSet<String> = options.exclusive_on.clone();
+        for instruction in transaction.transaction.instructions.iter() {
+            match instruction {
+                Instruction::CallMethod { component_address, .. }
+                | Instruction::CallMethodWithAllResources { component_address, .. }
+                    if self.serialize_writes_to.contains(component_address) =>
+                {
+                    exclusive_on.insert(component_address.to_string());
+                }
+                _ => {}
+            }
+        }
+        let _guard: ExclusiveGuard = self.exclusive_locks.acquire(&exclusive_on, self.clock.as_ref());
+        let faucet_permit: Option<FaucetPermit> = match self.faucet_limiter {
+            Some(ref faucet_limiter) if calls_faucet(&transaction.transaction) => Some(faucet_limiter.acquire()),
+            _ => None,
+        };
+
+        let outcome: Result<Receipt, TransactionSubmissionError> = match self.queue {
+            Some(ref queue) => queue.run(options, submit)?,
+            None => submit(),
+        };
+        if let Some(faucet_permit) = faucet_permit {
+            faucet_permit.complete(&outcome);
+        }
+        outcome
+    }
+
+    /// Builds a transaction with a nonce allocated for the given signing key and executes it. When a
+    /// nonce manager is configured this blocks while the key's window of outstanding nonces is full,
+    /// and the nonce's slot is freed as soon as the receipt is received. The nonces of transactions
+    /// which fail are burned and never reused.
+    pub fn execute_with_nonce(
+        &self,
+        key: &str,
+        options: &ExecuteOptions,
+        build: impl FnOnce(u64) -> SignedTransaction,
+    ) -> Result<Receipt, TransactionSubmissionError> {
+        let nonce_manager: &NonceManager = match self.nonce_manager {
+            Some(ref nonce_manager) => nonce_manager,
+            None => return self.execute(&build(self.next_nonce()), options),
+        };
+
+        let lease = nonce_manager.allocate(key);
+        let result = self.execute(&build(lease.nonce()), options);
+        match result {
+            Ok(ref receipt) if receipt.is_success() => lease.complete(NonceOutcome::Committed),
+            _ => lease.complete(NonceOutcome::Failed),
+        }
+        result
+    }
+
+    /// The metrics of the submission queue, if one is configured.
+    pub fn queue_metrics(&self) -> Option<QueueMetrics> {
+        self.queue.as_ref().map(|queue| QueueMetrics {
+            component_waits: self.exclusive_locks.waits(),
+            ..queue.metrics()
+        })
+    }
+
+    /// The time submissions waited for exclusive access to each component, whether or not a
+    /// submission queue is configured.
+    pub fn component_waits(&self) -> BTreeMap<String, ExclusiveWait> {
+        self.exclusive_locks.waits()
+    }
+
+    /// Gets the information of the component with the given address from the PTE's `/component`
+    /// endpoint. A component the PTE doesn't know is `NotFound`, and any other error status is
+    /// `UnexpectedStatus` rather than a body to parse.
+    pub fn get_component(&self, address: impl Into<LabeledAddress<ComponentAddress>>) -> Result<ComponentInfo, PteApiError> {
+        let labeled_address: LabeledAddress<ComponentAddress> = address.into();
+        let address: ComponentAddress = labeled_address.address;
+        let response = self.send(Horizon::Read, |url| {
+            self.http_client().get(format!("{}/component/{}", url, address))
+        })?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(PteApiError::NotFound(labeled_address.to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(PteApiError::UnexpectedStatus {
+                status: response.status().as_u16(),
+                body: response.text()?,
+            });
+        }
+
+        let info: ComponentInfo = ComponentInfo::from_envelope(
+            address,
+            ComponentEnvelope::parse(&response.text()?)?,
+        );
+        if let Some(ref mirror) = self.mirror {
+            mirror.record_component(address, &info);
+        }
+        Ok(info)
+    }
+
+    /// Polls the PTE until the component is visible or the timeout elapses. Components are not
+    /// always visible right after the transaction instantiating them is committed. Only a component
+    /// which is not found is polled for again; any other error is returned right away.
+    pub fn wait_for_component(
+        &self,
+        address: impl Into<LabeledAddress<ComponentAddress>>,
+        timeout: Duration,
+        poll: Duration,
+    ) -> Result<ComponentInfo, PteApiError> {
+        let address: LabeledAddress<ComponentAddress> = address.into();
+        let started_at: Instant = self.clock.now();
+        let mut attempts: u32 = 0;
+        loop {
+            attempts += 1;
+            match self.get_component(address.clone()) {
+                Ok(info) => return Ok(info),
+                Err(error @ PteApiError::NotFound(_)) if self.clock.now() - started_at + poll > timeout => {
+                    return Err(PteApiError::WaitTimeout {
+                        attempts,
+                        last_error: Box::new(error),
+                    })
+                }
+                Err(PteApiError::NotFound(_)) => self.clock.sleep(poll),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Gets the information of a component created by an instantiation, waiting for it to become
+    /// visible as configured through `with_component_wait`.
+    pub fn get_created_component(&self, address: ComponentAddress) -> Result<ComponentInfo, PteApiError> {
+        match self.component_wait {
+            Some(wait) => self.wait_for_component(address, wait.timeout, wait.poll),
+            None => self.get_component(address),
+        }
+    }
+}
+
+/// The domain of the URL and the first address it resolves to. URLs of IP addresses have nothing to
+/// resolve.
+fn resolve(url: &str) -> Option<(String, SocketAddr)> {
+    let parsed_url: reqwest::Url = reqwest::Url::parse(url).ok()?;
+    let domain: &str = parsed_url.domain()?;
+    let address: SocketAddr = (domain, parsed_url.port_or_known_default()?)
+        .to_socket_addrs()
+        .ok()?
+        .next()?;
+    Some((domain.to_string(), address))
+}
+
+fn normalize_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+impl Default for PteClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_PTE_URL)
+    }
+}
+
+/// The server selected for a session by `first_healthy_in_session`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ServerSelection {
+    pub server: String,
+}
+
+impl ServerSelection {
+    /// Loads the selection from the session file, returning `None` if there is none yet.
+    pub fn load(path: &Path) -> std::io::Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+}
+
+/// Why a request could not be sent to any of the servers.
+#[derive(Debug)]
+pub enum TransportError {
+    HttpRequestError(reqwest::Error),
+    CircuitOpen(CircuitOpen),
+    /// The response was sent in a content encoding which can't be decoded.
+    UnsupportedEncoding(String),
+    /// No server is configured for the horizon of the request.
+    NoServers(Horizon),
+}
+
+impl From<CircuitOpen> for TransportError {
+    fn from(error: CircuitOpen) -> TransportError {
+        TransportError::CircuitOpen(error)
+    }
+}
+
+impl From<TransportError> for PteApiError {
+    fn from(error: TransportError) -> PteApiError {
+        match error {
+            TransportError::HttpRequestError(error) => PteApiError::HttpRequestError(error),
+            TransportError::CircuitOpen(error) => PteApiError::CircuitOpen(error),
+            TransportError::UnsupportedEncoding(encoding) => PteApiError::UnsupportedEncoding(encoding),
+            TransportError::NoServers(_) => PteApiError::NoServers,
+        }
+    }
+}
+
+impl From<TransportError> for TransactionSubmissionError {
+    fn from(error: TransportError) -> TransactionSubmissionError {
+        match error {
+            TransportError::HttpRequestError(error) => TransactionSubmissionError::HttpRequestError(error),
+            TransportError::CircuitOpen(error) => TransactionSubmissionError::CircuitOpen(error),
+            TransportError::UnsupportedEncoding(encoding) => TransactionSubmissionError::UnsupportedEncoding(encoding),
+            TransportError::NoServers(_) => TransactionSubmissionError::NoServers,
+        }
+    }
+}
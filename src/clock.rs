@@ -0,0 +1,171 @@
+//! The source of time used by all of the retry, polling, staleness and timing logic. The system clock
+//! is used by default, while tests can use a `TestClock` whose time only advances when told to.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+pub trait Clock: Send + Sync {
+    /// The current monotonic time.
+    fn now(&self) -> Instant;
+
+    /// The current wall clock time.
+    fn system_time(&self) -> SystemTime;
+
+    fn sleep(&self, duration: Duration);
+}
+
+/// The clock of the system, sleeping for real.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// A clock whose time stands still until it is advanced. Sleeping on it advances it by the slept
+/// duration immediately, so code which polls or backs off runs without waiting.
+#[derive(Debug)]
+pub struct TestClock {
+    start: Instant,
+    start_system_time: SystemTime,
+    elapsed: Mutex<Duration>,
+}
+
+impl TestClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_system_time: SystemTime::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+
+    /// The total time the clock was advanced by.
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.lock().unwrap()
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.start_system_time + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+/// The clock used when none is injected.
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// How often `wait_until` checks a clock which only moves when told to, such as a `TestClock`.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A flag which stops a background thread, waking it up if it is waiting.
+#[derive(Debug, Default)]
+pub struct StopSignal {
+    stopped: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl StopSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stop(&self) {
+        *self.stopped.lock().unwrap_or_else(|error| error.into_inner()) = true;
+        self.condvar.notify_all();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.stopped.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// Waits until the clock reaches the deadline, returning `false` if the signal stopped the wait
+/// first. Unlike `Clock::sleep`, the wait never advances the clock: it blocks for real, in steps of
+/// at most `WAIT_POLL_INTERVAL`, so that background loops on a `TestClock` wait for the test to
+/// advance it instead of spinning.
+pub fn wait_until(clock: &dyn Clock, deadline: Instant, stop: &StopSignal) -> bool {
+    let mut stopped = stop.stopped.lock().unwrap_or_else(|error| error.into_inner());
+    loop {
+        if *stopped {
+            return false;
+        }
+        let remaining: Duration = deadline.saturating_duration_since(clock.now());
+        if remaining.is_zero() {
+            return true;
+        }
+        stopped = stop
+            .condvar
+            .wait_timeout(stopped, remaining.min(WAIT_POLL_INTERVAL))
+            .unwrap_or_else(|error| error.into_inner())
+            .0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_until_returns_once_the_test_clock_reaches_the_deadline() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let stop: Arc<StopSignal> = Arc::new(StopSignal::new());
+        let deadline: Instant = clock.now() + Duration::from_secs(60);
+
+        let waiter = {
+            let (clock, stop) = (clock.clone(), stop.clone());
+            std::thread::spawn(move || wait_until(clock.as_ref(), deadline, &stop))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        clock.advance(Duration::from_secs(60));
+        assert!(waiter.join().unwrap());
+    }
+
+    #[test]
+    fn wait_until_is_interrupted_by_the_stop_signal() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let stop: Arc<StopSignal> = Arc::new(StopSignal::new());
+        let deadline: Instant = clock.now() + Duration::from_secs(60);
+
+        let waiter = {
+            let (clock, stop) = (clock.clone(), stop.clone());
+            std::thread::spawn(move || wait_until(clock.as_ref(), deadline, &stop))
+        };
+        stop.stop();
+        assert!(!waiter.join().unwrap());
+        assert!(stop.is_stopped());
+    }
+}
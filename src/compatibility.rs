@@ -0,0 +1,158 @@
+//! Detection of the manifests produced by `decompile` which the PTE's manifest compiler refuses,
+//! which happens when the pinned Scrypto revision of this crate and the server's drift apart.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "blocking-http")]
+use crate::client::{Horizon, PteClient};
+#[cfg(feature = "blocking-http")]
+use crate::PteApiError;
+
+/// The Scrypto revision which this crate's transaction model and `decompile` are pinned to.
+pub const PINNED_SCRYPTO_REVISION: &str = "v0.4.1";
+
+/// Information about the revisions this crate was built against.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CompatibilityInfo {
+    pub crate_version: String,
+    pub scrypto_revision: String,
+}
+
+pub fn compatibility_info() -> CompatibilityInfo {
+    CompatibilityInfo {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        scrypto_revision: PINNED_SCRYPTO_REVISION.to_string(),
+    }
+}
+
+/// The PTE refused to compile a manifest produced by `decompile`.
+#[derive(Debug, Clone)]
+pub struct ManifestDialectMismatch {
+    /// The first line of the manifest the server reported as rejected, if it reported one.
+    pub rejected_line: Option<String>,
+    pub server_message: String,
+    pub compatibility: CompatibilityInfo,
+}
+
+/// The markers which the PTE's manifest compiler includes in the errors it returns.
+const COMPILER_ERROR_MARKERS: [&str; 4] = ["CompileError", "ParserError", "LexerError", "GeneratorError"];
+
+/// Checks whether the error body returned by the server is a rejection of the manifest's syntax and
+/// if so, finds the line of the manifest it refers to.
+pub fn detect_dialect_mismatch(manifest: &str, server_message: &str) -> Option<ManifestDialectMismatch> {
+    if !COMPILER_ERROR_MARKERS.iter().any(|x| server_message.contains(x)) {
+        return None;
+    }
+
+    Some(ManifestDialectMismatch {
+        rejected_line: reported_line_number(server_message)
+            .and_then(|line| manifest.lines().nth(line.saturating_sub(1)))
+            .map(str::to_string),
+        server_message: server_message.to_string(),
+        compatibility: compatibility_info(),
+    })
+}
+
+/// Finds the first line number in the server's message, reported as a `line` token followed by the
+/// number, either as `line: N` or as `line N`. Words which merely end in `line`, such as `newline`,
+/// are not taken for the token.
+fn reported_line_number(server_message: &str) -> Option<usize> {
+    let lowercase: String = server_message.to_ascii_lowercase();
+    lowercase.match_indices("line").find_map(|(index, _)| {
+        let preceded_by_word: bool = matches!(lowercase[..index].chars().next_back(), Some(c) if c.is_alphanumeric() || c == '_');
+        if preceded_by_word {
+            return None;
+        }
+        let rest: &str = &lowercase[index + 4..];
+        let number: &str = rest.strip_prefix(':').unwrap_or(rest).trim_start_matches(' ');
+        if number.len() == rest.len() {
+            // The token must be separated from the number.
+            return None;
+        }
+        number
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect::<String>()
+            .parse()
+            .ok()
+    })
+}
+
+/// The outcome of a parse-only check of a manifest.
+#[cfg(feature = "blocking-http")]
+#[derive(Debug)]
+pub enum RecompileCheckError {
+    /// The server does not expose a way of validating manifests without executing them.
+    Unsupported,
+    Rejected(ManifestDialectMismatch),
+    /// The server could not be reached, or answered with an error which is not a rejection of the
+    /// manifest, such as a server error.
+    HttpRequestError(PteApiError),
+}
+
+#[cfg(feature = "blocking-http")]
+impl PteClient {
+    /// Asks the server to parse the manifest without executing it, so that manifest templates can
+    /// be checked after upgrading dependencies. The manifest text is posted to
+    /// `/transaction/validate`, which the PTE itself does not document but which the mock and
+    /// proxies in front of the PTE can expose. Servers answering it with `404 Not Found` or
+    /// `405 Method Not Allowed` result in `RecompileCheckError::Unsupported`.
+    pub fn recompile_check(&self, manifest_text: &str) -> Result<(), RecompileCheckError> {
+        let response = self
+            .send(Horizon::Read, |url| {
+                self.http_client()
+                    .post(format!("{}/transaction/validate", url))
+                    .body(manifest_text.to_string())
+            })
+            .map_err(|error| RecompileCheckError::HttpRequestError(error.into()))?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::METHOD_NOT_ALLOWED => Err(RecompileCheckError::Unsupported),
+            status => {
+                let server_message: String = response.text().unwrap_or_default();
+                match detect_dialect_mismatch(manifest_text, &server_message) {
+                    Some(mismatch) => Err(RecompileCheckError::Rejected(mismatch)),
+                    None => Err(RecompileCheckError::HttpRequestError(PteApiError::UnexpectedStatus {
+                        status: status.as_u16(),
+                        body: server_message,
+                    })),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = "CALL_METHOD ComponentAddress(\"020000000000000000000000000000000000000000000000000002\") \"free_xrd\";\nCALL_METHOD_WITH_ALL_RESOURCES ComponentAddress(\"020000000000000000000000000000000000000000000000000002\") \"deposit_batch\";";
+
+    #[test]
+    fn line_numbers_are_read_from_line_tokens() {
+        assert_eq!(reported_line_number("ParserError at line: 2"), Some(2));
+        assert_eq!(reported_line_number("ParserError at line 12, column 3"), Some(12));
+        assert_eq!(reported_line_number("ParserError at Line 7"), Some(7));
+    }
+
+    #[test]
+    fn words_ending_in_line_are_not_line_tokens() {
+        assert_eq!(reported_line_number("LexerError: unexpected newline 3 times"), None);
+        assert_eq!(reported_line_number("pipeline 4 failed, see line 2"), Some(2));
+        assert_eq!(reported_line_number("the deadline passed"), None);
+        assert_eq!(reported_line_number("lines12"), None);
+    }
+
+    #[test]
+    fn compiler_errors_are_dialect_mismatches_with_the_rejected_line() {
+        let mismatch: ManifestDialectMismatch = detect_dialect_mismatch(MANIFEST, "CompileError: unknown instruction at line 2").unwrap();
+        assert_eq!(mismatch.rejected_line.as_deref(), MANIFEST.lines().nth(1));
+        assert_eq!(mismatch.compatibility, compatibility_info());
+    }
+
+    #[test]
+    fn other_errors_are_not_dialect_mismatches() {
+        assert!(detect_dialect_mismatch(MANIFEST, "insufficient balance on line 2").is_none());
+    }
+}
@@ -0,0 +1,270 @@
+//! The envelope which the PTE's `/component` endpoint wraps the state of components in.
+
+use sbor::Decode;
+use scrypto::buffer::scrypto_decode;
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::raw::{decode_partial, PartialDecode};
+use crate::PteApiError;
+
+/// The fields which the `/component` envelope is expected to have.
+const ENVELOPE_FIELDS: [&str; 4] = ["blueprint", "authorization", "state", "owned_resources"];
+
+/// The body of a successful response of the `/component/{address}` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComponentEnvelope {
+    /// The package and name of the blueprint the component was instantiated from.
+    pub blueprint: BlueprintInfo,
+    /// The access rules of the component's methods, kept as JSON as their shape varies by rule.
+    pub authorization: Vec<serde_json::Value>,
+    /// The SBOR encoded state of the component, hex encoded.
+    pub state: String,
+    /// The resources held in the vaults owned by the component.
+    pub owned_resources: Vec<OwnedResource>,
+    /// Any fields which are not part of the expected envelope.
+    #[serde(flatten)]
+    pub extra_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlueprintInfo {
+    pub package_address: String,
+    pub blueprint_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OwnedResource {
+    /// The ID of the vault holding the resource, only present on PTE builds which report it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_id: Option<String>,
+    pub resource_address: String,
+    pub amount: String,
+    /// The IDs of the non-fungibles held, only present for non-fungible resources.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_fungible_ids: Option<Vec<String>>,
+    /// The entry exactly as the PTE reported it, including any fields not parsed above.
+    #[serde(skip)]
+    pub raw: Option<serde_json::Value>,
+}
+
+/// Describes how a response differs from the shape this crate expects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDrift {
+    pub missing_fields: Vec<String>,
+    pub unexpected_fields: Vec<String>,
+    /// The error of the parser when the fields are all present but their content does not parse.
+    pub parse_error: Option<String>,
+}
+
+impl ComponentEnvelope {
+    /// Parses the envelope from the response body. Missing fields or fields of an unexpected type
+    /// result in a `SchemaDrift` error, while unexpected extra fields are kept in `extra_fields`.
+    pub fn parse(body: &str) -> Result<Self, PteApiError> {
+        let value: serde_json::Value = serde_json::from_str(body).map_err(|error| {
+            PteApiError::SchemaDrift(SchemaDrift {
+                parse_error: Some(error.to_string()),
+                ..Default::default()
+            })
+        })?;
+
+        let object = value.as_object().cloned().unwrap_or_default();
+        let missing_fields: Vec<String> = ENVELOPE_FIELDS
+            .iter()
+            .filter(|field| !object.contains_key(**field))
+            .map(|field| field.to_string())
+            .collect();
+        if !missing_fields.is_empty() {
+            return Err(PteApiError::SchemaDrift(SchemaDrift {
+                missing_fields,
+                unexpected_fields: unexpected_fields(&object),
+                parse_error: None,
+            }));
+        }
+
+        let mut envelope: ComponentEnvelope = serde_json::from_value(value).map_err(|error| {
+            PteApiError::SchemaDrift(SchemaDrift {
+                unexpected_fields: unexpected_fields(&object),
+                parse_error: Some(error.to_string()),
+                ..Default::default()
+            })
+        })?;
+        if let Some(serde_json::Value::Array(raw_resources)) = object.get("owned_resources") {
+            for (owned_resource, raw) in envelope.owned_resources.iter_mut().zip(raw_resources) {
+                owned_resource.raw = Some(raw.clone());
+            }
+        }
+        Ok(envelope)
+    }
+
+    /// The drift of this envelope from the expected shape, if it had any unexpected fields.
+    pub fn drift(&self) -> Option<SchemaDrift> {
+        match self.extra_fields.is_empty() {
+            true => None,
+            false => Some(SchemaDrift {
+                unexpected_fields: self.extra_fields.keys().cloned().collect(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+fn unexpected_fields(object: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+    object
+        .keys()
+        .filter(|key| !ENVELOPE_FIELDS.contains(&key.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// The information the PTE returns about a component.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComponentInfo {
+    pub address: String,
+    pub blueprint: BlueprintInfo,
+    pub authorization: Vec<serde_json::Value>,
+    /// The SBOR encoded state of the component, hex encoded.
+    pub state: String,
+    pub owned_resources: Vec<OwnedResource>,
+    pub extra_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ComponentInfo {
+    pub fn from_envelope(address: ComponentAddress, envelope: ComponentEnvelope) -> Self {
+        Self {
+            address: address.to_string(),
+            blueprint: envelope.blueprint,
+            authorization: envelope.authorization,
+            state: envelope.state,
+            owned_resources: envelope.owned_resources,
+            extra_fields: envelope.extra_fields,
+        }
+    }
+
+    /// The SBOR encoded state of the component, hex encoded, exactly as the PTE reported it.
+    pub fn state_hex(&self) -> &str {
+        &self.state
+    }
+
+    pub fn state_bytes(&self) -> Result<Vec<u8>, PteApiError> {
+        hex::decode(&self.state).map_err(|_| PteApiError::InvalidState(self.state.clone()))
+    }
+
+    /// Decodes the state as far as possible, for states holding values `T` doesn't know about.
+    pub fn decode_state_partial<T: Decode>(&self) -> Result<PartialDecode<T>, PteApiError> {
+        Ok(decode_partial(&self.state_bytes()?))
+    }
+
+    /// Decodes the state of the component into a type mirroring the blueprint's struct.
+    pub fn decode_state<T: Decode>(&self) -> Result<T, PteApiError> {
+        let bytes: Vec<u8> = self.state_bytes()?;
+        scrypto_decode(&bytes).map_err(|_| PteApiError::InvalidState(self.state.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: &str = include_str!("../tests/assets/component/account.json");
+    const VENDOR: &str = include_str!("../tests/assets/component/vendor.json");
+    const RENAMED_RESOURCES: &str = include_str!("../tests/assets/component/renamed_resources.json");
+    const NOT_FOUND: &str = include_str!("../tests/assets/component/not_found.txt");
+
+    fn drift(body: &str) -> SchemaDrift {
+        match ComponentEnvelope::parse(body) {
+            Err(PteApiError::SchemaDrift(drift)) => drift,
+            other => panic!("expected the envelope to drift, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn account_envelopes_are_parsed() {
+        let envelope: ComponentEnvelope = ComponentEnvelope::parse(ACCOUNT).unwrap();
+
+        assert_eq!(envelope.blueprint.blueprint_name, "Account");
+        assert_eq!(envelope.authorization.len(), 1);
+        assert_eq!(envelope.state, "21010000000901000000");
+        assert_eq!(envelope.owned_resources.len(), 1);
+        assert_eq!(envelope.owned_resources[0].amount, "1000000");
+        assert_eq!(envelope.owned_resources[0].vault_id, Some(format!("{}01000000", "0".repeat(64))));
+        assert_eq!(envelope.owned_resources[0].non_fungible_ids, None);
+        assert_eq!(envelope.drift(), None);
+    }
+
+    #[test]
+    fn non_fungibles_and_extra_fields_are_kept() {
+        let envelope: ComponentEnvelope = ComponentEnvelope::parse(VENDOR).unwrap();
+
+        assert_eq!(envelope.owned_resources[0].vault_id, None);
+        assert_eq!(envelope.owned_resources[0].non_fungible_ids, None);
+        assert_eq!(
+            envelope.owned_resources[1].non_fungible_ids,
+            Some(vec!["0001".to_string(), "0002".to_string()])
+        );
+        assert_eq!(
+            envelope.drift(),
+            Some(SchemaDrift {
+                unexpected_fields: vec!["metadata".to_string()],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn raw_resources_are_kept_as_reported() {
+        let envelope: ComponentEnvelope = ComponentEnvelope::parse(VENDOR).unwrap();
+        let reported: serde_json::Value = serde_json::from_str(VENDOR).unwrap();
+
+        for (index, owned_resource) in envelope.owned_resources.iter().enumerate() {
+            assert_eq!(owned_resource.raw.as_ref(), Some(&reported["owned_resources"][index]));
+        }
+        assert_eq!(envelope.owned_resources[1].raw.as_ref().unwrap()["divisibility"], 0);
+    }
+
+    #[test]
+    fn renamed_fields_are_missing_and_unexpected() {
+        assert_eq!(
+            drift(RENAMED_RESOURCES),
+            SchemaDrift {
+                missing_fields: vec!["owned_resources".to_string()],
+                unexpected_fields: vec!["resources".to_string()],
+                parse_error: None,
+            }
+        );
+    }
+
+    #[test]
+    fn fields_of_the_wrong_type_do_not_parse() {
+        let mut envelope: serde_json::Value = serde_json::from_str(ACCOUNT).unwrap();
+        envelope["state"] = serde_json::json!(42);
+
+        let drift: SchemaDrift = drift(&envelope.to_string());
+        assert!(drift.missing_fields.is_empty());
+        assert!(drift.parse_error.is_some());
+    }
+
+    #[test]
+    fn bodies_which_are_not_json_do_not_parse() {
+        let drift: SchemaDrift = drift(NOT_FOUND);
+        assert!(drift.missing_fields.is_empty());
+        assert!(drift.parse_error.is_some());
+    }
+
+    #[test]
+    fn component_info_keeps_the_whole_envelope() {
+        let envelope: ComponentEnvelope = ComponentEnvelope::parse(VENDOR).unwrap();
+        let address: ComponentAddress =
+            ComponentAddress::from_str("024c94485e0c21ae6c41ce1dfe7b6bfaceea5ab68e40a2476f5020").unwrap();
+        let info: ComponentInfo = ComponentInfo::from_envelope(address, envelope.clone());
+
+        assert_eq!(info.address, address.to_string());
+        assert_eq!(info.blueprint, envelope.blueprint);
+        assert_eq!(info.authorization, envelope.authorization);
+        assert_eq!(info.state_hex(), envelope.state);
+        assert_eq!(info.owned_resources, envelope.owned_resources);
+        assert_eq!(info.extra_fields, envelope.extra_fields);
+        assert_eq!(info.state_bytes().unwrap(), hex::decode(&envelope.state).unwrap());
+    }
+}
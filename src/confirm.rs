@@ -0,0 +1,206 @@
+//! Confirmation of dangerous CLI operations. An operation moving everything out of an account, or a
+//! large amount, prints a summary of what it does and where, and only runs once the user types `yes`
+//! or the last characters of the target address. Prompts are skipped with `--yes`, and when standard
+//! output isn't a terminal unless `--require-confirmation` is given.
+
+use scrypto::prelude::*;
+
+use crate::denomination::Denomination;
+
+use std::io::{BufRead, Write};
+
+/// The amount from which a transfer is dangerous.
+pub fn large_amount() -> Decimal {
+    dec!("1000")
+}
+
+/// The number of trailing characters of the target address accepted instead of `yes`.
+pub const ADDRESS_SUFFIX_LENGTH: usize = 6;
+
+/// A summary of what an operation is about to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub name: String,
+    pub server: String,
+    pub accounts: Vec<String>,
+    /// The resources moved and their amounts, or `None` when everything held is moved.
+    pub resources: Vec<(String, Option<Decimal>)>,
+    pub target: Option<String>,
+    /// The denomination the amounts are written in.
+    pub denomination: Denomination,
+}
+
+impl Operation {
+    /// Why the operation is dangerous, or `None` if it isn't.
+    pub fn danger(&self) -> Option<String> {
+        if self.resources.iter().any(|(_, amount)| amount.is_none()) {
+            return Some("it moves everything held of the resources".to_string());
+        }
+        self.resources
+            .iter()
+            .find(|(_, amount)| amount.is_some_and(|x| x >= large_amount()))
+            .map(|(resource, amount)| {
+                format!(
+                    "it moves {} of {}, at least {}",
+                    self.denomination.format(amount.unwrap()),
+                    resource,
+                    self.denomination.format(large_amount())
+                )
+            })
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary: String = format!("{} on {}\n", self.name, self.server);
+        for account in self.accounts.iter() {
+            summary.push_str(&format!("  from:     {}\n", account));
+        }
+        for (resource, amount) in self.resources.iter() {
+            match amount {
+                Some(amount) => summary.push_str(&format!("  resource: {} ({})\n", resource, self.denomination.format(*amount))),
+                None => summary.push_str(&format!("  resource: {} (everything held)\n", resource)),
+            }
+        }
+        if let Some(ref target) = self.target {
+            summary.push_str(&format!("  to:       {}\n", target));
+        }
+        summary
+    }
+}
+
+/// When to prompt for confirmation, from the flags of the command and the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmationPolicy {
+    pub yes: bool,
+    pub require_confirmation: bool,
+    pub interactive: bool,
+}
+
+impl ConfirmationPolicy {
+    /// Whether a dangerous operation should be prompted for. `--yes` always wins, and otherwise
+    /// prompts happen in terminals or when `--require-confirmation` forces them.
+    pub fn should_prompt(&self) -> bool {
+        !self.yes && (self.interactive || self.require_confirmation)
+    }
+}
+
+/// Prompts for the confirmation of the operation if it is dangerous and the policy asks for it.
+/// Returns whether the operation may run.
+pub fn confirm(
+    operation: &Operation,
+    policy: &ConfirmationPolicy,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<bool> {
+    let danger: String = match operation.danger() {
+        Some(danger) if policy.should_prompt() => danger,
+        _ => return Ok(true),
+    };
+
+    let suffix: Option<&str> = operation
+        .target
+        .as_deref()
+        .and_then(|x| x.get(x.len().saturating_sub(ADDRESS_SUFFIX_LENGTH)..));
+    writeln!(output, "{}This operation is dangerous: {}.", operation.summary(), danger)?;
+    match suffix {
+        Some(suffix) => write!(output, "Type \"yes\" or \"{}\" to continue: ", suffix)?,
+        None => write!(output, "Type \"yes\" to continue: ")?,
+    }
+    output.flush()?;
+
+    let mut answer: String = String::new();
+    input.read_line(&mut answer)?;
+    let answer: &str = answer.trim();
+    Ok(answer == "yes" || suffix.is_some_and(|x| x == answer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: &str = "020d3869346218a5e8deaaf2001216dc00fcacb79fb43e30ded79a";
+
+    fn sweep() -> Operation {
+        Operation {
+            name: "sweep".to_string(),
+            server: "http://localhost:3500".to_string(),
+            accounts: vec!["02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173".to_string()],
+            resources: vec![(RADIX_TOKEN.to_string(), None)],
+            target: Some(TARGET.to_string()),
+            denomination: Denomination::Whole,
+        }
+    }
+
+    fn prompting() -> ConfirmationPolicy {
+        ConfirmationPolicy {
+            yes: false,
+            require_confirmation: false,
+            interactive: true,
+        }
+    }
+
+    /// Runs the confirmation with the given standard input, returning its outcome and what was
+    /// written to standard output.
+    fn run(operation: &Operation, policy: &ConfirmationPolicy, input: &str) -> (bool, String) {
+        let mut output: Vec<u8> = Vec::new();
+        let confirmed: bool = confirm(operation, policy, &mut input.as_bytes(), &mut output).unwrap();
+        (confirmed, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn yes_and_the_address_suffix_accept() {
+        for input in ["yes\n", "  yes  \n", "ded79a\n", "yes"] {
+            let (confirmed, output) = run(&sweep(), &prompting(), input);
+            assert!(confirmed, "{:?}", input);
+            assert!(output.starts_with("sweep on http://localhost:3500\n"), "{}", output);
+            assert!(output.contains("This operation is dangerous: it moves everything held of the resources."), "{}", output);
+            assert!(output.ends_with("Type \"yes\" or \"ded79a\" to continue: "), "{}", output);
+        }
+    }
+
+    #[test]
+    fn other_answers_reject() {
+        for input in ["no\n", "y\n", "YES\n", "\n", "d79a\n", "yes please\n"] {
+            assert!(!run(&sweep(), &prompting(), input).0, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn end_of_input_rejects() {
+        let (confirmed, output) = run(&sweep(), &prompting(), "");
+        assert!(!confirmed);
+        assert!(output.ends_with("to continue: "), "{}", output);
+    }
+
+    #[test]
+    fn operations_without_a_target_only_accept_yes() {
+        let operation: Operation = Operation { target: None, ..sweep() };
+
+        let (confirmed, output) = run(&operation, &prompting(), "yes\n");
+        assert!(confirmed);
+        assert!(output.ends_with("Type \"yes\" to continue: "), "{}", output);
+        assert!(!run(&operation, &prompting(), "ded79a\n").0);
+    }
+
+    #[test]
+    fn safe_operations_and_skipped_prompts_run_without_reading_input() {
+        let small: Operation = Operation {
+            resources: vec![(RADIX_TOKEN.to_string(), Some(dec!("999")))],
+            ..sweep()
+        };
+        assert_eq!(run(&small, &prompting(), ""), (true, String::new()));
+
+        let skipped: [ConfirmationPolicy; 2] = [
+            ConfirmationPolicy { yes: true, ..prompting() },
+            ConfirmationPolicy { interactive: false, ..prompting() },
+        ];
+        for policy in skipped {
+            assert_eq!(run(&sweep(), &policy, ""), (true, String::new()));
+        }
+        let forced: ConfirmationPolicy = ConfirmationPolicy {
+            interactive: false,
+            require_confirmation: true,
+            ..prompting()
+        };
+        assert!(!run(&sweep(), &forced, "").0);
+    }
+}
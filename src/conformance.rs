@@ -0,0 +1,122 @@
+//! A conformance harness for comparing the wire payloads of this crate against other client
+//! implementations. Each scenario fixes everything that would otherwise vary (the key, the nonce and
+//! the manifest), and the exact `TransactionBody` JSON bytes and compiled transaction hex it produces
+//! are written next to it. Any change in the output then has to be regenerated explicitly.
+
+use radix_engine::model::SignedTransaction;
+use scrypto::buffer::scrypto_encode;
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::manifest_spec::ManifestSpec;
+use crate::TransactionBody;
+
+/// A canonical scenario description shared between client implementations.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceScenario {
+    pub name: String,
+    /// The private key signing the transaction, hex encoded.
+    pub private_key: String,
+    pub nonce: u64,
+    pub manifest: ManifestSpec,
+}
+
+/// The exact output produced for a scenario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceOutput {
+    pub transaction_body_json: Vec<u8>,
+    pub compiled_transaction_hex: String,
+}
+
+#[derive(Debug)]
+pub enum ConformanceError {
+    InvalidScenario(String),
+    IoError(std::io::Error),
+}
+
+impl From<std::io::Error> for ConformanceError {
+    fn from(error: std::io::Error) -> ConformanceError {
+        ConformanceError::IoError(error)
+    }
+}
+
+impl ConformanceScenario {
+    pub fn build(&self) -> Result<SignedTransaction, ConformanceError> {
+        let invalid = |what: &str| ConformanceError::InvalidScenario(format!("{}: invalid {}", self.name, what));
+        let private_key: EcdsaPrivateKey = hex::decode(&self.private_key)
+            .ok()
+            .and_then(|bytes| EcdsaPrivateKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| invalid("private key"))?;
+
+        self.manifest.build(&private_key, self.nonce).map_err(|what| invalid(&what))
+    }
+
+    pub fn output(&self) -> Result<ConformanceOutput, ConformanceError> {
+        let transaction: SignedTransaction = self.build()?;
+        let transaction_body: TransactionBody = TransactionBody::from_signed(&transaction)
+            .map_err(|error| ConformanceError::InvalidScenario(format!("{}: {:?}", self.name, error)))?;
+
+        Ok(ConformanceOutput {
+            transaction_body_json: transaction_body.to_canonical_json(),
+            compiled_transaction_hex: hex::encode(scrypto_encode(&transaction.transaction)),
+        })
+    }
+}
+
+fn body_path(directory: &Path, name: &str) -> PathBuf {
+    directory.join(format!("{}.body.json", name))
+}
+
+fn transaction_path(directory: &Path, name: &str) -> PathBuf {
+    directory.join(format!("{}.transaction.hex", name))
+}
+
+/// Loads all of the scenarios from the `scenarios` sub-directory of the conformance directory.
+pub fn load_scenarios(directory: &Path) -> Result<Vec<ConformanceScenario>, ConformanceError> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(directory.join("scenarios"))?
+        .filter_map(|entry| entry.ok().map(|x| x.path()))
+        .filter(|path| path.extension().map(|x| x == "json").unwrap_or(false))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            serde_json::from_str(&fs::read_to_string(path)?)
+                .map_err(|error| ConformanceError::InvalidScenario(format!("{}: {}", path.display(), error)))
+        })
+        .collect()
+}
+
+/// Regenerates the outputs of all of the scenarios in the conformance directory.
+pub fn write_outputs(directory: &Path) -> Result<usize, ConformanceError> {
+    let scenarios: Vec<ConformanceScenario> = load_scenarios(directory)?;
+    for scenario in scenarios.iter() {
+        let output: ConformanceOutput = scenario.output()?;
+        fs::write(body_path(directory, &scenario.name), &output.transaction_body_json)?;
+        fs::write(transaction_path(directory, &scenario.name), &output.compiled_transaction_hex)?;
+    }
+    Ok(scenarios.len())
+}
+
+/// Regenerates the outputs of all of the scenarios and compares them byte for byte against the
+/// checked-in outputs, returning the names of the scenarios which differ.
+pub fn check_outputs(directory: &Path) -> Result<Vec<String>, ConformanceError> {
+    let mut mismatches: Vec<String> = Vec::new();
+    for scenario in load_scenarios(directory)? {
+        let output: ConformanceOutput = scenario.output()?;
+        let body_matches: bool = fs::read(body_path(directory, &scenario.name))
+            .map(|x| x == output.transaction_body_json)
+            .unwrap_or(false);
+        let transaction_matches: bool = fs::read_to_string(transaction_path(directory, &scenario.name))
+            .map(|x| x == output.compiled_transaction_hex)
+            .unwrap_or(false);
+        if !body_matches || !transaction_matches {
+            mismatches.push(scenario.name);
+        }
+    }
+    Ok(mismatches)
+}
@@ -0,0 +1,122 @@
+//! The keys helpers sign with. Scripts usually sign everything with the same key, so a default signer
+//! can be registered on the client once and helpers given `Credentials::Default` instead of the key.
+//! A default notary can be registered the same way. The pinned engine has no notaries, so no helper
+//! of this crate asks for one yet; scripts resolve it to pass along to engines which do.
+
+use scrypto::prelude::*;
+
+use std::sync::Arc;
+
+use crate::client::PteClient;
+use crate::TransactionSubmissionError;
+
+/// Which key a helper signs with.
+#[derive(Debug, Clone, Copy)]
+pub enum Credentials<'a> {
+    /// The default signer registered on the client.
+    Default,
+    /// The given key, which takes precedence over the default signer or notary.
+    Explicit(&'a EcdsaPrivateKey),
+}
+
+impl<'a> From<&'a EcdsaPrivateKey> for Credentials<'a> {
+    fn from(private_key: &'a EcdsaPrivateKey) -> Self {
+        Credentials::Explicit(private_key)
+    }
+}
+
+impl PteClient {
+    /// Registers the key used by helpers given `Credentials::Default`.
+    pub fn with_default_signer(self, private_key: EcdsaPrivateKey) -> Self {
+        Self {
+            default_signer: Some(Arc::new(private_key)),
+            ..self
+        }
+    }
+
+    pub fn default_signer(&self) -> Option<&EcdsaPrivateKey> {
+        self.default_signer.as_deref()
+    }
+
+    /// Registers the notary used when resolving `Credentials::Default` through `resolve_notary`.
+    pub fn with_default_notary(self, private_key: EcdsaPrivateKey) -> Self {
+        Self {
+            default_notary: Some(Arc::new(private_key)),
+            ..self
+        }
+    }
+
+    pub fn default_notary(&self) -> Option<&EcdsaPrivateKey> {
+        self.default_notary.as_deref()
+    }
+
+    /// Resolves the credentials into the key to sign with: the explicit key when one is given,
+    /// otherwise the default signer. Fails with `NoDefaultSigner` when neither is available.
+    pub fn resolve_signer<'a>(&'a self, credentials: Credentials<'a>) -> Result<&'a EcdsaPrivateKey, TransactionSubmissionError> {
+        match credentials {
+            Credentials::Explicit(private_key) => Ok(private_key),
+            Credentials::Default => self.default_signer().ok_or(TransactionSubmissionError::NoDefaultSigner),
+        }
+    }
+
+    /// Resolves the credentials into the notary's key: the explicit key when one is given, otherwise
+    /// the default notary. The default signer doesn't stand in for a missing notary, so this fails
+    /// with `NoDefaultSigner` when neither is available.
+    pub fn resolve_notary<'a>(&'a self, credentials: Credentials<'a>) -> Result<&'a EcdsaPrivateKey, TransactionSubmissionError> {
+        match credentials {
+            Credentials::Explicit(private_key) => Ok(private_key),
+            Credentials::Default => self.default_notary().ok_or(TransactionSubmissionError::NoDefaultSigner),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> EcdsaPrivateKey {
+        EcdsaPrivateKey::from_bytes(&[byte; 32]).unwrap()
+    }
+
+    fn public_key(private_key: &EcdsaPrivateKey) -> Vec<u8> {
+        private_key.public_key().to_vec()
+    }
+
+    fn client() -> PteClient {
+        PteClient::new("http://127.0.0.1:1")
+            .with_default_signer(key(1))
+            .with_default_notary(key(2))
+    }
+
+    #[test]
+    fn explicit_keys_take_precedence_over_the_defaults() {
+        let client: PteClient = client();
+        let explicit: EcdsaPrivateKey = key(3);
+
+        assert_eq!(public_key(client.resolve_signer((&explicit).into()).unwrap()), public_key(&explicit));
+        assert_eq!(public_key(client.resolve_notary((&explicit).into()).unwrap()), public_key(&explicit));
+    }
+
+    #[test]
+    fn the_defaults_are_used_otherwise() {
+        let client: PteClient = client();
+
+        assert_eq!(public_key(client.resolve_signer(Credentials::Default).unwrap()), public_key(&key(1)));
+        assert_eq!(public_key(client.resolve_notary(Credentials::Default).unwrap()), public_key(&key(2)));
+    }
+
+    #[test]
+    fn missing_defaults_are_errors_and_do_not_stand_in_for_each_other() {
+        let signer_only: PteClient = PteClient::new("http://127.0.0.1:1").with_default_signer(key(1));
+        assert!(matches!(
+            signer_only.resolve_notary(Credentials::Default),
+            Err(TransactionSubmissionError::NoDefaultSigner)
+        ));
+
+        let notary_only: PteClient = PteClient::new("http://127.0.0.1:1").with_default_notary(key(2));
+        assert!(matches!(
+            notary_only.resolve_signer(Credentials::Default),
+            Err(TransactionSubmissionError::NoDefaultSigner)
+        ));
+    }
+}
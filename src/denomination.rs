@@ -0,0 +1,227 @@
+//! The unit amounts are written in for humans: whole units, such as `1.5` XRD, or attos, the
+//! 10^-18 fractions of a unit `Decimal` is made of, such as `1500000000000000000`. Conversions
+//! between the two go through the underlying number of attos of `Decimal` with integer arithmetic
+//! only, so they are exact over the whole range of `Decimal`. Machine outputs write amounts as a
+//! `DenominatedAmount` carrying both.
+
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Denomination {
+    #[default]
+    Whole,
+    Attos,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DenominationError {
+    UnknownDenomination(String),
+    /// The amount is not a number of the denomination, such as a fractional number of attos.
+    InvalidAmount { amount: String, denomination: Denomination },
+}
+
+impl FromStr for Denomination {
+    type Err = DenominationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "whole" => Ok(Denomination::Whole),
+            "attos" | "atto" => Ok(Denomination::Attos),
+            _ => Err(DenominationError::UnknownDenomination(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for Denomination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Denomination::Whole => write!(f, "whole units"),
+            Denomination::Attos => write!(f, "attos"),
+        }
+    }
+}
+
+impl Denomination {
+    /// Writes the amount in the denomination.
+    pub fn format(&self, amount: Decimal) -> String {
+        match self {
+            Denomination::Whole => to_whole(amount),
+            Denomination::Attos => to_attos(amount),
+        }
+    }
+
+    /// Parses an amount written in the denomination.
+    pub fn parse(&self, amount: &str) -> Result<Decimal, DenominationError> {
+        let parsed: Option<Decimal> = match self {
+            Denomination::Whole => from_whole(amount),
+            Denomination::Attos => from_attos(amount).ok(),
+        };
+        parsed.ok_or_else(|| DenominationError::InvalidAmount {
+            amount: amount.to_string(),
+            denomination: *self,
+        })
+    }
+}
+
+/// The number of attos in a whole unit.
+const ATTOS_PER_UNIT: u128 = 1_000_000_000_000_000_000;
+
+/// The number of digits of the fractional part of an amount in whole units.
+const FRACTION_DIGITS: usize = 18;
+
+/// The amount in whole units, without trailing zeros in the fractional part.
+fn to_whole(amount: Decimal) -> String {
+    let attos: u128 = amount.0.unsigned_abs();
+    let sign: &str = if amount.0 < 0 { "-" } else { "" };
+    let fraction: String = format!("{:0width$}", attos % ATTOS_PER_UNIT, width = FRACTION_DIGITS);
+    match fraction.trim_end_matches('0') {
+        "" => format!("{}{}", sign, attos / ATTOS_PER_UNIT),
+        fraction => format!("{}{}.{}", sign, attos / ATTOS_PER_UNIT, fraction),
+    }
+}
+
+/// The amount written in whole units, with at most as many fractional digits as `Decimal` has, or
+/// `None` if it isn't one or is out of the range of `Decimal`.
+fn from_whole(amount: &str) -> Option<Decimal> {
+    let amount: &str = amount.trim();
+    let (negative, digits): (bool, &str) = match amount.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, amount.strip_prefix('+').unwrap_or(amount)),
+    };
+    let (whole, fraction): (&str, Option<&str>) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (digits, None),
+    };
+    let is_digits = |x: &str| !x.is_empty() && x.bytes().all(|byte| byte.is_ascii_digit());
+    if !is_digits(whole) || fraction.is_some_and(|x| !is_digits(x)) {
+        return None;
+    }
+    let fraction: &str = fraction.unwrap_or("");
+    if fraction.len() > FRACTION_DIGITS {
+        return None;
+    }
+
+    let fraction: u128 = format!("{:0<width$}", fraction, width = FRACTION_DIGITS).parse().ok()?;
+    let attos: u128 = whole
+        .parse::<u128>()
+        .ok()?
+        .checked_mul(ATTOS_PER_UNIT)?
+        .checked_add(fraction)?;
+    let attos: i128 = match negative {
+        true => 0i128.checked_sub_unsigned(attos)?,
+        false => i128::try_from(attos).ok()?,
+    };
+    Some(Decimal(attos))
+}
+
+/// The number of attos of the amount.
+pub fn to_attos(amount: Decimal) -> String {
+    amount.0.to_string()
+}
+
+/// The amount made of the number of attos, which must be an integer.
+pub fn from_attos(attos: &str) -> Result<Decimal, DenominationError> {
+    attos
+        .trim()
+        .parse::<i128>()
+        .map(Decimal)
+        .map_err(|_| DenominationError::InvalidAmount {
+            amount: attos.to_string(),
+            denomination: Denomination::Attos,
+        })
+}
+
+/// An amount written in both denominations, for outputs read by programs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DenominatedAmount {
+    pub whole: String,
+    pub attos: String,
+}
+
+impl DenominatedAmount {
+    /// The amount, read from its number of attos.
+    pub fn amount(&self) -> Result<Decimal, DenominationError> {
+        from_attos(&self.attos)
+    }
+}
+
+impl Default for DenominatedAmount {
+    fn default() -> Self {
+        Decimal::zero().into()
+    }
+}
+
+impl From<Decimal> for DenominatedAmount {
+    fn from(amount: Decimal) -> Self {
+        Self {
+            whole: Denomination::Whole.format(amount),
+            attos: Denomination::Attos.format(amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Amounts across the whole range of `Decimal`, down to single attos.
+    fn amounts() -> Vec<Decimal> {
+        vec![
+            Decimal(i128::MIN),
+            Decimal(i128::MIN + 1),
+            Decimal(-1_500_000_000_000_000_000),
+            Decimal(-1),
+            Decimal::zero(),
+            Decimal(1),
+            Decimal(999_999_999_999_999_999),
+            Decimal::one(),
+            Decimal(1_000_000_000_000_000_001),
+            dec!("1000000"),
+            Decimal(i128::MAX - 1),
+            Decimal(i128::MAX),
+        ]
+    }
+
+    #[test]
+    fn amounts_round_trip_through_both_denominations() {
+        for amount in amounts() {
+            for denomination in [Denomination::Whole, Denomination::Attos] {
+                assert_eq!(denomination.parse(&denomination.format(amount)), Ok(amount), "{:?}", amount);
+            }
+            assert_eq!(from_attos(&to_attos(amount)), Ok(amount));
+            assert_eq!(DenominatedAmount::from(amount).amount(), Ok(amount));
+        }
+    }
+
+    #[test]
+    fn the_extremes_are_written_exactly() {
+        assert_eq!(to_attos(Decimal(i128::MIN)), "-170141183460469231731687303715884105728");
+        assert_eq!(to_attos(Decimal(i128::MAX)), "170141183460469231731687303715884105727");
+        assert_eq!(Denomination::Whole.format(Decimal(i128::MIN)), "-170141183460469231731.687303715884105728");
+        assert_eq!(Denomination::Whole.format(Decimal(i128::MAX)), "170141183460469231731.687303715884105727");
+    }
+
+    #[test]
+    fn whole_units_are_written_without_trailing_zeros() {
+        assert_eq!(Denomination::Whole.format(dec!("1000000")), "1000000");
+        assert_eq!(Denomination::Whole.format(Decimal(1_500_000_000_000_000_000)), "1.5");
+        assert_eq!(Denomination::Whole.format(Decimal(-250_000_000_000_000_000)), "-0.25");
+        assert_eq!(Denomination::Whole.format(Decimal(-1)), "-0.000000000000000001");
+        assert_eq!(Denomination::Attos.format(Decimal(1_500_000_000_000_000_000)), "1500000000000000000");
+    }
+
+    #[test]
+    fn amounts_outside_of_the_denomination_are_refused() {
+        for amount in ["", "-", ".5", "1.", "1.2.3", "1e18", "0.0000000000000000001", "170141183460469231731.687303715884105728"] {
+            assert!(Denomination::Whole.parse(amount).is_err(), "{}", amount);
+        }
+        for amount in ["1.5", "", "170141183460469231731687303715884105728", "ten"] {
+            assert!(Denomination::Attos.parse(amount).is_err(), "{}", amount);
+        }
+        assert_eq!(Denomination::Whole.parse(" +2.50 "), Ok(Decimal(2_500_000_000_000_000_000)));
+        assert_eq!(Denomination::Whole.parse("-170141183460469231731.687303715884105728"), Ok(Decimal(i128::MIN)));
+    }
+}
@@ -0,0 +1,306 @@
+//! A fixed battery of checks confirming that a freshly deployed or upgraded PTE works end to end.
+//! Every step is timed and passes or fails on its own, and steps which need something an earlier
+//! step failed to create are skipped rather than failed. The battery creates two accounts and a
+//! token, publishes and instantiates the test package when its WASM is built, and sweeps everything
+//! the second account received back into the first when it is done.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::builder::TransactionBuilderExt;
+use crate::client::PteClient;
+use crate::component::ComponentInfo;
+use crate::manifest_spec::ManifestSpec;
+use crate::queue::ExecuteOptions;
+use crate::test_package::{self, TestPackageError};
+use crate::Receipt;
+
+/// The supply of the token created by the battery.
+pub const VERIFICATION_TOKEN_SUPPLY: &str = "1000";
+
+/// The outcome of a step of the battery.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "outcome", content = "reason", rename_all = "snake_case")]
+pub enum StepOutcome {
+    Passed,
+    Failed(String),
+    /// The step was not run, because an earlier step it depends on failed or because the deployment
+    /// doesn't offer what it checks.
+    Skipped(String),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeploymentStep {
+    pub name: String,
+    #[serde(flatten)]
+    pub outcome: StepOutcome,
+    pub duration_ms: u128,
+}
+
+/// The outcome of every step of the battery against a deployment.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeploymentReport {
+    pub url: String,
+    pub steps: Vec<DeploymentStep>,
+}
+
+impl DeploymentReport {
+    /// Whether no step failed. Skipped steps don't count as failures.
+    pub fn is_success(&self) -> bool {
+        !self.steps.iter().any(|x| matches!(x.outcome, StepOutcome::Failed(_)))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl std::fmt::Display for DeploymentReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Deployment verification of {}", self.url)?;
+        for step in self.steps.iter() {
+            let (status, reason): (&str, Option<&str>) = match step.outcome {
+                StepOutcome::Passed => ("PASS", None),
+                StepOutcome::Failed(ref reason) => ("FAIL", Some(reason)),
+                StepOutcome::Skipped(ref reason) => ("SKIP", Some(reason)),
+            };
+            write!(f, "  {} {:<24} {:>6} ms", status, step.name, step.duration_ms)?;
+            match reason {
+                Some(reason) => writeln!(f, "  {}", reason)?,
+                None => writeln!(f)?,
+            }
+        }
+        write!(f, "{}", if self.is_success() { "The deployment works" } else { "The deployment is broken" })
+    }
+}
+
+/// Runs the battery one step after the other, recording each of them.
+struct Battery<'a> {
+    client: &'a PteClient,
+    steps: Vec<DeploymentStep>,
+}
+
+impl<'a> Battery<'a> {
+    /// Runs the step and records its outcome, returning what it produced if it passed.
+    fn step<T>(&mut self, name: &str, run: impl FnOnce(&PteClient) -> Result<T, String>) -> Option<T> {
+        let started_at: Instant = self.client.clock().now();
+        let result: Result<T, String> = run(self.client);
+        let duration_ms: u128 = self.client.clock().now().duration_since(started_at).as_millis();
+        let (outcome, value): (StepOutcome, Option<T>) = match result {
+            Ok(value) => (StepOutcome::Passed, Some(value)),
+            Err(reason) => (StepOutcome::Failed(reason), None),
+        };
+        self.steps.push(DeploymentStep {
+            name: name.to_string(),
+            outcome,
+            duration_ms,
+        });
+        value
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.steps.push(DeploymentStep {
+            name: name.to_string(),
+            outcome: StepOutcome::Skipped(reason.to_string()),
+            duration_ms: 0,
+        });
+    }
+
+    /// Runs the step only when what it depends on was produced, skipping it otherwise.
+    fn step_with<D, T>(
+        &mut self,
+        name: &str,
+        dependency: Option<D>,
+        dependency_name: &str,
+        run: impl FnOnce(&PteClient, D) -> Result<T, String>,
+    ) -> Option<T> {
+        match dependency {
+            Some(dependency) => self.step(name, |client| run(client, dependency)),
+            None => {
+                self.skip(name, &format!("needs {}", dependency_name));
+                None
+            }
+        }
+    }
+}
+
+/// Builds, signs and executes the spec, failing unless the transaction succeeded.
+fn execute(client: &PteClient, spec: &ManifestSpec, private_key: &EcdsaPrivateKey) -> Result<Receipt, String> {
+    let transaction: SignedTransaction = spec
+        .build(private_key, client.next_nonce())
+        .map_err(|field| format!("invalid {}", field))?;
+    succeeded(client.execute(&transaction, &options()))
+}
+
+fn options() -> ExecuteOptions {
+    ExecuteOptions::new().tag("flow", "verify-deployment")
+}
+
+fn succeeded<E: std::fmt::Debug>(outcome: Result<Receipt, E>) -> Result<Receipt, String> {
+    match outcome {
+        Ok(receipt) if receipt.is_success() => Ok(receipt),
+        Ok(receipt) => Err(format!("the transaction {} failed: {:?}", receipt.transaction_hash, receipt.logs)),
+        Err(error) => Err(format!("{:?}", error)),
+    }
+}
+
+fn new_private_key() -> EcdsaPrivateKey {
+    loop {
+        let bytes: [u8; 32] = rand::random();
+        if let Ok(private_key) = EcdsaPrivateKey::from_bytes(&bytes) {
+            return private_key;
+        }
+    }
+}
+
+/// Runs the battery against the deployment the client talks to.
+pub fn verify_deployment(client: &PteClient) -> DeploymentReport {
+    let mut battery: Battery = Battery {
+        client,
+        steps: Vec::new(),
+    };
+
+    battery.step("health", |client| {
+        let unhealthy: Vec<String> = client
+            .health_check()
+            .into_iter()
+            .filter(|(_, _, healthy)| !healthy)
+            .map(|(horizon, url, _)| format!("{} ({:?})", url, horizon))
+            .collect();
+        if unhealthy.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("unreachable: {}", unhealthy.join(", ")))
+        }
+    });
+    if client.supports_epoch().unwrap_or(true) {
+        battery.step("epoch", |client| match client.current_epoch() {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err("the epoch endpoint disappeared".to_string()),
+            Err(error) => Err(format!("{:?}", error)),
+        });
+    } else {
+        battery.skip("epoch", "the deployment has no epoch endpoint");
+    }
+
+    let first_key: EcdsaPrivateKey = new_private_key();
+    let second_key: EcdsaPrivateKey = new_private_key();
+    let create_account = |client: &PteClient, private_key: &EcdsaPrivateKey| {
+        let receipt: Receipt = execute(client, &ManifestSpec::NewAccount, private_key)?;
+        receipt
+            .new_components()
+            .first()
+            .copied()
+            .ok_or_else(|| "no account was created".to_string())
+    };
+    let first_account: Option<ComponentAddress> = battery.step("account creation", |client| create_account(client, &first_key));
+    let second_account: Option<ComponentAddress> =
+        battery.step("second account creation", |client| create_account(client, &second_key));
+
+    battery.step_with("faucet funding", first_account, "an account", |client, account| {
+        let balances: BTreeMap<ResourceAddress, Decimal> = client.get_balances(account).map_err(|x| format!("{:?}", x))?;
+        match balances.get(&RADIX_TOKEN) {
+            Some(amount) if *amount > Decimal::zero() => Ok(()),
+            _ => Err(format!("{} holds no XRD", account)),
+        }
+    });
+
+    let token: Option<(ResourceAddress, String)> = battery.step_with("token creation", first_account, "an account", |client, account| {
+        let spec: ManifestSpec = ManifestSpec::NewToken {
+            account: account.to_string(),
+            symbol: "VERIFY".to_string(),
+            supply: VERIFICATION_TOKEN_SUPPLY.to_string(),
+        };
+        let receipt: Receipt = execute(client, &spec, &first_key)?;
+        let token: ResourceAddress = *receipt.new_resources().first().ok_or("no token was created")?;
+        Ok((token, receipt.transaction_hash))
+    });
+
+    let accounts: Option<(ComponentAddress, ComponentAddress)> = first_account.zip(second_account);
+    let transferred: Option<ResourceAddress> = battery.step_with(
+        "transfer",
+        accounts.zip(token.clone()),
+        "two accounts and a token",
+        |client, ((from, to), (token, _))| {
+            let spec: ManifestSpec = ManifestSpec::Transfer {
+                from: from.to_string(),
+                to: to.to_string(),
+                resource: token.to_string(),
+                amount: "1".to_string(),
+            };
+            execute(client, &spec, &first_key)?;
+            match client.get_balances(to).map_err(|x| format!("{:?}", x))?.get(&token) {
+                Some(amount) if *amount == dec!("1") => Ok(token),
+                amount => Err(format!("{} holds {:?} of the token instead of 1", to, amount)),
+            }
+        },
+    );
+
+    battery.skip("non-fungible mint and transfer", "the pinned engine's builder has no non-fungible minting helper");
+
+    let package: Option<PackageAddress> = match test_package::wasm() {
+        Err(TestPackageError::Missing(path)) => {
+            battery.skip("package publish", &format!("the test package is not built at {}", path.display()));
+            None
+        }
+        code => battery.step("package publish", |client| {
+            let code: Vec<u8> = code.map_err(|error| format!("{:?}", error))?;
+            let transaction: SignedTransaction = TransactionBuilder::new()
+                .publish_package(&code)
+                .build(client.next_nonce())
+                .sign([&first_key]);
+            let receipt: Receipt = succeeded(client.execute(&transaction, &options()))?;
+            receipt.new_packages().first().copied().ok_or_else(|| "no package was published".to_string())
+        }),
+    };
+    battery.step_with("package instantiation", package, "a published package", |client, package| {
+        let transaction: SignedTransaction = test_package::instantiate_transaction(package, 7, client.next_nonce()).sign([&first_key]);
+        let receipt: Receipt = succeeded(client.execute(&transaction, &options()))?;
+        let component: ComponentAddress = *receipt.new_components().first().ok_or("no component was instantiated")?;
+        match client.get_created_component(component).map_err(|x| format!("{:?}", x))?.blueprint.blueprint_name.as_str() {
+            test_package::TEST_PACKAGE_BLUEPRINT => Ok(()),
+            blueprint => Err(format!("{} is a {} component", component, blueprint)),
+        }
+    });
+
+    battery.step_with("component state", first_account, "an account", |client, account| {
+        let info: ComponentInfo = client.get_component(account).map_err(|x| format!("{:?}", x))?;
+        match info.state_bytes() {
+            Ok(state) if !state.is_empty() => Ok(()),
+            Ok(_) => Err("the state of the account is empty".to_string()),
+            Err(error) => Err(format!("{:?}", error)),
+        }
+    });
+
+    match client.mirror() {
+        Some(mirror) => {
+            battery.step_with("receipt re-fetch", token.map(|(_, hash)| hash), "a token", |_, hash| {
+                mirror
+                    .get_receipt(&hash)
+                    .map(|_| ())
+                    .ok_or_else(|| format!("the receipt of {} is not known", hash))
+            });
+        }
+        None => battery.skip("receipt re-fetch", "the client has no mirror recording receipts"),
+    }
+
+    battery.step_with("cleanup", accounts, "two accounts", |client, (first_account, second_account)| {
+        let mut resources: Vec<ResourceAddress> = vec![RADIX_TOKEN];
+        resources.extend(transferred);
+        let transaction: SignedTransaction = TransactionBuilder::new()
+            .sweep(second_account, &resources, first_account)
+            .build(client.next_nonce())
+            .sign([&second_key]);
+        succeeded(client.execute(&transaction, &options())).map(|_| ())
+    });
+
+    DeploymentReport {
+        url: client.base_url().to_string(),
+        steps: battery.steps,
+    }
+}
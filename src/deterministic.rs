@@ -0,0 +1,84 @@
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use scrypto::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock::{system_clock, Clock};
+
+/// Controls where the randomness used when building transactions comes from. When running in the
+/// `Seeded` mode, the nonces, keys and timestamps produced are fully reproducible which is useful
+/// when comparing the output of this crate against other client implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeterministicMode {
+    /// Randomness comes from the thread-local RNG and timestamps come from the clock.
+    Disabled,
+
+    /// Randomness comes from an RNG seeded by the caller and timestamps are frozen at the given
+    /// number of seconds since the unix epoch.
+    Seeded { seed: u64, frozen_timestamp: u64 },
+}
+
+impl Default for DeterministicMode {
+    fn default() -> Self {
+        DeterministicMode::Disabled
+    }
+}
+
+/// A source of randomness and time which all of the nonce generation, key generation and time
+/// stamping should go through so that the `DeterministicMode` is respected.
+pub struct Randomness {
+    mode: DeterministicMode,
+    rng: StdRng,
+    clock: Arc<dyn Clock>,
+}
+
+impl Randomness {
+    pub fn new(mode: DeterministicMode) -> Self {
+        let rng: StdRng = match mode {
+            DeterministicMode::Disabled => StdRng::from_entropy(),
+            DeterministicMode::Seeded { seed, .. } => StdRng::seed_from_u64(seed),
+        };
+        Self {
+            mode,
+            rng,
+            clock: system_clock(),
+        }
+    }
+
+    /// Replaces the clock timestamps come from when not running in the `Seeded` mode.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub fn mode(&self) -> DeterministicMode {
+        self.mode
+    }
+
+    /// Generates a new nonce to use for a transaction, drawn from the whole range of `u64`.
+    pub fn next_nonce(&mut self) -> u64 {
+        self.rng.gen()
+    }
+
+    /// Generates a new private key. Random byte strings which are not valid P-256 scalars are
+    /// rejected and re-drawn, so the same seed always produces the same sequence of keys.
+    pub fn new_private_key(&mut self) -> EcdsaPrivateKey {
+        loop {
+            let mut bytes: [u8; 32] = [0u8; 32];
+            self.rng.fill_bytes(&mut bytes);
+            if let Ok(private_key) = EcdsaPrivateKey::from_bytes(&bytes) {
+                return private_key;
+            }
+        }
+    }
+
+    /// Returns the current time, or the frozen timestamp when running in the `Seeded` mode.
+    pub fn now(&self) -> SystemTime {
+        match self.mode {
+            DeterministicMode::Disabled => self.clock.system_time(),
+            DeterministicMode::Seeded { frozen_timestamp, .. } => {
+                UNIX_EPOCH + Duration::from_secs(frozen_timestamp)
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! The entities created by a transaction, correlated with the instructions which created them.
+//!
+//! The PTE reports the new packages, components and resources in three separate lists. Each list is
+//! in the order the entities were created, but the lists don't say which instruction created what,
+//! and nothing orders the entities of one list relative to those of another. The instruction which
+//! created an entity is found by looking for its address in the outputs of the instructions, which
+//! works for the functions returning the addresses they create and not for entities which are
+//! only deposited or stored.
+
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Receipt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Package,
+    Component,
+    Resource,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CreatedEntity {
+    pub kind: EntityKind,
+    pub address: String,
+    /// The index of the first instruction whose output holds the address, if any does.
+    pub origin_instruction: Option<usize>,
+}
+
+/// The error returned when a receipt doesn't have exactly one new entity of the expected kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityExpectationError {
+    NoneCreated {
+        kind: EntityKind,
+        origin_instruction: Option<usize>,
+    },
+    /// More than one entity matched, listed in the order of `Receipt::created_entities`.
+    Ambiguous {
+        kind: EntityKind,
+        origin_instruction: Option<usize>,
+        addresses: Vec<String>,
+    },
+}
+
+/// The index of the first output holding the raw bytes of the address.
+fn origin_instruction(outputs: &[String], address: &str) -> Option<usize> {
+    let address: Vec<u8> = hex::decode(address).ok()?;
+    outputs.iter().position(|output| {
+        hex::decode(output)
+            .map(|output| output.windows(address.len()).any(|window| window == address.as_slice()))
+            .unwrap_or(false)
+    })
+}
+
+impl Receipt {
+    /// The new entities of all kinds, without duplicates. Entities whose creating instruction was
+    /// found come first, ordered by that instruction and then by their order in the lists of the
+    /// receipt. The others follow in the order of the lists: packages, then components, then
+    /// resources. The addresses are taken as reported, so an address the PTE reported in two lists
+    /// is listed once for each kind.
+    pub fn created_entities(&self) -> Vec<CreatedEntity> {
+        let mut entities: Vec<CreatedEntity> = Vec::new();
+        let lists: [(EntityKind, &Vec<String>); 3] = [
+            (EntityKind::Package, &self.new_packages),
+            (EntityKind::Component, &self.new_components),
+            (EntityKind::Resource, &self.new_resources),
+        ];
+        for (kind, addresses) in lists {
+            entities.extend(crate::unique(addresses).map(|address| CreatedEntity {
+                kind,
+                origin_instruction: origin_instruction(&self.outputs, address),
+                address: address.clone(),
+            }));
+        }
+        // The sort is stable, so the order of the lists is kept between entities of one instruction.
+        entities.sort_by_key(|entity| entity.origin_instruction.unwrap_or(usize::MAX));
+        entities
+    }
+
+    /// The new entities of the kind, restricted to those created by the instruction when one is given.
+    pub fn created_entities_of_kind(&self, kind: EntityKind, origin_instruction: Option<usize>) -> Vec<CreatedEntity> {
+        self.created_entities()
+            .into_iter()
+            .filter(|entity| entity.kind == kind)
+            .filter(|entity| origin_instruction.is_none() || entity.origin_instruction == origin_instruction)
+            .collect()
+    }
+
+    fn expect_single(&self, kind: EntityKind, origin_instruction: Option<usize>) -> Result<String, EntityExpectationError> {
+        let mut entities: Vec<CreatedEntity> = self.created_entities_of_kind(kind, origin_instruction);
+        match entities.len() {
+            0 => Err(EntityExpectationError::NoneCreated { kind, origin_instruction }),
+            1 => Ok(entities.remove(0).address),
+            _ => Err(EntityExpectationError::Ambiguous {
+                kind,
+                origin_instruction,
+                addresses: entities.into_iter().map(|entity| entity.address).collect(),
+            }),
+        }
+    }
+
+    /// The only package created by the transaction, or by the instruction when one is given.
+    pub fn expect_single_new_package(&self, origin_instruction: Option<usize>) -> Result<PackageAddress, EntityExpectationError> {
+        self.expect_single(EntityKind::Package, origin_instruction)
+            .map(|address| PackageAddress::from_str(&address).unwrap())
+    }
+
+    /// The only component created by the transaction, or by the instruction when one is given.
+    pub fn expect_single_new_component(&self, origin_instruction: Option<usize>) -> Result<ComponentAddress, EntityExpectationError> {
+        self.expect_single(EntityKind::Component, origin_instruction)
+            .map(|address| ComponentAddress::from_str(&address).unwrap())
+    }
+
+    /// The only resource created by the transaction, or by the instruction when one is given.
+    pub fn expect_single_new_resource(&self, origin_instruction: Option<usize>) -> Result<ResourceAddress, EntityExpectationError> {
+        self.expect_single(EntityKind::Resource, origin_instruction)
+            .map(|address| ResourceAddress::from_str(&address).unwrap())
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    use crate::fixtures::{
+        duplicate_components_receipt, inconsistent_lists_receipt, multi_creation_receipt, GOLD_TOKEN, SILVER_TOKEN, VENDOR,
+    };
+
+    fn entity(kind: EntityKind, address: &str, origin_instruction: Option<usize>) -> CreatedEntity {
+        CreatedEntity {
+            kind,
+            address: address.to_string(),
+            origin_instruction,
+        }
+    }
+
+    #[test]
+    fn entities_are_ordered_by_the_instruction_creating_them() {
+        assert_eq!(
+            multi_creation_receipt().created_entities(),
+            vec![
+                entity(EntityKind::Resource, GOLD_TOKEN, Some(0)),
+                entity(EntityKind::Resource, SILVER_TOKEN, Some(1)),
+                entity(EntityKind::Component, VENDOR, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicated_entities_are_listed_once() {
+        let receipt: Receipt = duplicate_components_receipt();
+
+        assert_eq!(receipt.created_entities(), multi_creation_receipt().created_entities());
+        assert_eq!(
+            receipt.expect_single_new_component(None),
+            Ok(ComponentAddress::from_str(VENDOR).unwrap())
+        );
+    }
+
+    #[test]
+    fn entities_without_an_origin_follow_in_the_order_of_the_lists() {
+        let receipt: Receipt = Receipt {
+            outputs: vec![multi_creation_receipt().outputs[2].clone()],
+            ..multi_creation_receipt()
+        };
+        assert_eq!(
+            receipt.created_entities(),
+            vec![
+                entity(EntityKind::Component, VENDOR, Some(0)),
+                entity(EntityKind::Resource, GOLD_TOKEN, None),
+                entity(EntityKind::Resource, SILVER_TOKEN, None),
+            ]
+        );
+
+        let receipt: Receipt = Receipt {
+            outputs: Vec::new(),
+            ..multi_creation_receipt()
+        };
+        assert_eq!(
+            receipt.created_entities(),
+            vec![
+                entity(EntityKind::Component, VENDOR, None),
+                entity(EntityKind::Resource, GOLD_TOKEN, None),
+                entity(EntityKind::Resource, SILVER_TOKEN, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn addresses_reported_in_two_lists_are_listed_for_each_kind() {
+        assert_eq!(
+            inconsistent_lists_receipt().created_entities(),
+            vec![
+                entity(EntityKind::Component, GOLD_TOKEN, Some(0)),
+                entity(EntityKind::Resource, GOLD_TOKEN, Some(0)),
+                entity(EntityKind::Resource, SILVER_TOKEN, Some(1)),
+                entity(EntityKind::Component, VENDOR, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_origin_disambiguates_entities_of_the_same_kind() {
+        let receipt: Receipt = multi_creation_receipt();
+
+        assert_eq!(
+            receipt.expect_single_new_resource(None),
+            Err(EntityExpectationError::Ambiguous {
+                kind: EntityKind::Resource,
+                origin_instruction: None,
+                addresses: vec![GOLD_TOKEN.to_string(), SILVER_TOKEN.to_string()],
+            })
+        );
+        assert_eq!(receipt.expect_single_new_resource(Some(0)), Ok(ResourceAddress::from_str(GOLD_TOKEN).unwrap()));
+        assert_eq!(receipt.expect_single_new_resource(Some(1)), Ok(ResourceAddress::from_str(SILVER_TOKEN).unwrap()));
+        assert_eq!(
+            receipt.expect_single_new_component(Some(0)),
+            Err(EntityExpectationError::NoneCreated {
+                kind: EntityKind::Component,
+                origin_instruction: Some(0),
+            })
+        );
+        assert_eq!(
+            receipt.expect_single_new_package(None),
+            Err(EntityExpectationError::NoneCreated {
+                kind: EntityKind::Package,
+                origin_instruction: None,
+            })
+        );
+    }
+}
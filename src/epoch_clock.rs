@@ -0,0 +1,300 @@
+//! Conversions between epochs and wall-clock time based on the observed length of epochs. Expiry and
+//! scheduling think in epochs while people think in minutes, and epochs don't have a fixed length, so
+//! the length is estimated from samples of the current epoch taken over time.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "blocking-http")]
+use crate::client::PteClient;
+#[cfg(feature = "blocking-http")]
+use crate::PteApiError;
+
+/// The number of samples kept. Older samples are dropped so that the estimate follows changes in
+/// the epoch length.
+const MAX_SAMPLES: usize = 64;
+
+/// An observation of the current epoch at some point in time.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochSample {
+    pub epoch: u64,
+    /// Seconds since the unix epoch at which the sample was taken.
+    pub observed_at: u64,
+}
+
+/// The estimated length of an epoch along with the range it is likely in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochLengthEstimate {
+    pub mean: Duration,
+    pub low: Duration,
+    pub high: Duration,
+    /// The number of epoch transitions the estimate is based on. Zero when the estimate is the
+    /// default length given to the clock.
+    pub transitions: u64,
+}
+
+/// Estimates the length of epochs from samples of the current epoch. The clock is serializable so
+/// that its samples can be saved and restored across restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EpochClock {
+    default_length: Duration,
+    samples: Vec<EpochSample>,
+}
+
+impl EpochClock {
+    /// Creates a clock which assumes epochs are `default_length` long until it has samples.
+    pub fn new(default_length: Duration) -> Self {
+        Self {
+            default_length,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+
+    pub fn samples(&self) -> &[EpochSample] {
+        &self.samples
+    }
+
+    /// Records that `epoch` was the current epoch at the given time.
+    pub fn observe(&mut self, epoch: u64, at: SystemTime) {
+        let observed_at: u64 = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.samples.push(EpochSample { epoch, observed_at });
+        self.samples.sort_by_key(|x| (x.observed_at, x.epoch));
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.drain(..self.samples.len() - MAX_SAMPLES);
+        }
+    }
+
+    /// Samples the current epoch of the client. Returns whether a sample was taken, which isn't the
+    /// case when the server has no epoch endpoint; the estimate then stays at the default length.
+    #[cfg(feature = "blocking-http")]
+    pub fn sample(&mut self, client: &PteClient) -> Result<bool, PteApiError> {
+        match client.current_epoch()? {
+            Some(epoch) => {
+                self.observe(epoch, client.clock().system_time());
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Estimates the length of an epoch. The mean is taken over the whole span of the samples and
+    /// the range over the spans between consecutive samples in different epochs. Without at least
+    /// two samples in different epochs the default length is used, with a range of half to double
+    /// of it.
+    pub fn estimate(&self) -> EpochLengthEstimate {
+        let (first, last) = match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last)) if last.epoch > first.epoch => (first, last),
+            _ => {
+                return EpochLengthEstimate {
+                    mean: self.default_length,
+                    low: self.default_length / 2,
+                    high: self.default_length * 2,
+                    transitions: 0,
+                }
+            }
+        };
+
+        let transitions: u64 = last.epoch - first.epoch;
+        let mean: Duration = Duration::from_secs_f64((last.observed_at - first.observed_at) as f64 / transitions as f64);
+        let lengths: Vec<Duration> = self
+            .samples
+            .windows(2)
+            .filter(|x| x[1].epoch > x[0].epoch)
+            .map(|x| Duration::from_secs_f64((x[1].observed_at - x[0].observed_at) as f64 / (x[1].epoch - x[0].epoch) as f64))
+            .collect();
+        EpochLengthEstimate {
+            mean,
+            low: lengths.iter().copied().min().unwrap_or(mean).min(mean),
+            high: lengths.iter().copied().max().unwrap_or(mean).max(mean),
+            transitions,
+        }
+    }
+
+    /// The estimated duration of the given number of epochs, saturating at `Duration::MAX`.
+    pub fn duration_of(&self, epochs: u64) -> Duration {
+        duration_of_epochs(self.estimate().mean, epochs)
+    }
+
+    /// The number of epochs which cover at least the given duration at the estimated epoch length.
+    pub fn epochs_for(&self, duration: Duration) -> u64 {
+        let mean: f64 = self.estimate().mean.as_secs_f64();
+        if mean <= 0.0 {
+            return 0;
+        }
+        (duration.as_secs_f64() / mean).ceil() as u64
+    }
+
+    /// The estimated time at which the target epoch starts, extrapolated from the latest sample. Uses
+    /// the current time of the latest sample when there are none, which makes the estimate relative
+    /// to `now`.
+    pub fn eta_of_epoch(&self, target: u64, now: SystemTime, current_epoch: u64) -> SystemTime {
+        let (epoch, at): (u64, SystemTime) = self.anchor(now, current_epoch);
+        if target >= epoch {
+            saturating_add(at, self.duration_of(target - epoch))
+        } else {
+            saturating_sub(at, self.duration_of(epoch - target))
+        }
+    }
+
+    /// The epoch to schedule something at so that it doesn't happen before the given time: the
+    /// first epoch estimated to start at or after it, extrapolated from the latest sample like
+    /// `eta_of_epoch`. Times which already passed map to the epoch of the latest sample.
+    pub fn schedule_at(&self, at: SystemTime, now: SystemTime, current_epoch: u64) -> u64 {
+        let (epoch, sampled_at): (u64, SystemTime) = self.anchor(now, current_epoch);
+        match at.duration_since(sampled_at) {
+            Ok(ahead) => epoch.saturating_add(self.epochs_for(ahead)),
+            Err(_) => epoch,
+        }
+    }
+
+    /// The epoch and time which estimates are extrapolated from: the latest sample, or the current
+    /// epoch at `now` without samples.
+    fn anchor(&self, now: SystemTime, current_epoch: u64) -> (u64, SystemTime) {
+        match self.samples.last() {
+            Some(sample) => (sample.epoch, UNIX_EPOCH + Duration::from_secs(sample.observed_at)),
+            None => (current_epoch, now),
+        }
+    }
+
+    /// A human friendly rendering of how long until the target epoch, such as `~12 min`.
+    pub fn describe_eta(&self, target: u64, now: SystemTime, current_epoch: u64) -> String {
+        match self.eta_of_epoch(target, now, current_epoch).duration_since(now) {
+            Ok(remaining) => format!("~{}", describe_duration(remaining)),
+            Err(_) => "now".to_string(),
+        }
+    }
+}
+
+/// The duration of the given number of epochs of the given length, saturating at `Duration::MAX`
+/// instead of overflowing or truncating the number of epochs.
+pub fn duration_of_epochs(length: Duration, epochs: u64) -> Duration {
+    if length.is_zero() {
+        return Duration::ZERO;
+    }
+    u32::try_from(epochs)
+        .ok()
+        .and_then(|epochs| length.checked_mul(epochs))
+        .unwrap_or(Duration::MAX)
+}
+
+/// Adds the duration to the time, or as much of it as the platform can represent.
+fn saturating_add(time: SystemTime, mut duration: Duration) -> SystemTime {
+    loop {
+        match time.checked_add(duration) {
+            Some(time) => return time,
+            None => duration /= 2,
+        }
+    }
+}
+
+/// Subtracts the duration from the time, or as much of it as the platform can represent.
+fn saturating_sub(time: SystemTime, mut duration: Duration) -> SystemTime {
+    loop {
+        match time.checked_sub(duration) {
+            Some(time) => return time,
+            None => duration /= 2,
+        }
+    }
+}
+
+/// Renders a duration in the largest unit which keeps it above one, such as `12 min` or `3 h`.
+pub fn describe_duration(duration: Duration) -> String {
+    let seconds: u64 = duration.as_secs();
+    match seconds {
+        0..=59 => format!("{} s", seconds),
+        60..=3599 => format!("{} min", (seconds + 30) / 60),
+        3600..=86399 => format!("{} h", (seconds + 1800) / 3600),
+        _ => format!("{} d", (seconds + 43200) / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    /// A clock which observed epochs lasting exactly a minute from epoch 100 at one million seconds.
+    fn sampled_clock() -> EpochClock {
+        let mut clock: EpochClock = EpochClock::new(Duration::from_secs(30));
+        for epoch in 100..105 {
+            clock.observe(epoch, at(1_000_000 + (epoch - 100) * 60));
+        }
+        clock
+    }
+
+    #[test]
+    fn the_default_length_is_used_without_two_epochs() {
+        let mut clock: EpochClock = EpochClock::new(MINUTE);
+        clock.observe(7, at(1_000));
+        clock.observe(7, at(1_030));
+        let estimate: EpochLengthEstimate = clock.estimate();
+        assert_eq!((estimate.mean, estimate.low, estimate.high), (MINUTE, MINUTE / 2, MINUTE * 2));
+        assert_eq!(estimate.transitions, 0);
+    }
+
+    #[test]
+    fn the_length_is_estimated_from_the_samples() {
+        let estimate: EpochLengthEstimate = sampled_clock().estimate();
+        assert_eq!((estimate.mean, estimate.low, estimate.high), (MINUTE, MINUTE, MINUTE));
+        assert_eq!(estimate.transitions, 4);
+    }
+
+    #[test]
+    fn durations_and_epochs_convert_both_ways() {
+        let clock: EpochClock = sampled_clock();
+        assert_eq!(clock.duration_of(12), MINUTE * 12);
+        assert_eq!(clock.epochs_for(MINUTE * 12), 12);
+        assert_eq!(clock.epochs_for(MINUTE * 12 + Duration::from_secs(1)), 13);
+    }
+
+    #[test]
+    fn large_numbers_of_epochs_saturate_instead_of_truncating() {
+        assert_eq!(duration_of_epochs(MINUTE, u64::from(u32::MAX) + 1), Duration::MAX);
+        assert_eq!(duration_of_epochs(MINUTE, u64::MAX), Duration::MAX);
+        assert_eq!(duration_of_epochs(Duration::MAX, 2), Duration::MAX);
+        assert_eq!(duration_of_epochs(Duration::ZERO, u64::MAX), Duration::ZERO);
+        assert!(sampled_clock().eta_of_epoch(u64::MAX, at(0), 0) > at(1_000_000));
+        assert!(sampled_clock().eta_of_epoch(0, at(0), 0) < at(1_000_000));
+    }
+
+    #[test]
+    fn etas_are_extrapolated_from_the_latest_sample() {
+        let clock: EpochClock = sampled_clock();
+        assert_eq!(clock.eta_of_epoch(114, at(0), 0), at(1_000_240 + 600));
+        assert_eq!(clock.eta_of_epoch(100, at(0), 0), at(1_000_000));
+        assert_eq!(clock.describe_eta(116, at(1_000_240), 104), "~12 min");
+        assert_eq!(clock.describe_eta(104, at(1_000_300), 104), "now");
+    }
+
+    #[test]
+    fn scheduling_picks_the_first_epoch_starting_after_the_time() {
+        let clock: EpochClock = sampled_clock();
+        assert_eq!(clock.schedule_at(at(1_000_240 + 600), at(0), 0), 114);
+        assert_eq!(clock.schedule_at(at(1_000_240 + 601), at(0), 0), 115);
+        assert_eq!(clock.schedule_at(at(10), at(0), 0), 104);
+    }
+
+    #[test]
+    fn the_clock_survives_a_round_trip_through_json() {
+        let clock: EpochClock = sampled_clock();
+        let restored: EpochClock = serde_json::from_str(&serde_json::to_string(&clock).unwrap()).unwrap();
+        assert_eq!(restored, clock);
+        assert_eq!(restored.estimate(), clock.estimate());
+    }
+}
@@ -0,0 +1,147 @@
+//! Typed events extracted from the logs of a receipt. Blueprints emit events by logging lines made
+//! up of a prefix followed by a JSON payload, for example `EVENT {"kind":"Purchase","amount":"2"}`.
+
+use serde::de::DeserializeOwned;
+
+use crate::Receipt;
+
+/// The prefix used to mark log lines as events when no other prefix is given.
+pub const DEFAULT_EVENT_PREFIX: &str = "EVENT ";
+
+/// The error returned when a log line marked as an event has a payload which can not be parsed.
+#[derive(Debug)]
+pub struct EventParseError {
+    /// The 1-based number of the offending line in the receipt's logs.
+    pub line: usize,
+    pub log: String,
+    pub error: serde_json::Error,
+}
+
+/// Strips the log level which the PTE prepends to logs (e.g. `[INFO ] `) if one is present.
+fn strip_log_level(log: &str) -> &str {
+    match (log.starts_with('['), log.find("] ")) {
+        (true, Some(index)) => &log[index + 2..],
+        _ => log,
+    }
+}
+
+impl Receipt {
+    /// Parses all of the events in the logs of the receipt into `T`, using the default prefix.
+    pub fn events<T: DeserializeOwned>(&self) -> Result<Vec<T>, EventParseError> {
+        self.events_with_prefix(DEFAULT_EVENT_PREFIX)
+    }
+
+    /// Parses all of the logs starting with the given prefix into `T`. Logs without the prefix are
+    /// ignored.
+    pub fn events_with_prefix<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<T>, EventParseError> {
+        self.logs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, log)| {
+                strip_log_level(log)
+                    .strip_prefix(prefix)
+                    .map(|payload| (index, log, payload))
+            })
+            .map(|(index, log, payload)| {
+                serde_json::from_str(payload).map_err(|error| EventParseError {
+                    line: index + 1,
+                    log: log.clone(),
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the untyped payloads of all of the events whose `kind` field matches the given kind.
+    pub fn events_of_kind(&self, kind: &str) -> Result<Vec<serde_json::Value>, EventParseError> {
+        Ok(self
+            .events::<serde_json::Value>()?
+            .into_iter()
+            .filter(|event| event.get("kind").and_then(serde_json::Value::as_str) == Some(kind))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    #[serde(tag = "kind")]
+    enum VendorEvent {
+        Purchase { amount: String },
+        Restock { items: u32 },
+    }
+
+    fn receipt(logs: &[&str]) -> Receipt {
+        serde_json::from_value(serde_json::json!({
+            "transaction_hash": "00",
+            "status": "Success",
+            "outputs": [],
+            "logs": logs,
+            "new_packages": [],
+            "new_components": [],
+            "new_resources": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn only_the_event_lines_of_mixed_logs_are_parsed() {
+        let receipt: Receipt = receipt(&[
+            "[INFO ] Vendor opened",
+            r#"[INFO ] EVENT {"kind":"Restock","items":3}"#,
+            "[DEBUG] EVENTS are logged below",
+            "a line without a log level",
+            r#"EVENT {"kind":"Purchase","amount":"2"}"#,
+            r#"[WARN ] event {"kind":"Purchase","amount":"9"}"#,
+            r#"[INFO ] {"kind":"Purchase","amount":"7"}"#,
+            r#"[INFO ] EVENT {"kind":"Purchase","amount":"1.5"}"#,
+        ]);
+
+        assert_eq!(
+            receipt.events::<VendorEvent>().unwrap(),
+            vec![
+                VendorEvent::Restock { items: 3 },
+                VendorEvent::Purchase { amount: "2".to_string() },
+                VendorEvent::Purchase { amount: "1.5".to_string() },
+            ]
+        );
+        assert_eq!(receipt.events_of_kind("Purchase").unwrap().len(), 2);
+        assert!(receipt.events_of_kind("Refund").unwrap().is_empty());
+    }
+
+    #[test]
+    fn other_prefixes_select_other_lines() {
+        let receipt: Receipt = receipt(&[
+            r#"[INFO ] EVENT {"kind":"Restock","items":3}"#,
+            r#"[INFO ] AUDIT {"kind":"Purchase","amount":"4"}"#,
+        ]);
+
+        assert_eq!(
+            receipt.events_with_prefix::<VendorEvent>("AUDIT ").unwrap(),
+            vec![VendorEvent::Purchase { amount: "4".to_string() }]
+        );
+    }
+
+    #[test]
+    fn malformed_payloads_report_their_line() {
+        let receipt: Receipt = receipt(&[
+            "[INFO ] Vendor opened",
+            r#"[INFO ] EVENT {"kind":"Restock","items":3}"#,
+            "[INFO ] EVENT {not json",
+        ]);
+
+        let error: EventParseError = receipt.events::<VendorEvent>().unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.log, "[INFO ] EVENT {not json");
+    }
+
+    #[test]
+    fn logs_without_events_have_none() {
+        let receipt: Receipt = receipt(&["[INFO ] Vendor opened", "[INFO ] Vendor closed"]);
+        assert!(receipt.events::<VendorEvent>().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! The example flows run by the binary when no command is given: creating an account and
+//! transferring XRD out of it. The beginner flow does the same in a few lines through `quick`.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use crate::args::{call_args, Args};
+use crate::builder::TransactionBuilderExt;
+use crate::client::PteClient;
+use crate::credentials::Credentials;
+use crate::deterministic::{DeterministicMode, Randomness};
+use crate::queue::ExecuteOptions;
+use crate::quick::{self, QuickError};
+use crate::report::{self, BatchReport};
+use crate::{Receipt, TransactionSubmissionError};
+
+/// Runs the example flows: creating an account and transferring XRD out of it. Returns the report of the
+/// transactions submitted, or the error of the first submission which failed.
+pub fn run_example(client: &PteClient) -> Result<BatchReport, TransactionSubmissionError> {
+    let mut report: BatchReport = BatchReport::new();
+    let mut randomness: Randomness = Randomness::new(DeterministicMode::Disabled);
+
+    // A single client is used for all of the requests so that connections to the PTE are reused. Warming it up
+    // establishes the connection before the first transaction is submitted.
+    for (url, error) in client.warm_up() {
+        println!("Failed to warm up the connection to the PTE at {}: {:?}", url, error);
+    }
+
+    // Here is a sample key-pair which you can use to do some quick testing if you would like :) The default signer of the
+    // client, such as the default identity of the CLI's configuration, is used instead when there is one.
+    let sample_private_key: EcdsaPrivateKey = EcdsaPrivateKey::from_bytes(&[124,159,161,54,212,65,63,166,23,54,55,232,131,182,153,141,50,225,214,117,248,140,221,255,157,203,207,51,24,32,244,184]).unwrap();
+    let private_key: &EcdsaPrivateKey = client.resolve_signer(Credentials::Default).unwrap_or(&sample_private_key);
+    let public_key: EcdsaPublicKey = private_key.public_key();
+
+    // Building a sample transaction to create a new account for the above key-pair
+    let withdraw_auth: AccessRule = rule!(require(NonFungibleAddress::new(
+        ECDSA_TOKEN,
+        NonFungibleId::from_bytes(public_key.to_vec())
+    )));
+    let account_creation_nonce: u64 = randomness.next_nonce();
+    let account_creation_tx: SignedTransaction = TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+            builder.new_account_with_resource(&withdraw_auth, bucket_id)
+        })
+        .build(account_creation_nonce)
+        .sign([private_key]);
+
+    let options: ExecuteOptions = ExecuteOptions::new().tag("flow", "example");
+    let account_creation_receipt: Receipt = report::timed(&mut report, "account creation", &options, &account_creation_tx, |tx, options| {
+        client.execute(tx, options)
+    })?;
+    let account_component_address: ComponentAddress = account_creation_receipt.new_components()[0];
+    println!("Account {} was created, receipt is: {:?}", account_component_address, account_creation_receipt);
+
+    // A sample transaction where we withdraw some XRD from the account we just created and deposit them into another
+    // account in the PTE.
+    let xrd_transfer_nonce: u64 = randomness.next_nonce();
+    let xrd_transfer_tx: SignedTransaction = TransactionBuilder::new()
+        .withdraw_from_account_by_amount(dec!("10000"), RADIX_TOKEN, account_component_address)
+        .take_from_worktop_ref(RADIX_TOKEN, |builder, bucket| {
+            builder.call_method(
+                ComponentAddress::from_str("02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173").unwrap(),
+                "deposit",
+                call_args(Args::new().add_bucket(bucket))
+            )
+        })
+        .build(xrd_transfer_nonce)
+        .sign([private_key]);
+
+    let xrd_transfer_receipt: Receipt = report::timed(&mut report, "xrd transfer", &options, &xrd_transfer_tx, |tx, options| {
+        client.execute(tx, options)
+    })?;
+    println!("XRD has been transferred, receipt is: {:?}", xrd_transfer_receipt);
+
+    Ok(report)
+}
+
+/// The same flows through the free functions of `quick`, which is all a short script needs: creating
+/// two accounts, transferring XRD between them and reading the balances of the second.
+pub fn run_beginner_example(pte_url: &str) -> Result<(), QuickError> {
+    let (key, alice) = quick::create_account(pte_url)?;
+    let (_, bob) = quick::create_account(pte_url)?;
+    println!("Created the accounts {} and {}", alice, bob);
+
+    let transaction_hash: String = quick::transfer(pte_url, &key, &alice, &bob, "100")?;
+    println!("Transferred 100 XRD in transaction {}", transaction_hash);
+
+    for (resource, amount) in quick::balance(pte_url, &bob)? {
+        println!("{} holds {} of {}", bob, amount, resource);
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Typed access to the fields forks of the PTE add to receipts. Fields the `Receipt` type doesn't
+//! know are kept in `Receipt::extra`, and the client decodes them with the `ReceiptExtension`s it
+//! was configured with. Each extension's output is attached to the receipt by its type, so any
+//! number of extensions coexist. A receipt whose extra fields an extension can't decode is still
+//! returned, with the failure reported by `Receipt::warnings`.
+
+use serde_json::{Map, Value};
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{Receipt, ReceiptWarning};
+
+type Decoder = Arc<dyn Fn(&Map<String, Value>) -> Result<Arc<dyn Any + Send + Sync>, String> + Send + Sync>;
+
+/// A decoder of the extra fields of receipts into a value of type `T`.
+#[derive(Clone)]
+pub struct ReceiptExtension {
+    name: String,
+    type_id: TypeId,
+    decoder: Decoder,
+}
+
+impl ReceiptExtension {
+    /// Creates an extension named after the fields it decodes, such as `state_root`. The name is
+    /// used in the warnings of receipts it fails to decode.
+    pub fn new<T, E>(name: &str, decoder: fn(&Map<String, Value>) -> Result<T, E>) -> Self
+    where
+        T: Any + Send + Sync,
+        E: std::fmt::Debug,
+    {
+        Self {
+            name: name.to_string(),
+            type_id: TypeId::of::<T>(),
+            decoder: Arc::new(move |extra| {
+                decoder(extra)
+                    .map(|value| Arc::new(value) as Arc<dyn Any + Send + Sync>)
+                    .map_err(|error| format!("{:?}", error))
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Decodes the extra fields of the receipt and attaches the value to it, or records why they
+    /// could not be decoded.
+    pub fn apply(&self, receipt: &mut Receipt) {
+        match (self.decoder)(&receipt.extra) {
+            Ok(value) => {
+                receipt.extensions.values.insert(self.type_id, value);
+            }
+            Err(error) => receipt.extensions.failures.push(ReceiptWarning::ExtensionDecodeFailed {
+                extension: self.name.clone(),
+                error,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Debug for ReceiptExtension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReceiptExtension").field("name", &self.name).finish()
+    }
+}
+
+/// The values the extensions of the client decoded from a receipt, keyed by their type.
+#[derive(Clone, Default)]
+pub struct ReceiptExtensions {
+    values: HashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+    failures: Vec<ReceiptWarning>,
+}
+
+impl ReceiptExtensions {
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.values.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref::<T>())
+    }
+
+    /// The extensions which failed to decode the receipt.
+    pub fn failures(&self) -> &[ReceiptWarning] {
+        &self.failures
+    }
+}
+
+impl std::fmt::Debug for ReceiptExtensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReceiptExtensions")
+            .field("decoded", &self.values.len())
+            .field("failures", &self.failures)
+            .finish()
+    }
+}
@@ -0,0 +1,275 @@
+//! A cooperative limiter of the transactions calling the faucet. The PTE throttles the faucet for
+//! everyone once it is called too often, so clients which create many accounts at once limit how many
+//! of their faucet transactions are in flight and space them out with some jitter. When the PTE
+//! throttles a faucet transaction the cap is halved, and it grows back by one for every recovery
+//! interval without throttling.
+
+use radix_engine::model::{Instruction, Transaction};
+use scrypto::prelude::*;
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::clock::{system_clock, Clock};
+use crate::{Receipt, TransactionSubmissionError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaucetLimiterConfig {
+    /// The number of faucet transactions in flight at once while the PTE isn't throttling.
+    pub max_in_flight: usize,
+    /// The minimum time between the starts of two faucet transactions.
+    pub spacing: Duration,
+    /// The upper bound of the random time added to the spacing, so that clients started together
+    /// don't stay in lockstep.
+    pub jitter: Duration,
+    /// The time without throttling after which the cap grows by one.
+    pub recovery_interval: Duration,
+}
+
+impl Default for FaucetLimiterConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 2,
+            spacing: Duration::from_millis(500),
+            jitter: Duration::from_millis(250),
+            recovery_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A snapshot of the metrics of a faucet limiter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaucetMetrics {
+    /// The number of faucet transactions currently allowed in flight at once.
+    pub cap: usize,
+    pub in_flight: usize,
+    pub acquired: u64,
+    /// The faucet transactions the PTE throttled.
+    pub throttled: u64,
+}
+
+struct LimiterState {
+    cap: usize,
+    in_flight: usize,
+    next_start: Option<Instant>,
+    /// When the cap was last halved or grown back.
+    adjusted_at: Instant,
+    acquired: u64,
+    throttled: u64,
+}
+
+/// A faucet limiter which can be shared between clients through an `Arc`, so that all of the
+/// clients of a process stay within the same cap.
+pub struct FaucetLimiter {
+    config: FaucetLimiterConfig,
+    clock: Arc<dyn Clock>,
+    state: Mutex<LimiterState>,
+    condvar: Condvar,
+}
+
+impl FaucetLimiter {
+    pub fn new(config: FaucetLimiterConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Creates the limiter with the clock used to space out and recover.
+    pub fn with_clock(config: FaucetLimiterConfig, clock: Arc<dyn Clock>) -> Self {
+        let now: Instant = clock.now();
+        Self {
+            config,
+            clock,
+            state: Mutex::new(LimiterState {
+                cap: config.max_in_flight.max(1),
+                in_flight: 0,
+                next_start: None,
+                adjusted_at: now,
+                acquired: 0,
+                throttled: 0,
+            }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<LimiterState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    pub fn metrics(&self) -> FaucetMetrics {
+        let mut state = self.lock();
+        self.recover(&mut state);
+        FaucetMetrics {
+            cap: state.cap,
+            in_flight: state.in_flight,
+            acquired: state.acquired,
+            throttled: state.throttled,
+        }
+    }
+
+    /// Waits until a faucet transaction may be sent: until fewer than the cap are in flight and the
+    /// jittered spacing since the start of the previous one has passed.
+    pub fn acquire(&self) -> FaucetPermit<'_> {
+        let mut state = self.lock();
+        self.recover(&mut state);
+        while state.in_flight >= state.cap {
+            state = self.condvar.wait(state).unwrap_or_else(|error| error.into_inner());
+            self.recover(&mut state);
+        }
+        state.in_flight += 1;
+        state.acquired += 1;
+
+        let now: Instant = self.clock.now();
+        let start_at: Instant = state.next_start.map_or(now, |next_start| next_start.max(now));
+        let jitter: Duration = self.config.jitter.mul_f64(rand::random::<f64>());
+        state.next_start = Some(start_at + self.config.spacing + jitter);
+        drop(state);
+
+        self.clock.sleep(start_at.saturating_duration_since(now));
+        FaucetPermit { limiter: self }
+    }
+
+    /// Halves the cap, down to a single faucet transaction in flight.
+    pub fn record_throttle(&self) {
+        let mut state = self.lock();
+        state.cap = (state.cap / 2).max(1);
+        state.adjusted_at = self.clock.now();
+        state.throttled += 1;
+    }
+
+    /// Grows the cap back by one for every full recovery interval since it was last adjusted.
+    fn recover(&self, state: &mut LimiterState) {
+        let now: Instant = self.clock.now();
+        if state.cap >= self.config.max_in_flight || self.config.recovery_interval.is_zero() {
+            state.adjusted_at = now;
+            return;
+        }
+        let cap: usize = state.cap;
+        while state.cap < self.config.max_in_flight
+            && now.saturating_duration_since(state.adjusted_at) >= self.config.recovery_interval
+        {
+            state.cap += 1;
+            state.adjusted_at += self.config.recovery_interval;
+        }
+        if state.cap > cap {
+            self.condvar.notify_all();
+        }
+    }
+}
+
+/// A faucet transaction in flight, released when dropped.
+pub struct FaucetPermit<'a> {
+    limiter: &'a FaucetLimiter,
+}
+
+impl<'a> FaucetPermit<'a> {
+    /// Releases the permit, halving the cap of the limiter when the PTE throttled the transaction.
+    pub fn complete(self, outcome: &Result<Receipt, TransactionSubmissionError>) {
+        if let Err(ref error) = outcome {
+            if is_faucet_throttle(error) {
+                self.limiter.record_throttle();
+            }
+        }
+    }
+}
+
+impl<'a> Drop for FaucetPermit<'a> {
+    fn drop(&mut self) {
+        self.limiter.lock().in_flight -= 1;
+        self.limiter.condvar.notify_all();
+    }
+}
+
+/// Whether the transaction calls the faucet of the system component.
+pub fn calls_faucet(transaction: &Transaction) -> bool {
+    transaction.instructions.iter().any(|instruction| {
+        matches!(
+            instruction,
+            Instruction::CallMethod { component_address, method, .. }
+                if *component_address == SYSTEM_COMPONENT && method == "free_xrd"
+        )
+    })
+}
+
+/// Whether the PTE refused the transaction because it is being sent too much, which for faucet
+/// transactions means the faucet is throttled.
+pub fn is_faucet_throttle(error: &TransactionSubmissionError) -> bool {
+    matches!(error, TransactionSubmissionError::Rejected { status: 429, .. })
+}
+
+#[cfg(all(test, feature = "testing", feature = "blocking-http"))]
+mod tests {
+    use super::*;
+
+    use radix_engine::model::SignedTransaction;
+
+    use crate::client::PteClient;
+    use crate::clock::TestClock;
+    use crate::fixtures::{account_creation_receipt, private_key, ACCOUNT, OTHER_ACCOUNT};
+    use crate::manifest_spec::ManifestSpec;
+    use crate::mock::{EndpointConfig, MockPte};
+    use crate::queue::ExecuteOptions;
+
+    fn new_account(client: &PteClient) -> SignedTransaction {
+        ManifestSpec::NewAccount.build(&private_key(), client.next_nonce()).unwrap()
+    }
+
+    fn transfer(client: &PteClient) -> SignedTransaction {
+        ManifestSpec::Transfer {
+            from: ACCOUNT.to_string(),
+            to: OTHER_ACCOUNT.to_string(),
+            resource: RADIX_TOKEN.to_string(),
+            amount: "1".to_string(),
+        }
+        .build(&private_key(), client.next_nonce())
+        .unwrap()
+    }
+
+    #[test]
+    fn throttled_faucet_transactions_halve_the_cap_until_it_recovers() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let limiter: Arc<FaucetLimiter> = Arc::new(FaucetLimiter::with_clock(
+            FaucetLimiterConfig {
+                max_in_flight: 4,
+                spacing: Duration::ZERO,
+                jitter: Duration::ZERO,
+                recovery_interval: Duration::from_secs(30),
+            },
+            clock.clone(),
+        ));
+        let mock: MockPte = MockPte::start(0)
+            .unwrap()
+            .with_endpoint("POST", "/transaction", EndpointConfig::new(429, "slow down"));
+        let client: PteClient = mock.client().with_faucet_limiter(limiter.clone());
+
+        for cap in [2, 1, 1] {
+            let outcome: Result<Receipt, TransactionSubmissionError> = client.execute(&new_account(&client), &ExecuteOptions::new());
+            assert!(matches!(outcome, Err(TransactionSubmissionError::Rejected { status: 429, .. })));
+            assert_eq!(limiter.metrics().cap, cap);
+        }
+        // Other transactions are neither limited nor counted as throttling the faucet
+        assert!(client.execute(&transfer(&client), &ExecuteOptions::new()).is_err());
+        assert_eq!(
+            limiter.metrics(),
+            FaucetMetrics {
+                cap: 1,
+                in_flight: 0,
+                acquired: 3,
+                throttled: 3,
+            }
+        );
+
+        // Faucet transactions which get through don't throttle, and the cap grows back over time
+        mock.set_endpoint(
+            "POST",
+            "/transaction",
+            EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap()),
+        );
+        assert!(client.execute(&new_account(&client), &ExecuteOptions::new()).is_ok());
+        assert_eq!(limiter.metrics().cap, 1);
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(limiter.metrics().cap, 2);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(limiter.metrics().cap, 4);
+        assert_eq!(limiter.metrics().throttled, 3);
+        assert_eq!(mock.request_count("POST", "/transaction"), 5);
+    }
+}
@@ -0,0 +1,299 @@
+//! Realistic sample data for tests of code built on this crate, so that mocks don't have to
+//! hand-write receipts and envelopes which drift from what the PTE returns. Every fixture is built
+//! from the real types of the crate and returned as an owned value which tests are free to mutate.
+//! The transaction bodies are those of the checked-in test vectors, and the account of the envelope
+//! is controlled by the key which signed them.
+
+use scrypto::buffer::scrypto_encode;
+use scrypto::prelude::*;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::access_rules::virtual_badge;
+use crate::component::{BlueprintInfo, ComponentEnvelope, ComponentInfo, OwnedResource};
+use crate::deterministic::Randomness;
+use crate::entities::EntityKind;
+use crate::test_vectors::{generate_test_vectors, TEST_VECTORS_MODE};
+use crate::{CostSummary, Receipt, TransactionBody};
+
+/// The account created by the account creation fixtures.
+pub const ACCOUNT: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173";
+
+/// The account the transfer fixtures send to.
+pub const OTHER_ACCOUNT: &str = "020d3869346218a5e8deaaf2001216dc00fcacb79fb43e30ded79a";
+
+/// The package published by the publish fixture.
+pub const PACKAGE: &str = "01bda8686d6c2fa45dce04fac71a09b54efbc8028c23aac74bc00e";
+
+/// The first resource created by the multi-creation fixture.
+pub const GOLD_TOKEN: &str = "03ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b980";
+
+/// The second resource created by the multi-creation fixture.
+pub const SILVER_TOKEN: &str = "033e23e8160039594a33894f6564e1b1348bbd7a0088d42c4acb73";
+
+/// The component created by the multi-creation fixture.
+pub const VENDOR: &str = "024c94485e0c21ae6c41ce1dfe7b6bfaceea5ab68e40a2476f5020";
+
+/// The key which signed the fixture transactions and controls `ACCOUNT`.
+pub fn private_key() -> EcdsaPrivateKey {
+    Randomness::new(TEST_VECTORS_MODE).new_private_key()
+}
+
+/// The receipt of the successful creation of `ACCOUNT` funded from the faucet.
+pub fn account_creation_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "3f1a0c0b8e3ad5b7cd4f6e3a9d4b1d9d7b5c5f2e9e8a6d1c0b4a3f2e1d0c9b8a".to_string(),
+        status: "Success".to_string(),
+        outputs: vec![
+            hex::encode(scrypto_encode(&())),
+            hex::encode(scrypto_encode(&ComponentAddress::from_str(ACCOUNT).unwrap())),
+        ],
+        logs: Vec::new(),
+        new_packages: Vec::new(),
+        new_components: vec![ACCOUNT.to_string()],
+        new_resources: Vec::new(),
+        cost: Some(CostSummary {
+            total_cost_units: 48_212,
+            ..Default::default()
+        }),
+        extra: Default::default(),
+        extensions: Default::default(),
+    }
+}
+
+/// The receipt of a transfer out of `ACCOUNT` which failed because it wasn't signed by the key
+/// controlling the account.
+pub fn failed_transfer_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "9c2e7b1f4d6a8c0e2b4d6f8a0c2e4b6d8f0a2c4e6b8d0f2a4c6e8b0d2f4a6c8e".to_string(),
+        status: "Failure: AuthorizationError { function: \"withdraw_by_amount\", authorization: Unauthorized }".to_string(),
+        outputs: Vec::new(),
+        logs: vec![
+            format!("[ERROR] Authorization of the call to withdraw_by_amount on {} failed", ACCOUNT),
+            format!("[ERROR] The proofs on the auth zone don't satisfy the rule requiring {}", virtual_badge(&private_key().public_key())),
+        ],
+        new_packages: Vec::new(),
+        new_components: Vec::new(),
+        new_resources: Vec::new(),
+        cost: None,
+        extra: Default::default(),
+        extensions: Default::default(),
+    }
+}
+
+/// The receipt of the successful publication of `PACKAGE`.
+pub fn publish_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "5a7c9e1b3d5f7a9c1e3b5d7f9a1c3e5b7d9f1a3c5e7b9d1f3a5c7e9b1d3f5a7c".to_string(),
+        status: "Success".to_string(),
+        outputs: vec![hex::encode(scrypto_encode(&PackageAddress::from_str(PACKAGE).unwrap()))],
+        logs: vec!["[INFO ] Package published".to_string()],
+        new_packages: vec![PACKAGE.to_string()],
+        new_components: Vec::new(),
+        new_resources: Vec::new(),
+        cost: Some(CostSummary {
+            total_cost_units: 1_204_775,
+            ..Default::default()
+        }),
+        extra: Default::default(),
+        extensions: Default::default(),
+    }
+}
+
+/// The receipt of a transaction of four instructions against `PACKAGE`: two calls creating
+/// `GOLD_TOKEN` and then `SILVER_TOKEN` and returning their addresses, a call instantiating `VENDOR`
+/// with both and returning its address, and a deposit of everything left into `ACCOUNT`. Each list
+/// of new entities is in the order of creation.
+pub fn multi_creation_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "b4e2d0c8a6f4e2d0b8c6a4f2e0d8b6c4a2f0e8d6c4b2a0f8e6d4c2b0a8f6e4d2".to_string(),
+        status: "Success".to_string(),
+        outputs: vec![
+            hex::encode(scrypto_encode(&ResourceAddress::from_str(GOLD_TOKEN).unwrap())),
+            hex::encode(scrypto_encode(&ResourceAddress::from_str(SILVER_TOKEN).unwrap())),
+            hex::encode(scrypto_encode(&ComponentAddress::from_str(VENDOR).unwrap())),
+            hex::encode(scrypto_encode(&())),
+        ],
+        logs: Vec::new(),
+        new_packages: Vec::new(),
+        new_components: vec![VENDOR.to_string()],
+        new_resources: vec![GOLD_TOKEN.to_string(), SILVER_TOKEN.to_string()],
+        cost: None,
+        extra: Default::default(),
+        extensions: Default::default(),
+    }
+}
+
+/// The receipt of `multi_creation_receipt` as reported by PTE builds which list `VENDOR` twice in
+/// the new components.
+pub fn duplicate_components_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "c5f3e1d9b7a5f3e1c9d7b5a3f1e9c7b5a3f1e9d7c5b3a1f9e7d5c3b1a9f7e5d3".to_string(),
+        new_components: vec![VENDOR.to_string(), VENDOR.to_string()],
+        ..multi_creation_receipt()
+    }
+}
+
+/// The receipt of `multi_creation_receipt` with `GOLD_TOKEN` also reported as a new component.
+pub fn inconsistent_lists_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "d6a4f2e0c8b6a4f2d0e8c6b4a2f0d8e6c4b2a0f8d6e4c2b0a8f6d4e2c0b8a6f4".to_string(),
+        new_components: vec![VENDOR.to_string(), GOLD_TOKEN.to_string()],
+        ..multi_creation_receipt()
+    }
+}
+
+/// The `/component` envelope of `ACCOUNT` holding the XRD of the faucet. Withdrawing requires the
+/// virtual badge of `private_key`. The state stands in for the account's map of vaults, which the
+/// PTE reports by ID.
+pub fn account_envelope() -> ComponentEnvelope {
+    let badge: String = virtual_badge(&private_key().public_key()).to_string();
+    ComponentEnvelope {
+        blueprint: BlueprintInfo {
+            package_address: ACCOUNT_PACKAGE.to_string(),
+            blueprint_name: "Account".to_string(),
+        },
+        authorization: vec![json!({
+            "method_auth": {
+                "balance": "AllowAll",
+                "deposit": "AllowAll",
+                "deposit_batch": "AllowAll",
+            },
+            "default_auth": { "Protected": { "ProofRule": { "Require": badge } } },
+        })],
+        state: hex::encode(scrypto_encode(&(1u32,))),
+        owned_resources: vec![OwnedResource {
+            vault_id: Some(format!("{}01000000", "0".repeat(64))),
+            resource_address: RADIX_TOKEN.to_string(),
+            amount: "1000000".to_string(),
+            non_fungible_ids: None,
+            raw: None,
+        }],
+        extra_fields: serde_json::Map::new(),
+    }
+}
+
+pub fn account_component_info() -> ComponentInfo {
+    ComponentInfo::from_envelope(ComponentAddress::from_str(ACCOUNT).unwrap(), account_envelope())
+}
+
+/// The transaction body of the account creation test vector.
+pub fn account_creation_body() -> TransactionBody {
+    test_vector_body("account_creation")
+}
+
+/// The transaction body of the XRD transfer test vector.
+pub fn xrd_transfer_body() -> TransactionBody {
+    test_vector_body("xrd_transfer")
+}
+
+fn test_vector_body(name: &str) -> TransactionBody {
+    generate_test_vectors(TEST_VECTORS_MODE)
+        .expect("the test vectors always build")
+        .into_iter()
+        .find(|x| x.name == name)
+        .expect("the test vector exists")
+        .transaction_body
+}
+
+/// Checks that every fixture survives a round-trip through its serde types, returning the names of
+/// those which don't. A fixture failing here no longer matches the shape the crate parses.
+pub fn check_round_trips() -> Vec<String> {
+    let mut mismatches: Vec<String> = Vec::new();
+    let mut check = |name: &str, round_trips: bool| {
+        if !round_trips {
+            mismatches.push(name.to_string());
+        }
+    };
+    check("account_creation_receipt", round_trips(&account_creation_receipt()));
+    check("failed_transfer_receipt", round_trips(&failed_transfer_receipt()));
+    check("publish_receipt", round_trips(&publish_receipt()));
+    check("multi_creation_receipt", round_trips(&multi_creation_receipt()));
+    check("duplicate_components_receipt", round_trips(&duplicate_components_receipt()));
+    check("inconsistent_lists_receipt", round_trips(&inconsistent_lists_receipt()));
+    check("account_creation_body", round_trips(&account_creation_body()));
+    check("xrd_transfer_body", round_trips(&xrd_transfer_body()));
+    check("account_component_info", round_trips(&account_component_info()));
+
+    let envelope: ComponentEnvelope = account_envelope();
+    check(
+        "account_envelope",
+        ComponentEnvelope::parse(&serde_json::to_string(&envelope).unwrap())
+            .map(|parsed| serde_json::to_value(&parsed).unwrap() == serde_json::to_value(&envelope).unwrap() && parsed.drift().is_none())
+            .unwrap_or(false),
+    );
+    check("account_creation_receipt new_components", account_creation_receipt().new_components().len() == 1);
+    check("failed_transfer_receipt status", !failed_transfer_receipt().is_success());
+    check("publish_receipt new_packages", publish_receipt().new_packages().len() == 1);
+    check(
+        "multi_creation_receipt created_entities",
+        multi_creation_receipt()
+            .created_entities()
+            .iter()
+            .map(|entity| (entity.kind, entity.address.as_str(), entity.origin_instruction))
+            .eq([
+                (EntityKind::Resource, GOLD_TOKEN, Some(0)),
+                (EntityKind::Resource, SILVER_TOKEN, Some(1)),
+                (EntityKind::Component, VENDOR, Some(2)),
+            ]),
+    );
+    check(
+        "multi_creation_receipt expect_single_new_resource",
+        multi_creation_receipt().expect_single_new_resource(None).is_err()
+            && multi_creation_receipt().expect_single_new_resource(Some(1)) == Ok(ResourceAddress::from_str(SILVER_TOKEN).unwrap()),
+    );
+    mismatches
+}
+
+fn round_trips<T: Serialize + DeserializeOwned>(value: &T) -> bool {
+    let json: serde_json::Value = serde_json::to_value(value).unwrap();
+    serde_json::from_value::<T>(json.clone())
+        .map(|parsed| serde_json::to_value(&parsed).unwrap() == json)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the fixture survives a round-trip through both a JSON value and its text.
+    fn assert_round_trips<T: Serialize + DeserializeOwned>(value: &T) {
+        assert!(round_trips(value));
+        let text: String = serde_json::to_string(value).unwrap();
+        let parsed: T = serde_json::from_str(&text).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), text);
+    }
+
+    #[test]
+    fn receipts_round_trip() {
+        assert_round_trips(&account_creation_receipt());
+        assert_round_trips(&failed_transfer_receipt());
+        assert_round_trips(&publish_receipt());
+        assert_round_trips(&multi_creation_receipt());
+        assert_round_trips(&duplicate_components_receipt());
+        assert_round_trips(&inconsistent_lists_receipt());
+    }
+
+    #[test]
+    fn transaction_bodies_round_trip() {
+        assert_round_trips(&account_creation_body());
+        assert_round_trips(&xrd_transfer_body());
+    }
+
+    #[test]
+    fn components_round_trip() {
+        assert_round_trips(&account_envelope());
+        assert_round_trips(&account_component_info());
+
+        let parsed: ComponentEnvelope = ComponentEnvelope::parse(&serde_json::to_string(&account_envelope()).unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), serde_json::to_value(&account_envelope()).unwrap());
+        assert!(parsed.drift().is_none());
+    }
+
+    #[test]
+    fn all_of_the_fixtures_pass_the_round_trip_check() {
+        assert_eq!(check_round_trips(), Vec::<String>::new());
+    }
+}
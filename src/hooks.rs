@@ -0,0 +1,251 @@
+//! Hooks executed on the lifecycle events of submissions, allowing side effects such as notifying a
+//! chat channel on failure or recording successes in a database without changing the client. The
+//! blocking client runs them on the submitting thread, and the asynchronous client on the blocking
+//! threads of its runtime. The client's own mirror, counters and reports are built-in hooks
+//! registered in the same way.
+
+use radix_engine::model::SignedTransaction;
+use scrypto::buffer::scrypto_encode;
+use scrypto::crypto::hash;
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "blocking-http")]
+use crate::mirror::Mirror;
+#[cfg(feature = "metrics")]
+use crate::report::BatchReport;
+#[cfg(feature = "metrics")]
+use std::sync::Mutex;
+use crate::{Receipt, ReceiptWarning, TransactionBody, TransactionSubmissionError};
+
+/// The hashes identifying a submitted transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxHashes {
+    /// The hash of the SBOR encoded transaction, computed locally.
+    pub transaction_hash: String,
+}
+
+impl TxHashes {
+    pub fn of(transaction: &SignedTransaction) -> Self {
+        Self {
+            transaction_hash: hash(scrypto_encode(&transaction.transaction)).to_string(),
+        }
+    }
+}
+
+/// Why a submission failed: either the PTE committed a failed transaction, or the submission itself
+/// failed.
+#[derive(Debug)]
+pub enum SubmissionFailure<'a> {
+    Receipt(&'a Receipt),
+    Error(&'a TransactionSubmissionError),
+}
+
+/// A finished submission, passed to the hooks registered with `on_completed` after the committed or
+/// failed hooks ran.
+#[derive(Debug)]
+pub struct Completion<'a> {
+    pub hashes: &'a TxHashes,
+    pub transaction: &'a SignedTransaction,
+    /// How long the submission took on the clock of the client.
+    pub duration: Duration,
+    pub outcome: &'a Result<Receipt, TransactionSubmissionError>,
+}
+
+/// A condition the client adapted to instead of failing, reported once when it is first detected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientWarning {
+    /// The server has no epoch endpoint, so the current epoch is unknown. Intents are given the
+    /// static validity window instead and their expiry isn't checked.
+    EpochEndpointUnavailable { url: String, static_validity_window: u64 },
+    /// The worker of an outbox could not read its store, or could not record the outcome of an
+    /// entry in it. The entries concerned stay pending and are attempted again.
+    OutboxStoreFailed { entry: Option<u64>, error: String },
+    /// The receipt of a transaction reports its new entities inconsistently, or couldn't be decoded
+    /// by one of the receipt extensions of the client.
+    InconsistentReceipt { transaction_hash: String, warning: ReceiptWarning },
+    /// A test context could not sweep one of its accounts or remove its directory when dropped.
+    /// Cleanup is best effort, so the test itself doesn't fail.
+    TestContextCleanupFailed { context: String, resource: String, error: String },
+    /// A hook registered on the event panicked. The panic was caught and the submission and the
+    /// other hooks were not affected.
+    HookPanicked { event: String },
+}
+
+type SubmittedHook = Box<dyn Fn(&TxHashes, &TransactionBody) + Send + Sync>;
+type CommittedHook = Box<dyn Fn(&Receipt) + Send + Sync>;
+type FailedHook = Box<dyn Fn(&SubmissionFailure) + Send + Sync>;
+type CompletedHook = Box<dyn Fn(&Completion) + Send + Sync>;
+type WarningHook = Box<dyn Fn(&ClientWarning) + Send + Sync>;
+
+/// The hooks registered on a client. Hooks of the same event run in the order they were registered
+/// and a panicking hook is caught and reported as a `ClientWarning::HookPanicked`, so that it
+/// neither affects the submission nor the hooks registered after it.
+#[derive(Default)]
+pub struct Hooks {
+    submitted: Vec<SubmittedHook>,
+    committed: Vec<CommittedHook>,
+    failed: Vec<FailedHook>,
+    completion: Vec<CompletedHook>,
+    warning: Vec<WarningHook>,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_submitted(mut self, hook: impl Fn(&TxHashes, &TransactionBody) + Send + Sync + 'static) -> Self {
+        self.submitted.push(Box::new(hook));
+        self
+    }
+
+    pub fn on_committed(mut self, hook: impl Fn(&Receipt) + Send + Sync + 'static) -> Self {
+        self.committed.push(Box::new(hook));
+        self
+    }
+
+    pub fn on_failed(mut self, hook: impl Fn(&SubmissionFailure) + Send + Sync + 'static) -> Self {
+        self.failed.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a hook run after the committed or failed hooks of every submission, with how long
+    /// the submission took.
+    pub fn on_completed(mut self, hook: impl Fn(&Completion) + Send + Sync + 'static) -> Self {
+        self.completion.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a hook receiving the warnings of the client. With the `tracing` feature every
+    /// warning is also emitted as a `tracing` event. The client never prints warnings itself.
+    pub fn on_warning(mut self, hook: impl Fn(&ClientWarning) + Send + Sync + 'static) -> Self {
+        self.warning.push(Box::new(hook));
+        self
+    }
+
+    /// Appends the hooks of `other` after the hooks already registered.
+    pub fn extend(mut self, other: Hooks) -> Self {
+        self.submitted.extend(other.submitted);
+        self.committed.extend(other.committed);
+        self.failed.extend(other.failed);
+        self.completion.extend(other.completion);
+        self.warning.extend(other.warning);
+        self
+    }
+
+    /// Registers the built-in hooks recording the receipts of the submissions in the mirror,
+    /// whether the transaction committed or failed.
+    #[cfg(feature = "blocking-http")]
+    pub fn with_mirror(self, mirror: Arc<Mirror>) -> Self {
+        let failed: Arc<Mirror> = mirror.clone();
+        self.on_committed(move |receipt| mirror.record_receipt(receipt))
+            .on_failed(move |failure| {
+                if let SubmissionFailure::Receipt(receipt) = failure {
+                    failed.record_receipt(receipt);
+                }
+            })
+    }
+
+    /// Registers the built-in hook recording every submission into the report under the label.
+    #[cfg(feature = "metrics")]
+    pub fn with_report(self, label: &str, report: Arc<Mutex<BatchReport>>) -> Self {
+        let label: String = label.to_string();
+        self.on_completed(move |completion| {
+            report.lock().unwrap_or_else(|e| e.into_inner()).record(
+                &label,
+                &Default::default(),
+                completion.transaction,
+                completion.duration,
+                completion.outcome,
+            );
+        })
+    }
+
+    /// Registers the built-in hooks counting the submissions, returning the counters.
+    pub fn with_counters(self) -> (Self, Arc<SubmissionCounters>) {
+        let counters: Arc<SubmissionCounters> = Arc::new(SubmissionCounters::default());
+        let (submitted, committed, failed) = (counters.clone(), counters.clone(), counters.clone());
+        let hooks: Hooks = self
+            .on_submitted(move |_, _| {
+                submitted.submitted.fetch_add(1, Ordering::Relaxed);
+            })
+            .on_committed(move |_| {
+                committed.committed.fetch_add(1, Ordering::Relaxed);
+            })
+            .on_failed(move |_| {
+                failed.failed.fetch_add(1, Ordering::Relaxed);
+            });
+        (hooks, counters)
+    }
+
+    pub(crate) fn submitted(&self, hashes: &TxHashes, transaction_body: &TransactionBody) {
+        for hook in self.submitted.iter() {
+            self.run_isolated("on_submitted", || hook(hashes, transaction_body));
+        }
+    }
+
+    /// Runs the committed or failed hooks depending on the outcome of the submission, then the
+    /// completed hooks.
+    pub(crate) fn completed(&self, completion: &Completion) {
+        match completion.outcome {
+            Ok(receipt) if receipt.is_success() => {
+                for hook in self.committed.iter() {
+                    self.run_isolated("on_committed", || hook(receipt));
+                }
+            }
+            Ok(receipt) => self.failed(&SubmissionFailure::Receipt(receipt)),
+            Err(error) => self.failed(&SubmissionFailure::Error(error)),
+        }
+        for hook in self.completion.iter() {
+            self.run_isolated("on_completed", || hook(completion));
+        }
+    }
+
+    pub(crate) fn warned(&self, warning: &ClientWarning) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(?warning, "client warning");
+        for hook in self.warning.iter() {
+            self.run_isolated("on_warning", || hook(warning));
+        }
+    }
+
+    /// Reports each of the warnings of a receipt received by the client.
+    pub(crate) fn received(&self, receipt: &Receipt) {
+        for warning in receipt.warnings() {
+            self.warned(&ClientWarning::InconsistentReceipt {
+                transaction_hash: receipt.transaction_hash.clone(),
+                warning,
+            });
+        }
+    }
+
+    fn failed(&self, failure: &SubmissionFailure) {
+        for hook in self.failed.iter() {
+            self.run_isolated("on_failed", || hook(failure));
+        }
+    }
+
+    /// Runs the hook, reporting a panic as a warning. A panic of a warning hook is only emitted as
+    /// a `tracing` event, so that a panicking warning hook can't recurse.
+    fn run_isolated(&self, event: &str, hook: impl FnOnce()) {
+        if catch_unwind(AssertUnwindSafe(hook)).is_err() {
+            #[cfg(feature = "tracing")]
+            tracing::error!(event, "a hook panicked; the panic was ignored");
+            if event != "on_warning" {
+                self.warned(&ClientWarning::HookPanicked { event: event.to_string() });
+            }
+        }
+    }
+}
+
+/// Counts of the submissions made by a client, maintained by the built-in counter hooks.
+#[derive(Debug, Default)]
+pub struct SubmissionCounters {
+    pub submitted: AtomicU64,
+    pub committed: AtomicU64,
+    pub failed: AtomicU64,
+}
@@ -0,0 +1,225 @@
+//! Application level idempotency keys. Business operations which may be retried above the level of
+//! transactions are given a key, and a key which already executed successfully returns the receipt
+//! of its earlier execution instead of submitting a new transaction.
+
+use radix_engine::model::SignedTransaction;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::canonical_json::to_canonical_vec;
+use crate::client::PteClient;
+use crate::queue::ExecuteOptions;
+use crate::{Receipt, TransactionSubmissionError};
+
+/// A store mapping idempotency keys to the receipts of the transactions which committed them.
+pub trait IdempotencyStore: Send + Sync {
+    fn get(&self, key: &str) -> std::io::Result<Option<Receipt>>;
+
+    /// Records the committed receipt of the key. Must be atomic: either the mapping is fully
+    /// stored or not at all.
+    fn put(&self, key: &str, receipt: &Receipt) -> std::io::Result<()>;
+}
+
+/// An idempotency store which is lost when the process exits.
+#[derive(Default)]
+pub struct MemoryIdempotencyStore {
+    receipts: Mutex<HashMap<String, Receipt>>,
+}
+
+impl IdempotencyStore for MemoryIdempotencyStore {
+    fn get(&self, key: &str) -> std::io::Result<Option<Receipt>> {
+        Ok(self.receipts.lock().unwrap().get(key).cloned())
+    }
+
+    fn put(&self, key: &str, receipt: &Receipt) -> std::io::Result<()> {
+        self.receipts.lock().unwrap().insert(key.to_string(), receipt.clone());
+        Ok(())
+    }
+}
+
+/// An idempotency store persisted to a canonical JSON file, so that equal stores are always written
+/// as the same bytes. Writes go to a temporary file which is then
+/// renamed over the store so that a crash never leaves a partially written store behind.
+pub struct FileIdempotencyStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileIdempotencyStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> std::io::Result<BTreeMap<String, Receipt>> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl IdempotencyStore for FileIdempotencyStore {
+    fn get(&self, key: &str) -> std::io::Result<Option<Receipt>> {
+        let _guard = self.lock.lock().unwrap();
+        Ok(self.read()?.remove(key))
+    }
+
+    fn put(&self, key: &str, receipt: &Receipt) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut receipts: BTreeMap<String, Receipt> = self.read()?;
+        receipts.insert(key.to_string(), receipt.clone());
+
+        let temporary_path: PathBuf = self.path.with_extension("tmp");
+        fs::write(&temporary_path, to_canonical_vec(&receipts).unwrap())?;
+        fs::rename(&temporary_path, &self.path)
+    }
+}
+
+/// The idempotency store of a client along with the per-key locks which prevent concurrent calls
+/// with the same key from submitting twice. The lock of a key is only kept while calls with the key
+/// are in progress.
+pub struct Idempotency {
+    store: Box<dyn IdempotencyStore>,
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl Idempotency {
+    pub fn new(store: impl IdempotencyStore + 'static) -> Self {
+        Self {
+            store: Box::new(store),
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock_for(&self, key: &str) -> KeyLock<'_> {
+        let lock: Arc<Mutex<()>> = self
+            .locks
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        KeyLock {
+            idempotency: self,
+            key: key.to_string(),
+            lock,
+        }
+    }
+
+    /// The number of keys with calls in progress.
+    pub fn locked_keys(&self) -> usize {
+        self.locks.lock().unwrap_or_else(|error| error.into_inner()).len()
+    }
+}
+
+/// A share of the lock of a key. The last share of a key removes its lock from the map once it is
+/// dropped. Shares are only created and dropped while holding the map, so no other call can pick up
+/// a lock which is being removed.
+struct KeyLock<'a> {
+    idempotency: &'a Idempotency,
+    key: String,
+    lock: Arc<Mutex<()>>,
+}
+
+impl<'a> Drop for KeyLock<'a> {
+    fn drop(&mut self) {
+        let mut locks = self.idempotency.locks.lock().unwrap_or_else(|error| error.into_inner());
+        // The map holds one reference and this share the other.
+        if Arc::strong_count(&self.lock) == 2 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+impl PteClient {
+    /// Executes the transaction unless the idempotency key was already executed successfully, in
+    /// which case the receipt of the earlier execution is returned. The key is only recorded once
+    /// the transaction is committed successfully, so keys of failed transactions can be retried.
+    pub fn execute_idempotent(
+        &self,
+        key: &str,
+        transaction: &SignedTransaction,
+        options: &ExecuteOptions,
+    ) -> Result<Receipt, TransactionSubmissionError> {
+        let idempotency: &Idempotency = match self.idempotency() {
+            Some(idempotency) => idempotency,
+            None => return Err(TransactionSubmissionError::IdempotencyNotConfigured),
+        };
+
+        let key_lock: KeyLock = idempotency.lock_for(key);
+        let _guard = key_lock.lock.lock().unwrap_or_else(|error| error.into_inner());
+        let stored: Option<Receipt> = idempotency
+            .store
+            .get(key)
+            .map_err(TransactionSubmissionError::IdempotencyStoreError)?;
+        if let Some(receipt) = stored {
+            return Ok(receipt);
+        }
+
+        let receipt: Receipt = self.execute(transaction, options)?;
+        if receipt.is_success() {
+            idempotency
+                .store
+                .put(key, &receipt)
+                .map_err(TransactionSubmissionError::IdempotencyStoreError)?;
+        }
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn locks_are_evicted_once_no_call_holds_them() {
+        let idempotency: Idempotency = Idempotency::new(MemoryIdempotencyStore::default());
+        {
+            let first: KeyLock = idempotency.lock_for("payout-1");
+            let second: KeyLock = idempotency.lock_for("payout-1");
+            let _other: KeyLock = idempotency.lock_for("payout-2");
+            assert!(Arc::ptr_eq(&first.lock, &second.lock));
+            assert_eq!(idempotency.locked_keys(), 2);
+
+            drop(first);
+            assert_eq!(idempotency.locked_keys(), 2);
+        }
+        assert_eq!(idempotency.locked_keys(), 0);
+    }
+
+    #[test]
+    fn concurrent_calls_with_the_same_key_are_serialized_and_then_evicted() {
+        let idempotency: Arc<Idempotency> = Arc::new(Idempotency::new(MemoryIdempotencyStore::default()));
+        let in_critical_section: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<thread::JoinHandle<()>> = (0..8)
+            .map(|_| {
+                let (idempotency, in_critical_section) = (idempotency.clone(), in_critical_section.clone());
+                thread::spawn(move || {
+                    for _ in 0..50 {
+                        let key_lock: KeyLock = idempotency.lock_for("payout-1");
+                        let _guard = key_lock.lock.lock().unwrap();
+                        assert_eq!(in_critical_section.fetch_add(1, Ordering::SeqCst), 0);
+                        thread::sleep(Duration::from_micros(10));
+                        in_critical_section.fetch_sub(1, Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(idempotency.locked_keys(), 0);
+    }
+}
@@ -0,0 +1,439 @@
+//! Multi-party signing of a transaction intent whose signatures are collected over time. The
+//! coordinator builds the intent once and hands it out to the signers, who each return a signature
+//! over it. Intents are only valid for a window of epochs so that signatures collected long ago can't
+//! be used to submit a transaction nobody expects anymore.
+//!
+//! The current epoch is read from the `/epoch` endpoint of the PTE. Older PTE snapshots don't have
+//! the endpoint, in which case the epoch is unknown: intents are then given the client's static
+//! validity window and their expiry isn't checked, and a warning is emitted once per server.
+//!
+//! Rebasing an expired intent only replaces its nonce, which the decompiled manifest doesn't include,
+//! so the manifest text is decompiled once per package and reused by every rebuild and submission.
+
+use radix_engine::model::{Instruction, SignedTransaction, Transaction};
+use scrypto::buffer::scrypto_encode;
+use scrypto::prelude::*;
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::client::{Horizon, PteClient};
+use crate::epoch_clock::{describe_duration, duration_of_epochs, EpochClock};
+use crate::hooks::ClientWarning;
+use crate::utils::decompile;
+use crate::PteApiError;
+
+/// The nominal length of an epoch, used when no `EpochClock` estimate is at hand.
+pub const EPOCH_LENGTH: Duration = Duration::from_secs(60);
+
+/// The window, in epochs, given to intents created while the current epoch is unknown.
+pub const DEFAULT_STATIC_VALIDITY_WINDOW: u64 = 10;
+
+/// The number of epochs left before an intent expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EpochsRemaining(pub u64);
+
+impl EpochsRemaining {
+    pub fn is_expired(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The approximate wall-clock time left before the intent expires.
+    pub fn as_duration(&self) -> Duration {
+        duration_of_epochs(EPOCH_LENGTH, self.0)
+    }
+
+    /// A human friendly rendering of the time left based on the observed epoch length, such as
+    /// `expires in ~12 min`.
+    pub fn describe(&self, epoch_clock: &EpochClock) -> String {
+        if self.is_expired() {
+            "expired".to_string()
+        } else {
+            format!("expires in ~{}", describe_duration(epoch_clock.duration_of(self.0)))
+        }
+    }
+}
+
+/// The epochs an intent is valid in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntentValidity {
+    /// Valid from `start_epoch` until, but not including, `expires_at_epoch`.
+    Epochs { start_epoch: u64, expires_at_epoch: u64 },
+    /// The epoch was unknown when the intent was created, so it was given the static window of the
+    /// client and never expires client-side.
+    UnknownEpoch { window: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntentError {
+    /// The intent expired at `expires_at_epoch` and can't be signed or submitted anymore. The
+    /// coordinator needs to `rebase` it and collect the signatures again.
+    Expired { expires_at_epoch: u64, current_epoch: u64 },
+    /// The signature came from a key which isn't one of the signers of the intent.
+    UnexpectedSigner(EcdsaPublicKey),
+    /// The signature is not a valid signature of the signer over the intent, for example because it
+    /// was made over the intent before it was rebased.
+    InvalidSignature(EcdsaPublicKey),
+    /// The intent can't be submitted before all of its signers have signed it.
+    MissingSignatures(Vec<EcdsaPublicKey>),
+    /// The intent has no nonce instruction to replace when rebasing it.
+    NoNonceFound,
+    /// The current epoch could not be read from a server which has an epoch endpoint.
+    EpochUnavailable(String),
+    /// The instructions of the intent could not be decompiled into a manifest.
+    DecompileFailed(String),
+}
+
+/// A signed transaction along with its decompiled manifest text, which `PteClient::execute_prepared`
+/// submits without decompiling it again.
+#[derive(Debug, Clone)]
+pub struct PreparedTransaction {
+    pub transaction: SignedTransaction,
+    pub manifest: String,
+}
+
+/// A transaction intent along with the window of epochs it's valid in and the signatures collected
+/// over it so far.
+#[derive(Debug, Clone)]
+pub struct SignedIntentPackage {
+    transaction: Transaction,
+    signers: Vec<EcdsaPublicKey>,
+    signatures: BTreeMap<String, (EcdsaPublicKey, EcdsaSignature)>,
+    validity: IntentValidity,
+    /// The decompiled manifest, kept across rebases since they only replace the nonce.
+    manifest: OnceLock<String>,
+}
+
+impl SignedIntentPackage {
+    /// Creates a package for the transaction which must be signed by all of the given signers within
+    /// `window` epochs from now. `EpochClock::epochs_for` converts a duration into a window. When the
+    /// current epoch is unknown the client's static validity window is used instead.
+    pub fn new(
+        client: &PteClient,
+        transaction: Transaction,
+        signers: Vec<EcdsaPublicKey>,
+        window: u64,
+    ) -> Result<Self, IntentError> {
+        Ok(Self {
+            transaction,
+            signers,
+            signatures: BTreeMap::new(),
+            validity: validity_from_now(client, window)?,
+            manifest: OnceLock::new(),
+        })
+    }
+
+    /// Creates a package which must be signed by all of the given signers before the deadline. The
+    /// time left is converted into a window of epochs at the length estimated by the epoch clock.
+    pub fn new_until(
+        client: &PteClient,
+        transaction: Transaction,
+        signers: Vec<EcdsaPublicKey>,
+        deadline: SystemTime,
+        epoch_clock: &EpochClock,
+    ) -> Result<Self, IntentError> {
+        let time_left: Duration = deadline.duration_since(client.clock().system_time()).unwrap_or_default();
+        Self::new(client, transaction, signers, epoch_clock.epochs_for(time_left))
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    pub fn signers(&self) -> &[EcdsaPublicKey] {
+        &self.signers
+    }
+
+    pub fn validity(&self) -> IntentValidity {
+        self.validity
+    }
+
+    /// The first epoch in which the intent is no longer valid, or `None` if the epoch was unknown
+    /// when the intent was created.
+    pub fn expires_at_epoch(&self) -> Option<u64> {
+        match self.validity {
+            IntentValidity::Epochs { expires_at_epoch, .. } => Some(expires_at_epoch),
+            IntentValidity::UnknownEpoch { .. } => None,
+        }
+    }
+
+    /// The manifest text of the intent, decompiled the first time it is needed.
+    pub fn manifest(&self) -> Result<&str, IntentError> {
+        if let Some(manifest) = self.manifest.get() {
+            return Ok(manifest);
+        }
+        let manifest: String = decompile(&self.transaction)
+            .map_err(|error| IntentError::DecompileFailed(format!("{:?}", error)))?;
+        Ok(self.manifest.get_or_init(|| manifest))
+    }
+
+    /// The bytes each signer signs.
+    pub fn message(&self) -> Vec<u8> {
+        scrypto_encode(&self.transaction)
+    }
+
+    /// Checks that the intent hasn't expired. Signers should call this before signing so that they
+    /// don't sign an intent the coordinator will reject. Always passes when either the epoch of the
+    /// intent or the current epoch is unknown.
+    pub fn check_still_valid(&self, client: &PteClient) -> Result<(), IntentError> {
+        let expires_at_epoch: u64 = match self.expires_at_epoch() {
+            Some(expires_at_epoch) => expires_at_epoch,
+            None => return Ok(()),
+        };
+        match current_epoch(client)? {
+            Some(current_epoch) if current_epoch >= expires_at_epoch => Err(IntentError::Expired {
+                expires_at_epoch,
+                current_epoch,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The number of epochs left before the intent expires, or `None` when that can't be known
+    /// because either the epoch of the intent or the current epoch is unknown.
+    pub fn time_remaining(&self, client: &PteClient) -> Result<Option<EpochsRemaining>, IntentError> {
+        let expires_at_epoch: u64 = match self.expires_at_epoch() {
+            Some(expires_at_epoch) => expires_at_epoch,
+            None => return Ok(None),
+        };
+        Ok(current_epoch(client)?.map(|current_epoch| EpochsRemaining(expires_at_epoch.saturating_sub(current_epoch))))
+    }
+
+    /// Signs the intent with the given key. Used by signers which hold their key locally.
+    pub fn sign(&self, client: &PteClient, private_key: &EcdsaPrivateKey) -> Result<EcdsaSignature, IntentError> {
+        self.check_still_valid(client)?;
+        Ok(private_key.sign(&self.message()))
+    }
+
+    /// Adds the signature of one of the signers. Signatures arriving after the intent expired are
+    /// rejected, as are signatures made over an earlier version of the intent.
+    pub fn add_signature(
+        &mut self,
+        client: &PteClient,
+        public_key: EcdsaPublicKey,
+        signature: EcdsaSignature,
+    ) -> Result<(), IntentError> {
+        self.check_still_valid(client)?;
+        if !self.signers.contains(&public_key) {
+            return Err(IntentError::UnexpectedSigner(public_key));
+        }
+        if !EcdsaVerifier::verify(&self.message(), &public_key, &signature) {
+            return Err(IntentError::InvalidSignature(public_key));
+        }
+        self.signatures.insert(public_key.to_string(), (public_key, signature));
+        Ok(())
+    }
+
+    /// The signers which haven't signed the intent yet.
+    pub fn missing_signers(&self) -> Vec<EcdsaPublicKey> {
+        self.signers
+            .iter()
+            .filter(|x| !self.signatures.contains_key(&x.to_string()))
+            .cloned()
+            .collect()
+    }
+
+    /// Rebuilds the intent with a fresh nonce and a window of `new_window` epochs from now. All of the
+    /// signatures collected so far are over the old intent and are dropped. Returns the signers which
+    /// need to sign again. The other instructions and the cached manifest text are kept as they are.
+    pub fn rebase(&mut self, client: &PteClient, new_window: u64) -> Result<Vec<EcdsaPublicKey>, IntentError> {
+        let validity: IntentValidity = validity_from_now(client, new_window)?;
+        let nonce: u64 = client.next_nonce();
+        let nonce_instruction: &mut Instruction = self
+            .transaction
+            .instructions
+            .iter_mut()
+            .find(|x| matches!(x, Instruction::Nonce { .. }))
+            .ok_or(IntentError::NoNonceFound)?;
+        *nonce_instruction = Instruction::Nonce { nonce };
+
+        let must_resign: Vec<EcdsaPublicKey> = self.signatures.values().map(|(public_key, _)| *public_key).collect();
+        self.signatures.clear();
+        self.validity = validity;
+        Ok(must_resign)
+    }
+
+    /// Converts the package into a transaction ready to be submitted once all of the signers have
+    /// signed it and the intent is still valid.
+    pub fn into_signed_transaction(self, client: &PteClient) -> Result<SignedTransaction, IntentError> {
+        self.check_still_valid(client)?;
+        let missing_signers: Vec<EcdsaPublicKey> = self.missing_signers();
+        if !missing_signers.is_empty() {
+            return Err(IntentError::MissingSignatures(missing_signers));
+        }
+        Ok(SignedTransaction {
+            transaction: self.transaction,
+            signatures: self.signatures.into_values().collect(),
+        })
+    }
+
+    /// Same as `into_signed_transaction`, keeping the cached manifest text for
+    /// `PteClient::execute_prepared`.
+    pub fn into_prepared_transaction(self, client: &PteClient) -> Result<PreparedTransaction, IntentError> {
+        let manifest: String = self.manifest()?.to_string();
+        Ok(PreparedTransaction {
+            transaction: self.into_signed_transaction(client)?,
+            manifest,
+        })
+    }
+}
+
+fn validity_from_now(client: &PteClient, window: u64) -> Result<IntentValidity, IntentError> {
+    Ok(match current_epoch(client)? {
+        Some(start_epoch) => IntentValidity::Epochs {
+            start_epoch,
+            expires_at_epoch: start_epoch + window,
+        },
+        None => IntentValidity::UnknownEpoch {
+            window: client.static_validity_window,
+        },
+    })
+}
+
+fn current_epoch(client: &PteClient) -> Result<Option<u64>, IntentError> {
+    client
+        .current_epoch()
+        .map_err(|error| IntentError::EpochUnavailable(format!("{:?}", error)))
+}
+
+#[derive(Deserialize)]
+struct EpochResponse {
+    epoch: u64,
+}
+
+impl PteClient {
+    /// Sets the window, in epochs, given to intents created while the current epoch is unknown
+    /// because the server has no epoch endpoint.
+    pub fn with_static_validity_window(self, static_validity_window: u64) -> Self {
+        Self {
+            static_validity_window,
+            ..self
+        }
+    }
+
+    /// Checks whether the first read server has an epoch endpoint. The answer is cached per server
+    /// so that the probe only happens once, and a server without the endpoint is reported through
+    /// the warning hooks the first time it is probed. Failures to probe are returned rather than
+    /// taken for a missing endpoint.
+    pub fn supports_epoch(&self) -> Result<bool, PteApiError> {
+        let url: String = self.urls(Horizon::Read).first().cloned().ok_or(PteApiError::NoServers)?;
+        let probed: bool = self.epoch_support.lock().unwrap().contains_key(&url);
+        let supported: bool = self.probe_endpoint(&self.epoch_support, Horizon::Read, |url| {
+            self.http_client().get(format!("{}/epoch", url))
+        })?;
+        if !supported && !probed {
+            self.hooks().warned(&ClientWarning::EpochEndpointUnavailable {
+                url,
+                static_validity_window: self.static_validity_window,
+            });
+        }
+        Ok(supported)
+    }
+
+    /// The current epoch of the ledger, or `None` when the server has no epoch endpoint.
+    pub fn current_epoch(&self) -> Result<Option<u64>, PteApiError> {
+        if !self.supports_epoch()? {
+            return Ok(None);
+        }
+        let response = self.send(Horizon::Read, |url| self.http_client().get(format!("{}/epoch", url)))?;
+        if !response.status().is_success() {
+            return Err(PteApiError::UnexpectedStatus {
+                status: response.status().as_u16(),
+                body: response.text()?,
+            });
+        }
+        let response: EpochResponse = response.json()?;
+        Ok(Some(response.epoch))
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    use radix_engine::transaction::TransactionBuilder;
+
+    use crate::fixtures::{private_key, ACCOUNT, OTHER_ACCOUNT};
+    use crate::mock::{EndpointConfig, MockPte};
+
+    fn transfer() -> Transaction {
+        TransactionBuilder::new()
+            .withdraw_from_account_by_amount(dec!("10"), RADIX_TOKEN, ComponentAddress::from_str(ACCOUNT).unwrap())
+            .call_method_with_all_resources(ComponentAddress::from_str(OTHER_ACCOUNT).unwrap(), "deposit_batch")
+            .build(0)
+    }
+
+    fn set_epoch(mock: &MockPte, epoch: u64) {
+        mock.set_endpoint("GET", "/epoch", EndpointConfig::new(200, &format!("{{\"epoch\":{}}}", epoch)));
+    }
+
+    #[test]
+    fn signatures_arriving_after_expiry_are_rejected() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_epoch(100);
+        let client: PteClient = mock.client();
+        let private_key: EcdsaPrivateKey = private_key();
+        let mut package: SignedIntentPackage =
+            SignedIntentPackage::new(&client, transfer(), vec![private_key.public_key()], 5).unwrap();
+        assert_eq!(package.expires_at_epoch(), Some(105));
+
+        // The signer signs in time but the signature only reaches the coordinator once it expired
+        let signature: EcdsaSignature = package.sign(&client, &private_key).unwrap();
+        set_epoch(&mock, 105);
+
+        assert_eq!(
+            package.add_signature(&client, private_key.public_key(), signature),
+            Err(IntentError::Expired {
+                expires_at_epoch: 105,
+                current_epoch: 105,
+            })
+        );
+        assert_eq!(package.missing_signers(), vec![private_key.public_key()]);
+        assert_eq!(
+            package.sign(&client, &private_key).err(),
+            Some(IntentError::Expired {
+                expires_at_epoch: 105,
+                current_epoch: 105,
+            })
+        );
+    }
+
+    #[test]
+    fn signatures_arriving_in_the_last_epoch_of_the_window_are_accepted() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_epoch(100);
+        let client: PteClient = mock.client();
+        let private_key: EcdsaPrivateKey = private_key();
+        let mut package: SignedIntentPackage =
+            SignedIntentPackage::new(&client, transfer(), vec![private_key.public_key()], 5).unwrap();
+
+        let signature: EcdsaSignature = package.sign(&client, &private_key).unwrap();
+        set_epoch(&mock, 104);
+
+        assert_eq!(package.add_signature(&client, private_key.public_key(), signature), Ok(()));
+        assert!(package.missing_signers().is_empty());
+    }
+
+    #[test]
+    fn rebased_intents_keep_the_manifest_bytes_of_the_original() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_epoch(100);
+        let client: PteClient = mock.client();
+        let private_key: EcdsaPrivateKey = private_key();
+        let mut package: SignedIntentPackage =
+            SignedIntentPackage::new(&client, transfer(), vec![private_key.public_key()], 5).unwrap();
+        let original: Vec<u8> = package.manifest().unwrap().as_bytes().to_vec();
+        let signature: EcdsaSignature = package.sign(&client, &private_key).unwrap();
+        package.add_signature(&client, private_key.public_key(), signature).unwrap();
+
+        set_epoch(&mock, 105);
+        assert_eq!(package.rebase(&client, 5).unwrap(), vec![private_key.public_key()]);
+
+        assert_eq!(decompile(package.transaction()).unwrap().as_bytes(), original.as_slice());
+        assert_eq!(package.manifest().unwrap().as_bytes(), original.as_slice());
+        assert_eq!(package.expires_at_epoch(), Some(110));
+        let signature: EcdsaSignature = package.sign(&client, &private_key).unwrap();
+        package.add_signature(&client, private_key.public_key(), signature).unwrap();
+        let prepared: PreparedTransaction = package.into_prepared_transaction(&client).unwrap();
+        assert_eq!(prepared.manifest.as_bytes(), original.as_slice());
+    }
+}
@@ -0,0 +1,71 @@
+//! A directory of private keys stored under labels, one hex encoded key per `<label>.key` file. The
+//! `keys` directory written by `bootstrap` is a keystore of its accounts.
+
+use scrypto::prelude::*;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The extension of the key files.
+pub const KEY_FILE_EXTENSION: &str = "key";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystore {
+    directory: PathBuf,
+}
+
+impl Keystore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn path_of(&self, label: &str) -> PathBuf {
+        self.directory.join(label).with_extension(KEY_FILE_EXTENSION)
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.path_of(label).exists()
+    }
+
+    /// Loads the key stored under the label.
+    pub fn load(&self, label: &str) -> std::io::Result<EcdsaPrivateKey> {
+        let path: PathBuf = self.path_of(label);
+        hex::decode(fs::read_to_string(&path)?.trim())
+            .ok()
+            .and_then(|bytes| EcdsaPrivateKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid key in {}", path.display())))
+    }
+
+    /// Stores the key bytes under the label, replacing any key stored under it. The bytes are checked
+    /// to be a valid key before anything is written, and the file is replaced atomically.
+    pub fn insert(&self, label: &str, key_bytes: &[u8]) -> std::io::Result<EcdsaPrivateKey> {
+        let private_key: EcdsaPrivateKey = EcdsaPrivateKey::from_bytes(key_bytes)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a valid private key"))?;
+        fs::create_dir_all(&self.directory)?;
+        let temporary_path: PathBuf = self.path_of(label).with_extension("tmp");
+        fs::write(&temporary_path, hex::encode(key_bytes))?;
+        fs::rename(&temporary_path, self.path_of(label))?;
+        Ok(private_key)
+    }
+
+    /// The labels of the stored keys, sorted.
+    pub fn labels(&self) -> std::io::Result<Vec<String>> {
+        let mut labels: Vec<String> = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|x| x.path()))
+                .filter(|path| path.extension().is_some_and(|x| x == KEY_FILE_EXTENSION))
+                .filter_map(|path| path.file_stem().map(|x| x.to_string_lossy().into_owned()))
+                .collect(),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        labels.sort();
+        Ok(labels)
+    }
+}
@@ -0,0 +1,83 @@
+//! Addresses which remember the label they were given and where they came from, so that errors can
+//! refer to them the way the user knows them.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Where an address came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressSource {
+    /// An entry of the address book, optionally with the path of the file it was loaded from.
+    AddressBook { path: Option<String> },
+    /// The name registry component with the given address.
+    Registry { registry: String },
+    /// The new entities of the receipt of a transaction, optionally of a labelled pipeline step.
+    Receipt { transaction_hash: String, step: Option<String> },
+    /// An argument given on the command line.
+    CliArg,
+    Unknown,
+}
+
+/// An address along with an optional label and its source. It serializes to just the raw address
+/// so that wire formats are unaffected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabeledAddress<T> {
+    pub address: T,
+    pub label: Option<String>,
+    pub source: AddressSource,
+}
+
+impl<T> LabeledAddress<T> {
+    pub fn new(address: T, label: &str, source: AddressSource) -> Self {
+        Self {
+            address,
+            label: Some(label.to_string()),
+            source,
+        }
+    }
+}
+
+impl<T> From<T> for LabeledAddress<T> {
+    fn from(address: T) -> Self {
+        Self {
+            address,
+            label: None,
+            source: AddressSource::Unknown,
+        }
+    }
+}
+
+/// Shortens an address to its first few characters for display alongside its label.
+pub fn abbreviate(address: &str) -> String {
+    match address.char_indices().nth(6) {
+        Some((index, _)) => format!("{}…", &address[..index]),
+        None => address.to_string(),
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for LabeledAddress<T> {
+    /// Displays the address as `label (abbreviated address)` when it has a label, and as the raw
+    /// address otherwise.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.label {
+            Some(ref label) => write!(f, "{} ({})", label, abbreviate(&self.address.to_string())),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+impl<T: fmt::Display> Serialize for LabeledAddress<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.address.to_string())
+    }
+}
+
+impl<'de, T: FromStr> Deserialize<'de> for LabeledAddress<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let address: String = String::deserialize(deserializer)?;
+        T::from_str(&address)
+            .map(LabeledAddress::from)
+            .map_err(|_| serde::de::Error::custom(format!("invalid address: {}", address)))
+    }
+}
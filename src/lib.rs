@@ -0,0 +1,645 @@
+//! Building, signing and submitting transactions to the Radix PTE.
+//!
+//! Only the core types and the offline building and signing of transactions are enabled by
+//! default. Everything else is behind additive features:
+//!
+//! - `blocking-http`: the blocking `PteClient` and everything which talks to the PTE.
+//! - `async-http`: a minimal asynchronous client submitting transactions and reading components.
+//! - `keystore`: labeled keys on disk, and importing accounts into them with `blocking-http`.
+//! - `metrics`: aggregated reports over the receipts of batches of transactions.
+//! - `tracing`: the warnings of clients and receipts emitted as `tracing` events.
+//! - `testing`: test vectors and conformance fixtures.
+//! - `bridge`: an HTTP server exposing a configured client to other languages.
+//! - `cli`: the `programmatic-pte-interactions` binary and its commands.
+
+use radix_engine::model::{SignedTransaction, Instruction, Transaction};
+use scrypto::crypto::hash;
+use scrypto::prelude::*;
+
+// Used to handle the JSON serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+// Contains the code required to go from a Transaction -> Transaction manifest string
+pub mod utils;
+use utils::{DecompileError, decompile};
+
+// Routes all of the randomness used for nonces and keys through a single (optionally seeded) source
+#[cfg(any(feature = "blocking-http", feature = "testing"))]
+pub mod deterministic;
+
+// Generates and checks the test vectors for the canonical flows, sample data built from them and a
+// pre-built blueprint to publish
+#[cfg(feature = "testing")]
+pub mod test_vectors;
+#[cfg(feature = "testing")]
+pub mod fixtures;
+#[cfg(any(feature = "blocking-http", feature = "testing"))]
+pub mod test_package;
+
+// A mock of the PTE's API with configurable latencies and errors, contexts isolating parallel
+// integration tests, and benchmarks of submissions
+#[cfg(feature = "testing")]
+pub mod mock;
+#[cfg(all(feature = "testing", feature = "blocking-http"))]
+pub mod test_context;
+#[cfg(feature = "blocking-http")]
+pub mod bench;
+
+// Setting up funded demo environments and verifying fresh deployments
+#[cfg(feature = "blocking-http")]
+pub mod bootstrap;
+#[cfg(feature = "blocking-http")]
+pub mod deployment;
+
+// Exposing a configured client over HTTP to people who don't use Rust
+#[cfg(feature = "bridge")]
+pub mod bridge;
+
+// Writing and reading amounts in whole units or in attos
+pub mod denomination;
+
+// Typed access to the receipt fields added by forks of the PTE
+pub mod extensions;
+
+// Byte-exact conformance fixtures shared with other client implementations
+pub mod canonical_json;
+#[cfg(feature = "testing")]
+pub mod conformance;
+
+// The commands of the command line interface and the example flows it runs by default
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cli")]
+pub mod confirm;
+#[cfg(all(feature = "blocking-http", feature = "metrics"))]
+pub mod example;
+
+// The source of time used by all of the timing, polling and staleness logic
+pub mod clock;
+pub mod epoch_clock;
+
+// A client for the PTE's API along with the types it returns
+#[cfg(feature = "blocking-http")]
+pub mod client;
+#[cfg(feature = "async-http")]
+pub mod async_client;
+#[cfg(any(feature = "blocking-http", feature = "async-http"))]
+pub mod pool;
+pub mod component;
+pub mod resource;
+pub mod access_rules;
+pub mod vaults;
+#[cfg(feature = "blocking-http")]
+pub mod non_fungibles;
+#[cfg(feature = "blocking-http")]
+pub mod signer_account;
+
+// The default signer of a client and the keys helpers sign with
+#[cfg(feature = "blocking-http")]
+pub mod credentials;
+
+// Labeled keys on disk and importing the accounts of the browser extension into them
+#[cfg(feature = "keystore")]
+pub mod keystore;
+#[cfg(all(feature = "blocking-http", feature = "keystore"))]
+pub mod account_import;
+
+// Calling methods on a component right after instantiating it
+#[cfg(feature = "blocking-http")]
+pub mod chain;
+
+// A bounded submission queue with priorities and per-account fairness
+pub mod queue;
+
+// The string formats of public keys in transaction bodies
+pub mod public_key;
+
+// Failing fast while the PTE is unreachable
+pub mod circuit;
+
+// Limiting the transactions calling the faucet so that the PTE doesn't throttle it
+#[cfg(feature = "blocking-http")]
+pub mod faucet;
+
+// Windows of outstanding nonces per signing key
+pub mod nonce;
+
+// Signatures of several parties collected over an intent before it expires
+#[cfg(feature = "blocking-http")]
+pub mod intent;
+
+// Application level idempotency keys
+#[cfg(feature = "blocking-http")]
+pub mod idempotency;
+
+// A durable outbox of transactions submitted by a background worker
+#[cfg(feature = "blocking-http")]
+pub mod outbox;
+
+// Reporting the progress of operations which submit many transactions
+pub mod progress;
+
+// Hooks executed on the lifecycle events of submissions
+pub mod hooks;
+
+// Free functions on strings for beginners' scripts
+#[cfg(feature = "blocking-http")]
+pub mod quick;
+
+// Read-only method calls which don't commit a transaction
+#[cfg(feature = "blocking-http")]
+pub mod readonly;
+
+// The raw bytes behind decoded values and decoding as far as possible
+pub mod raw;
+
+// Typed events parsed from the logs of receipts, and the new entities of receipts by instruction
+pub mod events;
+pub mod entities;
+
+// An in-memory mirror of everything the client learns about the ledger
+#[cfg(feature = "blocking-http")]
+pub mod mirror;
+
+// Aggregated reports over the receipts of a batch of transactions
+#[cfg(feature = "metrics")]
+pub mod report;
+
+// Helpers on top of the transaction builder and the encoding of call arguments
+pub mod args;
+pub mod builder;
+pub mod manifest_spec;
+
+// Exact Decimal arithmetic, such as splitting amounts between recipients
+pub mod math;
+
+// Pipelines of transactions and the static analysis of their balances
+pub mod pipeline;
+
+// Checking that transactions only touch what they are expected to
+pub mod scope;
+
+// Detection of manifests the PTE's compiler refuses due to version skew
+pub mod compatibility;
+
+// Human readable labels for addresses, resolved locally or through an on-ledger registry
+pub mod address_book;
+pub mod labeled;
+#[cfg(feature = "blocking-http")]
+pub mod registry;
+
+// The commonly used types of whichever features are enabled
+pub mod prelude;
+
+use component::SchemaDrift;
+use public_key::{parse_public_key, PublicKeyError, PublicKeyFormat};
+
+/// A struct which describes the Nonce. Required for the TransactionBody struct
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Nonce {
+    value: u64,
+}
+
+/// A struct which defines the signature used in the TransactionBody struct.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Signature {
+    public_key: String,
+    signature: String,
+}
+
+impl Signature {
+    /// The public key of the signature, which may be written in any of the `PublicKeyFormat`s.
+    pub fn public_key(&self) -> Result<(EcdsaPublicKey, PublicKeyFormat), PublicKeyError> {
+        parse_public_key(&self.public_key)
+    }
+}
+
+/// A struct which defines the transaction payload that the PTE's API accepts.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionBody {
+    manifest: String,
+    nonce: Nonce,
+    signatures: Vec<Signature>,
+}
+
+impl TransactionBody {
+    pub fn manifest(&self) -> &str {
+        &self.manifest
+    }
+
+    pub fn signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    /// The canonical JSON bytes of the body: sorted keys, shortest number formatting and no
+    /// insignificant whitespace. Used wherever the body is hashed, stored or compared.
+    pub fn to_canonical_json(&self) -> Vec<u8> {
+        canonical_json::to_canonical_vec(self).expect("transaction bodies always serialize")
+    }
+
+    /// The hash of the canonical JSON bytes of the body.
+    pub fn payload_hash(&self) -> String {
+        hash(self.to_canonical_json()).to_string()
+    }
+
+    /// Creates the transaction body for a signed transaction by decompiling its instructions into a
+    /// manifest and converting the nonce and signatures into the format the PTE expects.
+    pub fn from_signed(transaction: &SignedTransaction) -> Result<Self, TransactionSubmissionError> {
+        Self::from_signed_with_format(transaction, PublicKeyFormat::default())
+    }
+
+    /// Creates the transaction body for a signed transaction with the public keys of the signatures
+    /// written in the given format.
+    pub fn from_signed_with_format(
+        transaction: &SignedTransaction,
+        public_key_format: PublicKeyFormat,
+    ) -> Result<Self, TransactionSubmissionError> {
+        Self::from_parts_with_format(&transaction.transaction, &transaction.signatures, public_key_format)
+    }
+
+    /// Creates the transaction body for a transaction and the signatures over it. Transactions which
+    /// are only previewed can be given no signatures.
+    pub fn from_parts(
+        transaction: &Transaction,
+        signatures: &[(EcdsaPublicKey, EcdsaSignature)]
+    ) -> Result<Self, TransactionSubmissionError> {
+        Self::from_parts_with_format(transaction, signatures, PublicKeyFormat::default())
+    }
+
+    /// Creates the transaction body for a transaction and the signatures over it with the public keys
+    /// written in the given format.
+    pub fn from_parts_with_format(
+        transaction: &Transaction,
+        signatures: &[(EcdsaPublicKey, EcdsaSignature)],
+        public_key_format: PublicKeyFormat,
+    ) -> Result<Self, TransactionSubmissionError> {
+        Self::from_parts_with_manifest(transaction, signatures, decompile(transaction)?, public_key_format)
+    }
+
+    /// Creates the transaction body for a transaction whose manifest was already decompiled. The
+    /// manifest text doesn't include the nonce, so the text of a transaction stays valid when only its
+    /// nonce is replaced.
+    pub fn from_parts_with_manifest(
+        transaction: &Transaction,
+        signatures: &[(EcdsaPublicKey, EcdsaSignature)],
+        manifest: String,
+        public_key_format: PublicKeyFormat,
+    ) -> Result<Self, TransactionSubmissionError> {
+        // Getting the nonce used in the transaction from the transaction object itself
+        let nonce: u64 = {
+            let nonce_instructions: Vec<Instruction> = transaction.instructions
+                .iter()
+                .filter(|x| {
+                    match x {
+                        Instruction::Nonce { nonce: _ } => true,
+                        _ => false
+                    }
+                })
+                .cloned()
+                .collect();
+
+            if nonce_instructions.len() == 0 {
+                Err(TransactionSubmissionError::NoNonceFound)
+            } 
+            else if nonce_instructions.len() == 1{ 
+                if let Instruction::Nonce { nonce } = nonce_instructions[0] {
+                    Ok(nonce)
+                } else {
+                    panic!("Expected a nonce");
+                }
+            } 
+            else {
+                Err(TransactionSubmissionError::MultipleNonceFound)
+            }
+        }?;
+        let nonce: Nonce = Nonce { value: nonce };
+
+        let signatures: Vec<Signature> = signatures
+            .iter()
+            .map(|x| Signature{
+                public_key: public_key_format.format(&x.0),
+                signature: x.1.to_string()
+            })
+            .collect();
+
+        Ok(TransactionBody {
+            manifest,
+            nonce: nonce,
+            signatures: signatures
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Receipt {
+    pub transaction_hash: String,
+    pub status: String,
+    pub outputs: Vec<String>,
+    pub logs: Vec<String>,
+    pub new_packages: Vec<String>,
+    pub new_components: Vec<String>,
+    pub new_resources: Vec<String>,
+    /// The execution cost of the transaction. Only reported by some PTE builds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<CostSummary>,
+    /// The fields of the receipt this type doesn't know, such as those added by forks of the PTE.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// The values decoded from `extra` by the receipt extensions of the client.
+    #[serde(skip)]
+    pub extensions: extensions::ReceiptExtensions,
+}
+
+impl Receipt {
+    /// Checks if the transaction was committed successfully.
+    pub fn is_success(&self) -> bool {
+        self.status.eq_ignore_ascii_case("success")
+    }
+
+    /// The new packages with duplicates removed, in the order they were first reported. The raw
+    /// list reported by the PTE is kept in `new_packages`.
+    pub fn new_packages(&self) -> Vec<PackageAddress> {
+        return unique(&self.new_packages)
+            .map(|x| PackageAddress::from_str(x).unwrap())
+            .collect()
+    }
+    
+    /// The new components with duplicates removed, in the order they were first reported. The raw
+    /// list reported by the PTE is kept in `new_components`.
+    pub fn new_components(&self) -> Vec<ComponentAddress> {
+        return unique(&self.new_components)
+            .map(|x| ComponentAddress::from_str(x).unwrap())
+            .collect()
+    }
+    
+    /// The new resources with duplicates removed, in the order they were first reported. The raw
+    /// list reported by the PTE is kept in `new_resources`.
+    pub fn new_resources(&self) -> Vec<ResourceAddress> {
+        return unique(&self.new_resources)
+            .map(|x| ResourceAddress::from_str(x).unwrap())
+            .collect()
+    }
+
+    /// The value of type `T` decoded from the extra fields of the receipt by one of the receipt
+    /// extensions of the client, if any.
+    pub fn extension<T: std::any::Any + Send + Sync>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+
+    /// The inconsistencies found in the lists of new entities: addresses reported more than once in
+    /// the same list, and addresses reported in more than one list. The receipt extensions which
+    /// failed to decode the receipt are reported too.
+    pub fn warnings(&self) -> Vec<ReceiptWarning> {
+        let lists: [(&str, &Vec<String>); 3] = [
+            ("new_packages", &self.new_packages),
+            ("new_components", &self.new_components),
+            ("new_resources", &self.new_resources),
+        ];
+
+        let mut warnings: Vec<ReceiptWarning> = Vec::new();
+        let mut lists_of_address: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (list, addresses) in lists {
+            for address in unique(addresses) {
+                let occurrences: usize = addresses.iter().filter(|x| *x == address).count();
+                if occurrences > 1 {
+                    warnings.push(ReceiptWarning::DuplicateEntry {
+                        list: list.to_string(),
+                        address: address.clone(),
+                        occurrences,
+                    });
+                }
+                lists_of_address.entry(address).or_default().push(list);
+            }
+        }
+        for (address, lists) in lists_of_address {
+            if lists.len() > 1 {
+                warnings.push(ReceiptWarning::InconsistentLists {
+                    address: address.to_string(),
+                    lists: lists.into_iter().map(str::to_string).collect(),
+                });
+            }
+        }
+        warnings.extend(self.extensions.failures().iter().cloned());
+        warnings
+    }
+}
+
+/// The entries of a list without duplicates, in the order they first appear.
+fn unique(list: &[String]) -> impl Iterator<Item = &String> {
+    list.iter()
+        .enumerate()
+        .filter(move |(index, x)| !list[..*index].contains(x))
+        .map(|(_, x)| x)
+}
+
+/// An inconsistency in the new entities reported by a receipt.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ReceiptWarning {
+    /// The address appears more than once in the same list.
+    DuplicateEntry { list: String, address: String, occurrences: usize },
+    /// The address appears in more than one list, such as both as a component and as a resource.
+    InconsistentLists { address: String, lists: Vec<String> },
+    /// A receipt extension of the client couldn't decode the extra fields of the receipt.
+    ExtensionDecodeFailed { extension: String, error: String },
+}
+
+/// The execution cost of a transaction as reported by PTE builds which report it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostSummary {
+    #[serde(alias = "cost_units_consumed")]
+    pub total_cost_units: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub breakdown: BTreeMap<String, u64>,
+}
+
+impl std::fmt::Display for CostSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} cost units", self.total_cost_units)?;
+        for (name, cost_units) in self.breakdown.iter() {
+            write!(f, "\n  {}: {}", name, cost_units)?;
+        }
+        Ok(())
+    }
+}
+
+/// An enum of the errors which could occur when submitting a transaction to the PTE API.
+#[derive(Debug)]
+pub enum TransactionSubmissionError {
+    NoNonceFound,
+    MultipleNonceFound,
+    DecompileError(DecompileError),
+    #[cfg(any(feature = "blocking-http", feature = "async-http"))]
+    HttpRequestError(reqwest::Error),
+    QueueFull(queue::QueueFull),
+    /// The PTE's manifest compiler refused the manifest produced by `decompile`.
+    ManifestDialectMismatch(compatibility::ManifestDialectMismatch),
+    /// The PTE responded with an error status which could not be classified further.
+    Rejected { status: u16, body: String },
+    /// `execute_idempotent` was called on a client without an idempotency store.
+    IdempotencyNotConfigured,
+    /// The idempotency store could not be read from or written to.
+    IdempotencyStoreError(std::io::Error),
+    /// The circuit breaker is open and the submission was not sent.
+    CircuitOpen(circuit::CircuitOpen),
+    /// The PTE answered in a content encoding which can't be decoded.
+    UnsupportedEncoding(String),
+    /// `Credentials::Default` was given to a helper on a client without a default signer, or
+    /// without a default notary where a notary was asked for.
+    NoDefaultSigner,
+    /// The transaction touches something outside of the scope it was executed with.
+    ScopeViolation(Box<scope::ScopeViolation>),
+    /// The client has no server to submit transactions to.
+    NoServers,
+}
+
+/// Who is at fault for an error and whether trying again may help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// The transaction or request is invalid and will fail the same way if tried again.
+    UserError,
+    /// The PTE, or the path to it, failed in a way which may go away if tried again later.
+    TransientServerError,
+    /// The PTE can't handle the request and won't be able to without a change on either side.
+    PermanentServerError,
+    /// The crate or the way it was configured is broken.
+    InternalBug,
+}
+
+impl TransactionSubmissionError {
+    /// Classifies the error. The match is exhaustive so that a new variant can't be added without
+    /// deciding its class, exit code and HTTP status.
+    pub fn classification(&self) -> ErrorClass {
+        match self {
+            TransactionSubmissionError::NoNonceFound => ErrorClass::UserError,
+            TransactionSubmissionError::MultipleNonceFound => ErrorClass::UserError,
+            TransactionSubmissionError::DecompileError(_) => ErrorClass::InternalBug,
+            #[cfg(any(feature = "blocking-http", feature = "async-http"))]
+            TransactionSubmissionError::HttpRequestError(_) => ErrorClass::TransientServerError,
+            TransactionSubmissionError::QueueFull(_) => ErrorClass::TransientServerError,
+            TransactionSubmissionError::ManifestDialectMismatch(_) => ErrorClass::PermanentServerError,
+            TransactionSubmissionError::Rejected { status: 429, .. } => ErrorClass::TransientServerError,
+            TransactionSubmissionError::Rejected { status: 400..=499, .. } => ErrorClass::UserError,
+            TransactionSubmissionError::Rejected { .. } => ErrorClass::TransientServerError,
+            TransactionSubmissionError::IdempotencyNotConfigured => ErrorClass::InternalBug,
+            TransactionSubmissionError::NoDefaultSigner => ErrorClass::InternalBug,
+            TransactionSubmissionError::IdempotencyStoreError(_) => ErrorClass::TransientServerError,
+            TransactionSubmissionError::CircuitOpen(_) => ErrorClass::TransientServerError,
+            TransactionSubmissionError::UnsupportedEncoding(_) => ErrorClass::PermanentServerError,
+            TransactionSubmissionError::ScopeViolation(_) => ErrorClass::UserError,
+            TransactionSubmissionError::NoServers => ErrorClass::InternalBug,
+        }
+    }
+
+    /// The exit code of a process which failed with the error, following the conventions of
+    /// `sysexits.h`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            // EX_DATAERR: the transaction itself is invalid.
+            TransactionSubmissionError::NoNonceFound => 65,
+            TransactionSubmissionError::MultipleNonceFound => 65,
+            // EX_SOFTWARE: a transaction built by the crate could not be decompiled.
+            TransactionSubmissionError::DecompileError(_) => 70,
+            // EX_UNAVAILABLE: the PTE could not be reached.
+            #[cfg(any(feature = "blocking-http", feature = "async-http"))]
+            TransactionSubmissionError::HttpRequestError(_) => 69,
+            // EX_TEMPFAIL: trying again later may succeed.
+            TransactionSubmissionError::QueueFull(_) => 75,
+            // EX_PROTOCOL: the PTE speaks a different manifest dialect.
+            TransactionSubmissionError::ManifestDialectMismatch(_) => 76,
+            TransactionSubmissionError::Rejected { status: 429, .. } => 75,
+            TransactionSubmissionError::Rejected { status: 400..=499, .. } => 65,
+            TransactionSubmissionError::Rejected { .. } => 69,
+            // EX_CONFIG: the client is missing its idempotency store.
+            TransactionSubmissionError::IdempotencyNotConfigured => 78,
+            // EX_CONFIG: the client has no default signer.
+            TransactionSubmissionError::NoDefaultSigner => 78,
+            // EX_IOERR: the idempotency store could not be read or written.
+            TransactionSubmissionError::IdempotencyStoreError(_) => 74,
+            TransactionSubmissionError::CircuitOpen(_) => 75,
+            // EX_PROTOCOL: the response can't be decoded.
+            TransactionSubmissionError::UnsupportedEncoding(_) => 76,
+            // EX_DATAERR: the transaction does more than it is allowed to.
+            TransactionSubmissionError::ScopeViolation(_) => 65,
+            // EX_CONFIG: the client was configured without servers.
+            TransactionSubmissionError::NoServers => 78,
+        }
+    }
+
+    /// The status with which a service proxying submissions to the PTE should answer its own
+    /// clients when a submission fails with the error.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            TransactionSubmissionError::NoNonceFound => 400,
+            TransactionSubmissionError::MultipleNonceFound => 400,
+            TransactionSubmissionError::DecompileError(_) => 500,
+            #[cfg(any(feature = "blocking-http", feature = "async-http"))]
+            TransactionSubmissionError::HttpRequestError(error) if error.is_timeout() => 504,
+            #[cfg(any(feature = "blocking-http", feature = "async-http"))]
+            TransactionSubmissionError::HttpRequestError(_) => 502,
+            TransactionSubmissionError::QueueFull(_) => 503,
+            TransactionSubmissionError::ManifestDialectMismatch(_) => 502,
+            TransactionSubmissionError::Rejected { status: 429, .. } => 503,
+            // Errors of the request are passed through, errors of the PTE become gateway errors.
+            TransactionSubmissionError::Rejected { status: status @ 400..=499, .. } => *status,
+            TransactionSubmissionError::Rejected { .. } => 502,
+            TransactionSubmissionError::IdempotencyNotConfigured => 500,
+            TransactionSubmissionError::NoDefaultSigner => 500,
+            TransactionSubmissionError::IdempotencyStoreError(_) => 503,
+            TransactionSubmissionError::CircuitOpen(_) => 503,
+            TransactionSubmissionError::UnsupportedEncoding(_) => 502,
+            TransactionSubmissionError::ScopeViolation(_) => 403,
+            TransactionSubmissionError::NoServers => 500,
+        }
+    }
+}
+
+impl From<utils::DecompileError> for TransactionSubmissionError {
+    fn from(error: DecompileError) -> TransactionSubmissionError {
+        TransactionSubmissionError::DecompileError(error)
+    }
+}
+
+impl From<queue::QueueFull> for TransactionSubmissionError {
+    fn from(error: queue::QueueFull) -> TransactionSubmissionError {
+        TransactionSubmissionError::QueueFull(error)
+    }
+}
+
+#[cfg(any(feature = "blocking-http", feature = "async-http"))]
+impl From<reqwest::Error> for TransactionSubmissionError {
+    fn from(error: reqwest::Error) -> TransactionSubmissionError {
+        TransactionSubmissionError::HttpRequestError(error)
+    }
+}
+
+/// An enum of the errors which could occur when reading data from the PTE API.
+#[derive(Debug)]
+pub enum PteApiError {
+    NotFound(String),
+    InvalidState(String),
+    /// The entity did not become visible before the timeout elapsed.
+    WaitTimeout { attempts: u32, last_error: Box<PteApiError> },
+    /// The response of the PTE no longer matches the shape this crate expects.
+    SchemaDrift(SchemaDrift),
+    /// The circuit breaker is open and the request was not sent.
+    CircuitOpen(circuit::CircuitOpen),
+    /// The server no longer knows the pagination cursor.
+    CursorExpired(String),
+    /// The PTE answered in a content encoding which can't be decoded.
+    UnsupportedEncoding(String),
+    /// The client has no server to send the request to.
+    NoServers,
+    /// The PTE answered with an error status where it was expected to succeed.
+    UnexpectedStatus { status: u16, body: String },
+    #[cfg(any(feature = "blocking-http", feature = "async-http"))]
+    HttpRequestError(reqwest::Error),
+}
+
+#[cfg(any(feature = "blocking-http", feature = "async-http"))]
+impl From<reqwest::Error> for PteApiError {
+    fn from(error: reqwest::Error) -> PteApiError {
+        PteApiError::HttpRequestError(error)
+    }
+}
@@ -0,0 +1,75 @@
+//! Declarative descriptions of the manifests of common flows. Specs are plain data, so they can be
+//! written to disk and built into transactions later, possibly by another process.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use crate::args::{call_args, Args};
+use crate::builder::TransactionBuilderExt;
+
+/// The manifests which can be described by a spec.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind")]
+pub enum ManifestSpec {
+    /// Takes XRD from the faucet and creates an account owned by the signing key with it.
+    NewAccount,
+    /// Transfers an amount of a resource from one account to another.
+    Transfer {
+        from: String,
+        to: String,
+        resource: String,
+        amount: String,
+    },
+    /// Creates a token with a fixed supply and deposits all of it into an account.
+    NewToken {
+        account: String,
+        symbol: String,
+        supply: String,
+    },
+}
+
+impl ManifestSpec {
+    /// Builds the transaction described by the spec with the given nonce and signs it. Returns which
+    /// field of the spec is invalid if it can't be built.
+    pub fn build(&self, private_key: &EcdsaPrivateKey, nonce: u64) -> Result<SignedTransaction, String> {
+        let mut builder: TransactionBuilder = TransactionBuilder::new();
+        match self {
+            ManifestSpec::NewAccount => {
+                let withdraw_auth: AccessRule = rule!(require(NonFungibleAddress::new(
+                    ECDSA_TOKEN,
+                    NonFungibleId::from_bytes(private_key.public_key().to_vec())
+                )));
+                builder
+                    .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+                    .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+                        builder.new_account_with_resource(&withdraw_auth, bucket_id)
+                    });
+            }
+            ManifestSpec::Transfer { from, to, resource, amount } => {
+                let from: ComponentAddress = ComponentAddress::from_str(from).map_err(|_| "source account".to_string())?;
+                let to: ComponentAddress = ComponentAddress::from_str(to).map_err(|_| "destination account".to_string())?;
+                let resource: ResourceAddress = ResourceAddress::from_str(resource).map_err(|_| "resource".to_string())?;
+                let amount: Decimal = Decimal::from_str(amount).map_err(|_| "amount".to_string())?;
+                builder
+                    .withdraw_from_account_by_amount(amount, resource, from)
+                    .take_from_worktop_ref(resource, |builder, bucket| {
+                        builder.call_method(to, "deposit", call_args(Args::new().add_bucket(bucket)))
+                    });
+            }
+            ManifestSpec::NewToken { account, symbol, supply } => {
+                let account: ComponentAddress = ComponentAddress::from_str(account).map_err(|_| "account".to_string())?;
+                let supply: Decimal = Decimal::from_str(supply).map_err(|_| "supply".to_string())?;
+                let mut metadata: HashMap<String, String> = HashMap::new();
+                metadata.insert("symbol".to_string(), symbol.clone());
+                builder
+                    .new_token_fixed(metadata, supply)
+                    .call_method_with_all_resources(account, "deposit_batch");
+            }
+        }
+
+        Ok(builder.build(nonce).sign([private_key]))
+    }
+}
@@ -0,0 +1,167 @@
+//! Exact `Decimal` arithmetic for splitting amounts between recipients. Amounts are handled as their
+//! underlying number of attos (10^-18) so that no precision is lost and the parts always sum to the
+//! total exactly.
+
+use scrypto::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitError {
+    NoWeights,
+    NegativeTotal,
+    NegativeWeight { index: usize },
+    /// All of the weights are zero, so there is nothing to split proportionally to.
+    ZeroTotalWeight,
+    /// The sum of the weights doesn't fit in 128 bits of attos.
+    WeightOverflow,
+    /// The number of weights doesn't match the number of recipients they are for.
+    WeightCountMismatch { weights: usize, recipients: usize },
+}
+
+/// Splits the total proportionally to the weights. Each part is first rounded down to whole attos,
+/// and the attos left over are then handed out one by one to the parts with the largest remainders
+/// (ties going to the earlier part), so the parts are never negative and always sum exactly to the
+/// total.
+pub fn split_amount(total: Decimal, weights: &[Decimal]) -> Result<Vec<Decimal>, SplitError> {
+    if weights.is_empty() {
+        return Err(SplitError::NoWeights);
+    }
+    if total.0 < 0 {
+        return Err(SplitError::NegativeTotal);
+    }
+    if let Some(index) = weights.iter().position(|x| x.0 < 0) {
+        return Err(SplitError::NegativeWeight { index });
+    }
+
+    let total: u128 = total.0 as u128;
+    let weights: Vec<u128> = weights.iter().map(|x| x.0 as u128).collect();
+    let total_weight: u128 = weights
+        .iter()
+        .try_fold(0u128, |sum, x| sum.checked_add(*x))
+        .ok_or(SplitError::WeightOverflow)?;
+    if total_weight == 0 {
+        return Err(SplitError::ZeroTotalWeight);
+    }
+
+    let shares: Vec<(u128, u128)> = weights.iter().map(|weight| mul_div(total, *weight, total_weight)).collect();
+    let mut parts: Vec<u128> = shares.iter().map(|(quotient, _)| *quotient).collect();
+    let leftover: u128 = total - parts.iter().sum::<u128>();
+
+    // The leftover is always smaller than the number of parts, since each part lost less than one atto
+    // to rounding.
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|a, b| shares[*b].1.cmp(&shares[*a].1).then(a.cmp(b)));
+    for index in by_remainder.into_iter().take(leftover as usize) {
+        parts[index] += 1;
+    }
+
+    Ok(parts.into_iter().map(|x| Decimal(x as i128)).collect())
+}
+
+/// Computes `a * b / c` and its remainder without overflowing, using a 256 bit intermediate product.
+fn mul_div(a: u128, b: u128, c: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & LOW);
+    let (b_high, b_low) = (b >> 64, b & LOW);
+
+    // Long multiplication of the 64 bit halves into the high and low 128 bits of the product.
+    let low_low: u128 = a_low * b_low;
+    let low_high: u128 = a_low * b_high;
+    let high_low: u128 = a_high * b_low;
+    let high_high: u128 = a_high * b_high;
+    let middle: u128 = (low_low >> 64) + (low_high & LOW) + (high_low & LOW);
+    let mut low: u128 = (low_low & LOW) | (middle << 64);
+    let mut high: u128 = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+
+    // Shift-subtract long division of the 256 bit product by c.
+    let mut quotient: u128 = 0;
+    let mut remainder: u128 = 0;
+    for _ in 0..256 {
+        let carry: bool = remainder >> 127 == 1;
+        remainder = (remainder << 1) | (high >> 127);
+        high = (high << 1) | (low >> 127);
+        low <<= 1;
+        quotient <<= 1;
+        if carry || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_exact_split(total: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+        let parts: Vec<Decimal> = split_amount(total, weights).unwrap();
+        assert_eq!(parts.len(), weights.len());
+        assert!(parts.iter().all(|x| x.0 >= 0), "{:?} of {:?}", parts, weights);
+        assert_eq!(parts.iter().map(|x| x.0).sum::<i128>(), total.0, "{:?} of {:?}", parts, weights);
+        parts
+    }
+
+    #[test]
+    fn parts_sum_exactly_to_the_total() {
+        // A linear congruential generator, so that the cases are the same on every run.
+        let mut state: u64 = 7;
+        let mut next = |modulus: u64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) % modulus
+        };
+        for _ in 0..500 {
+            let weights: Vec<Decimal> = (0..next(6) + 1).map(|_| Decimal(next(1_000) as i128)).collect();
+            if weights.iter().all(|x| x.0 == 0) {
+                continue;
+            }
+            assert_exact_split(Decimal(next(u64::MAX) as i128), &weights);
+        }
+    }
+
+    #[test]
+    fn leftover_attos_go_to_the_largest_remainders() {
+        let parts: Vec<Decimal> = assert_exact_split(Decimal(10), &[Decimal(1), Decimal(1), Decimal(1)]);
+        assert_eq!(parts, vec![Decimal(4), Decimal(3), Decimal(3)]);
+
+        let parts: Vec<Decimal> = assert_exact_split(Decimal(5), &[Decimal(1), Decimal(2), Decimal(4)]);
+        assert_eq!(parts, vec![Decimal(1), Decimal(1), Decimal(3)]);
+    }
+
+    #[test]
+    fn zero_weights_and_totals_get_nothing() {
+        let parts: Vec<Decimal> = assert_exact_split(Decimal(100), &[Decimal(0), Decimal(3), Decimal(0), Decimal(1)]);
+        assert_eq!(parts, vec![Decimal(0), Decimal(75), Decimal(0), Decimal(25)]);
+
+        let parts: Vec<Decimal> = assert_exact_split(Decimal(0), &[Decimal(1), Decimal(2)]);
+        assert_eq!(parts, vec![Decimal(0), Decimal(0)]);
+
+        assert_eq!(split_amount(Decimal(100), &[Decimal(0), Decimal(0)]), Err(SplitError::ZeroTotalWeight));
+    }
+
+    #[test]
+    fn very_large_weights_and_totals_are_split_exactly() {
+        let parts: Vec<Decimal> = assert_exact_split(Decimal(i128::MAX), &[Decimal(i128::MAX), Decimal(i128::MAX)]);
+        assert_eq!(parts, vec![Decimal(i128::MAX / 2 + 1), Decimal(i128::MAX / 2)]);
+
+        assert_exact_split(Decimal(i128::MAX), &[Decimal(i128::MAX), Decimal(1)]);
+        assert_exact_split(Decimal(1), &[Decimal(i128::MAX - 1), Decimal(i128::MAX)]);
+    }
+
+    #[test]
+    fn weights_summing_past_128_bits_are_rejected() {
+        assert_eq!(
+            split_amount(Decimal(100), &[Decimal(i128::MAX), Decimal(i128::MAX), Decimal(2)]),
+            Err(SplitError::WeightOverflow)
+        );
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        assert_eq!(split_amount(Decimal(100), &[]), Err(SplitError::NoWeights));
+        assert_eq!(split_amount(Decimal(-1), &[Decimal(1)]), Err(SplitError::NegativeTotal));
+        assert_eq!(
+            split_amount(Decimal(100), &[Decimal(1), Decimal(-1)]),
+            Err(SplitError::NegativeWeight { index: 1 })
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! An in-memory mirror of the components, resources and receipts the client has learned about,
+//! which serves repeated queries instantly.
+//!
+//! Consistency: every entry is keyed by the address (or transaction hash) it was fetched for, so the
+//! mirror may serve data which is stale by up to the age reported alongside it, but it never serves
+//! the data of a different entity. Receipts never change once committed and so are never stale,
+//! but only the most recent ones are kept, the oldest being evicted first once the mirror is full.
+
+use scrypto::prelude::*;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::client::PteClient;
+use crate::clock::{system_clock, wait_until, Clock, StopSignal};
+use crate::component::ComponentInfo;
+use crate::resource::ResourceInfo;
+use crate::Receipt;
+
+/// The number of receipts a mirror keeps when not given a capacity.
+pub const DEFAULT_RECEIPT_CAPACITY: usize = 10_000;
+
+/// A value served from the mirror along with how fresh it is.
+#[derive(Debug, Clone)]
+pub struct Mirrored<T> {
+    pub value: T,
+    pub age: Duration,
+    pub stale: bool,
+}
+
+pub struct Mirror {
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    components: Mutex<HashMap<ComponentAddress, (ComponentInfo, Instant)>>,
+    resources: Mutex<HashMap<ResourceAddress, (ResourceInfo, Instant)>>,
+    receipts: Mutex<Receipts>,
+    pending_refresh: Mutex<HashSet<ComponentAddress>>,
+    pending_resource_refresh: Mutex<HashSet<ResourceAddress>>,
+}
+
+impl Mirror {
+    /// Creates a new mirror where entries older than `ttl` are considered stale.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            clock: system_clock(),
+            components: Mutex::new(HashMap::new()),
+            resources: Mutex::new(HashMap::new()),
+            receipts: Mutex::new(Receipts::new(DEFAULT_RECEIPT_CAPACITY)),
+            pending_refresh: Mutex::new(HashSet::new()),
+            pending_resource_refresh: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Replaces the clock used to timestamp entries and to schedule the background refreshes.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Keeps at most `capacity` receipts, evicting the oldest recorded first.
+    pub fn with_receipt_capacity(self, capacity: usize) -> Self {
+        Self {
+            receipts: Mutex::new(Receipts::new(capacity)),
+            ..self
+        }
+    }
+
+    pub fn record_component(&self, address: ComponentAddress, info: &ComponentInfo) {
+        self.components
+            .lock()
+            .unwrap()
+            .insert(address, (info.clone(), self.clock.now()));
+    }
+
+    pub fn record_resource(&self, address: ResourceAddress, info: &ResourceInfo) {
+        self.resources
+            .lock()
+            .unwrap()
+            .insert(address, (info.clone(), self.clock.now()));
+    }
+
+    pub fn record_receipt(&self, receipt: &Receipt) {
+        self.receipts.lock().unwrap().insert(receipt);
+    }
+
+    /// Gets the mirrored information of a component. When the entry is stale a background refresh
+    /// is requested and the stale entry is returned in the meantime.
+    pub fn get_component(&self, address: ComponentAddress) -> Option<Mirrored<ComponentInfo>> {
+        let (info, fetched_at) = self.components.lock().unwrap().get(&address).cloned()?;
+        let mirrored: Mirrored<ComponentInfo> = self.mirrored(info, fetched_at);
+        if mirrored.stale {
+            self.pending_refresh.lock().unwrap().insert(address);
+        }
+        Some(mirrored)
+    }
+
+    /// Gets the mirrored information of a resource, requesting a background refresh when the entry
+    /// is stale in the same way as `get_component`.
+    pub fn get_resource(&self, address: ResourceAddress) -> Option<Mirrored<ResourceInfo>> {
+        let (info, fetched_at) = self.resources.lock().unwrap().get(&address).cloned()?;
+        let mirrored: Mirrored<ResourceInfo> = self.mirrored(info, fetched_at);
+        if mirrored.stale {
+            self.pending_resource_refresh.lock().unwrap().insert(address);
+        }
+        Some(mirrored)
+    }
+
+    fn mirrored<T>(&self, value: T, fetched_at: Instant) -> Mirrored<T> {
+        let age: Duration = self.clock.now().saturating_duration_since(fetched_at);
+        Mirrored {
+            value,
+            age,
+            stale: age > self.ttl,
+        }
+    }
+
+    pub fn get_receipt(&self, transaction_hash: &str) -> Option<Receipt> {
+        self.receipts.lock().unwrap().by_hash.get(transaction_hash).cloned()
+    }
+
+    /// The number of receipts currently kept.
+    pub fn receipt_count(&self) -> usize {
+        self.receipts.lock().unwrap().by_hash.len()
+    }
+
+    /// The addresses of the components waiting for a background refresh.
+    pub fn pending_refreshes(&self) -> Vec<ComponentAddress> {
+        self.pending_refresh.lock().unwrap().iter().copied().collect()
+    }
+
+    /// The addresses of the resources waiting for a background refresh.
+    pub fn pending_resource_refreshes(&self) -> Vec<ResourceAddress> {
+        self.pending_resource_refresh.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Refreshes all of the stale entries which were requested since the last refresh. Failed
+    /// refreshes keep the old entry and are retried on the next request.
+    pub fn refresh_pending(&self, client: &PteClient) {
+        let pending: Vec<ComponentAddress> = self.pending_refresh.lock().unwrap().drain().collect();
+        for address in pending {
+            if let Ok(info) = client.get_component(address) {
+                self.record_component(address, &info);
+            }
+        }
+        let pending: Vec<ResourceAddress> = self.pending_resource_refresh.lock().unwrap().drain().collect();
+        for address in pending {
+            if let Ok(info) = client.get_resource(address) {
+                self.record_resource(address, &info);
+            }
+        }
+    }
+
+    /// Spawns a thread which refreshes the stale entries every `interval` of the mirror's clock.
+    /// The thread only holds weak references, so it doesn't keep the client or the mirror alive,
+    /// and it stops once either of them is dropped or the returned handle is stopped or dropped.
+    pub fn spawn_refresher(self: &Arc<Self>, client: &Arc<PteClient>, interval: Duration) -> Refresher {
+        let mirror: Weak<Mirror> = Arc::downgrade(self);
+        let client: Weak<PteClient> = Arc::downgrade(client);
+        let clock: Arc<dyn Clock> = self.clock.clone();
+        let stop: Arc<StopSignal> = Arc::new(StopSignal::new());
+
+        let handle: JoinHandle<()> = {
+            let stop: Arc<StopSignal> = stop.clone();
+            std::thread::spawn(move || {
+                let mut deadline: Instant = clock.now() + interval;
+                while wait_until(clock.as_ref(), deadline, &stop) {
+                    match (mirror.upgrade(), client.upgrade()) {
+                        (Some(mirror), Some(client)) => mirror.refresh_pending(&client),
+                        _ => break,
+                    }
+                    deadline = clock.now() + interval;
+                }
+            })
+        };
+
+        Refresher {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+/// The receipts of a mirror along with the order they were recorded in.
+struct Receipts {
+    capacity: usize,
+    by_hash: HashMap<String, Receipt>,
+    order: VecDeque<String>,
+}
+
+impl Receipts {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            by_hash: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Records the receipt, evicting the oldest receipts beyond the capacity. Recording a receipt
+    /// which is already kept doesn't make it any younger.
+    fn insert(&mut self, receipt: &Receipt) {
+        if self
+            .by_hash
+            .insert(receipt.transaction_hash.clone(), receipt.clone())
+            .is_none()
+        {
+            self.order.push_back(receipt.transaction_hash.clone());
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.by_hash.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// The handle of the background refresher of a mirror. The refresher stops when the handle is
+/// dropped.
+pub struct Refresher {
+    stop: Arc<StopSignal>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Refresher {
+    /// Stops the refresher, waiting for a refresh in progress to finish.
+    pub fn stop(mut self) {
+        self.stop_and_join();
+    }
+
+    /// Whether the thread of the refresher has exited.
+    pub fn is_finished(&self) -> bool {
+        match self.handle {
+            Some(ref handle) => handle.is_finished(),
+            None => true,
+        }
+    }
+
+    fn stop_and_join(&mut self) {
+        self.stop.stop();
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        self.stop_and_join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+
+    const TTL: Duration = Duration::from_secs(30);
+    const INTERVAL: Duration = Duration::from_secs(5);
+
+    fn resource_info(total_supply: &str) -> ResourceInfo {
+        ResourceInfo {
+            resource_type: serde_json::json!({ "Fungible": { "divisibility": 18 } }),
+            metadata: Default::default(),
+            total_supply: total_supply.to_string(),
+            extra_fields: Default::default(),
+        }
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) -> bool {
+        let started_at: Instant = Instant::now();
+        while started_at.elapsed() < Duration::from_secs(5) {
+            if condition() {
+                return true;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        condition()
+    }
+
+    fn mirrored_client(url: &str, clock: &Arc<TestClock>) -> (Arc<Mirror>, Arc<PteClient>) {
+        let mirror: Arc<Mirror> = Arc::new(Mirror::new(TTL).with_clock(clock.clone()));
+        let client: Arc<PteClient> = Arc::new(PteClient::new(url).with_mirror(mirror.clone()));
+        (mirror, client)
+    }
+
+    fn receipt(transaction_hash: &str) -> Receipt {
+        serde_json::from_value(serde_json::json!({
+            "transaction_hash": transaction_hash,
+            "status": "Success",
+            "outputs": [],
+            "logs": [],
+            "new_packages": [],
+            "new_components": [],
+            "new_resources": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn the_oldest_receipts_are_evicted_first() {
+        let mirror: Mirror = Mirror::new(TTL).with_receipt_capacity(3);
+        for hash in ["a", "b", "c"] {
+            mirror.record_receipt(&receipt(hash));
+        }
+        // Recording a kept receipt again doesn't save it from eviction.
+        mirror.record_receipt(&receipt("a"));
+        assert_eq!(mirror.receipt_count(), 3);
+
+        mirror.record_receipt(&receipt("d"));
+        mirror.record_receipt(&receipt("e"));
+        assert_eq!(mirror.receipt_count(), 3);
+        assert!(mirror.get_receipt("a").is_none());
+        assert!(mirror.get_receipt("b").is_none());
+        for hash in ["c", "d", "e"] {
+            assert_eq!(mirror.get_receipt(hash).unwrap().transaction_hash, hash);
+        }
+    }
+
+    #[test]
+    fn a_mirror_without_receipt_capacity_keeps_none() {
+        let mirror: Mirror = Mirror::new(TTL).with_receipt_capacity(0);
+        mirror.record_receipt(&receipt("a"));
+        assert_eq!(mirror.receipt_count(), 0);
+        assert!(mirror.get_receipt("a").is_none());
+    }
+
+    #[test]
+    fn resources_are_served_until_they_go_stale() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let mirror: Mirror = Mirror::new(TTL).with_clock(clock.clone());
+        mirror.record_resource(RADIX_TOKEN, &resource_info("100"));
+
+        clock.advance(TTL);
+        let fresh: Mirrored<ResourceInfo> = mirror.get_resource(RADIX_TOKEN).unwrap();
+        assert_eq!(fresh.value.total_supply, "100");
+        assert!(!fresh.stale);
+        assert!(mirror.pending_resource_refreshes().is_empty());
+
+        clock.advance(Duration::from_secs(1));
+        assert!(mirror.get_resource(RADIX_TOKEN).unwrap().stale);
+        assert_eq!(mirror.pending_resource_refreshes(), vec![RADIX_TOKEN]);
+    }
+
+    #[test]
+    fn the_refresher_waits_for_the_test_clock_instead_of_spinning() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let (mirror, client) = mirrored_client("http://127.0.0.1:1", &clock);
+        let refresher: Refresher = mirror.spawn_refresher(&client, INTERVAL);
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+        assert!(!refresher.is_finished());
+        refresher.stop();
+    }
+
+    #[test]
+    fn the_refresher_does_not_keep_the_client_alive() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let (mirror, client) = mirrored_client("http://127.0.0.1:1", &clock);
+        let refresher: Refresher = mirror.spawn_refresher(&client, INTERVAL);
+
+        let weak_client: Weak<PteClient> = Arc::downgrade(&client);
+        drop(client);
+        assert!(weak_client.upgrade().is_none());
+
+        clock.advance(INTERVAL);
+        assert!(wait_for(|| refresher.is_finished()));
+    }
+
+    #[test]
+    fn dropping_the_handle_stops_the_refresher() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let (mirror, client) = mirrored_client("http://127.0.0.1:1", &clock);
+        let refresher: Refresher = mirror.spawn_refresher(&client, INTERVAL);
+        drop(refresher);
+
+        assert_eq!(Arc::strong_count(&client), 1);
+        assert_eq!(Arc::strong_count(&mirror), 2);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn stale_resources_are_refreshed_once_the_interval_elapses() {
+        use crate::mock::{EndpointConfig, MockPte};
+
+        let mock: MockPte = MockPte::start(0)
+            .unwrap()
+            .with_endpoint("GET", "/resource/", EndpointConfig::new(200, &serde_json::to_string(&resource_info("250")).unwrap()));
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        let (mirror, client) = mirrored_client(&mock.url(), &clock);
+        mirror.record_resource(RADIX_TOKEN, &resource_info("100"));
+        clock.advance(TTL + Duration::from_secs(1));
+        assert!(mirror.get_resource(RADIX_TOKEN).unwrap().stale);
+
+        let _refresher: Refresher = mirror.spawn_refresher(&client, INTERVAL);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(mock.request_count("GET", "/resource/"), 0);
+
+        clock.advance(INTERVAL);
+        assert!(wait_for(|| mock.request_count("GET", "/resource/") == 1));
+        assert!(wait_for(|| mirror.get_resource(RADIX_TOKEN).unwrap().value.total_supply == "250"));
+        assert!(!mirror.get_resource(RADIX_TOKEN).unwrap().stale);
+    }
+}
@@ -0,0 +1,613 @@
+//! An embedded mock of the PTE's HTTP API for exercising timeouts, deadlines and the submission
+//! queue against realistic latency tails without a real network. Each endpoint can be given a
+//! latency distribution and an error rate, and the requests received are counted per endpoint.
+//!
+//! The mock answers over plain HTTP/1.1 on a local port, one connection per request.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::canonical_json::to_canonical_string;
+use crate::clock::{system_clock, Clock};
+#[cfg(feature = "blocking-http")]
+use crate::client::PteClient;
+use crate::vaults::{VaultInfo, VaultPage};
+use crate::Receipt;
+
+/// How long the mock waits before answering a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDistribution {
+    Fixed(Duration),
+    Uniform { min: Duration, max: Duration },
+    /// A log-normal distribution with the given median and standard deviation of the underlying
+    /// normal distribution. Typical of real network latencies with their long tails.
+    LogNormal { median: Duration, sigma: f64 },
+}
+
+impl Default for LatencyDistribution {
+    fn default() -> Self {
+        Self::Fixed(Duration::ZERO)
+    }
+}
+
+impl LatencyDistribution {
+    pub fn sample(&self, rng: &mut StdRng) -> Duration {
+        match *self {
+            Self::Fixed(latency) => latency,
+            Self::Uniform { min, max } if max > min => rng.gen_range(min..=max),
+            Self::Uniform { min, .. } => min,
+            Self::LogNormal { median, sigma } => {
+                // Box-Muller transform of two uniform samples into a standard normal sample.
+                let u1: f64 = 1.0 - rng.gen::<f64>();
+                let u2: f64 = rng.gen::<f64>();
+                let normal: f64 = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                median.mul_f64((sigma * normal).exp())
+            }
+        }
+    }
+
+    /// The latency below which the given fraction of samples fall.
+    pub fn quantile(&self, quantile: f64) -> Duration {
+        match *self {
+            Self::Fixed(latency) => latency,
+            Self::Uniform { min, max } => min + max.saturating_sub(min).mul_f64(quantile),
+            Self::LogNormal { median, sigma } => median.mul_f64((sigma * standard_normal_quantile(quantile)).exp()),
+        }
+    }
+}
+
+/// The quantile function of the standard normal distribution, using the rational approximation of
+/// Abramowitz and Stegun (26.2.23). Accurate to about 4.5e-4, which is plenty for latency checks.
+fn standard_normal_quantile(quantile: f64) -> f64 {
+    let p: f64 = quantile.clamp(1e-12, 1.0 - 1e-12);
+    let tail: f64 = p.min(1.0 - p);
+    let t: f64 = (-2.0 * tail.ln()).sqrt();
+    let magnitude: f64 = t - (2.515517 + 0.802853 * t + 0.010328 * t * t)
+        / (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t);
+    if p < 0.5 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// The behaviour of the mock for requests to one endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointConfig {
+    pub latency: LatencyDistribution,
+    /// The fraction of requests which are answered with `500 Internal Server Error`.
+    pub error_rate: f64,
+    pub status: u16,
+    pub body: String,
+    /// The content encoding the body is sent in. `gzip` bodies are compressed by the mock, while
+    /// any other encoding is only announced in the header, for exercising unsupported encodings.
+    pub content_encoding: Option<String>,
+}
+
+impl EndpointConfig {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            latency: LatencyDistribution::default(),
+            error_rate: 0.0,
+            status,
+            body: body.to_string(),
+            content_encoding: None,
+        }
+    }
+
+    pub fn latency(self, latency: LatencyDistribution) -> Self {
+        Self { latency, ..self }
+    }
+
+    pub fn error_rate(self, error_rate: f64) -> Self {
+        Self { error_rate, ..self }
+    }
+
+    pub fn content_encoding(self, content_encoding: &str) -> Self {
+        Self {
+            content_encoding: Some(content_encoding.to_string()),
+            ..self
+        }
+    }
+}
+
+/// The number of requests an endpoint received and how many of them had an error injected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestCounters {
+    pub received: u64,
+    pub injected_errors: u64,
+}
+
+struct MockState {
+    endpoints: Vec<(String, String, EndpointConfig)>,
+    /// The vaults served page by page from the paginated vaults endpoint, keyed by component.
+    paginated_vaults: BTreeMap<String, Vec<VaultInfo>>,
+    /// The cursors which are answered with `410 Gone` the next time they are used.
+    expiring_cursors: BTreeSet<String>,
+    counters: BTreeMap<String, RequestCounters>,
+    /// The bodies of the requests received, keyed like the counters.
+    bodies: BTreeMap<String, Vec<String>>,
+    rng: StdRng,
+    /// The clock the latencies are slept on.
+    clock: Arc<dyn Clock>,
+}
+
+impl MockState {
+    /// Finds the endpoint with the longest path prefix matching the request.
+    fn endpoint(&self, method: &str, path: &str) -> Option<(String, EndpointConfig)> {
+        self.endpoints
+            .iter()
+            // The root only matches itself, so that the health probe can be configured without
+            // catching every other path.
+            .filter(|(x, prefix, _)| {
+                x == method && path.starts_with(prefix.as_str()) && (prefix != "/" || path == "/")
+            })
+            .max_by_key(|(_, prefix, _)| prefix.len())
+            .map(|(method, prefix, config)| (format!("{} {}", method, prefix), config.clone()))
+    }
+
+    /// Counts a request under the key and keeps its body.
+    fn received(&mut self, key: String, body: &[u8]) -> &mut RequestCounters {
+        self.bodies.entry(key.clone()).or_default().push(String::from_utf8_lossy(body).to_string());
+        let counters: &mut RequestCounters = self.counters.entry(key).or_default();
+        counters.received += 1;
+        counters
+    }
+}
+
+/// A mock PTE listening on a local port. The server stops when the mock is dropped.
+pub struct MockPte {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    stopped: Arc<AtomicBool>,
+    /// The number of connections accepted.
+    connections: Arc<AtomicU64>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockPte {
+    /// Starts a mock which answers the health probe on its root with `200 OK`, transaction
+    /// submissions with a successful receipt, parse-only checks of manifests with the result of
+    /// compiling them, and all other requests, previews included, with `404 Not Found`. The seed
+    /// makes the sampled latencies and injected errors reproducible.
+    pub fn start(seed: u64) -> std::io::Result<Self> {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0")?;
+        let address: SocketAddr = listener.local_addr()?;
+        let state: Arc<Mutex<MockState>> = Arc::new(Mutex::new(MockState {
+            endpoints: vec![
+                (
+                    "POST".to_string(),
+                    "/transaction".to_string(),
+                    EndpointConfig::new(200, &to_canonical_string(&success_receipt()).unwrap()),
+                ),
+                // The health probe of the client.
+                ("GET".to_string(), "/".to_string(), EndpointConfig::new(200, "ok")),
+                // Previews would otherwise match the prefix of submissions.
+                (
+                    "POST".to_string(),
+                    "/transaction/preview".to_string(),
+                    EndpointConfig::new(404, "not found"),
+                ),
+            ],
+            paginated_vaults: BTreeMap::new(),
+            expiring_cursors: BTreeSet::new(),
+            counters: BTreeMap::new(),
+            bodies: BTreeMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+            clock: system_clock(),
+        }));
+        let stopped: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let connections: Arc<AtomicU64> = Arc::new(AtomicU64::new(0));
+
+        let handle: JoinHandle<()> = {
+            let state: Arc<Mutex<MockState>> = state.clone();
+            let stopped: Arc<AtomicBool> = stopped.clone();
+            let connections: Arc<AtomicU64> = connections.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        connections.fetch_add(1, Ordering::SeqCst);
+                        let state: Arc<Mutex<MockState>> = state.clone();
+                        std::thread::spawn(move || handle_connection(stream, &state));
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            address,
+            state,
+            stopped,
+            connections,
+            handle: Some(handle),
+        })
+    }
+
+    /// The base URL of the mock, to be given to a `PteClient`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// The number of connections the mock accepted. The mock closes every connection after answering
+    /// it, so this is also the number of requests it received.
+    pub fn connections(&self) -> u64 {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// A client which sends all of its requests to the mock.
+    #[cfg(feature = "blocking-http")]
+    pub fn client(&self) -> PteClient {
+        PteClient::new(&self.url())
+    }
+
+    /// Serves the given epoch from the epoch endpoint. Without it the mock behaves like an older PTE
+    /// which has no epoch endpoint.
+    pub fn with_epoch(self, epoch: u64) -> Self {
+        self.with_endpoint("GET", "/epoch", EndpointConfig::new(200, &format!("{{\"epoch\":{}}}", epoch)))
+    }
+
+    /// Sleeps the latencies of the endpoints on the given clock. On a `TestClock` the latencies
+    /// advance the clock instead of delaying the responses, so that a client sharing the clock
+    /// measures them exactly without the test waiting for them.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        self.state.lock().unwrap().clock = clock;
+        self
+    }
+
+    /// Serves the vaults of the component from the paginated vaults endpoint. The cursors are the
+    /// offsets of the pages.
+    pub fn with_paginated_vaults(self, component: &str, vaults: Vec<VaultInfo>) -> Self {
+        self.state.lock().unwrap().paginated_vaults.insert(component.to_string(), vaults);
+        self
+    }
+
+    /// Answers the next request using the cursor with `410 Gone`, as a server does once it forgot the
+    /// cursor.
+    pub fn expire_cursor(&self, cursor: &str) {
+        self.state.lock().unwrap().expiring_cursors.insert(cursor.to_string());
+    }
+
+    /// Configures how requests with the given method and path prefix are answered. The endpoint with
+    /// the longest matching prefix is used.
+    pub fn with_endpoint(self, method: &str, path_prefix: &str, config: EndpointConfig) -> Self {
+        self.set_endpoint(method, path_prefix, config);
+        self
+    }
+
+    pub fn set_endpoint(&self, method: &str, path_prefix: &str, config: EndpointConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.endpoints.retain(|(x, prefix, _)| !(x == method && prefix == path_prefix));
+        state.endpoints.push((method.to_string(), path_prefix.to_string(), config));
+    }
+
+    /// The counters of the requests received, keyed by `"<method> <path prefix>"` of the endpoint
+    /// which answered them. Requests matching no endpoint are counted under `"<method> <path>"`.
+    pub fn counters(&self) -> BTreeMap<String, RequestCounters> {
+        self.state.lock().unwrap().counters.clone()
+    }
+
+    pub fn request_count(&self, method: &str, path_prefix: &str) -> u64 {
+        self.counters()
+            .get(&format!("{} {}", method, path_prefix))
+            .map(|x| x.received)
+            .unwrap_or(0)
+    }
+
+    /// The bodies of the requests received by an endpoint, in the order they were received, keyed
+    /// like the counters.
+    pub fn request_bodies(&self, method: &str, path_prefix: &str) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .bodies
+            .get(&format!("{} {}", method, path_prefix))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for MockPte {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes up the accepting thread so that it sees the flag.
+        TcpStream::connect(self.address).ok();
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
+
+fn success_receipt() -> Receipt {
+    Receipt {
+        transaction_hash: "0".repeat(64),
+        status: "Success".to_string(),
+        outputs: Vec::new(),
+        logs: Vec::new(),
+        new_packages: Vec::new(),
+        new_components: Vec::new(),
+        new_resources: Vec::new(),
+        cost: None,
+        extra: Default::default(),
+        extensions: Default::default(),
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) {
+    let mut reader: BufReader<&TcpStream> = BufReader::new(&stream);
+
+    let mut request_line: String = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, path): (String, String) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return,
+    };
+
+    let mut content_length: usize = 0;
+    loop {
+        let mut header: String = String::new();
+        if reader.read_line(&mut header).is_err() {
+            return;
+        }
+        let header: &str = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut request_body: Vec<u8> = vec![0; content_length];
+    if reader.read_exact(&mut request_body).is_err() {
+        return;
+    }
+
+    // The latency and error are sampled under the lock so that a seed always produces the same
+    // sequence, and slept outside of it so that requests are answered concurrently.
+    let (latency, status, response_body, content_encoding): (Duration, u16, String, Option<String>) = {
+        let mut state = state.lock().unwrap();
+        if let Some((status, body)) = vault_page(&mut state, &method, &path) {
+            state.received("GET /component/vaults".to_string(), &request_body);
+            (Duration::ZERO, status, body, None)
+        } else if let Some((status, body)) = validate_manifest(&state, &method, &path, &request_body) {
+            state.received("POST /transaction/validate".to_string(), &request_body);
+            (Duration::ZERO, status, body, None)
+        } else if let Some((key, config)) = state.endpoint(&method, &path) {
+            let latency: Duration = config.latency.sample(&mut state.rng);
+            let injected_error: bool = state.rng.gen::<f64>() < config.error_rate;
+            let counters: &mut RequestCounters = state.received(key, &request_body);
+            if injected_error {
+                counters.injected_errors += 1;
+                (latency, 500, "injected error".to_string(), None)
+            } else {
+                (latency, config.status, config.body, config.content_encoding)
+            }
+        } else {
+            state.received(format!("{} {}", method, path), &request_body);
+            (Duration::ZERO, 404, "not found".to_string(), None)
+        }
+    };
+    let clock: Arc<dyn Clock> = state.lock().unwrap().clock.clone();
+    clock.sleep(latency);
+
+    let (body, encoding_header): (Vec<u8>, String) = match content_encoding {
+        Some(encoding) if encoding == "gzip" => (gzip_stored(response_body.as_bytes()), "Content-Encoding: gzip\r\n".to_string()),
+        Some(encoding) => (response_body.into_bytes(), format!("Content-Encoding: {}\r\n", encoding)),
+        None => (response_body.into_bytes(), String::new()),
+    };
+    let head: String = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason_phrase(status),
+        encoding_header,
+        body.len()
+    );
+    (&stream).write_all(head.as_bytes()).ok();
+    (&stream).write_all(&body).ok();
+}
+
+/// Wraps the data in a gzip member made of uncompressed deflate blocks. The mock only needs valid
+/// gzip, not small gzip, so this avoids depending on a compression library.
+fn gzip_stored(data: &[u8]) -> Vec<u8> {
+    let mut gzip: Vec<u8> = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        gzip.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let length: u16 = block.len() as u16;
+        gzip.push(blocks.peek().is_none() as u8);
+        gzip.extend_from_slice(&length.to_le_bytes());
+        gzip.extend_from_slice(&(!length).to_le_bytes());
+        gzip.extend_from_slice(block);
+    }
+    gzip.extend_from_slice(&crc32(data).to_le_bytes());
+    gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    gzip
+}
+
+/// The CRC-32 (IEEE) checksum of the data, as used by gzip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xffff_ffff;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Answers a request to the paginated vaults endpoint of a component whose vaults are paginated:
+/// `GET /component/<address>/vaults?limit=<n>&cursor=<offset>`.
+fn vault_page(state: &mut MockState, method: &str, path: &str) -> Option<(u16, String)> {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let component: &str = path.strip_prefix("/component/")?.strip_suffix("/vaults")?;
+    if method != "GET" || !state.paginated_vaults.contains_key(component) {
+        return None;
+    }
+
+    let parameter = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|x| x.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    };
+    let cursor: Option<String> = parameter("cursor");
+    if let Some(ref cursor) = cursor {
+        if state.expiring_cursors.remove(cursor) {
+            return Some((410, "cursor expired".to_string()));
+        }
+    }
+
+    let vaults: &[VaultInfo] = &state.paginated_vaults[component];
+    let limit: usize = parameter("limit").and_then(|x| x.parse().ok()).unwrap_or(vaults.len()).max(1);
+    let offset: usize = cursor.and_then(|x| x.parse().ok()).unwrap_or(0).min(vaults.len());
+    let end: usize = vaults.len().min(offset + limit);
+    let page: VaultPage = VaultPage {
+        vaults: vaults[offset..end].to_vec(),
+        next_cursor: (end < vaults.len()).then(|| end.to_string()),
+    };
+    Some((200, to_canonical_string(&page).unwrap()))
+}
+
+/// Answers a parse-only check of a manifest, `POST /transaction/validate` with the manifest text as
+/// the body, by compiling it with the manifest compiler this crate is pinned to. A manifest which
+/// doesn't compile is answered with `400 Bad Request` and the compiler's error. Configuring the
+/// endpoint replaces this behaviour.
+fn validate_manifest(state: &MockState, method: &str, path: &str, body: &[u8]) -> Option<(u16, String)> {
+    if method != "POST" || path != "/transaction/validate" {
+        return None;
+    }
+    if state.endpoints.iter().any(|(x, prefix, _)| x == method && prefix == path) {
+        return None;
+    }
+
+    match transaction_manifest::compile(&String::from_utf8_lossy(body)) {
+        Ok(_) => Some((200, "{}".to_string())),
+        Err(error) => Some((400, format!("{:?}", error))),
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        410 => "Gone",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The empirical quantile of the samples, using the nearest rank method like the bench.
+    fn empirical_quantile(distribution: &LatencyDistribution, samples: usize, quantile: f64) -> Duration {
+        let mut rng: StdRng = StdRng::seed_from_u64(7);
+        let mut latencies: Vec<Duration> = (0..samples).map(|_| distribution.sample(&mut rng)).collect();
+        latencies.sort();
+        latencies[((quantile * samples as f64).ceil() as usize).clamp(1, samples) - 1]
+    }
+
+    fn assert_close(actual: Duration, expected: Duration, tolerance: f64) {
+        let ratio: f64 = actual.as_secs_f64() / expected.as_secs_f64();
+        assert!((ratio - 1.0).abs() <= tolerance, "{:?} is not within {} of {:?}", actual, tolerance, expected);
+    }
+
+    #[test]
+    fn sampled_tails_match_the_quantiles_of_the_distributions() {
+        let distributions: [LatencyDistribution; 3] = [
+            LatencyDistribution::LogNormal {
+                median: Duration::from_millis(100),
+                sigma: 0.5,
+            },
+            LatencyDistribution::LogNormal {
+                median: Duration::from_millis(20),
+                sigma: 1.2,
+            },
+            LatencyDistribution::Uniform {
+                min: Duration::from_millis(10),
+                max: Duration::from_millis(50),
+            },
+        ];
+        for distribution in distributions.iter() {
+            for quantile in [0.5, 0.9, 0.99] {
+                assert_close(
+                    empirical_quantile(distribution, 200_000, quantile),
+                    distribution.quantile(quantile),
+                    0.03,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_latencies_have_no_tail() {
+        let distribution: LatencyDistribution = LatencyDistribution::Fixed(Duration::from_millis(30));
+        assert_eq!(empirical_quantile(&distribution, 1_000, 0.99), Duration::from_millis(30));
+        assert_eq!(distribution.quantile(0.99), Duration::from_millis(30));
+    }
+
+    /// Reads back the data of a gzip member made of stored deflate blocks, checking its framing.
+    fn gunzip_stored(gzip: &[u8]) -> Vec<u8> {
+        assert_eq!(gzip[..4], [0x1f, 0x8b, 8, 0]);
+        let mut data: Vec<u8> = Vec::new();
+        let mut offset: usize = 10;
+        loop {
+            let last: bool = gzip[offset] == 1;
+            let length: u16 = u16::from_le_bytes([gzip[offset + 1], gzip[offset + 2]]);
+            assert_eq!(u16::from_le_bytes([gzip[offset + 3], gzip[offset + 4]]), !length);
+            data.extend_from_slice(&gzip[offset + 5..offset + 5 + length as usize]);
+            offset += 5 + length as usize;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(gzip[offset..offset + 4], crc32(&data).to_le_bytes());
+        assert_eq!(gzip[offset + 4..], (data.len() as u32).to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn the_checksum_is_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn gzip_bodies_round_trip_across_blocks() {
+        let large: Vec<u8> = (0..150_000u32).map(|x| (x % 251) as u8).collect();
+        for data in [&b""[..], &b"{\"status\":\"Success\"}"[..], &large[..]] {
+            assert_eq!(gunzip_stored(&gzip_stored(data)), data);
+        }
+
+        // The large body needs three blocks, of which only the last is final.
+        let gzip: Vec<u8> = gzip_stored(&large);
+        assert_eq!(gzip.len(), 10 + 3 * 5 + large.len() + 8);
+        assert_eq!(gzip[10], 0);
+        assert_eq!(gzip[10 + 5 + u16::MAX as usize], 0);
+        assert_eq!(gzip[10 + 2 * (5 + u16::MAX as usize)], 1);
+    }
+}
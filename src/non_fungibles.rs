@@ -0,0 +1,109 @@
+//! Transfers of non-fungibles where the IDs to send can be picked from what the account holds.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::collections::BTreeSet;
+
+use crate::args::{call_args, Args};
+use crate::builder::TransactionBuilderExt;
+use crate::client::PteClient;
+use crate::credentials::Credentials;
+use crate::labeled::LabeledAddress;
+use crate::vaults::DEFAULT_PAGE_LIMIT;
+use crate::{PteApiError, Receipt, TransactionSubmissionError};
+
+/// Which of the non-fungibles of a resource to transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Any `count` of the non-fungibles held by the account, picked in a deterministic order.
+    Any(usize),
+    /// Exactly the non-fungibles with the given IDs.
+    Ids(Vec<NonFungibleId>),
+}
+
+#[derive(Debug)]
+pub enum TransferError {
+    /// The account holds fewer non-fungibles of the resource than were asked for.
+    InsufficientNonFungibles { requested: usize, available: usize },
+    ApiError(PteApiError),
+    SubmissionError(TransactionSubmissionError),
+}
+
+impl From<PteApiError> for TransferError {
+    fn from(error: PteApiError) -> TransferError {
+        TransferError::ApiError(error)
+    }
+}
+
+impl From<TransactionSubmissionError> for TransferError {
+    fn from(error: TransactionSubmissionError) -> TransferError {
+        TransferError::SubmissionError(error)
+    }
+}
+
+impl PteClient {
+    /// Picks `count` of the non-fungibles of the resource held by the account, in ascending order
+    /// of their IDs so that the same holdings always result in the same selection.
+    pub fn pick_non_fungibles(
+        &self,
+        account: impl Into<LabeledAddress<ComponentAddress>>,
+        resource: impl Into<LabeledAddress<ResourceAddress>>,
+        count: usize,
+    ) -> Result<Vec<NonFungibleId>, TransferError> {
+        let resource_address: String = resource.into().address.to_string();
+        let mut ids: Vec<String> = self
+            .get_vaults_all(account, DEFAULT_PAGE_LIMIT)?
+            .into_iter()
+            .filter(|vault| vault.resource == resource_address)
+            .flat_map(|vault| vault.non_fungible_ids.unwrap_or_default())
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        if ids.len() < count {
+            return Err(TransferError::InsufficientNonFungibles {
+                requested: count,
+                available: ids.len(),
+            });
+        }
+
+        ids.iter()
+            .take(count)
+            .map(|id| NonFungibleId::from_str(id).map_err(|_| PteApiError::InvalidState(id.clone()).into()))
+            .collect()
+    }
+
+    /// Transfers the selected non-fungibles of the resource from one account to another. When
+    /// picking any of the held non-fungibles, insufficient holdings are reported before any
+    /// transaction is built.
+    pub fn transfer_non_fungibles<'a>(
+        &'a self,
+        from: impl Into<LabeledAddress<ComponentAddress>>,
+        to: impl Into<LabeledAddress<ComponentAddress>>,
+        resource: impl Into<LabeledAddress<ResourceAddress>>,
+        selection: Selection,
+        signer: impl Into<Credentials<'a>>,
+    ) -> Result<Receipt, TransferError> {
+        let (from, to, resource): (LabeledAddress<ComponentAddress>, ComponentAddress, ResourceAddress) =
+            (from.into(), to.into().address, resource.into().address);
+        let private_key: &EcdsaPrivateKey = self.resolve_signer(signer.into())?;
+        let ids: BTreeSet<NonFungibleId> = match selection {
+            Selection::Any(count) => self.pick_non_fungibles(from.clone(), resource, count)?,
+            Selection::Ids(ids) => ids,
+        }
+        .into_iter()
+        .collect();
+
+        let transaction: SignedTransaction = TransactionBuilder::new()
+            .withdraw_non_fungibles_from_account(&ids, resource, from.address)
+            .take_from_worktop_ref(resource, |builder, bucket| {
+                builder.call_method(to, "deposit", call_args(Args::new().add_bucket(bucket)))
+            })
+            .build(self.next_nonce())
+            .sign([private_key]);
+
+        Ok(self.submit_transaction(&transaction)?)
+    }
+}
@@ -0,0 +1,363 @@
+//! Allocation of nonces for transactions signed by the same key. Each key has a window of nonces
+//! which can be outstanding at the same time, allowing several transactions from the same key to be
+//! in flight at once as long as their nonces are distinct. The counter of a key starts from the
+//! current time in microseconds on the manager's clock, or from where a previous process left it if that is further, so
+//! that a restarted process doesn't hand out the nonces it used before.
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clock::{system_clock, Clock};
+
+/// How a transaction which used a nonce ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceOutcome {
+    Committed,
+    /// The transaction failed or its outcome is unknown. Its nonce is burned and is never handed
+    /// out again.
+    Failed,
+}
+
+/// The next nonce of each key, persisted so that a later process continues from it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NonceState {
+    pub next: BTreeMap<String, u64>,
+}
+
+impl NonceState {
+    /// Loads the state from the file, returning `None` if there is none yet.
+    pub fn load(path: &Path) -> std::io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let temporary_path = path.with_extension("tmp");
+        fs::write(&temporary_path, serde_json::to_string_pretty(self).unwrap())?;
+        fs::rename(&temporary_path, path)
+    }
+}
+
+type Seed = Box<dyn Fn() -> u64 + Send + Sync>;
+
+pub struct NonceManager {
+    window_size: usize,
+    keys: Mutex<HashMap<String, KeyNonces>>,
+    condvar: Condvar,
+    /// Where the counter of a key starts when the key is first used, instead of the clock.
+    seed: Option<Seed>,
+    clock: Arc<dyn Clock>,
+    /// The counters left by a previous process, used when they are further than the seed.
+    restored: NonceState,
+}
+
+#[derive(Default)]
+struct KeyNonces {
+    next: u64,
+    outstanding: BTreeSet<u64>,
+    /// The burned nonces from the oldest outstanding one on. Older ones are pruned, as the counter
+    /// has moved past them and nothing in flight can be confused with them.
+    burned: BTreeSet<u64>,
+    burned_count: u64,
+}
+
+impl NonceManager {
+    /// Creates a nonce manager which allows up to `window_size` outstanding nonces per key.
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            window_size: window_size.max(1),
+            keys: Mutex::new(HashMap::new()),
+            condvar: Condvar::new(),
+            seed: None,
+            clock: system_clock(),
+            restored: NonceState::default(),
+        }
+    }
+
+    /// Starts the counter of each key from the given function instead of the current time.
+    pub fn with_seed<F>(self, seed: F) -> Self
+    where
+        F: Fn() -> u64 + Send + Sync + 'static,
+    {
+        Self {
+            seed: Some(Box::new(seed)),
+            ..self
+        }
+    }
+
+    /// Starts the counter of each key from the time of the clock rather than the system's. The
+    /// client gives its own clock to the manager it is configured with.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Continues the counters saved by a previous process, for the keys whose saved counter is
+    /// further than the seed.
+    pub fn with_state(self, state: NonceState) -> Self {
+        Self { restored: state, ..self }
+    }
+
+    /// The next nonce of each key used so far, to be saved and given to `with_state` later.
+    pub fn state(&self) -> NonceState {
+        let mut state: NonceState = self.restored.clone();
+        for (key, nonces) in self.keys.lock().unwrap().iter() {
+            state.next.insert(key.clone(), nonces.next);
+        }
+        state
+    }
+
+    /// Allocates the next nonce for the key, blocking while the key's window is full.
+    pub fn allocate(&self, key: &str) -> NonceLease {
+        let mut keys = self.keys.lock().unwrap();
+        while keys.get(key).map(|x| x.outstanding.len()).unwrap_or(0) >= self.window_size {
+            keys = self.condvar.wait(keys).unwrap();
+        }
+
+        let nonces: &mut KeyNonces = keys.entry(key.to_string()).or_insert_with(|| KeyNonces {
+            next: self.seed().max(self.restored.next.get(key).copied().unwrap_or(0)),
+            ..KeyNonces::default()
+        });
+        let nonce: u64 = nonces.next;
+        nonces.next += 1;
+        nonces.outstanding.insert(nonce);
+
+        NonceLease {
+            manager: self,
+            key: key.to_string(),
+            nonce,
+            completed: false,
+        }
+    }
+
+    /// The nonces of the key which are currently allocated to in-flight transactions.
+    pub fn outstanding(&self, key: &str) -> Vec<u64> {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|x| x.outstanding.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// The nonces of the key which were used by failed transactions and are not older than the
+    /// oldest nonce still outstanding.
+    pub fn burned(&self, key: &str) -> Vec<u64> {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|x| x.burned.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// The number of nonces of the key used by failed transactions, pruned or not.
+    pub fn burned_count(&self, key: &str) -> u64 {
+        self.keys.lock().unwrap().get(key).map(|x| x.burned_count).unwrap_or(0)
+    }
+
+    fn seed(&self) -> u64 {
+        match self.seed {
+            Some(ref seed) => seed(),
+            None => micros_since_epoch(self.clock.system_time()),
+        }
+    }
+
+    fn complete(&self, key: &str, nonce: u64, outcome: NonceOutcome) {
+        if let Ok(mut keys) = self.keys.lock() {
+            if let Some(nonces) = keys.get_mut(key) {
+                nonces.outstanding.remove(&nonce);
+                if outcome == NonceOutcome::Failed {
+                    nonces.burned.insert(nonce);
+                    nonces.burned_count += 1;
+                }
+                let oldest: u64 = nonces.outstanding.iter().next().copied().unwrap_or(nonces.next);
+                nonces.burned = nonces.burned.split_off(&oldest);
+            }
+        }
+        self.condvar.notify_all();
+    }
+}
+
+/// The time in microseconds since the Unix epoch. A process allocating fewer than a million nonces
+/// per second for a key never catches up with the clock of the next process.
+fn micros_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64
+}
+
+/// A nonce allocated from the `NonceManager`. The lease frees its slot in the window once it is
+/// completed; a lease which is dropped without being completed is treated as failed.
+pub struct NonceLease<'a> {
+    manager: &'a NonceManager,
+    key: String,
+    nonce: u64,
+    completed: bool,
+}
+
+impl<'a> NonceLease<'a> {
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn complete(mut self, outcome: NonceOutcome) {
+        self.completed = true;
+        self.manager.complete(&self.key, self.nonce, outcome);
+    }
+}
+
+impl<'a> Drop for NonceLease<'a> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.manager.complete(&self.key, self.nonce, NonceOutcome::Failed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::clock::TestClock;
+
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn counters_start_from_the_seed() {
+        let manager: NonceManager = NonceManager::new(2).with_seed(|| 1_000);
+        let first: NonceLease = manager.allocate("alice");
+        let second: NonceLease = manager.allocate("alice");
+        assert_eq!((first.nonce(), second.nonce()), (1_000, 1_001));
+        assert_eq!(manager.allocate("bob").nonce(), 1_000);
+    }
+
+    #[test]
+    fn the_default_seed_is_the_clock() {
+        let before: u64 = micros_since_epoch(SystemTime::now());
+        let nonce: u64 = NonceManager::new(1).allocate("alice").nonce();
+        assert!(nonce >= before && nonce <= micros_since_epoch(SystemTime::now()));
+    }
+
+    #[test]
+    fn the_seed_follows_an_injected_clock() {
+        let clock: Arc<TestClock> = Arc::new(TestClock::new());
+        clock.advance(Duration::from_secs(3_600));
+        let manager: NonceManager = NonceManager::new(1).with_clock(clock.clone());
+
+        assert_eq!(manager.allocate("alice").nonce(), micros_since_epoch(clock.system_time()));
+    }
+
+    #[test]
+    fn burned_nonces_are_pruned_once_nothing_older_is_outstanding() {
+        let manager: NonceManager = NonceManager::new(3).with_seed(|| 100);
+        let oldest: NonceLease = manager.allocate("alice");
+        let failed: NonceLease = manager.allocate("alice");
+        let newest: NonceLease = manager.allocate("alice");
+
+        failed.complete(NonceOutcome::Failed);
+        assert_eq!(manager.burned("alice"), vec![101]);
+
+        oldest.complete(NonceOutcome::Committed);
+        assert!(manager.burned("alice").is_empty());
+        assert_eq!(manager.burned_count("alice"), 1);
+
+        newest.complete(NonceOutcome::Failed);
+        assert!(manager.burned("alice").is_empty());
+        assert_eq!(manager.burned_count("alice"), 2);
+        assert_eq!(manager.allocate("alice").nonce(), 103);
+    }
+
+    #[test]
+    fn a_restarted_manager_continues_after_the_saved_counters() {
+        let directory: PathBuf = std::env::temp_dir().join(format!("nonce-state-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path: PathBuf = directory.join("nonces.json");
+        assert_eq!(NonceState::load(&path).unwrap(), None);
+
+        let used: Vec<u64> = {
+            let manager: NonceManager = NonceManager::new(4).with_seed(|| 500);
+            let used: Vec<u64> = (0..3).map(|_| manager.allocate("alice").nonce()).collect();
+            manager.state().save(&path).unwrap();
+            used
+        };
+
+        // A clock which went backwards doesn't bring back the used nonces, and keys the previous
+        // process didn't use start from the seed.
+        let manager: NonceManager = NonceManager::new(4)
+            .with_seed(|| 0)
+            .with_state(NonceState::load(&path).unwrap().unwrap());
+        let nonce: u64 = manager.allocate("alice").nonce();
+        assert!(used.iter().all(|x| *x < nonce), "{:?} then {}", used, nonce);
+        assert_eq!(manager.allocate("bob").nonce(), 0);
+
+        // A seed further than the saved counter wins.
+        let manager: NonceManager = NonceManager::new(4)
+            .with_seed(|| 10_000)
+            .with_state(NonceState::load(&path).unwrap().unwrap());
+        assert_eq!(manager.allocate("alice").nonce(), 10_000);
+        assert_eq!(manager.state().next.get("alice"), Some(&10_001));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn concurrent_allocations_are_distinct_and_stay_within_the_window() {
+        const WINDOW: usize = 3;
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 25;
+
+        let manager: Arc<NonceManager> = Arc::new(NonceManager::new(WINDOW));
+        let in_flight: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let most_in_flight: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<thread::JoinHandle<Vec<u64>>> = (0..THREADS)
+            .map(|thread| {
+                let (manager, in_flight, most_in_flight) = (manager.clone(), in_flight.clone(), most_in_flight.clone());
+                thread::spawn(move || {
+                    (0..PER_THREAD)
+                        .map(|index| {
+                            let lease: NonceLease = manager.allocate("alice");
+                            let now: usize = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                            most_in_flight.fetch_max(now, Ordering::SeqCst);
+                            thread::sleep(Duration::from_micros(100));
+                            let nonce: u64 = lease.nonce();
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                            match (thread + index) % 5 {
+                                0 => drop(lease),
+                                _ => lease.complete(NonceOutcome::Committed),
+                            }
+                            nonce
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+
+        let mut nonces: Vec<u64> = handles.into_iter().flat_map(|x| x.join().unwrap()).collect();
+        nonces.sort_unstable();
+        nonces.dedup();
+        assert_eq!(nonces.len(), THREADS * PER_THREAD);
+        assert!(most_in_flight.load(Ordering::SeqCst) <= WINDOW);
+        assert!(manager.outstanding("alice").is_empty());
+
+        // The dropped leases burned their nonces, which were pruned once nothing was outstanding,
+        // and the counter moved past every nonce handed out.
+        let dropped: usize = (0..THREADS)
+            .map(|thread| (0..PER_THREAD).filter(|index| (thread + index) % 5 == 0).count())
+            .sum();
+        assert_eq!(manager.burned_count("alice"), dropped as u64);
+        assert!(manager.burned("alice").is_empty());
+        assert_eq!(manager.state().next.get("alice"), Some(&(nonces.last().unwrap() + 1)));
+    }
+}
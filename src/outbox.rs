@@ -0,0 +1,351 @@
+//! A durable outbox for services which write business records and submit transactions. Services
+//! enqueue the intent of a transaction in the same step as their records, and a background worker
+//! builds, signs and submits it later. Entries are only marked as done once their outcome is
+//! recorded, so a crash at any point means the entry is submitted again (at-least-once), and the
+//! idempotency key of the entry keeps a resubmission from committing twice.
+//!
+//! The worker submits through `execute_idempotent`, so its client must be configured with
+//! idempotency, backed by a durable store for the guard to survive restarts.
+
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::canonical_json::to_canonical_vec;
+use crate::client::PteClient;
+use crate::clock::{wait_until, StopSignal};
+use crate::hooks::ClientWarning;
+use crate::manifest_spec::ManifestSpec;
+use crate::queue::ExecuteOptions;
+use crate::{Receipt, TransactionSubmissionError};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxState {
+    Pending,
+    Committed,
+    /// The entry failed permanently, or more times than the outbox allows, and is no longer retried.
+    DeadLetter,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub idempotency_key: String,
+    pub manifest: ManifestSpec,
+    pub tags: BTreeMap<String, String>,
+    pub state: OutboxState,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub receipt: Option<Receipt>,
+}
+
+/// A durable store of the entries of an outbox.
+pub trait OutboxStore: Send + Sync {
+    fn load(&self) -> std::io::Result<Vec<OutboxEntry>>;
+    fn save(&self, entries: &[OutboxEntry]) -> std::io::Result<()>;
+}
+
+/// An outbox store which only lives as long as the process. Useful in tests.
+#[derive(Default)]
+pub struct MemoryOutboxStore {
+    entries: Mutex<Vec<OutboxEntry>>,
+}
+
+impl OutboxStore for MemoryOutboxStore {
+    fn load(&self) -> std::io::Result<Vec<OutboxEntry>> {
+        Ok(self.entries.lock().unwrap().clone())
+    }
+
+    fn save(&self, entries: &[OutboxEntry]) -> std::io::Result<()> {
+        *self.entries.lock().unwrap() = entries.to_vec();
+        Ok(())
+    }
+}
+
+/// An outbox store persisted to a canonical JSON file. Writes go to a temporary file which is then
+/// renamed over the store so that a crash never leaves a partially written store behind.
+pub struct FileOutboxStore {
+    path: PathBuf,
+}
+
+impl FileOutboxStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl OutboxStore for FileOutboxStore {
+    fn load(&self) -> std::io::Result<Vec<OutboxEntry>> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn save(&self, entries: &[OutboxEntry]) -> std::io::Result<()> {
+        let temporary_path: PathBuf = self.path.with_extension("tmp");
+        fs::write(&temporary_path, to_canonical_vec(&entries).unwrap())?;
+        fs::rename(&temporary_path, &self.path)
+    }
+}
+
+/// The entries waiting to be submitted, backed by a durable store.
+pub struct Outbox {
+    store: Box<dyn OutboxStore>,
+    max_attempts: u32,
+    lock: Mutex<()>,
+}
+
+impl Outbox {
+    /// Creates an outbox whose entries are dead-lettered after failing `max_attempts` times.
+    pub fn new(store: impl OutboxStore + 'static, max_attempts: u32) -> Self {
+        Self {
+            store: Box::new(store),
+            max_attempts: max_attempts.max(1),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Durably records the intent of a transaction. Enqueuing an idempotency key which is already in
+    /// the outbox returns the existing entry's ID instead of adding a second entry.
+    pub fn enqueue(&self, manifest: ManifestSpec, tags: BTreeMap<String, String>, idempotency_key: &str) -> std::io::Result<u64> {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries: Vec<OutboxEntry> = self.store.load()?;
+        if let Some(entry) = entries.iter().find(|x| x.idempotency_key == idempotency_key) {
+            return Ok(entry.id);
+        }
+
+        let id: u64 = entries.iter().map(|x| x.id + 1).max().unwrap_or(0);
+        entries.push(OutboxEntry {
+            id,
+            idempotency_key: idempotency_key.to_string(),
+            manifest,
+            tags,
+            state: OutboxState::Pending,
+            attempts: 0,
+            last_error: None,
+            receipt: None,
+        });
+        self.store.save(&entries)?;
+        Ok(id)
+    }
+
+    pub fn entries(&self) -> std::io::Result<Vec<OutboxEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        self.store.load()
+    }
+
+    pub fn pending(&self) -> std::io::Result<Vec<OutboxEntry>> {
+        Ok(self.entries()?.into_iter().filter(|x| x.state == OutboxState::Pending).collect())
+    }
+
+    pub fn dead_letters(&self) -> std::io::Result<Vec<OutboxEntry>> {
+        Ok(self.entries()?.into_iter().filter(|x| x.state == OutboxState::DeadLetter).collect())
+    }
+
+    /// Moves a dead-lettered entry back to pending with its attempts reset, for example once the
+    /// cause of its failures was fixed.
+    pub fn requeue_dead_letter(&self, id: u64) -> std::io::Result<bool> {
+        self.update(id, |entry| {
+            if entry.state != OutboxState::DeadLetter {
+                return false;
+            }
+            entry.state = OutboxState::Pending;
+            entry.attempts = 0;
+            true
+        })
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut OutboxEntry) -> bool) -> std::io::Result<bool> {
+        let _guard = self.lock.lock().unwrap();
+        let mut entries: Vec<OutboxEntry> = self.store.load()?;
+        let changed: bool = match entries.iter_mut().find(|x| x.id == id) {
+            Some(entry) => change(entry),
+            None => false,
+        };
+        if changed {
+            self.store.save(&entries)?;
+        }
+        Ok(changed)
+    }
+
+    /// Builds, signs and submits a single entry and records its outcome.
+    fn process(&self, entry: &OutboxEntry, client: &PteClient, private_key: &EcdsaPrivateKey) -> std::io::Result<OutboxState> {
+        let options: ExecuteOptions = entry
+            .tags
+            .iter()
+            .fold(ExecuteOptions::new(), |options, (key, value)| options.tag(key, value));
+        let outcome: Result<Receipt, (String, bool)> = match entry.manifest.build(private_key, client.next_nonce()) {
+            // A spec which can't be built will never succeed, so it is dead-lettered right away.
+            Err(what) => Err((format!("invalid {}", what), true)),
+            Ok(transaction) => client
+                .execute_idempotent(&entry.idempotency_key, &transaction, &options)
+                .map_err(|error| (format!("{:?}", error), false)),
+        };
+
+        let max_attempts: u32 = self.max_attempts;
+        let mut state: OutboxState = OutboxState::Pending;
+        self.update(entry.id, |entry| {
+            entry.attempts += 1;
+            match outcome {
+                Ok(receipt) if receipt.is_success() => {
+                    entry.state = OutboxState::Committed;
+                    entry.last_error = None;
+                    entry.receipt = Some(receipt);
+                }
+                Ok(receipt) => {
+                    entry.last_error = Some(format!("transaction status {}", receipt.status));
+                    entry.receipt = Some(receipt);
+                }
+                Err((error, permanent)) => {
+                    entry.last_error = Some(error);
+                    if permanent {
+                        entry.state = OutboxState::DeadLetter;
+                    }
+                }
+            }
+            if entry.state == OutboxState::Pending && entry.attempts >= max_attempts {
+                entry.state = OutboxState::DeadLetter;
+            }
+            state = entry.state;
+            true
+        })?;
+        Ok(state)
+    }
+}
+
+/// What the worker of an outbox has done so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerStatus {
+    pub running: bool,
+    pub attempts: u64,
+    pub committed: u64,
+    pub dead_lettered: u64,
+    /// The times the outbox store could not be read or an outcome could not be recorded in it.
+    pub store_errors: u64,
+}
+
+#[derive(Default)]
+struct WorkerCounters {
+    running: AtomicBool,
+    attempts: AtomicU64,
+    committed: AtomicU64,
+    dead_lettered: AtomicU64,
+    store_errors: AtomicU64,
+}
+
+/// A background thread submitting the pending entries of an outbox.
+pub struct OutboxWorker {
+    counters: Arc<WorkerCounters>,
+    stop: Arc<StopSignal>,
+    drain: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl OutboxWorker {
+    /// Starts a worker which signs the entries of the outbox with the given key and submits them
+    /// through the client, looking for new entries every `poll_interval` of the client's clock.
+    /// Fails with `IdempotencyNotConfigured` if the client has no idempotency configured, as
+    /// resubmitted entries could then commit twice. Failures of the outbox store are reported
+    /// through the warning hooks of the client and the entries concerned are retried.
+    pub fn spawn(
+        outbox: Arc<Outbox>,
+        client: Arc<PteClient>,
+        private_key: EcdsaPrivateKey,
+        poll_interval: Duration,
+    ) -> Result<Self, TransactionSubmissionError> {
+        if client.idempotency().is_none() {
+            return Err(TransactionSubmissionError::IdempotencyNotConfigured);
+        }
+
+        let counters: Arc<WorkerCounters> = Arc::new(WorkerCounters::default());
+        let stop: Arc<StopSignal> = Arc::new(StopSignal::new());
+        let drain: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        counters.running.store(true, Ordering::SeqCst);
+
+        let handle: JoinHandle<()> = {
+            let (counters, stop, drain) = (counters.clone(), stop.clone(), drain.clone());
+            std::thread::spawn(move || {
+                let store_failed = |entry: Option<u64>, error: std::io::Error| {
+                    counters.store_errors.fetch_add(1, Ordering::SeqCst);
+                    client.hooks().warned(&ClientWarning::OutboxStoreFailed {
+                        entry,
+                        error: error.to_string(),
+                    });
+                };
+                while !stop.is_stopped() {
+                    let pending: Vec<OutboxEntry> = match outbox.pending() {
+                        Ok(pending) => pending,
+                        Err(error) => {
+                            store_failed(None, error);
+                            Vec::new()
+                        }
+                    };
+                    for entry in pending.iter() {
+                        if stop.is_stopped() && !drain.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        counters.attempts.fetch_add(1, Ordering::SeqCst);
+                        match outbox.process(entry, &client, &private_key) {
+                            Ok(OutboxState::Committed) => {
+                                counters.committed.fetch_add(1, Ordering::SeqCst);
+                            }
+                            Ok(OutboxState::DeadLetter) => {
+                                counters.dead_lettered.fetch_add(1, Ordering::SeqCst);
+                            }
+                            Ok(OutboxState::Pending) => {}
+                            Err(error) => store_failed(Some(entry.id), error),
+                        }
+                    }
+                    let deadline: Instant = client.clock().now() + poll_interval;
+                    wait_until(client.clock().as_ref(), deadline, &stop);
+                }
+                counters.running.store(false, Ordering::SeqCst);
+            })
+        };
+
+        Ok(Self {
+            counters,
+            stop,
+            drain,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn status(&self) -> WorkerStatus {
+        WorkerStatus {
+            running: self.counters.running.load(Ordering::SeqCst),
+            attempts: self.counters.attempts.load(Ordering::SeqCst),
+            committed: self.counters.committed.load(Ordering::SeqCst),
+            dead_lettered: self.counters.dead_lettered.load(Ordering::SeqCst),
+            store_errors: self.counters.store_errors.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Stops the worker and waits for it to exit. When `drain` is set the entries which were already
+    /// picked up are attempted before the worker exits, otherwise it stops after the entry in flight.
+    /// Entries left pending are picked up by the next worker.
+    pub fn shutdown(mut self, drain: bool) -> WorkerStatus {
+        self.drain.store(drain, Ordering::SeqCst);
+        self.stop.stop();
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+        self.status()
+    }
+}
+
+impl Drop for OutboxWorker {
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
@@ -0,0 +1,345 @@
+//! Pipelines of transactions which are submitted one after the other, along with a static analysis
+//! of the balances the withdrawals in each step rely on.
+
+use radix_engine::engine::{IdValidator, ProofKind};
+use radix_engine::model::{Instruction, SignedTransaction};
+use scrypto::engine::types::BucketId;
+use scrypto::buffer::scrypto_decode;
+use scrypto::prelude::*;
+
+use std::collections::HashMap;
+
+use crate::denomination::Denomination;
+
+#[cfg(feature = "blocking-http")]
+use crate::client::PteClient;
+#[cfg(feature = "blocking-http")]
+use crate::progress::{NoProgress, Progress};
+#[cfg(feature = "blocking-http")]
+use crate::{PteApiError, Receipt, TransactionSubmissionError};
+
+/// The amount of XRD the system component's `free_xrd` faucet method gives out.
+pub fn faucet_amount() -> Decimal {
+    dec!("1000000")
+}
+
+pub struct PipelineStep {
+    pub label: String,
+    pub transaction: SignedTransaction,
+}
+
+/// A sequence of transactions which are submitted in order.
+#[derive(Default)]
+pub struct Pipeline {
+    pub steps: Vec<PipelineStep>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_step(mut self, label: &str, transaction: SignedTransaction) -> Self {
+        self.steps.push(PipelineStep {
+            label: label.to_string(),
+            transaction,
+        });
+        self
+    }
+
+    /// Projects the balances of the accounts across all of the steps of the pipeline starting from
+    /// the given snapshot, and reports the withdrawals which would exceed the projected balance.
+    pub fn analyze_balances(&self, snapshot: &BalanceSnapshot) -> Vec<BalanceViolation> {
+        let mut balances: HashMap<(ComponentAddress, ResourceAddress), Decimal> = snapshot.balances.clone();
+        let mut consumers: HashMap<(ComponentAddress, ResourceAddress), Vec<usize>> = HashMap::new();
+        let mut violations: Vec<BalanceViolation> = Vec::new();
+
+        for (index, step) in self.steps.iter().enumerate() {
+            for flow in balance_flows(&step.transaction.transaction.instructions) {
+                match flow {
+                    BalanceFlow::Withdraw { account, resource, amount } => {
+                        let balance: &mut Decimal = balances.entry((account, resource)).or_insert(Decimal::zero());
+                        if amount > *balance {
+                            violations.push(BalanceViolation {
+                                step: index,
+                                step_label: step.label.clone(),
+                                account,
+                                resource,
+                                requested: amount,
+                                projected_balance: *balance,
+                                consumed_by: consumers
+                                    .get(&(account, resource))
+                                    .cloned()
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .map(|x| self.steps[x].label.clone())
+                                    .collect(),
+                            });
+                            *balance = Decimal::zero();
+                        } else {
+                            *balance = *balance - amount;
+                        }
+                        consumers.entry((account, resource)).or_default().push(index);
+                    }
+                    BalanceFlow::Deposit { account, resource, amount } => {
+                        let balance: &mut Decimal = balances.entry((account, resource)).or_insert(Decimal::zero());
+                        *balance = *balance + amount;
+                    }
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Submits the steps in order, stopping at the first one which fails to submit or is not
+    /// committed successfully.
+    #[cfg(feature = "blocking-http")]
+    pub fn execute(&self, client: &PteClient) -> Result<Vec<Receipt>, (usize, TransactionSubmissionError, Vec<Receipt>)> {
+        self.execute_with_progress(client, &NoProgress)
+    }
+
+    /// Same as `execute`, reporting a step, labeled with the label of the pipeline step, as each
+    /// step is committed successfully.
+    #[cfg(feature = "blocking-http")]
+    pub fn execute_with_progress(
+        &self,
+        client: &PteClient,
+        progress: &dyn Progress,
+    ) -> Result<Vec<Receipt>, (usize, TransactionSubmissionError, Vec<Receipt>)> {
+        let mut receipts: Vec<Receipt> = Vec::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            match client.submit_transaction(&step.transaction) {
+                Ok(receipt) => {
+                    let success: bool = receipt.is_success();
+                    receipts.push(receipt);
+                    if !success {
+                        break;
+                    }
+                    progress.on_step(index + 1, self.steps.len(), &step.label);
+                }
+                Err(error) => return Err((index, error, receipts)),
+            }
+        }
+        Ok(receipts)
+    }
+}
+
+/// The balances of a set of accounts at some point in time.
+#[derive(Debug, Clone, Default)]
+pub struct BalanceSnapshot {
+    pub balances: HashMap<(ComponentAddress, ResourceAddress), Decimal>,
+}
+
+impl BalanceSnapshot {
+    /// Takes a snapshot of the live balances of the given accounts.
+    #[cfg(feature = "blocking-http")]
+    pub fn fetch(client: &PteClient, accounts: &[ComponentAddress]) -> Result<Self, PteApiError> {
+        let mut balances: HashMap<(ComponentAddress, ResourceAddress), Decimal> = HashMap::new();
+        for account in accounts {
+            for (resource, amount) in client.get_balances(*account)? {
+                balances.insert((*account, resource), amount);
+            }
+        }
+        Ok(Self { balances })
+    }
+}
+
+/// A withdrawal which would exceed the projected balance of the account at that step.
+#[derive(Debug, Clone)]
+pub struct BalanceViolation {
+    pub step: usize,
+    pub step_label: String,
+    pub account: ComponentAddress,
+    pub resource: ResourceAddress,
+    pub requested: Decimal,
+    pub projected_balance: Decimal,
+    /// The labels of the earlier steps which withdrew from the same account and resource.
+    pub consumed_by: Vec<String>,
+}
+
+impl BalanceViolation {
+    /// A human readable description of the violation with the amounts written in the denomination.
+    pub fn describe(&self, denomination: Denomination) -> String {
+        let mut description: String = format!(
+            "Step {} ({}) withdraws {} {} of {} from {}, which is projected to hold only {}",
+            self.step,
+            self.step_label,
+            denomination.format(self.requested),
+            denomination,
+            self.resource,
+            self.account,
+            denomination.format(self.projected_balance)
+        );
+        if !self.consumed_by.is_empty() {
+            description.push_str(&format!(" after {}", self.consumed_by.join(", ")));
+        }
+        description
+    }
+}
+
+enum BalanceFlow {
+    Withdraw { account: ComponentAddress, resource: ResourceAddress, amount: Decimal },
+    Deposit { account: ComponentAddress, resource: ResourceAddress, amount: Decimal },
+}
+
+/// Follows the resources through the worktop and buckets of a transaction to find out how much of
+/// each resource is withdrawn from and deposited into accounts. Resources whose amounts can not be
+/// determined statically (e.g. returned by arbitrary component methods) are ignored.
+fn balance_flows(instructions: &[Instruction]) -> Vec<BalanceFlow> {
+    let mut flows: Vec<BalanceFlow> = Vec::new();
+    let mut worktop: HashMap<ResourceAddress, Decimal> = HashMap::new();
+    let mut buckets: HashMap<BucketId, (ResourceAddress, Decimal)> = HashMap::new();
+    let mut id_validator: IdValidator = IdValidator::new();
+
+    for instruction in instructions {
+        match instruction {
+            Instruction::CallMethod { component_address, method, args } => {
+                if *component_address == SYSTEM_COMPONENT && method == "free_xrd" {
+                    *worktop.entry(RADIX_TOKEN).or_insert(Decimal::zero()) += faucet_amount();
+                } else if method == "withdraw_by_amount" && args.len() == 2 {
+                    if let (Ok(amount), Ok(resource)) = (scrypto_decode::<Decimal>(&args[0]), scrypto_decode::<ResourceAddress>(&args[1])) {
+                        flows.push(BalanceFlow::Withdraw { account: *component_address, resource, amount });
+                        *worktop.entry(resource).or_insert(Decimal::zero()) += amount;
+                    }
+                } else if method == "deposit" && args.len() == 1 {
+                    if let Ok(bucket) = scrypto_decode::<scrypto::resource::Bucket>(&args[0]) {
+                        if let Some((resource, amount)) = buckets.get(&bucket.0).copied() {
+                            flows.push(BalanceFlow::Deposit { account: *component_address, resource, amount });
+                        }
+                    }
+                }
+            }
+            Instruction::CallMethodWithAllResources { component_address, .. } => {
+                for (resource, amount) in worktop.drain() {
+                    flows.push(BalanceFlow::Deposit { account: *component_address, resource, amount });
+                }
+            }
+            Instruction::TakeFromWorktop { resource_address } => {
+                let amount: Decimal = worktop.remove(resource_address).unwrap_or(Decimal::zero());
+                if let Ok(bucket_id) = id_validator.new_bucket() {
+                    buckets.insert(bucket_id, (*resource_address, amount));
+                }
+            }
+            Instruction::TakeFromWorktopByAmount { amount, resource_address } => {
+                let available: &mut Decimal = worktop.entry(*resource_address).or_insert(Decimal::zero());
+                *available = *available - *amount;
+                if let Ok(bucket_id) = id_validator.new_bucket() {
+                    buckets.insert(bucket_id, (*resource_address, *amount));
+                }
+            }
+            Instruction::TakeFromWorktopByIds { resource_address, .. } => {
+                if let Ok(bucket_id) = id_validator.new_bucket() {
+                    buckets.insert(bucket_id, (*resource_address, Decimal::zero()));
+                }
+            }
+            // Proofs are allocated ids from the same allocator as buckets and so must be tracked too
+            Instruction::PopFromAuthZone
+            | Instruction::CreateProofFromAuthZone { .. }
+            | Instruction::CreateProofFromAuthZoneByAmount { .. }
+            | Instruction::CreateProofFromAuthZoneByIds { .. } => {
+                id_validator.new_proof(ProofKind::AuthZoneProof).ok();
+            }
+            Instruction::CreateProofFromBucket { bucket_id } => {
+                id_validator.new_proof(ProofKind::BucketProof(*bucket_id)).ok();
+            }
+            Instruction::CloneProof { proof_id } => {
+                id_validator.clone_proof(*proof_id).ok();
+            }
+            _ => {}
+        }
+    }
+
+    flows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use radix_engine::transaction::TransactionBuilder;
+
+    const ACCOUNT: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173";
+    const OTHER_ACCOUNT: &str = "020d3869346218a5e8deaaf2001216dc00fcacb79fb43e30ded79a";
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::from_str(ACCOUNT).unwrap()
+    }
+
+    fn other_account() -> ComponentAddress {
+        ComponentAddress::from_str(OTHER_ACCOUNT).unwrap()
+    }
+
+    fn transfer(amount: Decimal, from: ComponentAddress, to: ComponentAddress) -> SignedTransaction {
+        let private_key: EcdsaPrivateKey = EcdsaPrivateKey::from_bytes(&[1u8; 32]).unwrap();
+        TransactionBuilder::new()
+            .withdraw_from_account_by_amount(amount, RADIX_TOKEN, from)
+            .call_method_with_all_resources(to, "deposit_batch")
+            .build(0)
+            .sign([&private_key])
+    }
+
+    fn snapshot(balances: &[(ComponentAddress, Decimal)]) -> BalanceSnapshot {
+        BalanceSnapshot {
+            balances: balances.iter().map(|(account, amount)| ((*account, RADIX_TOKEN), *amount)).collect(),
+        }
+    }
+
+    #[test]
+    fn withdrawals_exceeding_the_projected_balance_are_reported() {
+        let pipeline: Pipeline = Pipeline::new()
+            .add_step("signup", transfer(dec!("60"), account(), other_account()))
+            .add_step("payout", transfer(dec!("60"), account(), other_account()));
+
+        let violations: Vec<BalanceViolation> = pipeline.analyze_balances(&snapshot(&[(account(), dec!("100"))]));
+
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        let violation: &BalanceViolation = &violations[0];
+        assert_eq!(violation.step, 1);
+        assert_eq!(violation.step_label, "payout");
+        assert_eq!(violation.account, account());
+        assert_eq!(violation.resource, RADIX_TOKEN);
+        assert_eq!(violation.requested, dec!("60"));
+        assert_eq!(violation.projected_balance, dec!("40"));
+        assert_eq!(violation.consumed_by, vec!["signup".to_string()]);
+    }
+
+    #[test]
+    fn deposits_made_by_earlier_steps_fund_later_withdrawals() {
+        let pipeline: Pipeline = Pipeline::new()
+            .add_step("funding", transfer(dec!("50"), other_account(), account()))
+            .add_step("spending", transfer(dec!("50"), account(), other_account()));
+
+        let violations: Vec<BalanceViolation> =
+            pipeline.analyze_balances(&snapshot(&[(account(), Decimal::zero()), (other_account(), dec!("50"))]));
+        assert!(violations.is_empty(), "{:?}", violations);
+
+        // Without the deposit of the first step the same withdrawal overdraws the account
+        let violations: Vec<BalanceViolation> = Pipeline::new()
+            .add_step("spending", transfer(dec!("50"), account(), other_account()))
+            .analyze_balances(&snapshot(&[(account(), Decimal::zero()), (other_account(), dec!("50"))]));
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].projected_balance, Decimal::zero());
+    }
+
+    #[test]
+    fn violations_are_described_in_the_denomination() {
+        let violation: BalanceViolation = BalanceViolation {
+            step: 2,
+            step_label: "payout".to_string(),
+            account: ComponentAddress::from_str("02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173").unwrap(),
+            resource: RADIX_TOKEN,
+            requested: Decimal(1_500_000_000_000_000_000),
+            projected_balance: Decimal(1),
+            consumed_by: vec!["signup".to_string()],
+        };
+
+        let whole: String = violation.describe(Denomination::Whole);
+        assert!(whole.starts_with("Step 2 (payout) withdraws 1.5 whole units of "), "{}", whole);
+        assert!(whole.ends_with(", which is projected to hold only 0.000000000000000001 after signup"), "{}", whole);
+
+        let attos: String = violation.describe(Denomination::Attos);
+        assert!(attos.starts_with("Step 2 (payout) withdraws 1500000000000000000 attos of "), "{}", attos);
+        assert!(attos.ends_with(", which is projected to hold only 1 after signup"), "{}", attos);
+    }
+}
@@ -0,0 +1,55 @@
+//! The configuration of the pool of connections the blocking and asynchronous clients keep open to
+//! the PTE servers.
+
+use std::time::Duration;
+
+/// The configuration of the pool of connections the client keeps open to the PTE servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// How long an idle connection is kept in the pool before being closed.
+    pub idle_timeout: Option<Duration>,
+    pub max_idle_per_host: usize,
+    /// How long a request may take, from connecting to reading the whole response.
+    pub request_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Some(Duration::from_secs(90)),
+            max_idle_per_host: usize::MAX,
+            // The default of the blocking HTTP client, which the asynchronous client is given too.
+            request_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl PoolConfig {
+    #[cfg(feature = "blocking-http")]
+    pub(crate) fn http_client_builder(&self) -> reqwest::blocking::ClientBuilder {
+        reqwest::blocking::Client::builder()
+            .pool_idle_timeout(self.idle_timeout)
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .timeout(self.request_timeout)
+    }
+
+    #[cfg(feature = "blocking-http")]
+    pub(crate) fn build_http_client(&self) -> reqwest::blocking::Client {
+        self.http_client_builder()
+            .build()
+            .expect("Failed to initialize the HTTP client")
+    }
+
+    #[cfg(feature = "async-http")]
+    pub(crate) fn build_async_http_client(&self) -> reqwest::Client {
+        let builder: reqwest::ClientBuilder = reqwest::Client::builder()
+            .pool_idle_timeout(self.idle_timeout)
+            .pool_max_idle_per_host(self.max_idle_per_host);
+        match self.request_timeout {
+            Some(request_timeout) => builder.timeout(request_timeout),
+            None => builder,
+        }
+        .build()
+        .expect("Failed to initialize the HTTP client")
+    }
+}
@@ -0,0 +1,89 @@
+//! The commonly used types of the crate. Only the types of the enabled features are re-exported, so
+//! `use programmatic_pte_interactions::prelude::*` works the same way in minimal and full builds.
+
+pub use crate::address_book::{annotate_manifest, AddressBook};
+pub use crate::args::{call_args, encode_args, Args, BucketRef, ProofRef};
+pub use crate::builder::{Distribution, ManifestEditor, RouteTarget, SpliceError, TransactionBuilderExt};
+pub use crate::circuit::{CircuitBreaker, CircuitConfig, CircuitOpen, CircuitState};
+pub use crate::clock::{system_clock, wait_until, Clock, StopSignal, SystemClock, TestClock};
+pub use crate::component::{ComponentInfo, SchemaDrift};
+pub use crate::resource::ResourceInfo;
+pub use crate::epoch_clock::{EpochClock, EpochLengthEstimate};
+pub use crate::denomination::{from_attos, to_attos, DenominatedAmount, Denomination, DenominationError};
+pub use crate::entities::{CreatedEntity, EntityExpectationError, EntityKind};
+pub use crate::events::EventParseError;
+pub use crate::extensions::{ReceiptExtension, ReceiptExtensions};
+pub use crate::hooks::{ClientWarning, Hooks, SubmissionFailure, TxHashes};
+pub use crate::labeled::{AddressSource, LabeledAddress};
+pub use crate::manifest_spec::ManifestSpec;
+pub use crate::math::{split_amount, SplitError};
+pub use crate::nonce::{NonceManager, NonceOutcome, NonceState};
+pub use crate::pipeline::{BalanceSnapshot, BalanceViolation, Pipeline};
+pub use crate::progress::{ChannelProgress, NoProgress, Progress, ProgressEvent};
+pub use crate::public_key::{convert_public_key, parse_public_key, PublicKeyError, PublicKeyFormat};
+pub use crate::raw::{decode_partial, PartialDecode, ScryptoValueExt};
+pub use crate::queue::{ExecuteOptions, Priority, SubmissionQueue};
+pub use crate::scope::{verify_manifest_scope, ScopePolicy, ScopeViolation, ScopeViolationKind};
+pub use crate::vaults::{PageOptions, VaultInfo, VaultPage, VaultSnapshot};
+pub use crate::{CostSummary, ErrorClass, PteApiError, Receipt, ReceiptWarning, TransactionBody, TransactionSubmissionError};
+
+#[cfg(any(feature = "blocking-http", feature = "testing"))]
+pub use crate::deterministic::{DeterministicMode, Randomness};
+#[cfg(any(feature = "blocking-http", feature = "testing"))]
+pub use crate::test_package::{TestPackageError, TEST_PACKAGE_BLUEPRINT};
+
+#[cfg(all(feature = "blocking-http", feature = "keystore"))]
+pub use crate::account_import::{import_account, ExtensionExport, ImportError, ImportedAccount};
+#[cfg(feature = "blocking-http")]
+pub use crate::address_book::AddressResolver;
+#[cfg(feature = "blocking-http")]
+pub use crate::bootstrap::{bootstrap, bootstrap_with_progress, BootstrapConfig, BootstrapError, BootstrapSession};
+#[cfg(feature = "blocking-http")]
+pub use crate::chain::{ChainError, ChainedCalls, Instantiation};
+#[cfg(feature = "blocking-http")]
+pub use crate::client::{Horizon, PoolConfig, PteClient, ServerSelection, DEFAULT_PTE_URL};
+#[cfg(feature = "blocking-http")]
+pub use crate::credentials::Credentials;
+#[cfg(feature = "blocking-http")]
+pub use crate::deployment::{verify_deployment, DeploymentReport, DeploymentStep, StepOutcome};
+#[cfg(feature = "blocking-http")]
+pub use crate::faucet::{FaucetLimiter, FaucetLimiterConfig, FaucetMetrics};
+#[cfg(feature = "blocking-http")]
+pub use crate::idempotency::{FileIdempotencyStore, IdempotencyStore, MemoryIdempotencyStore};
+#[cfg(feature = "blocking-http")]
+pub use crate::intent::{EpochsRemaining, IntentError, IntentValidity, PreparedTransaction, SignedIntentPackage};
+#[cfg(feature = "blocking-http")]
+pub use crate::mirror::{Mirror, Refresher};
+#[cfg(feature = "blocking-http")]
+pub use crate::non_fungibles::Selection;
+#[cfg(feature = "blocking-http")]
+pub use crate::outbox::{FileOutboxStore, MemoryOutboxStore, Outbox, OutboxStore, OutboxWorker};
+#[cfg(feature = "blocking-http")]
+pub use crate::readonly::ReadOnlyError;
+#[cfg(feature = "blocking-http")]
+pub use crate::signer_account::{DepositTarget, SignerAccountError, SignerAccountResolver};
+
+#[cfg(feature = "async-http")]
+pub use crate::async_client::AsyncPteClient;
+
+#[cfg(feature = "keystore")]
+pub use crate::keystore::Keystore;
+
+#[cfg(feature = "bridge")]
+pub use crate::bridge::{Bridge, BridgeConfig, BridgeOperation};
+
+#[cfg(feature = "cli")]
+pub use crate::progress::BarProgress;
+
+#[cfg(feature = "metrics")]
+pub use crate::report::{BatchReport, RunReport};
+
+#[cfg(feature = "testing")]
+pub use crate::conformance::ConformanceScenario;
+#[cfg(feature = "testing")]
+pub use crate::mock::{EndpointConfig, LatencyDistribution, MockPte};
+#[cfg(feature = "testing")]
+pub use crate::test_vectors::TestVector;
+
+#[cfg(all(feature = "testing", feature = "blocking-http"))]
+pub use crate::test_context::{TestAccount, TestContext, TestContextError};
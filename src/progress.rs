@@ -0,0 +1,211 @@
+//! Progress reporting for operations which submit many transactions, such as bootstraps, pipelines
+//! and benchmarks. Totals are known up front, and `current` is the number of steps completed so far,
+//! so a step which is retried or resumed is only counted once it actually completes.
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+/// Receives the progress of a long running operation. Implementations must be cheap, as they are
+/// called from the thread doing the work.
+pub trait Progress: Send + Sync {
+    /// Called when a step completes, with the number of steps completed so far out of `total`.
+    fn on_step(&self, current: usize, total: usize, label: &str);
+
+    /// Called when part of the step in progress completes.
+    fn on_substep(&self, _current: usize, _total: usize, _label: &str) {}
+}
+
+/// Discards the progress. Used by the helpers which are called without a progress reporter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn on_step(&self, _current: usize, _total: usize, _label: &str) {}
+}
+
+/// A progress report sent by `ChannelProgress`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    Step { current: usize, total: usize, label: String },
+    Substep { current: usize, total: usize, label: String },
+}
+
+/// Sends the progress over a channel, for embedding in applications which render it on another
+/// thread. Reports are dropped once the receiver is gone.
+#[derive(Debug)]
+pub struct ChannelProgress {
+    sender: Mutex<Sender<ProgressEvent>>,
+}
+
+impl ChannelProgress {
+    pub fn new() -> (Self, Receiver<ProgressEvent>) {
+        let (sender, receiver) = channel();
+        (Self { sender: Mutex::new(sender) }, receiver)
+    }
+
+    fn send(&self, event: ProgressEvent) {
+        let _ = self.sender.lock().unwrap().send(event);
+    }
+}
+
+impl Progress for ChannelProgress {
+    fn on_step(&self, current: usize, total: usize, label: &str) {
+        self.send(ProgressEvent::Step {
+            current,
+            total,
+            label: label.to_string(),
+        });
+    }
+
+    fn on_substep(&self, current: usize, total: usize, label: &str) {
+        self.send(ProgressEvent::Substep {
+            current,
+            total,
+            label: label.to_string(),
+        });
+    }
+}
+
+/// Renders the progress as a progress bar on the terminal.
+#[cfg(feature = "cli")]
+pub struct BarProgress {
+    bar: indicatif::ProgressBar,
+}
+
+#[cfg(feature = "cli")]
+impl BarProgress {
+    pub fn new() -> Self {
+        let bar: indicatif::ProgressBar = indicatif::ProgressBar::new(0);
+        bar.set_style(
+            indicatif::ProgressStyle::with_template("{bar:40} {pos}/{len} {msg}")
+                .expect("the template is valid"),
+        );
+        Self { bar }
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish();
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Default for BarProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Progress for BarProgress {
+    fn on_step(&self, current: usize, total: usize, label: &str) {
+        self.bar.set_length(total as u64);
+        self.bar.set_position(current as u64);
+        self.bar.set_message(label.to_string());
+    }
+
+    fn on_substep(&self, current: usize, total: usize, label: &str) {
+        self.bar.set_message(format!("{} ({}/{})", label, current, total));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_channel_reporter_forwards_steps_and_substeps_in_order() {
+        let (progress, receiver) = ChannelProgress::new();
+        progress.on_step(1, 3, "first");
+        progress.on_substep(1, 2, "half of second");
+        progress.on_step(2, 3, "second");
+
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<ProgressEvent>>(),
+            vec![
+                ProgressEvent::Step {
+                    current: 1,
+                    total: 3,
+                    label: "first".to_string(),
+                },
+                ProgressEvent::Substep {
+                    current: 1,
+                    total: 2,
+                    label: "half of second".to_string(),
+                },
+                ProgressEvent::Step {
+                    current: 2,
+                    total: 3,
+                    label: "second".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_are_dropped_once_the_receiver_is_gone() {
+        let (progress, receiver) = ChannelProgress::new();
+        drop(receiver);
+        progress.on_step(1, 1, "done");
+        progress.on_substep(1, 1, "done");
+    }
+
+    #[test]
+    fn the_channel_reporter_can_be_shared_between_threads() {
+        let (progress, receiver) = ChannelProgress::new();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| progress.on_step(1, 1, "done"));
+            }
+        });
+        assert_eq!(receiver.try_iter().count(), 4);
+    }
+
+    /// A bootstrap whose token step fails twice, once with a receipt creating no token and once
+    /// with a server error, before being retried successfully.
+    #[cfg(all(feature = "testing", feature = "blocking-http"))]
+    #[test]
+    fn retried_steps_are_only_counted_once_they_complete() {
+        use crate::bootstrap::{bootstrap_with_progress, BootstrapConfig};
+        use crate::fixtures::{account_creation_receipt, multi_creation_receipt};
+        use crate::mock::{EndpointConfig, MockPte};
+        use crate::Receipt;
+
+        use scrypto::prelude::*;
+        use std::path::PathBuf;
+
+        let receipt_endpoint = |receipt: Receipt| EndpointConfig::new(200, &serde_json::to_string(&receipt).unwrap());
+        let mock: MockPte = MockPte::start(0)
+            .unwrap()
+            .with_endpoint("POST", "/transaction", receipt_endpoint(account_creation_receipt()));
+        let out: PathBuf = std::env::temp_dir().join(format!("pte-progress-{}", std::process::id()));
+        std::fs::remove_dir_all(&out).ok();
+        let config: BootstrapConfig = BootstrapConfig {
+            accounts: 2,
+            token_symbol: "GLD".to_string(),
+            token_supply: dec!("1000"),
+            out: out.clone(),
+        };
+        let (progress, receiver) = ChannelProgress::new();
+
+        assert!(bootstrap_with_progress(&mock.client(), &config, &progress).is_err());
+        mock.set_endpoint("POST", "/transaction", EndpointConfig::new(500, "overloaded"));
+        assert!(bootstrap_with_progress(&mock.client(), &config, &progress).is_err());
+        mock.set_endpoint("POST", "/transaction", receipt_endpoint(multi_creation_receipt()));
+        assert!(bootstrap_with_progress(&mock.client(), &config, &progress).is_ok());
+        std::fs::remove_dir_all(&out).ok();
+
+        let steps: Vec<(usize, usize, String)> = receiver
+            .try_iter()
+            .map(|event| match event {
+                ProgressEvent::Step { current, total, label } => (current, total, label),
+                ProgressEvent::Substep { .. } => panic!("a bootstrap only reports steps"),
+            })
+            .collect();
+        let completed: Vec<usize> = steps.iter().filter(|x| x.2 != "resumed").map(|x| x.0).collect();
+        let resumed: Vec<usize> = steps.iter().filter(|x| x.2 == "resumed").map(|x| x.0).collect();
+        assert_eq!(completed, vec![1, 2, 3, 4], "{:?}", steps);
+        assert_eq!(resumed, vec![2, 2], "{:?}", steps);
+        assert!(steps.iter().all(|x| x.1 == 4), "{:?}", steps);
+        assert_eq!(mock.request_count("POST", "/transaction"), 6);
+    }
+}
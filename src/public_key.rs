@@ -0,0 +1,94 @@
+//! The string formats of ECDSA public keys. PTE deployments disagree on how the public keys of the
+//! signatures of a transaction body are written, so the format is chosen explicitly when the body is
+//! built, and keys read from strings are accepted in any of the formats.
+
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use scrypto::prelude::*;
+
+/// The prefix of the `PrefixedCompressedHex` format.
+pub const PUBLIC_KEY_PREFIX: &str = "0x";
+
+/// How a public key is written as a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PublicKeyFormat {
+    /// The hex of the 33 byte SEC1 compressed point, which is what the upstream types print.
+    #[default]
+    CompressedHex,
+    /// The compressed hex prefixed with `0x`.
+    PrefixedCompressedHex,
+    /// The hex of the 65 byte SEC1 uncompressed point.
+    Uncompressed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicKeyError {
+    InvalidHex(String),
+    /// The key has a length or leading byte which matches none of the formats.
+    UnknownFormat(String),
+    /// The bytes have the shape of a key but are not the encoding of a point on the curve.
+    InvalidPoint(String),
+}
+
+impl PublicKeyFormat {
+    pub const ALL: [PublicKeyFormat; 3] = [
+        PublicKeyFormat::CompressedHex,
+        PublicKeyFormat::PrefixedCompressedHex,
+        PublicKeyFormat::Uncompressed,
+    ];
+
+    /// A short name of the format, used in file names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PublicKeyFormat::CompressedHex => "compressed_hex",
+            PublicKeyFormat::PrefixedCompressedHex => "prefixed_compressed_hex",
+            PublicKeyFormat::Uncompressed => "uncompressed",
+        }
+    }
+
+    /// Writes the public key in this format.
+    pub fn format(&self, public_key: &EcdsaPublicKey) -> String {
+        match self {
+            PublicKeyFormat::CompressedHex => hex::encode(public_key.to_vec()),
+            PublicKeyFormat::PrefixedCompressedHex => format!("{}{}", PUBLIC_KEY_PREFIX, hex::encode(public_key.to_vec())),
+            PublicKeyFormat::Uncompressed => {
+                // Keys of the upstream type are always valid points, so decoding them can't fail.
+                let point: p256::PublicKey = p256::PublicKey::from_sec1_bytes(&public_key.to_vec())
+                    .expect("upstream public keys are valid points");
+                hex::encode(point.to_encoded_point(false).as_bytes())
+            }
+        }
+    }
+
+    /// Detects the format a key is written in from its prefix, length and leading byte. Doesn't check
+    /// that the key is a valid point; `parse_public_key` does.
+    pub fn detect(text: &str) -> Result<PublicKeyFormat, PublicKeyError> {
+        let (prefixed, hex_text) = match text.strip_prefix(PUBLIC_KEY_PREFIX) {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        match (prefixed, hex_text.len(), hex_text.get(..2)) {
+            (false, 66, Some("02" | "03")) => Ok(PublicKeyFormat::CompressedHex),
+            (true, 66, Some("02" | "03")) => Ok(PublicKeyFormat::PrefixedCompressedHex),
+            (false, 130, Some("04")) => Ok(PublicKeyFormat::Uncompressed),
+            _ => Err(PublicKeyError::UnknownFormat(text.to_string())),
+        }
+    }
+}
+
+/// Parses a public key written in any of the formats, returning the key and the format it was in.
+/// The point encoding is validated, so keys which are not on the curve are rejected.
+pub fn parse_public_key(text: &str) -> Result<(EcdsaPublicKey, PublicKeyFormat), PublicKeyError> {
+    let format: PublicKeyFormat = PublicKeyFormat::detect(text)?;
+    let bytes: Vec<u8> = hex::decode(text.trim_start_matches(PUBLIC_KEY_PREFIX))
+        .map_err(|_| PublicKeyError::InvalidHex(text.to_string()))?;
+    let point: p256::PublicKey = p256::PublicKey::from_sec1_bytes(&bytes)
+        .map_err(|_| PublicKeyError::InvalidPoint(text.to_string()))?;
+    let public_key: EcdsaPublicKey = EcdsaPublicKey::from_str(&hex::encode(point.to_encoded_point(true).as_bytes()))
+        .map_err(|_| PublicKeyError::InvalidPoint(text.to_string()))?;
+    Ok((public_key, format))
+}
+
+/// Converts a public key written in any of the formats into the given format.
+pub fn convert_public_key(text: &str, to: PublicKeyFormat) -> Result<String, PublicKeyError> {
+    parse_public_key(text).map(|(public_key, _)| to.format(&public_key))
+}
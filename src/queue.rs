@@ -0,0 +1,558 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+#[cfg(feature = "async-http")]
+use std::future::Future;
+#[cfg(feature = "async-http")]
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+#[cfg(feature = "async-http")]
+use std::task::{Context, Poll};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+use crate::clock::{system_clock, Clock};
+use crate::scope::ScopePolicy;
+
+/// The priority of a submission in the queue. Higher priority submissions are always dispatched
+/// before lower priority ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::Normal
+    }
+}
+
+/// A snapshot of the metrics of the submission queue.
+#[derive(Debug, Clone, Default)]
+pub struct QueueMetrics {
+    pub depth: usize,
+    pub max_depth_observed: usize,
+    pub dispatched: u64,
+    pub rejected: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+    /// The time submissions waited for exclusive access to each component.
+    pub component_waits: BTreeMap<String, ExclusiveWait>,
+}
+
+impl QueueMetrics {
+    pub fn mean_wait(&self) -> Duration {
+        match self.dispatched {
+            0 => Duration::ZERO,
+            dispatched => self.total_wait / dispatched as u32,
+        }
+    }
+}
+
+/// The error returned when the queue has reached its maximum depth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull {
+    pub max_depth: usize,
+}
+
+/// The options of a single call to `execute`.
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptions {
+    /// The priority of the transaction in the submission queue.
+    pub priority: Priority,
+
+    /// The key used to share the submission queue fairly between its users, such as the source
+    /// account of the transaction. Submissions with the same priority are dispatched round-robin
+    /// across keys.
+    pub fairness_key: Option<String>,
+
+    /// Client-side metadata attached to the submission for later analysis, such as
+    /// `feature=onboarding`. Tags are only recorded locally and are never sent to the PTE.
+    pub tags: BTreeMap<String, String>,
+
+    /// The components this submission needs exclusive access to. Submissions sharing one of these
+    /// components execute one at a time, from submission until the receipt is received.
+    pub exclusive_on: BTreeSet<String>,
+
+    /// What the transaction may touch. Transactions outside of the scope are refused before they are
+    /// submitted.
+    pub scope: Option<ScopePolicy>,
+}
+
+impl ExecuteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(self, priority: Priority) -> Self {
+        Self { priority, ..self }
+    }
+
+    pub fn fairness_key(self, fairness_key: &str) -> Self {
+        Self {
+            fairness_key: Some(fairness_key.to_string()),
+            ..self
+        }
+    }
+
+    pub fn tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn scope(self, scope: ScopePolicy) -> Self {
+        Self {
+            scope: Some(scope),
+            ..self
+        }
+    }
+
+    pub fn exclusive_on(mut self, component: impl std::fmt::Display) -> Self {
+        self.exclusive_on.insert(component.to_string());
+        self
+    }
+}
+
+/// A bounded queue which decides the order in which submissions made from multiple threads or tasks
+/// get dispatched to the PTE. At most `concurrency` submissions are in flight at any time.
+pub struct SubmissionQueue {
+    max_depth: usize,
+    concurrency: usize,
+    clock: Arc<dyn Clock>,
+    state: Mutex<QueueState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_ticket: u64,
+    in_flight: usize,
+    lanes: BTreeMap<Priority, Lane>,
+    metrics: QueueMetrics,
+    /// The tasks waiting for their turn in `run_async`, woken whenever the queue moves.
+    wakers: Vec<Waker>,
+}
+
+/// All of the waiting submissions of a single priority, grouped by their fairness key.
+#[derive(Default)]
+struct Lane {
+    keys: VecDeque<String>,
+    tickets: HashMap<String, VecDeque<u64>>,
+}
+
+impl QueueState {
+    fn depth(&self) -> usize {
+        self.lanes
+            .values()
+            .map(|lane| lane.tickets.values().map(VecDeque::len).sum::<usize>())
+            .sum()
+    }
+
+    fn push(&mut self, priority: Priority, key: String) -> u64 {
+        let ticket: u64 = self.next_ticket;
+        self.next_ticket += 1;
+
+        let lane: &mut Lane = self.lanes.entry(priority).or_default();
+        let tickets: &mut VecDeque<u64> = lane.tickets.entry(key.clone()).or_default();
+        if tickets.is_empty() {
+            lane.keys.push_back(key);
+        }
+        tickets.push_back(ticket);
+        ticket
+    }
+
+    /// The ticket which should be dispatched next: the first ticket of the key at the front of the
+    /// highest priority non-empty lane.
+    fn peek(&self) -> Option<u64> {
+        self.lanes
+            .values()
+            .find(|lane| !lane.keys.is_empty())
+            .and_then(|lane| lane.tickets[&lane.keys[0]].front().copied())
+    }
+
+    /// Removes the ticket at the front of the queue and moves its key to the back of the lane so
+    /// that the other keys get their turn.
+    fn pop(&mut self) {
+        if let Some(lane) = self.lanes.values_mut().find(|lane| !lane.keys.is_empty()) {
+            let key: String = lane.keys.pop_front().unwrap();
+            let tickets: &mut VecDeque<u64> = lane.tickets.get_mut(&key).unwrap();
+            tickets.pop_front();
+            if tickets.is_empty() {
+                lane.tickets.remove(&key);
+            } else {
+                lane.keys.push_back(key);
+            }
+        }
+    }
+
+    /// Removes a ticket which is given up on before its turn.
+    fn remove(&mut self, ticket: u64) {
+        for lane in self.lanes.values_mut() {
+            let key: Option<String> = lane
+                .tickets
+                .iter()
+                .find(|(_, tickets)| tickets.contains(&ticket))
+                .map(|(key, _)| key.clone());
+            if let Some(key) = key {
+                let tickets: &mut VecDeque<u64> = lane.tickets.get_mut(&key).unwrap();
+                tickets.retain(|x| *x != ticket);
+                if tickets.is_empty() {
+                    lane.tickets.remove(&key);
+                    lane.keys.retain(|x| *x != key);
+                }
+                return;
+            }
+        }
+    }
+}
+
+impl SubmissionQueue {
+    pub fn new(max_depth: usize, concurrency: usize) -> Self {
+        Self {
+            max_depth,
+            concurrency: concurrency.max(1),
+            clock: system_clock(),
+            state: Mutex::new(QueueState::default()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Replaces the clock used to measure the time submissions wait in the queue.
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    /// Waits for the submission's turn in the queue and then runs `dispatch`. Fails immediately
+    /// with `QueueFull` if the queue is already at its maximum depth.
+    pub fn run<T>(&self, options: &ExecuteOptions, dispatch: impl FnOnce() -> T) -> Result<T, QueueFull> {
+        let enqueued_at: Instant = self.clock.now();
+        let ticket: u64 = self.enqueue(options)?;
+
+        let mut state = self.lock();
+        while !self.try_dispatch(&mut state, ticket, enqueued_at) {
+            state = self.condvar.wait(state).unwrap_or_else(|error| error.into_inner());
+        }
+        drop(state);
+        self.notify();
+
+        let _guard: InFlightGuard = InFlightGuard(self);
+        Ok(dispatch())
+    }
+
+    /// Same as `run` for submissions made from async tasks: the task yields while waiting for its
+    /// turn instead of blocking its thread. A submission whose future is dropped while it waits
+    /// leaves the queue, and one dropped while it is dispatched frees its in-flight slot.
+    #[cfg(feature = "async-http")]
+    pub async fn run_async<T, F>(&self, options: &ExecuteOptions, dispatch: impl FnOnce() -> F) -> Result<T, QueueFull>
+    where
+        F: Future<Output = T>,
+    {
+        let enqueued_at: Instant = self.clock.now();
+        let ticket: u64 = self.enqueue(options)?;
+        Turn {
+            queue: self,
+            ticket,
+            enqueued_at,
+            dispatched: false,
+        }
+        .await;
+
+        let _guard: InFlightGuard = InFlightGuard(self);
+        Ok(dispatch().await)
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        let state = self.lock();
+        QueueMetrics {
+            depth: state.depth(),
+            ..state.metrics.clone()
+        }
+    }
+
+    /// Locks the state of the queue. Dispatches run outside of the lock, so the state stays
+    /// consistent even if a thread panicked while holding it.
+    fn lock(&self) -> MutexGuard<QueueState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Adds a submission to the back of its lane, or fails if the queue is full.
+    fn enqueue(&self, options: &ExecuteOptions) -> Result<u64, QueueFull> {
+        let mut state = self.lock();
+        if state.depth() >= self.max_depth {
+            state.metrics.rejected += 1;
+            return Err(QueueFull { max_depth: self.max_depth });
+        }
+
+        let key: String = options.fairness_key.clone().unwrap_or_default();
+        let ticket: u64 = state.push(options.priority, key);
+        let depth: usize = state.depth();
+        state.metrics.max_depth_observed = state.metrics.max_depth_observed.max(depth);
+        Ok(ticket)
+    }
+
+    /// Takes an in-flight slot for the ticket if it is its turn.
+    fn try_dispatch(&self, state: &mut QueueState, ticket: u64, enqueued_at: Instant) -> bool {
+        if state.in_flight >= self.concurrency || state.peek() != Some(ticket) {
+            return false;
+        }
+        state.pop();
+        state.in_flight += 1;
+
+        let wait: Duration = self.clock.now().saturating_duration_since(enqueued_at);
+        state.metrics.dispatched += 1;
+        state.metrics.total_wait += wait;
+        state.metrics.max_wait = state.metrics.max_wait.max(wait);
+        true
+    }
+
+    /// Wakes the threads and tasks waiting for their turn.
+    fn notify(&self) {
+        let wakers: Vec<Waker> = std::mem::take(&mut self.lock().wakers);
+        self.condvar.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// The turn of a submission made through `run_async`, ready once the submission is dispatched.
+#[cfg(feature = "async-http")]
+struct Turn<'a> {
+    queue: &'a SubmissionQueue,
+    ticket: u64,
+    enqueued_at: Instant,
+    dispatched: bool,
+}
+
+#[cfg(feature = "async-http")]
+impl<'a> Future for Turn<'a> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+        let queue: &SubmissionQueue = self.queue;
+        let mut state = queue.lock();
+        if queue.try_dispatch(&mut state, self.ticket, self.enqueued_at) {
+            drop(state);
+            self.dispatched = true;
+            queue.notify();
+            return Poll::Ready(());
+        }
+        state.wakers.push(context.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[cfg(feature = "async-http")]
+impl<'a> Drop for Turn<'a> {
+    fn drop(&mut self) {
+        if !self.dispatched {
+            self.queue.lock().remove(self.ticket);
+            self.queue.notify();
+        }
+    }
+}
+
+/// Frees up the in-flight slot of a dispatched submission once it completes, even if it panics.
+struct InFlightGuard<'a>(&'a SubmissionQueue);
+
+impl<'a> Drop for InFlightGuard<'a> {
+    fn drop(&mut self) {
+        self.0.lock().in_flight -= 1;
+        self.0.notify();
+    }
+}
+
+/// How long submissions waited for exclusive access to a single key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExclusiveWait {
+    pub acquired: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+impl ExclusiveWait {
+    pub fn mean_wait(&self) -> Duration {
+        match self.acquired {
+            0 => Duration::ZERO,
+            acquired => self.total_wait / acquired as u32,
+        }
+    }
+}
+
+/// Locks keyed by an arbitrary string, such as a component address, which let submissions to the
+/// same key run one at a time while submissions to other keys stay parallel. Waiters are served in
+/// the order they arrived. A submission which panics while holding a lock releases it as the guard
+/// is dropped during unwinding, so the lock never stays poisoned for the next waiter.
+#[derive(Default)]
+pub struct ExclusiveLocks {
+    state: Mutex<ExclusiveState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct ExclusiveState {
+    /// The next ticket to hand out and the ticket being served, per key.
+    tickets: HashMap<String, (u64, u64)>,
+    waits: BTreeMap<String, ExclusiveWait>,
+}
+
+impl ExclusiveLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<ExclusiveState> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Waits until all of the keys are free and takes them. Keys are taken one by one in sorted
+    /// order so that two submissions sharing several keys can't deadlock.
+    pub fn acquire(&self, keys: &BTreeSet<String>, clock: &dyn Clock) -> ExclusiveGuard {
+        for key in keys {
+            let requested_at: Instant = clock.now();
+            let mut state = self.lock();
+            let entry: &mut (u64, u64) = state.tickets.entry(key.clone()).or_default();
+            let ticket: u64 = entry.0;
+            entry.0 += 1;
+            while state.tickets[key].1 != ticket {
+                state = self.condvar.wait(state).unwrap_or_else(|error| error.into_inner());
+            }
+
+            let wait: Duration = clock.now().saturating_duration_since(requested_at);
+            let waits: &mut ExclusiveWait = state.waits.entry(key.clone()).or_default();
+            waits.acquired += 1;
+            waits.total_wait += wait;
+            waits.max_wait = waits.max_wait.max(wait);
+        }
+        ExclusiveGuard {
+            locks: self,
+            keys: keys.iter().cloned().collect(),
+        }
+    }
+
+    /// The time waited for each of the keys so far.
+    pub fn waits(&self) -> BTreeMap<String, ExclusiveWait> {
+        self.lock().waits.clone()
+    }
+}
+
+/// Exclusive access to a set of keys, released when dropped.
+pub struct ExclusiveGuard<'a> {
+    locks: &'a ExclusiveLocks,
+    keys: Vec<String>,
+}
+
+impl<'a> Drop for ExclusiveGuard<'a> {
+    fn drop(&mut self) {
+        let mut state = self.locks.lock();
+        for key in self.keys.iter() {
+            let finished: bool = match state.tickets.get_mut(key) {
+                Some(entry) => {
+                    entry.1 += 1;
+                    entry.0 == entry.1
+                }
+                None => false,
+            };
+            if finished {
+                state.tickets.remove(key);
+            }
+        }
+        drop(state);
+        self.locks.condvar.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    fn keys(keys: &[&str]) -> BTreeSet<String> {
+        keys.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// Waits until `count` tickets of the key were handed out, the holder's included.
+    fn wait_for_tickets(locks: &ExclusiveLocks, key: &str, count: u64) {
+        while locks.lock().tickets.get(key).map_or(0, |x| x.0) < count {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn waiters_are_served_in_the_order_they_arrived() {
+        let locks: Arc<ExclusiveLocks> = Arc::new(ExclusiveLocks::new());
+        let order: Arc<Mutex<Vec<usize>>> = Arc::new(Mutex::new(Vec::new()));
+        let holder: ExclusiveGuard = locks.acquire(&keys(&["vendor"]), &*system_clock());
+
+        let waiters: Vec<thread::JoinHandle<()>> = (0..6)
+            .map(|index| {
+                let (locks, order) = (locks.clone(), order.clone());
+                let waiter: thread::JoinHandle<()> = thread::spawn(move || {
+                    let _guard: ExclusiveGuard = locks.acquire(&keys(&["vendor"]), &*system_clock());
+                    order.lock().unwrap().push(index);
+                });
+                wait_for_tickets(&locks, "vendor", index as u64 + 2);
+                waiter
+            })
+            .collect();
+        drop(holder);
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), (0..6).collect::<Vec<usize>>());
+        assert!(locks.lock().tickets.is_empty());
+        assert_eq!(locks.waits()["vendor"].acquired, 7);
+    }
+
+    #[test]
+    fn a_holder_panicking_hands_the_key_to_the_next_waiter() {
+        let locks: Arc<ExclusiveLocks> = Arc::new(ExclusiveLocks::new());
+        let (acquired, has_acquired) = channel::<()>();
+        let (release, released) = channel::<()>();
+
+        let holder: thread::JoinHandle<()> = {
+            let locks: Arc<ExclusiveLocks> = locks.clone();
+            thread::spawn(move || {
+                let _guard: ExclusiveGuard = locks.acquire(&keys(&["vendor", "treasury"]), &*system_clock());
+                acquired.send(()).unwrap();
+                released.recv().unwrap();
+                panic!("the submission failed");
+            })
+        };
+        has_acquired.recv().unwrap();
+        let waiter: thread::JoinHandle<()> = {
+            let locks: Arc<ExclusiveLocks> = locks.clone();
+            thread::spawn(move || drop(locks.acquire(&keys(&["treasury"]), &*system_clock())))
+        };
+        wait_for_tickets(&locks, "treasury", 2);
+
+        release.send(()).unwrap();
+        assert!(holder.join().is_err());
+        waiter.join().unwrap();
+        drop(locks.acquire(&keys(&["vendor", "treasury"]), &*system_clock()));
+        assert!(locks.lock().tickets.is_empty());
+    }
+
+    #[test]
+    fn a_poisoned_state_is_recovered() {
+        let locks: Arc<ExclusiveLocks> = Arc::new(ExclusiveLocks::new());
+        let guard: ExclusiveGuard = locks.acquire(&keys(&["vendor"]), &*system_clock());
+
+        let poisoning: Arc<ExclusiveLocks> = locks.clone();
+        assert!(thread::spawn(move || {
+            let _state = poisoning.state.lock().unwrap();
+            panic!("panicked while holding the state");
+        })
+        .join()
+        .is_err());
+        assert!(locks.state.is_poisoned());
+
+        drop(guard);
+        drop(locks.acquire(&keys(&["vendor"]), &*system_clock()));
+        assert_eq!(locks.waits()["vendor"].acquired, 2);
+        assert!(locks.lock().tickets.is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+//! Free functions taking and returning only strings, for scripts which just want to get something
+//! done on the PTE. Each call builds a default client for the given URL and goes through the same
+//! builders and client methods as everything else. Nothing is printed.
+//!
+//! ```ignore
+//! use programmatic_pte_interactions::quick;
+//!
+//! let url: &str = "https://pte01.radixdlt.com";
+//! let (key, alice) = quick::create_account(url)?;
+//! let (_, bob) = quick::create_account(url)?;
+//! quick::transfer(url, &key, &alice, &bob, "100")?;
+//! println!("{:?}", quick::balance(url, &bob)?);
+//! ```
+
+use radix_engine::model::SignedTransaction;
+use scrypto::prelude::*;
+
+use crate::client::PteClient;
+use crate::manifest_spec::ManifestSpec;
+use crate::queue::ExecuteOptions;
+use crate::{PteApiError, Receipt, TransactionSubmissionError};
+
+#[derive(Debug)]
+pub enum QuickError {
+    /// One of the arguments is not valid, such as an address which doesn't parse.
+    InvalidArgument(String),
+    /// The transaction was submitted and committed with a failure.
+    TransactionFailed(Receipt),
+    Submission(TransactionSubmissionError),
+    Api(PteApiError),
+}
+
+impl From<TransactionSubmissionError> for QuickError {
+    fn from(error: TransactionSubmissionError) -> QuickError {
+        QuickError::Submission(error)
+    }
+}
+
+impl From<PteApiError> for QuickError {
+    fn from(error: PteApiError) -> QuickError {
+        QuickError::Api(error)
+    }
+}
+
+/// Creates an account funded by the faucet and owned by a new key. Returns the hex of the private
+/// key and the address of the account.
+pub fn create_account(pte_url: &str) -> Result<(String, String), QuickError> {
+    let client: PteClient = PteClient::new(pte_url);
+    let (key_bytes, private_key): ([u8; 32], EcdsaPrivateKey) = loop {
+        let bytes: [u8; 32] = rand::random();
+        if let Ok(private_key) = EcdsaPrivateKey::from_bytes(&bytes) {
+            break (bytes, private_key);
+        }
+    };
+
+    let receipt: Receipt = execute(&client, &ManifestSpec::NewAccount, &private_key)?;
+    let address: ComponentAddress = receipt.new_components()[0];
+    Ok((hex::encode(key_bytes), address.to_string()))
+}
+
+/// Transfers an amount of XRD from an account controlled by the key to another account. Returns the
+/// hash of the transaction.
+pub fn transfer(pte_url: &str, key_hex: &str, from_address: &str, to_address: &str, amount: &str) -> Result<String, QuickError> {
+    let client: PteClient = PteClient::new(pte_url);
+    let private_key: EcdsaPrivateKey = hex::decode(key_hex.trim().trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| EcdsaPrivateKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| QuickError::InvalidArgument("the key is not a hex encoded private key".to_string()))?;
+
+    let spec: ManifestSpec = ManifestSpec::Transfer {
+        from: from_address.to_string(),
+        to: to_address.to_string(),
+        resource: RADIX_TOKEN.to_string(),
+        amount: amount.to_string(),
+    };
+    Ok(execute(&client, &spec, &private_key)?.transaction_hash)
+}
+
+/// The balances of an account as pairs of resource address and amount, sorted by resource address.
+pub fn balance(pte_url: &str, address: &str) -> Result<Vec<(String, String)>, QuickError> {
+    let address: ComponentAddress = ComponentAddress::from_str(address)
+        .map_err(|_| QuickError::InvalidArgument(format!("{} is not an account address", address)))?;
+    Ok(PteClient::new(pte_url)
+        .get_balances(address)?
+        .into_iter()
+        .map(|(resource, amount)| (resource.to_string(), amount.to_string()))
+        .collect())
+}
+
+/// Builds, signs and executes the spec, treating a failed receipt as an error.
+fn execute(client: &PteClient, spec: &ManifestSpec, private_key: &EcdsaPrivateKey) -> Result<Receipt, QuickError> {
+    let transaction: SignedTransaction = spec
+        .build(private_key, client.next_nonce())
+        .map_err(|field| QuickError::InvalidArgument(format!("invalid {}", field)))?;
+    let receipt: Receipt = client.execute(&transaction, &ExecuteOptions::new().tag("flow", "quick"))?;
+    if !receipt.is_success() {
+        return Err(QuickError::TransactionFailed(receipt));
+    }
+    Ok(receipt)
+}
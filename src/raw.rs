@@ -0,0 +1,139 @@
+//! Access to the raw bytes behind decoded values. Decoding into the types of this crate or of a
+//! blueprint fails as soon as it meets something it doesn't know, such as an enum variant added in a
+//! newer version of the blueprint, so the raw bytes are always kept next to the decoded value and
+//! values can be decoded as far as possible instead of not at all.
+
+use sbor::{decode_any, Decode, Value};
+use scrypto::buffer::scrypto_decode;
+use scrypto::values::ScryptoValue;
+
+/// The raw SBOR bytes a `ScryptoValue` was decoded from.
+pub trait ScryptoValueExt {
+    fn raw_bytes(&self) -> &[u8];
+
+    fn raw_hex(&self) -> String {
+        hex::encode(self.raw_bytes())
+    }
+}
+
+impl ScryptoValueExt for ScryptoValue {
+    fn raw_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+/// The outcome of decoding bytes as far as possible.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartialDecode<T> {
+    /// The bytes decoded into the expected type.
+    Typed(T),
+    /// The bytes are valid SBOR but don't match the expected type, for example because they hold an
+    /// enum variant the type doesn't have. The schemaless tree of the whole value is returned, with
+    /// the error of the typed decoding.
+    Untyped { value: Value, error: String },
+    /// The bytes are not valid SBOR. The longest prefix which decodes into a value is returned when
+    /// there is one, along with the hex of the bytes after it.
+    Undecodable { prefix: Option<Value>, remainder_hex: String },
+}
+
+impl<T> PartialDecode<T> {
+    pub fn typed(self) -> Option<T> {
+        match self {
+            PartialDecode::Typed(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self, PartialDecode::Typed(_))
+    }
+}
+
+/// Decodes the bytes into `T`, falling back to the schemaless SBOR tree when they don't match `T`
+/// and to the longest decodable prefix when they aren't valid SBOR at all.
+pub fn decode_partial<T: Decode>(bytes: &[u8]) -> PartialDecode<T> {
+    let error: String = match scrypto_decode::<T>(bytes) {
+        Ok(value) => return PartialDecode::Typed(value),
+        Err(error) => format!("{:?}", error),
+    };
+    if let Ok(value) = decode_any(bytes) {
+        return PartialDecode::Untyped { value, error };
+    }
+    match (1..bytes.len()).rev().find_map(|end| decode_any(&bytes[..end]).ok().map(|value| (end, value))) {
+        Some((end, value)) => PartialDecode::Undecodable {
+            prefix: Some(value),
+            remainder_hex: hex::encode(&bytes[end..]),
+        },
+        None => PartialDecode::Undecodable {
+            prefix: None,
+            remainder_hex: hex::encode(bytes),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sbor::{Encode, TypeId};
+    use scrypto::buffer::scrypto_encode;
+
+    /// The status of an order as an older version of a blueprint knows it.
+    #[derive(TypeId, Decode, Debug, PartialEq)]
+    enum KnownStatus {
+        Open,
+        Filled(u32),
+    }
+
+    /// The same status once a newer version of the blueprint added a variant.
+    #[derive(TypeId, Encode)]
+    enum Status {
+        #[allow(dead_code)]
+        Open,
+        Filled(u32),
+        Cancelled(u32),
+    }
+
+    #[test]
+    fn known_variants_decode_into_the_expected_type() {
+        let bytes: Vec<u8> = scrypto_encode(&Status::Filled(7));
+
+        assert_eq!(decode_partial::<KnownStatus>(&bytes), PartialDecode::Typed(KnownStatus::Filled(7)));
+    }
+
+    #[test]
+    fn unknown_variants_fall_back_to_the_schemaless_value() {
+        let bytes: Vec<u8> = scrypto_encode(&Status::Cancelled(7));
+
+        match decode_partial::<KnownStatus>(&bytes) {
+            PartialDecode::Untyped { value, error } => {
+                assert!(matches!(value, Value::Enum { .. }), "{:?}", value);
+                assert_eq!(value, decode_any(&bytes).unwrap());
+                assert!(!error.is_empty());
+            }
+            other => panic!("expected an untyped decoding, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_bytes_keep_the_longest_decodable_prefix() {
+        let mut bytes: Vec<u8> = scrypto_encode(&Status::Cancelled(7));
+        let valid: usize = bytes.len();
+        bytes.push(0xff);
+
+        match decode_partial::<KnownStatus>(&bytes) {
+            PartialDecode::Undecodable { prefix, remainder_hex } => {
+                assert_eq!(prefix, Some(decode_any(&bytes[..valid]).unwrap()));
+                assert_eq!(remainder_hex, "ff");
+            }
+            other => panic!("expected an undecodable value, got {:?}", other),
+        }
+        assert_eq!(
+            decode_partial::<KnownStatus>(&[0xff]),
+            PartialDecode::Undecodable {
+                prefix: None,
+                remainder_hex: "ff".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Read-only method calls. Committing a transaction just to call a getter pollutes the ledger and
+//! burns nonces, so these calls are previewed when the server supports it, or answered from the
+//! component's state when a hint of where the value lives in the state is given.
+
+use radix_engine::model::Transaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+use scrypto::values::ScryptoValue;
+
+use crate::client::{Horizon, PteClient, TransportError};
+use crate::component::ComponentInfo;
+use crate::labeled::LabeledAddress;
+use crate::{PteApiError, Receipt, TransactionBody};
+
+/// Extracts the value a read-only method would return from the state of its component.
+pub type StateHint<'a> = &'a dyn Fn(&ComponentInfo) -> Result<Vec<ScryptoValue>, PteApiError>;
+
+#[derive(Debug)]
+pub enum ReadOnlyError {
+    /// The server can not preview transactions and no state hint was given.
+    ReadOnlyUnsupported,
+    /// The preview was executed but did not succeed.
+    PreviewFailed(Receipt),
+    InvalidOutput(String),
+    ApiError(PteApiError),
+}
+
+impl From<PteApiError> for ReadOnlyError {
+    fn from(error: PteApiError) -> ReadOnlyError {
+        ReadOnlyError::ApiError(error)
+    }
+}
+
+impl From<reqwest::Error> for ReadOnlyError {
+    fn from(error: reqwest::Error) -> ReadOnlyError {
+        ReadOnlyError::ApiError(error.into())
+    }
+}
+
+impl From<TransportError> for ReadOnlyError {
+    fn from(error: TransportError) -> ReadOnlyError {
+        ReadOnlyError::ApiError(error.into())
+    }
+}
+
+impl PteClient {
+    /// Checks whether the first read server supports previewing transactions. The answer is cached
+    /// per server so that the probe only happens once. The probe posts an empty body, which a server
+    /// with the endpoint rejects as a bad request and one without answers with `404 Not Found`.
+    /// Failures to probe are returned rather than taken for a missing endpoint.
+    pub fn supports_preview(&self) -> Result<bool, PteApiError> {
+        self.probe_endpoint(&self.preview_support, Horizon::Read, |url| {
+            self.http_client()
+                .post(format!("{}/transaction/preview", url))
+                .json(&serde_json::Map::new())
+        })
+    }
+
+    /// Executes the transaction on the first read server without committing it. The receipt is
+    /// returned whether or not the transaction succeeded.
+    pub fn preview(&self, transaction_body: &TransactionBody) -> Result<Receipt, PteApiError> {
+        let response = self.send(Horizon::Read, |url| {
+            self.http_client()
+                .post(format!("{}/transaction/preview", url))
+                .json(transaction_body)
+        })?;
+        if !response.status().is_success() {
+            return Err(PteApiError::UnexpectedStatus {
+                status: response.status().as_u16(),
+                body: response.text()?,
+            });
+        }
+        let mut receipt: Receipt = response.json()?;
+        self.decode_extensions(&mut receipt);
+        Ok(receipt)
+    }
+
+    /// Calls a method without committing a transaction. The call is previewed when the server
+    /// supports it; otherwise the value is read from the component's state through `state_hint`, and
+    /// without a hint the call fails with `ReadOnlyUnsupported`.
+    pub fn call_method_readonly(
+        &self,
+        component: impl Into<LabeledAddress<ComponentAddress>>,
+        method: &str,
+        args: Vec<Vec<u8>>,
+        state_hint: Option<StateHint>,
+    ) -> Result<Vec<ScryptoValue>, ReadOnlyError> {
+        let component: LabeledAddress<ComponentAddress> = component.into();
+        if self.supports_preview()? {
+            let transaction: Transaction = TransactionBuilder::new()
+                .call_method(component.address, method, args)
+                .build(self.next_nonce());
+            let transaction_body: TransactionBody = TransactionBody::from_parts(&transaction, &[])
+                .map_err(|error| ReadOnlyError::InvalidOutput(format!("{:?}", error)))?;
+            let receipt: Receipt = self.preview(&transaction_body)?;
+            if !receipt.is_success() {
+                return Err(ReadOnlyError::PreviewFailed(receipt));
+            }
+
+            return receipt.outputs
+                .iter()
+                .map(|output| {
+                    hex::decode(output)
+                        .ok()
+                        .and_then(|bytes| ScryptoValue::from_slice(&bytes).ok())
+                        .ok_or_else(|| ReadOnlyError::InvalidOutput(output.clone()))
+                })
+                .collect();
+        }
+
+        match state_hint {
+            Some(state_hint) => Ok(state_hint(&self.get_component(component)?)?),
+            None => Err(ReadOnlyError::ReadOnlyUnsupported),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Integration with an on-ledger name registry component which allows human readable names to be
+//! shared by everybody using the same PTE.
+//!
+//! The registry blueprint is expected to follow the contract below. Any blueprint whose state and
+//! `register` method match it can be used as a registry.
+//!
+//! ```ignore
+//! blueprint! {
+//!     struct NameRegistry {
+//!         /// Maps each registered name to the component it points to and the resource address
+//!         /// of the badge which owns the name.
+//!         names: HashMap<String, (ComponentAddress, ResourceAddress)>,
+//!     }
+//!
+//!     impl NameRegistry {
+//!         /// Registers `name` as pointing to `target`. If the name is already registered then
+//!         /// the `owner_badge` proof must be of the badge that owns the name.
+//!         pub fn register(&mut self, name: String, target: ComponentAddress, owner_badge: Proof);
+//!     }
+//! }
+//! ```
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use sbor::{Decode, TypeId};
+use scrypto::prelude::*;
+
+use crate::args::{call_args, Args};
+use crate::builder::TransactionBuilderExt;
+use crate::client::PteClient;
+use crate::labeled::LabeledAddress;
+use crate::{PteApiError, Receipt, TransactionSubmissionError};
+
+/// Mirrors the state of the registry blueprint so that it can be decoded from the component state.
+#[derive(TypeId, Decode, Debug)]
+pub struct NameRegistryState {
+    pub names: HashMap<String, (ComponentAddress, ResourceAddress)>,
+}
+
+impl PteClient {
+    /// Registers a name in the registry which points to the `target` component. A proof of the
+    /// `owner_badge` is created from the `account` and passed to the registry to prove ownership.
+    pub fn register_name(
+        &self,
+        registry: impl Into<LabeledAddress<ComponentAddress>>,
+        name: &str,
+        target: impl Into<LabeledAddress<ComponentAddress>>,
+        owner_badge: impl Into<LabeledAddress<ResourceAddress>>,
+        account: impl Into<LabeledAddress<ComponentAddress>>,
+        private_key: &EcdsaPrivateKey,
+    ) -> Result<Receipt, TransactionSubmissionError> {
+        let (registry, target): (ComponentAddress, ComponentAddress) = (registry.into().address, target.into().address);
+        let (owner_badge, account): (ResourceAddress, ComponentAddress) = (owner_badge.into().address, account.into().address);
+        let transaction: SignedTransaction = TransactionBuilder::new()
+            .create_proof_from_account(owner_badge, account)
+            .create_proof_from_auth_zone_ref(owner_badge, |builder, proof| {
+                builder.call_method(
+                    registry,
+                    "register",
+                    call_args(
+                        Args::new()
+                            .add_string(name)
+                            .add_address(target)
+                            .add_proof(proof)
+                    )
+                )
+            })
+            .build(self.next_nonce())
+            .sign([private_key]);
+
+        self.submit_transaction(&transaction)
+    }
+
+    /// Resolves a name by reading it from the state of the registry component.
+    pub fn resolve_name(&self, registry: impl Into<LabeledAddress<ComponentAddress>>, name: &str) -> Result<Option<ComponentAddress>, PteApiError> {
+        let state: NameRegistryState = self.get_component(registry)?.decode_state()?;
+        Ok(state.names.get(name).map(|(target, _)| *target))
+    }
+}
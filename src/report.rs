@@ -0,0 +1,500 @@
+use radix_engine::model::{Instruction, SignedTransaction};
+use scrypto::prelude::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::clock::{system_clock, Clock};
+use crate::denomination::{DenominatedAmount, Denomination};
+use crate::pipeline::faucet_amount;
+use crate::queue::ExecuteOptions;
+use crate::{CostSummary, Receipt, TransactionSubmissionError};
+
+/// A single transaction submitted during a run, along with how long it took and its outcome.
+#[derive(Debug, Clone)]
+pub struct BatchEntry {
+    pub label: String,
+    pub duration: Duration,
+    pub faucet_calls: u32,
+    /// The client-side tags the transaction was submitted with.
+    pub tags: BTreeMap<String, String>,
+    /// The receipt of the transaction, or the reason it failed to be submitted.
+    pub outcome: Result<Receipt, String>,
+}
+
+/// Aggregates the receipts of all of the transactions submitted during a run.
+#[derive(Clone)]
+pub struct BatchReport {
+    pub clock: Arc<dyn Clock>,
+    pub started_at: Instant,
+    pub entries: Vec<BatchEntry>,
+    /// The denomination the exports of the report write amounts in.
+    pub denomination: Denomination,
+}
+
+impl Default for BatchReport {
+    fn default() -> Self {
+        Self::with_clock(system_clock())
+    }
+}
+
+impl BatchReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a report which times the run and its transactions using the given clock.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            started_at: clock.now(),
+            clock,
+            entries: Vec::new(),
+            denomination: Denomination::default(),
+        }
+    }
+
+    /// Writes the amounts of the exports of the report in the given denomination.
+    pub fn with_denomination(mut self, denomination: Denomination) -> Self {
+        self.denomination = denomination;
+        self
+    }
+
+    /// How long the run has been going for.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.started_at)
+    }
+
+    /// Records the outcome of submitting the transaction.
+    pub fn record(
+        &mut self,
+        label: &str,
+        tags: &BTreeMap<String, String>,
+        transaction: &SignedTransaction,
+        duration: Duration,
+        outcome: &Result<Receipt, TransactionSubmissionError>,
+    ) {
+        let faucet_calls: u32 = transaction.transaction.instructions
+            .iter()
+            .filter(|instruction| match instruction {
+                Instruction::CallMethod { component_address, method, .. } => {
+                    *component_address == SYSTEM_COMPONENT && method == "free_xrd"
+                }
+                _ => false,
+            })
+            .count() as u32;
+
+        self.entries.push(BatchEntry {
+            label: label.to_string(),
+            duration,
+            faucet_calls,
+            tags: tags.clone(),
+            outcome: match outcome {
+                Ok(receipt) => Ok(receipt.clone()),
+                Err(error) => Err(format!("{:?}", error)),
+            },
+        });
+    }
+
+    pub fn receipts(&self) -> impl Iterator<Item = &Receipt> {
+        self.entries.iter().filter_map(|entry| entry.outcome.as_ref().ok())
+    }
+
+    /// The number of transactions with each status. Transactions which failed to be submitted are
+    /// counted under `SubmissionFailed`.
+    pub fn status_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for entry in self.entries.iter() {
+            let status: String = match entry.outcome {
+                Ok(ref receipt) => receipt.status.clone(),
+                Err(_) => "SubmissionFailed".to_string(),
+            };
+            *counts.entry(status).or_default() += 1;
+        }
+        counts
+    }
+
+    /// The total cost of all of the transactions which reported their cost, or `None` if none of
+    /// them did.
+    pub fn total_cost(&self) -> Option<CostSummary> {
+        let costs: Vec<&CostSummary> = self.receipts()
+            .filter_map(|receipt| receipt.cost.as_ref())
+            .collect();
+        if costs.is_empty() {
+            return None;
+        }
+
+        let mut total: CostSummary = CostSummary::default();
+        for cost in costs {
+            total.total_cost_units += cost.total_cost_units;
+            for (name, cost_units) in cost.breakdown.iter() {
+                *total.breakdown.entry(name.clone()).or_default() += cost_units;
+            }
+        }
+        Some(total)
+    }
+
+    /// The XRD handed out by the faucet calls of the transactions.
+    pub fn faucet_xrd(&self) -> Decimal {
+        faucet_amount() * self.entries.iter().map(|x| x.faucet_calls).sum::<u32>()
+    }
+
+    /// Renders the report as CSV with a row per transaction, with the XRD handed out by the faucet
+    /// in the denomination of the report.
+    pub fn to_csv(&self) -> String {
+        let mut csv: String = String::from("transaction_hash,status,cost_units,faucet_xrd\n");
+        for entry in self.entries.iter() {
+            if let Ok(ref receipt) = entry.outcome {
+                csv.push_str(&format!(
+                    "{},{},{},{}\n",
+                    receipt.transaction_hash,
+                    receipt.status,
+                    receipt.cost.as_ref().map(|x| x.total_cost_units.to_string()).unwrap_or_default(),
+                    self.denomination.format(faucet_amount() * entry.faucet_calls)
+                ));
+            }
+        }
+        csv
+    }
+}
+
+/// A summary of an entire run meant both for humans (through its Markdown rendering) and for CI
+/// (through its JSON form, which can be diffed against an expected baseline).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunReport {
+    pub duration_ms: u64,
+    pub transactions_by_status: BTreeMap<String, usize>,
+    pub new_packages: usize,
+    pub new_components: usize,
+    pub new_resources: usize,
+    pub faucet_calls: u32,
+    /// The XRD handed out by the faucet calls, in both denominations.
+    #[serde(default)]
+    pub faucet_xrd: DenominatedAmount,
+    /// The denomination the Markdown rendering writes amounts in.
+    #[serde(default)]
+    pub denomination: Denomination,
+    pub total_cost: Option<CostSummary>,
+    pub slowest_transaction: Option<TransactionSummary>,
+    pub failures: Vec<FailureSummary>,
+    pub transactions: Vec<TransactionSummary>,
+    /// The transactions grouped by each of their `key=value` tags.
+    pub by_tag: BTreeMap<String, TagSummary>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagSummary {
+    pub transactions: usize,
+    pub failures: usize,
+    pub cost_units: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransactionSummary {
+    pub label: String,
+    pub transaction_hash: Option<String>,
+    pub status: String,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FailureSummary {
+    pub label: String,
+    pub transaction_hash: Option<String>,
+    pub reason: String,
+}
+
+impl RunReport {
+    pub fn from_batch(batch: &BatchReport) -> Self {
+        let transactions: Vec<TransactionSummary> = batch.entries
+            .iter()
+            .map(|entry| TransactionSummary {
+                label: entry.label.clone(),
+                transaction_hash: entry.outcome.as_ref().ok().map(|x| x.transaction_hash.clone()),
+                status: match entry.outcome {
+                    Ok(ref receipt) => receipt.status.clone(),
+                    Err(_) => "SubmissionFailed".to_string(),
+                },
+                duration_ms: entry.duration.as_millis() as u64,
+                tags: entry.tags.clone(),
+            })
+            .collect();
+
+        let mut by_tag: BTreeMap<String, TagSummary> = BTreeMap::new();
+        for entry in batch.entries.iter() {
+            for (key, value) in entry.tags.iter() {
+                let summary: &mut TagSummary = by_tag.entry(format!("{}={}", key, value)).or_default();
+                summary.transactions += 1;
+                match entry.outcome {
+                    Ok(ref receipt) => {
+                        summary.failures += !receipt.is_success() as usize;
+                        summary.cost_units += receipt.cost.as_ref().map(|x| x.total_cost_units).unwrap_or(0);
+                    }
+                    Err(_) => summary.failures += 1,
+                }
+            }
+        }
+
+        let failures: Vec<FailureSummary> = batch.entries
+            .iter()
+            .filter_map(|entry| match entry.outcome {
+                Ok(ref receipt) if receipt.is_success() => None,
+                Ok(ref receipt) => Some(FailureSummary {
+                    label: entry.label.clone(),
+                    transaction_hash: Some(receipt.transaction_hash.clone()),
+                    reason: receipt.status.clone(),
+                }),
+                Err(ref reason) => Some(FailureSummary {
+                    label: entry.label.clone(),
+                    transaction_hash: None,
+                    reason: reason.clone(),
+                }),
+            })
+            .collect();
+
+        Self {
+            duration_ms: batch.elapsed().as_millis() as u64,
+            transactions_by_status: batch.status_counts(),
+            new_packages: batch.receipts().map(|x| x.new_packages.len()).sum(),
+            new_components: batch.receipts().map(|x| x.new_components.len()).sum(),
+            new_resources: batch.receipts().map(|x| x.new_resources.len()).sum(),
+            faucet_calls: batch.entries.iter().map(|x| x.faucet_calls).sum(),
+            faucet_xrd: batch.faucet_xrd().into(),
+            denomination: batch.denomination,
+            total_cost: batch.total_cost(),
+            slowest_transaction: transactions.iter().max_by_key(|x| x.duration_ms).cloned(),
+            failures,
+            transactions,
+            by_tag,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Reads a report written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// The transactions which were submitted with all of the given tags.
+    pub fn transactions_tagged(&self, tags: &BTreeMap<String, String>) -> Vec<&TransactionSummary> {
+        self.transactions
+            .iter()
+            .filter(|transaction| tags.iter().all(|(key, value)| transaction.tags.get(key) == Some(value)))
+            .collect()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown: String = String::from("# Run Report\n\n");
+        markdown.push_str(&format!("* Duration: {} ms\n", self.duration_ms));
+        for (status, count) in self.transactions_by_status.iter() {
+            markdown.push_str(&format!("* Transactions with status `{}`: {}\n", status, count));
+        }
+        markdown.push_str(&format!(
+            "* Entities created: {} packages, {} components, {} resources\n",
+            self.new_packages, self.new_components, self.new_resources
+        ));
+        markdown.push_str(&format!("* Faucet calls: {}\n", self.faucet_calls));
+        if let Ok(faucet_xrd) = self.faucet_xrd.amount() {
+            markdown.push_str(&format!(
+                "* XRD from the faucet: {} ({})\n",
+                self.denomination.format(faucet_xrd),
+                self.denomination
+            ));
+        }
+        if let Some(ref total_cost) = self.total_cost {
+            markdown.push_str(&format!("* Total cost: {} cost units\n", total_cost.total_cost_units));
+        }
+        if let Some(ref slowest) = self.slowest_transaction {
+            markdown.push_str(&format!("* Slowest transaction: {} ({} ms)\n", slowest.label, slowest.duration_ms));
+        }
+
+        if !self.failures.is_empty() {
+            markdown.push_str("\n## Failures\n\n| Transaction | Hash | Reason |\n|---|---|---|\n");
+            for failure in self.failures.iter() {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    failure.label,
+                    failure.transaction_hash.as_deref().unwrap_or("-"),
+                    failure.reason.replace('|', "\\|")
+                ));
+            }
+        }
+
+        if !self.by_tag.is_empty() {
+            markdown.push_str("\n## Tags\n\n| Tag | Transactions | Failures | Cost Units |\n|---|---|---|---|\n");
+            for (tag, summary) in self.by_tag.iter() {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    tag, summary.transactions, summary.failures, summary.cost_units
+                ));
+            }
+        }
+
+        markdown.push_str("\n## Transactions\n\n| Transaction | Hash | Status | Duration (ms) |\n|---|---|---|---|\n");
+        for transaction in self.transactions.iter() {
+            markdown.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                transaction.label,
+                transaction.transaction_hash.as_deref().unwrap_or("-"),
+                transaction.status,
+                transaction.duration_ms
+            ));
+        }
+        markdown
+    }
+}
+
+/// Parses a tag written as `key=value`.
+pub fn parse_tag(tag: &str) -> Option<(String, String)> {
+    match tag.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Some((key.to_string(), value.to_string())),
+        _ => None,
+    }
+}
+
+/// Submits the transaction through `submit` while timing it and recording it in the report along
+/// with the tags of the options it is submitted with.
+pub fn timed<F>(
+    report: &mut BatchReport,
+    label: &str,
+    options: &ExecuteOptions,
+    transaction: &SignedTransaction,
+    submit: F,
+) -> Result<Receipt, TransactionSubmissionError>
+where
+    F: FnOnce(&SignedTransaction, &ExecuteOptions) -> Result<Receipt, TransactionSubmissionError>,
+{
+    let started_at: Instant = report.clock.now();
+    let outcome = submit(transaction, options);
+    let duration: Duration = report.clock.now().saturating_duration_since(started_at);
+    report.record(label, &options.tags, transaction, duration, &outcome);
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(label: &str, tags: &[(&str, &str)]) -> TransactionSummary {
+        TransactionSummary {
+            label: label.to_string(),
+            transaction_hash: None,
+            status: "Success".to_string(),
+            duration_ms: 0,
+            tags: tags.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn tags_are_parsed_from_key_value_pairs() {
+        assert_eq!(parse_tag("feature=onboarding"), Some(("feature".to_string(), "onboarding".to_string())));
+        assert_eq!(parse_tag("query=a=b"), Some(("query".to_string(), "a=b".to_string())));
+        assert_eq!(parse_tag("feature"), None);
+        assert_eq!(parse_tag("=onboarding"), None);
+    }
+
+    #[test]
+    fn transactions_are_filtered_by_all_of_the_tags() {
+        let report: RunReport = RunReport::from_batch(&BatchReport::new());
+        let report: RunReport = RunReport {
+            transactions: vec![
+                summary("signup", &[("feature", "onboarding"), ("ticket", "ABC-123")]),
+                summary("login", &[("feature", "onboarding")]),
+                summary("payout", &[("feature", "payments")]),
+            ],
+            ..report
+        };
+
+        let filter = |tags: &[(&str, &str)]| -> Vec<String> {
+            let tags: BTreeMap<String, String> = tags.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+            report.transactions_tagged(&tags).iter().map(|x| x.label.clone()).collect()
+        };
+        assert_eq!(filter(&[("feature", "onboarding")]), ["signup", "login"]);
+        assert_eq!(filter(&[("feature", "onboarding"), ("ticket", "ABC-123")]), ["signup"]);
+        assert_eq!(filter(&[]).len(), 3);
+        assert!(filter(&[("feature", "search")]).is_empty());
+    }
+
+    /// A batch with a transaction calling the faucet twice, and one which failed to be submitted.
+    fn faucet_batch(denomination: Denomination) -> BatchReport {
+        let receipt: Receipt = Receipt {
+            transaction_hash: "a1".to_string(),
+            status: "Success".to_string(),
+            outputs: Vec::new(),
+            logs: Vec::new(),
+            new_packages: Vec::new(),
+            new_components: Vec::new(),
+            new_resources: Vec::new(),
+            cost: None,
+            extra: Default::default(),
+            extensions: Default::default(),
+        };
+        let entry = |faucet_calls: u32, outcome: Result<Receipt, String>| BatchEntry {
+            label: "faucet".to_string(),
+            duration: Duration::from_millis(5),
+            faucet_calls,
+            tags: BTreeMap::new(),
+            outcome,
+        };
+        let mut batch: BatchReport = BatchReport::new().with_denomination(denomination);
+        batch.entries.push(entry(2, Ok(receipt)));
+        batch.entries.push(entry(1, Err("Rejected".to_string())));
+        batch
+    }
+
+    #[test]
+    fn csv_exports_write_amounts_in_the_denomination_of_the_report() {
+        assert_eq!(
+            faucet_batch(Denomination::Whole).to_csv(),
+            "transaction_hash,status,cost_units,faucet_xrd\na1,Success,,2000000\n"
+        );
+        assert_eq!(
+            faucet_batch(Denomination::Attos).to_csv(),
+            "transaction_hash,status,cost_units,faucet_xrd\na1,Success,,2000000000000000000000000\n"
+        );
+    }
+
+    #[test]
+    fn markdown_writes_amounts_in_the_denomination_of_the_report() {
+        let whole: String = RunReport::from_batch(&faucet_batch(Denomination::Whole)).to_markdown();
+        assert!(whole.contains("* XRD from the faucet: 3000000 (whole units)\n"), "{}", whole);
+
+        let attos: String = RunReport::from_batch(&faucet_batch(Denomination::Attos)).to_markdown();
+        assert!(attos.contains("* XRD from the faucet: 3000000000000000000000000 (attos)\n"), "{}", attos);
+    }
+
+    #[test]
+    fn json_reports_carry_both_denominations() {
+        for denomination in [Denomination::Whole, Denomination::Attos] {
+            let json: serde_json::Value = serde_json::from_str(&RunReport::from_batch(&faucet_batch(denomination)).to_json()).unwrap();
+            assert_eq!(json["faucet_xrd"]["whole"], "3000000");
+            assert_eq!(json["faucet_xrd"]["attos"], "3000000000000000000000000");
+        }
+    }
+
+    #[test]
+    fn reports_without_amounts_are_still_read() {
+        let mut json: serde_json::Value = serde_json::from_str(&RunReport::from_batch(&BatchReport::new()).to_json()).unwrap();
+        json.as_object_mut().unwrap().remove("faucet_xrd");
+        json.as_object_mut().unwrap().remove("denomination");
+
+        let report: RunReport = RunReport::from_json(&json.to_string()).unwrap();
+        assert_eq!(report.faucet_xrd.amount(), Ok(Decimal::zero()));
+        assert_eq!(report.denomination, Denomination::Whole);
+    }
+
+    #[test]
+    fn reports_round_trip_through_json() {
+        let report: RunReport = RunReport {
+            transactions: vec![summary("signup", &[("feature", "onboarding")])],
+            ..RunReport::from_batch(&BatchReport::new())
+        };
+        assert_eq!(RunReport::from_json(&report.to_json()).unwrap(), report);
+    }
+}
@@ -0,0 +1,50 @@
+//! The information the PTE's `/resource` endpoint reports about resource managers.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "blocking-http")]
+use scrypto::prelude::*;
+
+#[cfg(feature = "blocking-http")]
+use crate::client::{Horizon, PteClient};
+#[cfg(feature = "blocking-http")]
+use crate::labeled::LabeledAddress;
+#[cfg(feature = "blocking-http")]
+use crate::PteApiError;
+
+/// The body of a successful response of the `/resource/{address}` endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResourceInfo {
+    /// `Fungible` along with its divisibility, or `NonFungible`, as reported by the PTE.
+    pub resource_type: serde_json::Value,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    pub total_supply: String,
+    /// Any fields which are not part of the expected response.
+    #[serde(flatten)]
+    pub extra_fields: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(feature = "blocking-http")]
+impl PteClient {
+    /// Gets the information of the resource with the given address from the PTE's `/resource`
+    /// endpoint.
+    pub fn get_resource(&self, address: impl Into<LabeledAddress<ResourceAddress>>) -> Result<ResourceInfo, PteApiError> {
+        let labeled_address: LabeledAddress<ResourceAddress> = address.into();
+        let address: ResourceAddress = labeled_address.address;
+        let response = self.send(Horizon::Read, |url| {
+            self.http_client().get(format!("{}/resource/{}", url, address))
+        })?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(PteApiError::NotFound(labeled_address.to_string()));
+        }
+
+        let info: ResourceInfo = serde_json::from_str(&response.text()?)
+            .map_err(|error| PteApiError::InvalidState(error.to_string()))?;
+        if let Some(mirror) = self.mirror() {
+            mirror.record_resource(address, &info);
+        }
+        Ok(info)
+    }
+}
@@ -0,0 +1,327 @@
+//! Verifying that a transaction only touches what it is expected to. Manifests built from templates
+//! or spec files written by someone else can carry an extra withdrawal or a catch-all deposit which
+//! is easy to miss when reading them, so the accounts, components, methods and resources a
+//! transaction may touch are listed in a policy and every instruction is checked against it.
+
+use radix_engine::model::{Instruction, SignedTransaction, Transaction};
+use scrypto::buffer::scrypto_decode;
+use scrypto::prelude::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::address_book::AddressBook;
+use crate::labeled::{abbreviate, LabeledAddress};
+use crate::utils::decompile;
+
+/// The methods of accounts which withdraw resources from them.
+pub const WITHDRAW_METHODS: [&str; 3] = ["withdraw", "withdraw_by_amount", "withdraw_by_ids"];
+
+/// The methods of accounts which deposit resources into them.
+pub const DEPOSIT_METHODS: [&str; 2] = ["deposit", "deposit_batch"];
+
+/// The method name which allows calling any method of a component.
+pub const ANY_METHOD: &str = "*";
+
+/// What a transaction is allowed to touch. Everything which isn't listed is refused, including the
+/// resources a transaction merely takes from the worktop or asserts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScopePolicy {
+    pub withdraw_from: BTreeSet<ComponentAddress>,
+    pub deposit_to: BTreeSet<ComponentAddress>,
+    /// The components and their methods which may be called, with `ANY_METHOD` allowing all of
+    /// them. Withdrawals and deposits are governed by `withdraw_from` and `deposit_to` instead.
+    pub methods: BTreeSet<(ComponentAddress, String)>,
+    /// The package, blueprint and function of the functions which may be called.
+    pub functions: BTreeSet<(PackageAddress, String, String)>,
+    pub resources: BTreeSet<ResourceAddress>,
+    pub allow_publish: bool,
+    /// The labels of the addresses the policy was given, keyed by the raw address, so that
+    /// violations refer to addresses the way the user knows them.
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Why an instruction is outside of the scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScopeViolationKind {
+    WithdrawalNotAllowed(ComponentAddress),
+    /// Resources are deposited into a component which isn't an allowed deposit target. Catch-all
+    /// calls passing the whole worktop count as deposits whatever the name of the method.
+    DepositNotAllowed(ComponentAddress),
+    MethodNotAllowed { component: ComponentAddress, method: String },
+    FunctionNotAllowed { package: PackageAddress, blueprint: String, function: String },
+    ResourceNotAllowed(ResourceAddress),
+    PublishNotAllowed,
+}
+
+impl ScopeViolationKind {
+    /// The address the violation is about, if it is about one.
+    pub fn address(&self) -> Option<String> {
+        match self {
+            ScopeViolationKind::WithdrawalNotAllowed(account) | ScopeViolationKind::DepositNotAllowed(account) => {
+                Some(account.to_string())
+            }
+            ScopeViolationKind::MethodNotAllowed { component, .. } => Some(component.to_string()),
+            ScopeViolationKind::FunctionNotAllowed { package, .. } => Some(package.to_string()),
+            ScopeViolationKind::ResourceNotAllowed(resource) => Some(resource.to_string()),
+            ScopeViolationKind::PublishNotAllowed => None,
+        }
+    }
+}
+
+/// The first instruction of a transaction which is outside of the scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeViolation {
+    /// The index of the instruction in the transaction.
+    pub index: usize,
+    /// The decompiled text of the instruction.
+    pub instruction: String,
+    pub kind: ScopeViolationKind,
+    /// The label the policy knows the address of the violation by, if any.
+    pub label: Option<String>,
+}
+
+impl fmt::Display for ScopeViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let address: String = match (self.kind.address(), self.label.as_ref()) {
+            (Some(address), Some(label)) => format!("{} ({})", label, abbreviate(&address)),
+            (Some(address), None) => address,
+            (None, _) => String::new(),
+        };
+        let reason: String = match self.kind {
+            ScopeViolationKind::WithdrawalNotAllowed(_) => format!("withdraws from {}", address),
+            ScopeViolationKind::DepositNotAllowed(_) => format!("deposits into {}", address),
+            ScopeViolationKind::MethodNotAllowed { ref method, .. } => format!("calls {} on {}", method, address),
+            ScopeViolationKind::FunctionNotAllowed { ref blueprint, ref function, .. } => {
+                format!("calls {}::{} of {}", blueprint, function, address)
+            }
+            ScopeViolationKind::ResourceNotAllowed(_) => format!("uses the resource {}", address),
+            ScopeViolationKind::PublishNotAllowed => "publishes a package".to_string(),
+        };
+        write!(
+            f,
+            "instruction {} ({}) {}, which is outside of the scope",
+            self.index, self.instruction, reason
+        )
+    }
+}
+
+impl ScopePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn withdraw_from(mut self, account: impl Into<LabeledAddress<ComponentAddress>>) -> Self {
+        let account: ComponentAddress = self.remember(account.into());
+        self.withdraw_from.insert(account);
+        self
+    }
+
+    pub fn deposit_to(mut self, account: impl Into<LabeledAddress<ComponentAddress>>) -> Self {
+        let account: ComponentAddress = self.remember(account.into());
+        self.deposit_to.insert(account);
+        self
+    }
+
+    pub fn method(mut self, component: impl Into<LabeledAddress<ComponentAddress>>, method: &str) -> Self {
+        let component: ComponentAddress = self.remember(component.into());
+        self.methods.insert((component, method.to_string()));
+        self
+    }
+
+    pub fn function(mut self, package: impl Into<LabeledAddress<PackageAddress>>, blueprint: &str, function: &str) -> Self {
+        let package: PackageAddress = self.remember(package.into());
+        self.functions.insert((package, blueprint.to_string(), function.to_string()));
+        self
+    }
+
+    pub fn resource(mut self, resource: impl Into<LabeledAddress<ResourceAddress>>) -> Self {
+        let resource: ResourceAddress = self.remember(resource.into());
+        self.resources.insert(resource);
+        self
+    }
+
+    /// Labels the addresses of the address book in violations, unless the policy was given another
+    /// label for them.
+    pub fn with_address_book(mut self, address_book: &AddressBook) -> Self {
+        for (label, address) in address_book.entries() {
+            self.labels.entry(address.to_string()).or_insert_with(|| label.to_string());
+        }
+        self
+    }
+
+    /// Keeps the label of the address, returning the raw address.
+    fn remember<T: fmt::Display>(&mut self, address: LabeledAddress<T>) -> T {
+        if let Some(label) = address.label {
+            self.labels.insert(address.address.to_string(), label);
+        }
+        address.address
+    }
+
+    pub fn allow_publish(self) -> Self {
+        Self {
+            allow_publish: true,
+            ..self
+        }
+    }
+
+    /// Signs the transaction with the keys only if it is within the scope.
+    pub fn sign(&self, transaction: Transaction, private_keys: &[&EcdsaPrivateKey]) -> Result<SignedTransaction, ScopeViolation> {
+        verify_manifest_scope(&transaction, self)?;
+        Ok(transaction.sign(private_keys))
+    }
+
+    fn check(&self, instruction: &Instruction) -> Option<ScopeViolationKind> {
+        let resource = |resource: &ResourceAddress| {
+            (!self.resources.contains(resource)).then(|| ScopeViolationKind::ResourceNotAllowed(*resource))
+        };
+        match instruction {
+            Instruction::CallMethod { component_address, method, args } if WITHDRAW_METHODS.contains(&method.as_str()) => {
+                if !self.withdraw_from.contains(component_address) {
+                    return Some(ScopeViolationKind::WithdrawalNotAllowed(*component_address));
+                }
+                // `withdraw` takes the resource first and the others take it second.
+                let position: usize = if method == "withdraw" { 0 } else { 1 };
+                match args.get(position).map(|x| scrypto_decode::<ResourceAddress>(x)) {
+                    Some(Ok(withdrawn)) => resource(&withdrawn),
+                    _ => Some(ScopeViolationKind::MethodNotAllowed {
+                        component: *component_address,
+                        method: method.clone(),
+                    }),
+                }
+            }
+            Instruction::CallMethod { component_address, method, .. } if DEPOSIT_METHODS.contains(&method.as_str()) => {
+                (!self.deposit_to.contains(component_address))
+                    .then(|| ScopeViolationKind::DepositNotAllowed(*component_address))
+            }
+            Instruction::CallMethod { component_address, method, .. } => {
+                let allowed: bool = self.methods.contains(&(*component_address, method.clone()))
+                    || self.methods.contains(&(*component_address, ANY_METHOD.to_string()));
+                (!allowed).then(|| ScopeViolationKind::MethodNotAllowed {
+                    component: *component_address,
+                    method: method.clone(),
+                })
+            }
+            Instruction::CallMethodWithAllResources { component_address, .. } => {
+                (!self.deposit_to.contains(component_address))
+                    .then(|| ScopeViolationKind::DepositNotAllowed(*component_address))
+            }
+            Instruction::CallFunction { package_address, blueprint_name, function, .. } => {
+                let allowed: bool = self
+                    .functions
+                    .contains(&(*package_address, blueprint_name.clone(), function.clone()));
+                (!allowed).then(|| ScopeViolationKind::FunctionNotAllowed {
+                    package: *package_address,
+                    blueprint: blueprint_name.clone(),
+                    function: function.clone(),
+                })
+            }
+            Instruction::PublishPackage { .. } => (!self.allow_publish).then(|| ScopeViolationKind::PublishNotAllowed),
+            Instruction::TakeFromWorktop { resource_address }
+            | Instruction::TakeFromWorktopByAmount { resource_address, .. }
+            | Instruction::TakeFromWorktopByIds { resource_address, .. }
+            | Instruction::AssertWorktopContains { resource_address }
+            | Instruction::AssertWorktopContainsByAmount { resource_address, .. }
+            | Instruction::AssertWorktopContainsByIds { resource_address, .. }
+            | Instruction::CreateProofFromAuthZone { resource_address }
+            | Instruction::CreateProofFromAuthZoneByAmount { resource_address, .. }
+            | Instruction::CreateProofFromAuthZoneByIds { resource_address, .. } => resource(resource_address),
+            _ => None,
+        }
+    }
+}
+
+/// Checks every instruction of the transaction against the policy, returning the first one which is
+/// outside of it.
+pub fn verify_manifest_scope(transaction: &Transaction, policy: &ScopePolicy) -> Result<(), ScopeViolation> {
+    let violation = transaction
+        .instructions
+        .iter()
+        .enumerate()
+        .find_map(|(index, instruction)| policy.check(instruction).map(|kind| (index, kind)));
+    match violation {
+        Some((index, kind)) => Err(ScopeViolation {
+            index,
+            instruction: instruction_text(transaction, index),
+            label: kind.address().and_then(|address| policy.labels.get(&address).cloned()),
+            kind,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The decompiled text of the instruction at the index. The decompiler writes one line per
+/// instruction except for the nonce, which it skips.
+fn instruction_text(transaction: &Transaction, index: usize) -> String {
+    let line: usize = transaction.instructions[..index]
+        .iter()
+        .filter(|x| !matches!(x, Instruction::Nonce { .. }))
+        .count();
+    decompile(transaction)
+        .ok()
+        .and_then(|manifest| manifest.lines().nth(line).map(str::to_string))
+        .unwrap_or_else(|| format!("{:?}", transaction.instructions[index]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::labeled::AddressSource;
+    use radix_engine::transaction::TransactionBuilder;
+
+    const ACCOUNT: &str = "02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173";
+    const OTHER_ACCOUNT: &str = "020d3869346218a5e8deaaf2001216dc00fcacb79fb43e30ded79a";
+
+    fn account() -> ComponentAddress {
+        ComponentAddress::from_str(ACCOUNT).unwrap()
+    }
+
+    fn other_account() -> ComponentAddress {
+        ComponentAddress::from_str(OTHER_ACCOUNT).unwrap()
+    }
+
+    fn withdrawal() -> Transaction {
+        TransactionBuilder::new()
+            .withdraw_from_account(RADIX_TOKEN, account())
+            .call_method_with_all_resources(other_account(), "deposit_batch")
+            .build(0)
+    }
+
+    #[test]
+    fn violations_name_the_address_by_the_label_the_policy_was_given() {
+        let policy: ScopePolicy = ScopePolicy::new()
+            .deposit_to(LabeledAddress::new(account(), "treasury", AddressSource::CliArg))
+            .resource(RADIX_TOKEN);
+        let violation: ScopeViolation = verify_manifest_scope(&withdrawal(), &policy).unwrap_err();
+
+        assert_eq!(violation.kind, ScopeViolationKind::WithdrawalNotAllowed(account()));
+        assert_eq!(violation.label.as_deref(), Some("treasury"));
+        assert!(violation.to_string().contains("withdraws from treasury (02c1d7…)"));
+    }
+
+    #[test]
+    fn violations_fall_back_to_the_address_book_and_then_the_raw_address() {
+        let mut address_book: AddressBook = AddressBook::new();
+        address_book.insert("savings", OTHER_ACCOUNT);
+        let policy: ScopePolicy = ScopePolicy::new()
+            .withdraw_from(account())
+            .resource(RADIX_TOKEN)
+            .with_address_book(&address_book);
+        let violation: ScopeViolation = verify_manifest_scope(&withdrawal(), &policy).unwrap_err();
+        assert_eq!(violation.label.as_deref(), Some("savings"));
+
+        let violation: ScopeViolation = verify_manifest_scope(&withdrawal(), &ScopePolicy::new()).unwrap_err();
+        assert_eq!(violation.label, None);
+        assert!(violation.to_string().contains(&format!("withdraws from {}", ACCOUNT)));
+    }
+
+    #[test]
+    fn labels_given_to_the_policy_win_over_the_address_book() {
+        let mut address_book: AddressBook = AddressBook::new();
+        address_book.insert("old name", ACCOUNT);
+        let policy: ScopePolicy = ScopePolicy::new()
+            .deposit_to(LabeledAddress::new(account(), "treasury", AddressSource::CliArg))
+            .with_address_book(&address_book);
+        assert_eq!(policy.labels.get(ACCOUNT).map(String::as_str), Some("treasury"));
+    }
+}
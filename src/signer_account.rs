@@ -0,0 +1,150 @@
+//! Finding the account a signer controls, for helpers which deposit what a transaction leaves over
+//! and are told to use "the signer's account" rather than an address. The accounts to consider come
+//! from the address book or from a bootstrap session, and each of them is checked on the ledger: an
+//! account is controlled by a key when its withdraw rule requires the virtual badge of the key. A
+//! key controlling none or several of the known accounts is an error rather than a guess. Results
+//! are cached for the lifetime of the resolver, which is typically a run.
+
+use scrypto::prelude::*;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::access_rules::{method_rule, rule_requires, virtual_badge};
+use crate::address_book::AddressBook;
+use crate::bootstrap::BootstrapSession;
+use crate::client::PteClient;
+use crate::component::ComponentInfo;
+use crate::PteApiError;
+
+/// Where the resources left over by a transaction are deposited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepositTarget {
+    Account(ComponentAddress),
+    /// The only known account controlled by the key signing the transaction.
+    SignerAccount,
+}
+
+impl From<ComponentAddress> for DepositTarget {
+    fn from(account: ComponentAddress) -> Self {
+        DepositTarget::Account(account)
+    }
+}
+
+#[derive(Debug)]
+pub enum SignerAccountError {
+    /// The client has no resolver to find the account of the signer with.
+    NoResolver,
+    /// None of the known accounts is controlled by the key.
+    NoAccount { public_key: String, checked: Vec<String> },
+    /// Several of the known accounts are controlled by the key.
+    Ambiguous { public_key: String, candidates: Vec<String> },
+    ApiError(PteApiError),
+}
+
+impl From<PteApiError> for SignerAccountError {
+    fn from(error: PteApiError) -> SignerAccountError {
+        SignerAccountError::ApiError(error)
+    }
+}
+
+/// Resolves public keys into the known account they control.
+pub struct SignerAccountResolver {
+    accounts: Vec<ComponentAddress>,
+    resolved: Mutex<HashMap<String, ComponentAddress>>,
+}
+
+impl SignerAccountResolver {
+    pub fn new() -> Self {
+        Self {
+            accounts: Vec::new(),
+            resolved: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A resolver considering every component of the address book. Components which aren't accounts
+    /// have no withdraw rule, so they never match.
+    pub fn from_address_book(book: &AddressBook) -> Self {
+        book.entries()
+            .filter_map(|(_, address)| ComponentAddress::from_str(address).ok())
+            .fold(Self::new(), Self::with_account)
+    }
+
+    /// A resolver considering the accounts created by the bootstrap.
+    pub fn from_session(session: &BootstrapSession) -> Self {
+        session
+            .accounts
+            .iter()
+            .filter_map(|account| account.address.as_deref())
+            .filter_map(|address| ComponentAddress::from_str(address).ok())
+            .fold(Self::new(), Self::with_account)
+    }
+
+    pub fn with_account(mut self, account: ComponentAddress) -> Self {
+        if !self.accounts.contains(&account) {
+            self.accounts.push(account);
+        }
+        self
+    }
+
+    pub fn accounts(&self) -> &[ComponentAddress] {
+        &self.accounts
+    }
+
+    /// The only known account controlled by the key, checked on the ledger the first time the key is
+    /// resolved and cached afterwards.
+    pub fn resolve(&self, client: &PteClient, public_key: &EcdsaPublicKey) -> Result<ComponentAddress, SignerAccountError> {
+        let key: String = hex::encode(public_key.to_vec());
+        if let Some(account) = self.resolved.lock().unwrap().get(&key) {
+            return Ok(*account);
+        }
+
+        let badge: NonFungibleAddress = virtual_badge(public_key);
+        let mut controlled: Vec<ComponentAddress> = Vec::new();
+        for account in self.accounts.iter() {
+            let info: ComponentInfo = client.get_component(*account)?;
+            if method_rule(&info.authorization, "withdraw").is_some_and(|rule| rule_requires(rule, &badge)) {
+                controlled.push(*account);
+            }
+        }
+
+        match controlled.as_slice() {
+            [account] => {
+                self.resolved.lock().unwrap().insert(key, *account);
+                Ok(*account)
+            }
+            [] => Err(SignerAccountError::NoAccount {
+                public_key: key,
+                checked: self.accounts.iter().map(ToString::to_string).collect(),
+            }),
+            _ => Err(SignerAccountError::Ambiguous {
+                public_key: key,
+                candidates: controlled.iter().map(ToString::to_string).collect(),
+            }),
+        }
+    }
+}
+
+impl Default for SignerAccountResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PteClient {
+    /// The account to deposit into for the target, resolving the account of the signer through the
+    /// resolver of the client.
+    pub fn resolve_deposit_target(
+        &self,
+        target: &DepositTarget,
+        signer: &EcdsaPublicKey,
+    ) -> Result<ComponentAddress, SignerAccountError> {
+        match target {
+            DepositTarget::Account(account) => Ok(*account),
+            DepositTarget::SignerAccount => self
+                .signer_account_resolver()
+                .ok_or(SignerAccountError::NoResolver)?
+                .resolve(self, signer),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Isolation of integration tests running in parallel against the same PTE. Every test gets its own
+//! directory for sessions, address books and keys, a prefix for the labels of everything it creates
+//! and accounts of its own, so that no test can find or spend what another one created. Dropping the
+//! context sweeps the resources of its accounts to the sweep account, if one is set, and removes its
+//! directory. Cleanup is best effort: failures are reported to the warning hooks of the client as
+//! `ClientWarning::TestContextCleanupFailed` and never fail the test. The accounts of
+//! all of the contexts created from the environment are funded through one faucet limiter, so that
+//! many tests starting at once don't get the faucet throttled.
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use crate::address_book::AddressBook;
+use crate::bootstrap::{BootstrapConfig, ADDRESS_BOOK_FILE, SESSION_FILE};
+use crate::builder::TransactionBuilderExt;
+use crate::client::{PteClient, DEFAULT_PTE_URL};
+use crate::faucet::{FaucetLimiter, FaucetLimiterConfig};
+use crate::hooks::ClientWarning;
+use crate::keystore::Keystore;
+use crate::manifest_spec::ManifestSpec;
+use crate::queue::ExecuteOptions;
+use crate::{Receipt, TransactionSubmissionError};
+
+/// The environment variable holding the URL of the PTE the tests run against.
+pub const TEST_URL_VARIABLE: &str = "PTE_TEST_URL";
+
+/// The environment variable holding the account the resources of the tests are swept to.
+pub const SWEEP_TO_VARIABLE: &str = "PTE_TEST_SWEEP_TO";
+
+/// Distinguishes contexts of the same test name created by the same process.
+static NEXT_CONTEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// The faucet limiter shared by the contexts created from the environment.
+static FAUCET_LIMITER: OnceLock<Arc<FaucetLimiter>> = OnceLock::new();
+
+/// An account created for a single context.
+pub struct TestAccount {
+    pub label: String,
+    pub address: ComponentAddress,
+    pub private_key: EcdsaPrivateKey,
+}
+
+#[derive(Debug)]
+pub enum TestContextError {
+    IoError(std::io::Error),
+    SubmissionError(TransactionSubmissionError),
+    /// The transaction creating an account was committed but didn't create it.
+    AccountNotCreated(Box<Receipt>),
+}
+
+impl From<std::io::Error> for TestContextError {
+    fn from(error: std::io::Error) -> TestContextError {
+        TestContextError::IoError(error)
+    }
+}
+
+impl From<TransactionSubmissionError> for TestContextError {
+    fn from(error: TransactionSubmissionError) -> TestContextError {
+        TestContextError::SubmissionError(error)
+    }
+}
+
+/// Everything a test needs to run in isolation from the other tests.
+pub struct TestContext {
+    client: PteClient,
+    prefix: String,
+    directory: PathBuf,
+    accounts: Vec<TestAccount>,
+    sweep_to: Option<ComponentAddress>,
+}
+
+impl TestContext {
+    /// Creates a context for the named test, with a unique label prefix and a fresh temporary
+    /// directory.
+    pub fn new(client: PteClient, name: &str) -> Self {
+        let prefix: String = format!(
+            "{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT_CONTEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let directory: PathBuf = std::env::temp_dir().join(format!("pte-test-{}", prefix));
+        std::fs::create_dir_all(&directory).expect("failed to create the directory of the test context");
+        Self {
+            client,
+            prefix,
+            directory,
+            accounts: Vec::new(),
+            sweep_to: None,
+        }
+    }
+
+    /// Creates a context for the named test against the PTE given through `PTE_TEST_URL`, or PTE01,
+    /// sweeping to the account given through `PTE_TEST_SWEEP_TO` when it is set. The faucet limiter of
+    /// the client is shared with all of the other contexts created from the environment.
+    pub fn from_env(name: &str) -> Self {
+        let url: String = std::env::var(TEST_URL_VARIABLE).unwrap_or_else(|_| DEFAULT_PTE_URL.to_string());
+        let faucet_limiter: Arc<FaucetLimiter> = FAUCET_LIMITER
+            .get_or_init(|| Arc::new(FaucetLimiter::new(FaucetLimiterConfig::default())))
+            .clone();
+        let context: TestContext = Self::new(PteClient::new(&url).with_faucet_limiter(faucet_limiter), name);
+        match std::env::var(SWEEP_TO_VARIABLE).ok().map(|x| ComponentAddress::from_str(&x)) {
+            Some(Ok(sweep_to)) => context.sweep_to(sweep_to),
+            Some(Err(_)) => panic!("{} is not a component address", SWEEP_TO_VARIABLE),
+            None => context,
+        }
+    }
+
+    /// Sets the account the resources of the accounts of the context are swept to on drop.
+    pub fn sweep_to(self, account: ComponentAddress) -> Self {
+        Self {
+            sweep_to: Some(account),
+            ..self
+        }
+    }
+
+    /// Creates `count` accounts owned by this context, labeled `account-1` onwards with the prefix
+    /// of the context, and records them in the address book and keystore of the context.
+    pub fn with_accounts(mut self, count: usize) -> Result<Self, TestContextError> {
+        for _ in 0..count {
+            self.create_account()?;
+        }
+        Ok(self)
+    }
+
+    pub fn client(&self) -> &PteClient {
+        &self.client
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn accounts(&self) -> &[TestAccount] {
+        &self.accounts
+    }
+
+    pub fn account(&self, index: usize) -> &TestAccount {
+        &self.accounts[index]
+    }
+
+    /// The label namespaced to this context.
+    pub fn label(&self, name: &str) -> String {
+        format!("{}-{}", self.prefix, name)
+    }
+
+    pub fn session_path(&self) -> PathBuf {
+        self.directory.join(SESSION_FILE)
+    }
+
+    pub fn address_book_path(&self) -> PathBuf {
+        self.directory.join(ADDRESS_BOOK_FILE)
+    }
+
+    pub fn keystore(&self) -> Keystore {
+        Keystore::new(self.directory.join("keys"))
+    }
+
+    pub fn address_book(&self) -> AddressBook {
+        AddressBook::load(&self.address_book_path()).expect("the address book of the context is readable")
+    }
+
+    /// Records an entity created by the test in the address book of the context under its namespaced
+    /// label, returning the label.
+    pub fn register(&self, name: &str, address: &str) -> String {
+        let label: String = self.label(name);
+        let mut book: AddressBook = self.address_book();
+        book.insert(&label, address);
+        book.save(&self.address_book_path()).expect("the address book of the context is writable");
+        label
+    }
+
+    /// A bootstrap configuration writing into the directory of the context.
+    pub fn bootstrap_config(&self, accounts: usize, token_symbol: &str, token_supply: Decimal) -> BootstrapConfig {
+        BootstrapConfig {
+            accounts,
+            token_symbol: token_symbol.to_string(),
+            token_supply,
+            out: self.directory.clone(),
+        }
+    }
+
+    /// Creates another account owned by this context.
+    pub fn create_account(&mut self) -> Result<&TestAccount, TestContextError> {
+        let bytes: [u8; 32] = loop {
+            let bytes: [u8; 32] = rand::random();
+            if EcdsaPrivateKey::from_bytes(&bytes).is_ok() {
+                break bytes;
+            }
+        };
+        let name: String = format!("account-{}", self.accounts.len() + 1);
+        let private_key: EcdsaPrivateKey = self.keystore().insert(&self.label(&name), &bytes)?;
+
+        let transaction: SignedTransaction = ManifestSpec::NewAccount
+            .build(&private_key, self.client.next_nonce())
+            .expect("the new account spec has no fields to be invalid");
+        let receipt: Receipt = self.client.execute(&transaction, &ExecuteOptions::new().tag("flow", "test-context"))?;
+        let address: ComponentAddress = match receipt.new_components().first() {
+            Some(address) if receipt.is_success() => *address,
+            _ => return Err(TestContextError::AccountNotCreated(Box::new(receipt))),
+        };
+
+        let label: String = self.register(&name, &address.to_string());
+        self.accounts.push(TestAccount {
+            label,
+            address,
+            private_key,
+        });
+        Ok(self.accounts.last().unwrap())
+    }
+
+    /// Moves all of the resources of the accounts of the context to the sweep account. Failures are
+    /// reported as warnings and the remaining accounts are still swept.
+    pub fn sweep(&self) {
+        let sweep_to: ComponentAddress = match self.sweep_to {
+            Some(sweep_to) => sweep_to,
+            None => return,
+        };
+        for account in self.accounts.iter() {
+            if let Err(error) = self.sweep_account(account, sweep_to) {
+                self.cleanup_failed(&account.label, error);
+            }
+        }
+    }
+
+    fn cleanup_failed(&self, resource: &str, error: String) {
+        self.client.hooks().warned(&ClientWarning::TestContextCleanupFailed {
+            context: self.prefix.clone(),
+            resource: resource.to_string(),
+            error,
+        });
+    }
+
+    fn sweep_account(&self, account: &TestAccount, sweep_to: ComponentAddress) -> Result<(), String> {
+        let vaults = self.client.get_vaults(account.address).map_err(|error| format!("{:?}", error))?;
+        let resources: BTreeSet<String> = vaults
+            .into_iter()
+            .filter(|x| Decimal::from_str(&x.amount).is_ok_and(|amount| amount > Decimal::zero()))
+            .map(|x| x.resource)
+            .collect();
+        if resources.is_empty() {
+            return Ok(());
+        }
+
+        let resources: Vec<ResourceAddress> = resources
+            .iter()
+            .map(|x| ResourceAddress::from_str(x).map_err(|_| format!("invalid resource {}", x)))
+            .collect::<Result<_, _>>()?;
+        let transaction: SignedTransaction = TransactionBuilder::new()
+            .sweep(account.address, &resources, sweep_to)
+            .build(self.client.next_nonce())
+            .sign([&account.private_key]);
+        match self.client.submit_transaction(&transaction) {
+            Ok(receipt) if receipt.is_success() => Ok(()),
+            Ok(receipt) => Err(format!("the sweep failed: {:?}", receipt)),
+            Err(error) => Err(format!("{:?}", error)),
+        }
+    }
+}
+
+impl Drop for TestContext {
+    fn drop(&mut self) {
+        self.sweep();
+        if let Err(error) = std::fs::remove_dir_all(&self.directory) {
+            self.cleanup_failed(&self.directory.display().to_string(), error.to_string());
+        }
+    }
+}
+
+/// Defines a test receiving an isolated `TestContext` named after the test and created from the
+/// environment, optionally with a number of accounts of its own:
+///
+/// ```ignore
+/// pte_test!(fn transfers_between_accounts(context, accounts = 2) {
+///     let from = context.account(0);
+///     // ...
+/// });
+/// ```
+#[macro_export]
+macro_rules! pte_test {
+    ($(#[$meta:meta])* fn $name:ident($context:ident) $body:block) => {
+        $crate::pte_test!($(#[$meta])* fn $name($context, accounts = 0) $body);
+    };
+    ($(#[$meta:meta])* fn $name:ident($context:ident, accounts = $accounts:expr) $body:block) => {
+        $(#[$meta])*
+        #[test]
+        fn $name() {
+            #[allow(unused_mut)]
+            let mut $context = $crate::test_context::TestContext::from_env(stringify!($name))
+                .with_accounts($accounts)
+                .expect("failed to create the accounts of the test context");
+            $body
+        }
+    };
+}
@@ -0,0 +1,113 @@
+//! A tiny blueprint for exercising the publication and instantiation of packages against a real
+//! PTE without the Scrypto toolchain. Its source, ABI and pre-built WASM are checked in under
+//! `tests/assets/test_package`, along with the hash of the WASM in `test_package.wasm.hash`, which
+//! guards the WASM against accidental modification. The toolchain is only needed to change it.
+//!
+//! To regenerate the WASM after changing the source, run `scrypto build` in that directory, copy
+//! `target/wasm32-unknown-unknown/release/test_package.wasm` next to the ABI and record its hash
+//! with `cargo run --features cli -- test-vectors`.
+
+use radix_engine::model::Transaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::args::{call_args, Args};
+
+pub const TEST_PACKAGE_BLUEPRINT: &str = "TestPackage";
+
+const WASM_FILE: &str = "test_package.wasm";
+const ABI_FILE: &str = "test_package.abi.json";
+const HASH_FILE: &str = "test_package.wasm.hash";
+
+#[derive(Debug)]
+pub enum TestPackageError {
+    /// The WASM has not been built into the assets directory yet.
+    Missing(PathBuf),
+    /// The WASM doesn't have the recorded hash.
+    HashMismatch { expected: String, actual: String },
+    InvalidAbi(String),
+    Io(std::io::Error),
+}
+
+impl From<std::io::Error> for TestPackageError {
+    fn from(error: std::io::Error) -> TestPackageError {
+        TestPackageError::Io(error)
+    }
+}
+
+/// The directory holding the source, ABI and WASM of the test package.
+pub fn assets_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets/test_package")
+}
+
+/// The WASM of the test package, checked against its recorded hash.
+pub fn wasm() -> Result<Vec<u8>, TestPackageError> {
+    let path: PathBuf = assets_directory().join(WASM_FILE);
+    if !path.exists() {
+        return Err(TestPackageError::Missing(path));
+    }
+    let code: Vec<u8> = fs::read(path)?;
+    let expected: String = fs::read_to_string(assets_directory().join(HASH_FILE))?.trim().to_string();
+    let actual: String = wasm_hash(&code);
+    if actual != expected {
+        return Err(TestPackageError::HashMismatch { expected, actual });
+    }
+    Ok(code)
+}
+
+/// The ABI of the test package as produced by `scrypto build`.
+pub fn abi() -> Result<serde_json::Value, TestPackageError> {
+    let abi: String = fs::read_to_string(assets_directory().join(ABI_FILE))?;
+    serde_json::from_str(&abi).map_err(|error| TestPackageError::InvalidAbi(error.to_string()))
+}
+
+pub fn wasm_hash(code: &[u8]) -> String {
+    hash(code).to_string()
+}
+
+/// Records the hash of the WASM in the assets directory, returning it. Nothing is recorded when
+/// the WASM has not been built.
+pub fn record_hash() -> Result<Option<String>, TestPackageError> {
+    let path: PathBuf = assets_directory().join(WASM_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let hash: String = wasm_hash(&fs::read(path)?);
+    fs::write(assets_directory().join(HASH_FILE), format!("{}\n", hash))?;
+    Ok(Some(hash))
+}
+
+/// Checks the WASM against its recorded hash and that the ABI parses, returning the problems found.
+/// A missing WASM or hash is a problem, since the package can't be published without them.
+pub fn check_test_package() -> Vec<String> {
+    let mut mismatches: Vec<String> = Vec::new();
+    let hash_path: PathBuf = assets_directory().join(HASH_FILE);
+    if !hash_path.exists() {
+        mismatches.push(format!("{} is missing", hash_path.display()));
+    }
+    match wasm() {
+        Ok(_) => {}
+        Err(TestPackageError::Missing(path)) => mismatches.push(format!("{} is missing", path.display())),
+        Err(_) if !hash_path.exists() => {}
+        Err(error) => mismatches.push(format!("{}: {:?}", WASM_FILE, error)),
+    }
+    if let Err(error) = abi() {
+        mismatches.push(format!("{}: {:?}", ABI_FILE, error));
+    }
+    mismatches
+}
+
+/// A transaction publishing the test package.
+pub fn publish_transaction(nonce: u64) -> Result<Transaction, TestPackageError> {
+    Ok(TransactionBuilder::new().publish_package(&wasm()?).build(nonce))
+}
+
+/// A transaction instantiating a component of the published test package holding the value.
+pub fn instantiate_transaction(package: PackageAddress, value: u32, nonce: u64) -> Transaction {
+    TransactionBuilder::new()
+        .call_function(package, TEST_PACKAGE_BLUEPRINT, "new", call_args(Args::new().add_value(&value)))
+        .build(nonce)
+}
@@ -0,0 +1,143 @@
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::buffer::scrypto_encode;
+use scrypto::prelude::*;
+
+use std::fs;
+use std::path::Path;
+
+use crate::args::{call_args, Args};
+use crate::builder::TransactionBuilderExt;
+use crate::deterministic::{DeterministicMode, Randomness};
+use crate::public_key::PublicKeyFormat;
+use crate::{TransactionBody, TransactionSubmissionError};
+
+/// The seed and timestamp used for the checked-in test vectors. Changing either of these changes
+/// every vector that gets generated.
+pub const TEST_VECTORS_MODE: DeterministicMode = DeterministicMode::Seeded {
+    seed: 0,
+    frozen_timestamp: 1_650_000_000,
+};
+
+/// A single test vector made up of the JSON payload sent to the PTE and the SBOR encoded
+/// transaction, hex encoded.
+#[derive(Debug)]
+pub struct TestVector {
+    pub name: String,
+    pub transaction_body: TransactionBody,
+    pub compiled_transaction: String,
+}
+
+impl TestVector {
+    pub fn new(name: &str, transaction: &SignedTransaction) -> Result<Self, TransactionSubmissionError> {
+        Self::with_public_key_format(name, transaction, PublicKeyFormat::default())
+    }
+
+    /// A test vector whose transaction body has the public keys written in the given format.
+    pub fn with_public_key_format(
+        name: &str,
+        transaction: &SignedTransaction,
+        public_key_format: PublicKeyFormat,
+    ) -> Result<Self, TransactionSubmissionError> {
+        Ok(Self {
+            name: name.to_string(),
+            transaction_body: TransactionBody::from_signed_with_format(transaction, public_key_format)?,
+            compiled_transaction: hex::encode(scrypto_encode(&transaction.transaction)),
+        })
+    }
+
+    /// Checks that the public keys of the transaction body parse back into the format they were
+    /// written in and are written the same way again.
+    pub fn public_keys_round_trip(&self) -> bool {
+        self.transaction_body.signatures().iter().all(|signature| match signature.public_key() {
+            Ok((public_key, format)) => format.format(&public_key) == signature.public_key,
+            Err(_) => false,
+        })
+    }
+
+    /// The JSON representation of the transaction body as it would be sent to the PTE.
+    pub fn transaction_body_json(&self) -> String {
+        serde_json::to_string_pretty(&self.transaction_body).unwrap()
+    }
+}
+
+/// Generates the test vectors for the canonical flows shown in the main example: the creation of a
+/// new account and a transfer of XRD between two accounts. The account creation is also generated
+/// with the public key written in each of the other formats.
+pub fn generate_test_vectors(mode: DeterministicMode) -> Result<Vec<TestVector>, TransactionSubmissionError> {
+    let mut randomness: Randomness = Randomness::new(mode);
+    let private_key: EcdsaPrivateKey = randomness.new_private_key();
+    let public_key: EcdsaPublicKey = private_key.public_key();
+
+    let withdraw_auth: AccessRule = rule!(require(NonFungibleAddress::new(
+        ECDSA_TOKEN,
+        NonFungibleId::from_bytes(public_key.to_vec())
+    )));
+    let account_creation_tx: SignedTransaction = TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+            builder.new_account_with_resource(&withdraw_auth, bucket_id)
+        })
+        .build(randomness.next_nonce())
+        .sign([&private_key]);
+
+    let source_account: ComponentAddress = ComponentAddress::from_str("02c1d7add487dbcbb8c81da378aa8d4924d9844874d1cc3829a173").unwrap();
+    let destination_account: ComponentAddress = ComponentAddress::from_str("020d3869346218a5e8deaaf2001216dc00fcacb79fb43e30ded79a").unwrap();
+    let xrd_transfer_tx: SignedTransaction = TransactionBuilder::new()
+        .withdraw_from_account_by_amount(dec!("10000"), RADIX_TOKEN, source_account)
+        .take_from_worktop_ref(RADIX_TOKEN, |builder, bucket| {
+            builder.call_method(
+                destination_account,
+                "deposit",
+                call_args(Args::new().add_bucket(bucket))
+            )
+        })
+        .build(randomness.next_nonce())
+        .sign([&private_key]);
+
+    let mut vectors: Vec<TestVector> = vec![
+        TestVector::new("account_creation", &account_creation_tx)?,
+        TestVector::new("xrd_transfer", &xrd_transfer_tx)?,
+    ];
+    for format in PublicKeyFormat::ALL.into_iter().filter(|x| *x != PublicKeyFormat::default()) {
+        vectors.push(TestVector::with_public_key_format(
+            &format!("account_creation_{}", format.name()),
+            &account_creation_tx,
+            format,
+        )?);
+    }
+    Ok(vectors)
+}
+
+/// Writes the test vectors to the given directory as a `<name>.json` and `<name>.hex` pair of files.
+pub fn write_test_vectors(vectors: &[TestVector], directory: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(directory)?;
+    for vector in vectors {
+        fs::write(directory.join(format!("{}.json", vector.name)), vector.transaction_body_json())?;
+        fs::write(directory.join(format!("{}.hex", vector.name)), &vector.compiled_transaction)?;
+    }
+    Ok(())
+}
+
+/// Compares the test vectors against the ones checked into the given directory and returns the
+/// names of the files which are missing or whose content has changed, along with the vectors whose
+/// public keys don't round-trip through parsing.
+pub fn check_test_vectors(vectors: &[TestVector], directory: &Path) -> Vec<String> {
+    let mut mismatches: Vec<String> = Vec::new();
+    for vector in vectors {
+        if !vector.public_keys_round_trip() {
+            mismatches.push(format!("{} (public key round-trip)", vector.name));
+        }
+        let expected_files = [
+            (format!("{}.json", vector.name), vector.transaction_body_json()),
+            (format!("{}.hex", vector.name), vector.compiled_transaction.clone()),
+        ];
+        for (file_name, expected_content) in expected_files {
+            match fs::read_to_string(directory.join(&file_name)) {
+                Ok(content) if content == expected_content => {}
+                _ => mismatches.push(file_name),
+            }
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,394 @@
+//! Vault level inspection of components. Balances summed per resource lose information when a
+//! component holds several vaults of the same resource, so vaults are kept as separate entries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "blocking-http")]
+use scrypto::prelude::*;
+
+use crate::component::OwnedResource;
+
+#[cfg(feature = "blocking-http")]
+use crate::client::{Horizon, PteClient};
+#[cfg(feature = "blocking-http")]
+use crate::labeled::LabeledAddress;
+#[cfg(feature = "blocking-http")]
+use crate::PteApiError;
+
+/// The contents of a single vault owned by a component.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VaultInfo {
+    /// The ID of the vault when the PTE reports it. Used as the stable key of the vault when
+    /// comparing snapshots.
+    pub vault_id: Option<String>,
+    pub resource: String,
+    pub amount: String,
+    pub non_fungible_ids: Option<Vec<String>>,
+    /// The entry exactly as the PTE reported it, including any fields not parsed above.
+    #[serde(skip)]
+    pub raw: Option<serde_json::Value>,
+}
+
+impl VaultInfo {
+    pub fn from_owned_resource(owned_resource: &OwnedResource) -> Self {
+        Self {
+            vault_id: owned_resource.vault_id.clone(),
+            resource: owned_resource.resource_address.clone(),
+            amount: owned_resource.amount.clone(),
+            non_fungible_ids: owned_resource.non_fungible_ids.clone(),
+            raw: owned_resource.raw.clone(),
+        }
+    }
+
+    /// The key identifying the vault across snapshots: its ID when known, otherwise its resource
+    /// and position among the vaults of that resource.
+    pub fn key(&self, position: usize) -> String {
+        match self.vault_id {
+            Some(ref vault_id) => vault_id.clone(),
+            None => format!("{}#{}", self.resource, position),
+        }
+    }
+}
+
+/// The number of entries in a page when no limit is given.
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// The number of times a pager restarts a listing whose cursor expired before giving up.
+pub const MAX_CURSOR_RESTARTS: u32 = 3;
+
+/// Which page of a listing to get.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageOptions {
+    /// The maximum number of entries in the page.
+    pub limit: usize,
+    /// The cursor returned with the previous page, or `None` for the first page.
+    pub cursor: Option<String>,
+}
+
+impl Default for PageOptions {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAGE_LIMIT)
+    }
+}
+
+impl PageOptions {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            cursor: None,
+        }
+    }
+
+    pub fn cursor(self, cursor: Option<String>) -> Self {
+        Self { cursor, ..self }
+    }
+}
+
+/// A page of the vaults of a component.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VaultPage {
+    pub vaults: Vec<VaultInfo>,
+    /// The cursor of the next page, or `None` if this is the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[cfg(feature = "blocking-http")]
+impl PteClient {
+    /// Gets a page of the vaults owned by the component from the paginated vaults endpoint. Servers
+    /// without the endpoint are paged client-side over the full listing, with the offset as the
+    /// cursor, which fetches the full listing for every page. Fails with `CursorExpired` when the
+    /// server no longer knows the cursor.
+    pub fn get_vaults_page(
+        &self,
+        component: impl Into<LabeledAddress<ComponentAddress>>,
+        options: &PageOptions,
+    ) -> Result<VaultPage, PteApiError> {
+        let component: LabeledAddress<ComponentAddress> = component.into();
+        let response = self.send(Horizon::Read, |url| {
+            let request = self
+                .http_client()
+                .get(format!("{}/component/{}/vaults", url, component.address))
+                .query(&[("limit", options.limit.to_string())]);
+            match options.cursor {
+                Some(ref cursor) => request.query(&[("cursor", cursor)]),
+                None => request,
+            }
+        })?;
+        match response.status() {
+            reqwest::StatusCode::GONE => Err(PteApiError::CursorExpired(options.cursor.clone().unwrap_or_default())),
+            reqwest::StatusCode::NOT_FOUND => {
+                let vaults: Vec<VaultInfo> = self.get_vaults(component.clone())?;
+                let offset: usize = match options.cursor {
+                    Some(ref cursor) => cursor.parse().map_err(|_| PteApiError::CursorExpired(cursor.clone()))?,
+                    None => 0,
+                };
+                let end: usize = vaults.len().min(offset.saturating_add(options.limit.max(1)));
+                Ok(VaultPage {
+                    vaults: vaults.get(offset..end).map(<[VaultInfo]>::to_vec).unwrap_or_default(),
+                    next_cursor: (end < vaults.len()).then(|| end.to_string()),
+                })
+            }
+            _ => {
+                let raw: serde_json::Value = response.json()?;
+                let mut page: VaultPage = serde_json::from_value(raw.clone())
+                    .map_err(|error| PteApiError::InvalidState(error.to_string()))?;
+                if let Some(serde_json::Value::Array(raw_vaults)) = raw.get("vaults") {
+                    for (vault, raw) in page.vaults.iter_mut().zip(raw_vaults) {
+                        vault.raw = Some(raw.clone());
+                    }
+                }
+                Ok(page)
+            }
+        }
+    }
+
+    /// A lazy iterator over the vaults of the component, fetching `limit` vaults at a time. Callers
+    /// can stop early without fetching the remaining pages.
+    pub fn vault_pager(&self, component: impl Into<LabeledAddress<ComponentAddress>>, limit: usize) -> VaultPager<'_> {
+        VaultPager {
+            client: self,
+            component: component.into(),
+            limit,
+            cursor: None,
+            buffer: Vec::new(),
+            yielded: 0,
+            skip: 0,
+            restarts: 0,
+            done: false,
+        }
+    }
+
+    /// Gets all of the vaults of the component by iterating over all of the pages.
+    pub fn get_vaults_all(&self, component: impl Into<LabeledAddress<ComponentAddress>>, limit: usize) -> Result<Vec<VaultInfo>, PteApiError> {
+        self.vault_pager(component, limit).collect()
+    }
+
+    /// Gets the vaults owned by the component, one entry per vault.
+    pub fn get_vaults(&self, component: impl Into<LabeledAddress<ComponentAddress>>) -> Result<Vec<VaultInfo>, PteApiError> {
+        Ok(self
+            .get_component(component)?
+            .owned_resources
+            .iter()
+            .map(VaultInfo::from_owned_resource)
+            .collect())
+    }
+
+    /// Gets the balances of an account, summing the amounts of all of the vaults of each resource.
+    pub fn get_balances(&self, account: impl Into<LabeledAddress<ComponentAddress>>) -> Result<BTreeMap<ResourceAddress, Decimal>, PteApiError> {
+        let mut balances: BTreeMap<ResourceAddress, Decimal> = BTreeMap::new();
+        for vault in self.get_vaults(account)? {
+            let resource: ResourceAddress = ResourceAddress::from_str(&vault.resource)
+                .map_err(|_| PteApiError::InvalidState(vault.resource.clone()))?;
+            let amount: Decimal = Decimal::from_str(&vault.amount)
+                .map_err(|_| PteApiError::InvalidState(vault.amount.clone()))?;
+            let balance: &mut Decimal = balances.entry(resource).or_insert(Decimal::zero());
+            *balance = *balance + amount;
+        }
+        Ok(balances)
+    }
+}
+
+/// Iterates over the vaults of a component page by page. When the cursor expires mid-listing, the
+/// listing restarts from the first page and skips the vaults already yielded, which is the last
+/// point known to be stable as long as the order of the listing doesn't change.
+#[cfg(feature = "blocking-http")]
+pub struct VaultPager<'a> {
+    client: &'a PteClient,
+    component: LabeledAddress<ComponentAddress>,
+    limit: usize,
+    cursor: Option<String>,
+    /// The vaults of the current page which weren't yielded yet, in reverse order.
+    buffer: Vec<VaultInfo>,
+    yielded: usize,
+    /// The number of vaults to drop after a restart because they were yielded before it.
+    skip: usize,
+    restarts: u32,
+    done: bool,
+}
+
+#[cfg(feature = "blocking-http")]
+impl<'a> Iterator for VaultPager<'a> {
+    type Item = Result<VaultInfo, PteApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(vault) = self.buffer.pop() {
+                if self.skip > 0 {
+                    self.skip -= 1;
+                    continue;
+                }
+                self.yielded += 1;
+                return Some(Ok(vault));
+            }
+            if self.done {
+                return None;
+            }
+
+            let options: PageOptions = PageOptions::new(self.limit).cursor(self.cursor.clone());
+            match self.client.get_vaults_page(self.component.clone(), &options) {
+                Ok(page) => {
+                    self.done = page.next_cursor.is_none();
+                    self.cursor = page.next_cursor;
+                    self.buffer = page.vaults.into_iter().rev().collect();
+                }
+                Err(PteApiError::CursorExpired(_)) if self.restarts < MAX_CURSOR_RESTARTS => {
+                    self.restarts += 1;
+                    self.cursor = None;
+                    self.skip = self.yielded;
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// The vaults of a component at some point in time, keyed by their stable vault keys.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct VaultSnapshot {
+    pub vaults: BTreeMap<String, VaultInfo>,
+}
+
+/// A vault whose contents differ between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultChange {
+    pub key: String,
+    pub before: Option<VaultInfo>,
+    pub after: Option<VaultInfo>,
+}
+
+impl VaultSnapshot {
+    pub fn new(vaults: &[VaultInfo]) -> Self {
+        let mut positions: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut snapshot: VaultSnapshot = VaultSnapshot::default();
+        for vault in vaults {
+            let position: &mut usize = positions.entry(vault.resource.as_str()).or_default();
+            snapshot.vaults.insert(vault.key(*position), vault.clone());
+            *position += 1;
+        }
+        snapshot
+    }
+
+    #[cfg(feature = "blocking-http")]
+    pub fn fetch(client: &PteClient, component: impl Into<LabeledAddress<ComponentAddress>>) -> Result<Self, PteApiError> {
+        Ok(Self::new(&client.get_vaults(component)?))
+    }
+
+    /// The vaults which were added, removed or whose contents changed, so that tokens moving
+    /// between vaults of the same resource are visible.
+    pub fn diff(&self, after: &VaultSnapshot) -> Vec<VaultChange> {
+        self.vaults
+            .keys()
+            .chain(after.vaults.keys())
+            .collect::<std::collections::BTreeSet<&String>>()
+            .into_iter()
+            .filter_map(|key| {
+                let before: Option<&VaultInfo> = self.vaults.get(key);
+                let after: Option<&VaultInfo> = after.vaults.get(key);
+                match before == after {
+                    true => None,
+                    false => Some(VaultChange {
+                        key: key.clone(),
+                        before: before.cloned(),
+                        after: after.cloned(),
+                    }),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "testing", feature = "blocking-http"))]
+mod tests {
+    use super::*;
+
+    use crate::fixtures::VENDOR;
+    use crate::mock::MockPte;
+
+    fn vendor() -> ComponentAddress {
+        ComponentAddress::from_str(VENDOR).unwrap()
+    }
+
+    fn vaults(count: usize) -> Vec<VaultInfo> {
+        (0..count)
+            .map(|x| VaultInfo {
+                vault_id: Some(format!("vault-{}", x)),
+                resource: RADIX_TOKEN.to_string(),
+                amount: x.to_string(),
+                non_fungible_ids: None,
+                raw: None,
+            })
+            .collect()
+    }
+
+    fn vault_ids(vaults: &[VaultInfo]) -> Vec<String> {
+        vaults.iter().map(|x| x.vault_id.clone().unwrap()).collect()
+    }
+
+    #[test]
+    fn the_pager_traverses_all_of_the_pages_in_order() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_paginated_vaults(VENDOR, vaults(7));
+        let client: PteClient = mock.client();
+
+        let listed: Vec<VaultInfo> = client.get_vaults_all(vendor(), 3).unwrap();
+
+        assert_eq!(vault_ids(&listed), vault_ids(&vaults(7)));
+        assert_eq!(mock.request_count("GET", "/component/vaults"), 3);
+    }
+
+    #[test]
+    fn the_pager_only_fetches_the_pages_it_needs() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_paginated_vaults(VENDOR, vaults(7));
+        let client: PteClient = mock.client();
+
+        let listed: Vec<VaultInfo> = client
+            .vault_pager(vendor(), 3)
+            .take(4)
+            .collect::<Result<Vec<VaultInfo>, PteApiError>>()
+            .unwrap();
+
+        assert_eq!(vault_ids(&listed), vault_ids(&vaults(4)));
+        assert_eq!(mock.request_count("GET", "/component/vaults"), 2);
+    }
+
+    #[test]
+    fn an_expired_cursor_restarts_the_listing_without_repeating_vaults() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_paginated_vaults(VENDOR, vaults(7));
+        let client: PteClient = mock.client();
+        let mut pager: VaultPager = client.vault_pager(vendor(), 3);
+
+        let mut listed: Vec<VaultInfo> = Vec::new();
+        for _ in 0..3 {
+            listed.push(pager.next().unwrap().unwrap());
+        }
+        // The cursor of the second page expires before the pager uses it
+        mock.expire_cursor("3");
+        for vault in pager {
+            listed.push(vault.unwrap());
+        }
+
+        assert_eq!(vault_ids(&listed), vault_ids(&vaults(7)));
+        // The first page, the expired second page, the first page again, then the last two pages
+        assert_eq!(mock.request_count("GET", "/component/vaults"), 5);
+    }
+
+    #[test]
+    fn an_expired_cursor_is_reported_by_the_page_getter() {
+        let mock: MockPte = MockPte::start(0).unwrap().with_paginated_vaults(VENDOR, vaults(7));
+        let client: PteClient = mock.client();
+        mock.expire_cursor("3");
+
+        let options: PageOptions = PageOptions::new(3).cursor(Some("3".to_string()));
+        assert!(matches!(
+            client.get_vaults_page(vendor(), &options),
+            Err(PteApiError::CursorExpired(cursor)) if cursor == "3"
+        ));
+        // The server only forgets the cursor once in the mock, as if it had been reissued
+        let page: VaultPage = client.get_vaults_page(vendor(), &options).unwrap();
+        assert_eq!(vault_ids(&page.vaults), vec!["vault-3", "vault-4", "vault-5"]);
+        assert_eq!(page.next_cursor.as_deref(), Some("6"));
+    }
+}
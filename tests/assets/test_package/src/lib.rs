@@ -0,0 +1,23 @@
+use scrypto::prelude::*;
+
+blueprint! {
+    /// A component holding a single number, kept as small as possible so that the WASM checked in
+    /// next to this source stays small.
+    struct TestPackage {
+        value: u32,
+    }
+
+    impl TestPackage {
+        pub fn new(value: u32) -> ComponentAddress {
+            Self { value }.instantiate().globalize()
+        }
+
+        pub fn get(&self) -> u32 {
+            self.value
+        }
+
+        pub fn set(&mut self, value: u32) {
+            self.value = value;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! The asynchronous client against a mock PTE.
+#![cfg(all(feature = "async-http", feature = "testing"))]
+
+use programmatic_pte_interactions::async_client::AsyncPteClient;
+use programmatic_pte_interactions::fixtures::{account_envelope, private_key, ACCOUNT};
+use programmatic_pte_interactions::hooks::{ClientWarning, Hooks};
+use programmatic_pte_interactions::mock::{EndpointConfig, LatencyDistribution, MockPte};
+use programmatic_pte_interactions::pool::PoolConfig;
+use programmatic_pte_interactions::{PteApiError, TransactionSubmissionError};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+#[tokio::test]
+async fn transactions_are_submitted() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url());
+
+    assert!(client.submit_transaction(&transaction()).await.unwrap().is_success());
+    assert_eq!(mock.request_count("POST", "/transaction"), 1);
+}
+
+#[tokio::test]
+async fn rejected_submissions_keep_the_status_and_body() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction", EndpointConfig::new(400, "bad request"));
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url());
+
+    match client.submit_transaction(&transaction()).await {
+        Err(TransactionSubmissionError::Rejected { status, body }) => assert_eq!((status, body.as_str()), (400, "bad request")),
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn components_are_read() {
+    let mock: MockPte = MockPte::start(0).unwrap().with_endpoint(
+        "GET",
+        "/component/",
+        EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap()),
+    );
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url());
+
+    assert_eq!(client.get_component(account()).await.unwrap().address, ACCOUNT);
+}
+
+#[tokio::test]
+async fn missing_components_are_not_found() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url());
+
+    assert!(matches!(client.get_component(account()).await, Err(PteApiError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn error_statuses_are_unexpected_rather_than_drift() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/component/", EndpointConfig::new(500, "internal error"));
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url());
+
+    assert!(matches!(
+        client.get_component(account()).await,
+        Err(PteApiError::UnexpectedStatus { status: 500, .. })
+    ));
+}
+
+#[tokio::test]
+async fn hooks_run_in_order_and_panics_are_isolated() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction", EndpointConfig::new(400, "bad request"));
+    let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let (submitted, failed, completed, warned) = (log.clone(), log.clone(), log.clone(), log.clone());
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url()).with_hooks(
+        Hooks::new()
+            .on_submitted(|_, _| panic!("the submitted hook failed"))
+            .on_submitted(move |_, _| submitted.lock().unwrap().push("submitted".to_string()))
+            .on_failed(move |_| failed.lock().unwrap().push("failed".to_string()))
+            .on_completed(move |_| completed.lock().unwrap().push("completed".to_string()))
+            .on_warning(move |warning| {
+                if let ClientWarning::HookPanicked { event } = warning {
+                    warned.lock().unwrap().push(format!("panicked in {}", event))
+                }
+            }),
+    );
+
+    assert!(matches!(
+        client.submit_transaction(&transaction()).await,
+        Err(TransactionSubmissionError::Rejected { status: 400, .. })
+    ));
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["panicked in on_submitted", "submitted", "failed", "completed"]
+    );
+}
+
+#[tokio::test]
+async fn requests_time_out_as_configured_by_the_pool() {
+    let mock: MockPte = MockPte::start(0).unwrap().with_endpoint(
+        "GET",
+        "/component/",
+        EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap())
+            .latency(LatencyDistribution::Fixed(Duration::from_secs(2))),
+    );
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url()).with_pool_config(PoolConfig {
+        request_timeout: Some(Duration::from_millis(200)),
+        ..PoolConfig::default()
+    });
+
+    assert!(matches!(
+        client.get_component(account()).await,
+        Err(PteApiError::HttpRequestError(error)) if error.is_timeout()
+    ));
+}
@@ -0,0 +1,132 @@
+//! The HTTP bridge in front of a client talking to a mock PTE.
+#![cfg(all(feature = "testing", feature = "bridge"))]
+
+use programmatic_pte_interactions::address_book::AddressBook;
+use programmatic_pte_interactions::bridge::{Bridge, BridgeConfig, BridgeOperation, MAX_BODY_BYTES, SECRET_HEADER};
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::{account_envelope, private_key, ACCOUNT, OTHER_ACCOUNT};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+
+use scrypto::prelude::*;
+use serde_json::json;
+
+use std::sync::Arc;
+
+const SECRET: &str = "correct horse battery staple";
+
+fn address_book() -> AddressBook {
+    let mut address_book: AddressBook = AddressBook::new();
+    address_book.insert("alice", ACCOUNT);
+    address_book.insert("bob", OTHER_ACCOUNT);
+    address_book.insert("xrd", &RADIX_TOKEN.to_string());
+    address_book
+}
+
+fn mock() -> MockPte {
+    MockPte::start(0).unwrap().with_endpoint(
+        "GET",
+        "/component/",
+        EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap()),
+    )
+}
+
+fn bridge(mock: &MockPte, config: BridgeConfig) -> Bridge {
+    let client: Arc<PteClient> = Arc::new(mock.client().with_default_signer(private_key()));
+    Bridge::start(client, config.with_address_book(address_book()), "127.0.0.1:0").unwrap()
+}
+
+fn get(bridge: &Bridge, path: &str, secret: Option<&str>) -> reqwest::blocking::Response {
+    let request = reqwest::blocking::Client::new().get(format!("{}{}", bridge.url(), path));
+    match secret {
+        Some(secret) => request.header(SECRET_HEADER, secret),
+        None => request,
+    }
+    .send()
+    .unwrap()
+}
+
+fn post(bridge: &Bridge, path: &str, body: Vec<u8>) -> reqwest::blocking::Response {
+    reqwest::blocking::Client::new()
+        .post(format!("{}{}", bridge.url(), path))
+        .header(SECRET_HEADER, SECRET)
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .unwrap()
+}
+
+fn transfer() -> Vec<u8> {
+    serde_json::to_vec(&json!({
+        "kind": "Transfer",
+        "from": "alice",
+        "to": "bob",
+        "resource": "xrd",
+        "amount": "10",
+    }))
+    .unwrap()
+}
+
+#[test]
+fn requests_without_the_secret_are_unauthorized() {
+    let mock: MockPte = mock();
+    let bridge: Bridge = bridge(&mock, BridgeConfig::new(SECRET));
+
+    for secret in [None, Some("wrong"), Some("correct horse battery stapl")] {
+        assert_eq!(get(&bridge, "/balances/alice", secret).status().as_u16(), 401);
+        assert_eq!(get(&bridge, "/no-such-route", secret).status().as_u16(), 401);
+    }
+    assert_eq!(mock.request_count("GET", "/component/"), 0);
+    assert_eq!(get(&bridge, "/no-such-route", Some(SECRET)).status().as_u16(), 404);
+}
+
+#[test]
+fn execute_is_only_served_when_allowed() {
+    let mock: MockPte = mock();
+    let bridge: Bridge = bridge(&mock, BridgeConfig::new(SECRET));
+
+    assert_eq!(post(&bridge, "/execute", transfer()).status().as_u16(), 403);
+    assert_eq!(mock.request_count("POST", "/transaction"), 0);
+}
+
+#[test]
+fn allowed_executions_resolve_labels_and_are_kept_as_receipts() {
+    let mock: MockPte = mock();
+    let bridge: Bridge = bridge(&mock, BridgeConfig::new(SECRET).allow(BridgeOperation::Execute));
+
+    let response = post(&bridge, "/execute", transfer());
+    assert_eq!(response.status().as_u16(), 200);
+    let receipt: serde_json::Value = response.json().unwrap();
+
+    let submitted: Vec<String> = mock.request_bodies("POST", "/transaction");
+    assert_eq!(submitted.len(), 1);
+    assert!(submitted[0].contains(ACCOUNT));
+    assert!(submitted[0].contains(OTHER_ACCOUNT));
+    assert!(!submitted[0].contains("alice"));
+
+    let hash: &str = receipt["transaction_hash"].as_str().unwrap();
+    let response = get(&bridge, &format!("/receipt/{}", hash), Some(SECRET));
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.json::<serde_json::Value>().unwrap(), receipt);
+}
+
+#[test]
+fn balances_accept_labels_and_addresses() {
+    let mock: MockPte = mock();
+    let bridge: Bridge = bridge(&mock, BridgeConfig::new(SECRET));
+
+    let by_label: serde_json::Value = get(&bridge, "/balances/alice", Some(SECRET)).json().unwrap();
+    let by_address: serde_json::Value = get(&bridge, &format!("/balances/{}", ACCOUNT), Some(SECRET)).json().unwrap();
+    assert_eq!(by_label, by_address);
+    assert!(by_label.get(RADIX_TOKEN.to_string()).is_some());
+
+    assert_eq!(get(&bridge, "/balances/carol", Some(SECRET)).status().as_u16(), 400);
+}
+
+#[test]
+fn oversized_bodies_are_refused() {
+    let mock: MockPte = mock();
+    let bridge: Bridge = bridge(&mock, BridgeConfig::new(SECRET).allow(BridgeOperation::Execute));
+
+    assert_eq!(post(&bridge, "/execute", vec![b' '; MAX_BODY_BYTES + 1]).status().as_u16(), 413);
+    assert_eq!(mock.request_count("POST", "/transaction"), 0);
+}
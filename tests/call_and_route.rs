@@ -0,0 +1,120 @@
+//! Golden manifests of `call_and_route` for each route target, checked in under
+//! `tests/assets/call_and_route`. Running the tests with `PTE_UPDATE_GOLDEN=1` rewrites them.
+#![cfg(feature = "testing")]
+
+use programmatic_pte_interactions::args::Args;
+use programmatic_pte_interactions::builder::{RouteTarget, TransactionBuilderExt};
+use programmatic_pte_interactions::fixtures::{ACCOUNT, GOLD_TOKEN, OTHER_ACCOUNT, VENDOR};
+use programmatic_pte_interactions::utils::decompile;
+
+use radix_engine::model::{Instruction, Transaction};
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::path::PathBuf;
+
+fn component(address: &str) -> ComponentAddress {
+    ComponentAddress::from_str(address).unwrap()
+}
+
+fn gold() -> ResourceAddress {
+    ResourceAddress::from_str(GOLD_TOKEN).unwrap()
+}
+
+/// Withdraws the fees of the vendor and routes them to the target.
+fn route_fees(builder: &mut TransactionBuilder, expected_amount: Option<Decimal>, route_to: RouteTarget) -> &mut TransactionBuilder {
+    builder.call_and_route(component(VENDOR), "withdraw_fees", Args::new(), gold(), expected_amount, route_to)
+}
+
+fn without_nonce(transaction: &Transaction) -> Vec<Instruction> {
+    transaction
+        .instructions
+        .iter()
+        .filter(|x| !matches!(x, Instruction::Nonce { .. }))
+        .cloned()
+        .collect()
+}
+
+/// Checks that the transaction decompiles to the golden manifest, and that the golden manifest
+/// compiles back to the instructions of the transaction.
+fn assert_golden(name: &str, transaction: Transaction) {
+    let path: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/assets/call_and_route")
+        .join(format!("{}.rtm", name));
+    let manifest: String = decompile(&transaction).unwrap();
+    if std::env::var_os("PTE_UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &manifest).unwrap();
+    }
+
+    let golden: String = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(manifest, golden, "{} differs from the golden manifest", name);
+    assert_eq!(
+        without_nonce(&transaction_manifest::compile(&golden).unwrap()),
+        without_nonce(&transaction),
+        "{} doesn't compile back to the transaction",
+        name
+    );
+}
+
+#[test]
+fn deposits_match_the_golden_manifest() {
+    assert_golden(
+        "deposit",
+        route_fees(&mut TransactionBuilder::new(), None, RouteTarget::Deposit(component(ACCOUNT))).build(0),
+    );
+}
+
+#[test]
+fn expected_amounts_are_asserted_and_routed_exactly() {
+    assert_golden(
+        "deposit_expected_amount",
+        route_fees(&mut TransactionBuilder::new(), Some(dec!("10")), RouteTarget::Deposit(component(ACCOUNT))).build(0),
+    );
+}
+
+#[test]
+fn calls_match_the_golden_manifest() {
+    let transaction: Transaction = TransactionBuilder::new()
+        .call_and_route(
+            component(VENDOR),
+            "sell",
+            Args::new().add_string("gold"),
+            gold(),
+            None,
+            RouteTarget::Call {
+                component: component(OTHER_ACCOUNT),
+                method: "restock".to_string(),
+            },
+        )
+        .build(0);
+    assert_golden("call", transaction);
+}
+
+#[test]
+fn burns_match_the_golden_manifest() {
+    assert_golden(
+        "burn",
+        route_fees(
+            &mut TransactionBuilder::new(),
+            None,
+            RouteTarget::Burn {
+                burner: component(OTHER_ACCOUNT),
+            },
+        )
+        .build(0),
+    );
+}
+
+#[test]
+fn chained_routes_get_their_own_buckets() {
+    let mut builder: TransactionBuilder = TransactionBuilder::new();
+    route_fees(&mut builder, None, RouteTarget::Deposit(component(ACCOUNT)));
+    route_fees(
+        &mut builder,
+        Some(dec!("10")),
+        RouteTarget::Burn {
+            burner: component(OTHER_ACCOUNT),
+        },
+    );
+    assert_golden("chained", builder.build(0));
+}
@@ -0,0 +1,8 @@
+//! Misuses of the scoped bucket and proof references which must not compile. The expected errors
+//! are in the `.stderr` file next to each case; `TRYBUILD=overwrite cargo test --test compile_fail`
+//! updates them after a toolchain changes the wording.
+
+#[test]
+fn scoped_references_cannot_be_misused() {
+    trybuild::TestCases::new().compile_fail("tests/compile_fail/*.rs");
+}
@@ -0,0 +1,11 @@
+use programmatic_pte_interactions::args::{call_args, Args};
+use programmatic_pte_interactions::builder::TransactionBuilderExt;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+fn main() {
+    TransactionBuilder::new().take_from_worktop_ref(RADIX_TOKEN, |builder, bucket| {
+        let id: u32 = bucket.id();
+        builder.call_method(SYSTEM_COMPONENT, "deposit", call_args(Args::new().add_value(&id)))
+    });
+}
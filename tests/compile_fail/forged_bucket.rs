@@ -0,0 +1,8 @@
+use programmatic_pte_interactions::args::{call_args, Args, BucketRef};
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+fn main() {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "deposit", call_args(Args::new().add_bucket(BucketRef::new(512))));
+}
@@ -0,0 +1,13 @@
+use programmatic_pte_interactions::args::BucketRef;
+use programmatic_pte_interactions::builder::TransactionBuilderExt;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+fn main() {
+    let mut leaked: Option<BucketRef> = None;
+    TransactionBuilder::new().take_from_worktop_ref(RADIX_TOKEN, |builder, bucket| {
+        leaked = Some(bucket);
+        builder
+    });
+    drop(leaked);
+}
@@ -0,0 +1,56 @@
+//! Component reads against a mock PTE serving the envelopes in `tests/assets/component` and error
+//! statuses.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::component::ComponentInfo;
+use programmatic_pte_interactions::fixtures::ACCOUNT;
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::PteApiError;
+
+use scrypto::prelude::*;
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+fn mock(status: u16, body: &str) -> MockPte {
+    MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/component/", EndpointConfig::new(status, body))
+}
+
+#[test]
+fn served_envelopes_are_parsed() {
+    let mock: MockPte = mock(200, include_str!("assets/component/vendor.json"));
+
+    let info: ComponentInfo = mock.client().get_component(account()).unwrap();
+    assert_eq!(info.address, ACCOUNT);
+    assert_eq!(info.blueprint.blueprint_name, "Vendor");
+    assert_eq!(info.owned_resources.len(), 2);
+    assert!(info.extra_fields.contains_key("metadata"));
+}
+
+#[test]
+fn missing_components_are_not_found() {
+    let mock: MockPte = mock(404, include_str!("assets/component/not_found.txt"));
+
+    assert!(matches!(
+        mock.client().get_component(account()),
+        Err(PteApiError::NotFound(address)) if address == ACCOUNT
+    ));
+}
+
+#[test]
+fn error_statuses_are_unexpected_rather_than_drift() {
+    for status in [400, 403, 500, 503] {
+        let mock: MockPte = mock(status, "the PTE is having a bad day");
+
+        match mock.client().get_component(account()) {
+            Err(PteApiError::UnexpectedStatus { status: reported, body }) => {
+                assert_eq!(reported, status);
+                assert_eq!(body, "the PTE is having a bad day");
+            }
+            other => panic!("expected an unexpected status {}, got {:?}", status, other),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Byte for byte checks of the checked-in conformance outputs. Running the tests with
+//! `PTE_UPDATE_GOLDEN=1` regenerates the outputs.
+#![cfg(feature = "testing")]
+
+use programmatic_pte_interactions::conformance::{check_outputs, load_scenarios, write_outputs, ConformanceScenario};
+
+use std::path::PathBuf;
+
+fn conformance_directory() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("conformance")
+}
+
+#[test]
+fn checked_in_conformance_outputs_match_byte_for_byte() {
+    if std::env::var_os("PTE_UPDATE_GOLDEN").is_some() {
+        write_outputs(&conformance_directory()).unwrap();
+    }
+    assert_eq!(check_outputs(&conformance_directory()).unwrap(), Vec::<String>::new());
+}
+
+#[test]
+fn every_scenario_has_checked_in_outputs() {
+    let directory: PathBuf = conformance_directory();
+    for scenario in load_scenarios(&directory).unwrap() {
+        assert!(directory.join(format!("{}.body.json", scenario.name)).is_file(), "{}", scenario.name);
+        assert!(directory.join(format!("{}.transaction.hex", scenario.name)).is_file(), "{}", scenario.name);
+    }
+}
+
+#[test]
+fn conformance_outputs_are_reproducible() {
+    let scenarios: Vec<ConformanceScenario> = load_scenarios(&conformance_directory()).unwrap();
+    assert!(!scenarios.is_empty());
+    for scenario in scenarios {
+        assert_eq!(scenario.output().unwrap(), scenario.output().unwrap());
+    }
+}
@@ -0,0 +1,123 @@
+//! The deployment verification battery against a mock PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::component::{ComponentEnvelope, OwnedResource};
+use programmatic_pte_interactions::deployment::{verify_deployment, DeploymentReport, StepOutcome};
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, account_envelope, GOLD_TOKEN, PACKAGE};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::test_package;
+use programmatic_pte_interactions::Receipt;
+
+/// The steps which depend on whether the test package is built.
+const PACKAGE_STEPS: [&str; 2] = ["package publish", "package instantiation"];
+
+/// A receipt creating everything the battery looks for, so that it serves every submission.
+fn creating_receipt() -> Receipt {
+    Receipt {
+        new_packages: vec![PACKAGE.to_string()],
+        new_resources: vec![GOLD_TOKEN.to_string()],
+        ..account_creation_receipt()
+    }
+}
+
+/// The envelope of an account funded by the faucet which received one of the battery's token.
+fn funded_envelope() -> ComponentEnvelope {
+    let mut envelope: ComponentEnvelope = account_envelope();
+    envelope.owned_resources.push(OwnedResource {
+        vault_id: Some(format!("{}02000000", "0".repeat(64))),
+        resource_address: GOLD_TOKEN.to_string(),
+        amount: "1".to_string(),
+        non_fungible_ids: None,
+        raw: None,
+    });
+    envelope
+}
+
+fn outcomes(report: &DeploymentReport) -> Vec<(&str, &StepOutcome)> {
+    report
+        .steps
+        .iter()
+        .filter(|x| !PACKAGE_STEPS.contains(&x.name.as_str()))
+        .map(|x| (x.name.as_str(), &x.outcome))
+        .collect()
+}
+
+fn skipped(reason: &str) -> StepOutcome {
+    StepOutcome::Skipped(reason.to_string())
+}
+
+#[test]
+fn a_working_deployment_passes_the_battery() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_epoch(1)
+        .with_endpoint("POST", "/transaction", EndpointConfig::new(200, &serde_json::to_string(&creating_receipt()).unwrap()))
+        .with_endpoint("GET", "/component", EndpointConfig::new(200, &serde_json::to_string(&funded_envelope()).unwrap()));
+
+    let report: DeploymentReport = verify_deployment(&mock.client());
+
+    assert_eq!(
+        outcomes(&report),
+        vec![
+            ("health", &StepOutcome::Passed),
+            ("epoch", &StepOutcome::Passed),
+            ("account creation", &StepOutcome::Passed),
+            ("second account creation", &StepOutcome::Passed),
+            ("faucet funding", &StepOutcome::Passed),
+            ("token creation", &StepOutcome::Passed),
+            ("transfer", &StepOutcome::Passed),
+            (
+                "non-fungible mint and transfer",
+                &skipped("the pinned engine's builder has no non-fungible minting helper")
+            ),
+            ("component state", &StepOutcome::Passed),
+            ("receipt re-fetch", &skipped("the client has no mirror recording receipts")),
+            ("cleanup", &StepOutcome::Passed),
+        ]
+    );
+    if test_package::wasm().is_err() {
+        assert!(report.steps.iter().any(|x| x.name == "package publish" && matches!(x.outcome, StepOutcome::Skipped(_))));
+        assert!(report.is_success(), "{}", report);
+        assert!(report.to_string().ends_with("The deployment works"));
+    }
+}
+
+#[test]
+fn steps_depending_on_a_failed_step_are_skipped() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction", EndpointConfig::new(500, "overloaded"));
+
+    let report: DeploymentReport = verify_deployment(&mock.client());
+
+    let failed = |name: &str| {
+        report
+            .steps
+            .iter()
+            .find(|x| x.name == name)
+            .is_some_and(|x| matches!(x.outcome, StepOutcome::Failed(ref reason) if reason.contains("500")))
+    };
+    assert!(failed("account creation"), "{}", report);
+    assert!(failed("second account creation"), "{}", report);
+    let outcomes: Vec<(&str, &StepOutcome)> = outcomes(&report);
+    assert_eq!(outcomes[0], ("health", &StepOutcome::Passed));
+    assert_eq!(outcomes[1], ("epoch", &skipped("the deployment has no epoch endpoint")));
+    for (name, dependency) in [
+        ("faucet funding", "an account"),
+        ("token creation", "an account"),
+        ("transfer", "two accounts and a token"),
+        ("component state", "an account"),
+        ("cleanup", "two accounts"),
+    ] {
+        assert!(
+            outcomes.contains(&(name, &skipped(&format!("needs {}", dependency)))),
+            "{} is not skipped: {}",
+            name,
+            report
+        );
+    }
+    assert!(outcomes.contains(&("receipt re-fetch", &skipped("the client has no mirror recording receipts"))));
+    assert!(!report.is_success());
+    assert!(report.to_string().ends_with("The deployment is broken"));
+    assert!(report.to_json().contains("\"outcome\": \"failed\""), "{}", report.to_json());
+}
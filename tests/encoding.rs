@@ -0,0 +1,93 @@
+//! Responses of the PTE in a content encoding, served gzip-compressed or merely announced by a
+//! mock PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, account_envelope, private_key, ACCOUNT};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::{PteApiError, Receipt, TransactionSubmissionError};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+/// A mock PTE answering submissions and component reads in the content encoding.
+fn mock(content_encoding: &str) -> MockPte {
+    MockPte::start(0)
+        .unwrap()
+        .with_endpoint(
+            "POST",
+            "/transaction",
+            EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap())
+                .content_encoding(content_encoding),
+        )
+        .with_endpoint(
+            "GET",
+            "/component/",
+            EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap())
+                .content_encoding(content_encoding),
+        )
+}
+
+#[test]
+fn gzip_responses_are_decoded() {
+    let mock: MockPte = mock("gzip");
+    let client: PteClient = mock.client();
+
+    let receipt: Receipt = client.submit_transaction(&transaction()).unwrap();
+    assert_eq!(receipt.transaction_hash, account_creation_receipt().transaction_hash);
+    assert_eq!(receipt.new_components, account_creation_receipt().new_components);
+    assert_eq!(client.get_component(account()).unwrap().address, ACCOUNT);
+}
+
+#[test]
+fn large_gzip_receipts_are_decoded() {
+    let mut large: Receipt = account_creation_receipt();
+    large.logs = (0..5_000).map(|x| format!("log line {} of a large receipt", x)).collect();
+    let mock: MockPte = MockPte::start(0).unwrap().with_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&large).unwrap()).content_encoding("gzip"),
+    );
+
+    assert_eq!(mock.client().submit_transaction(&transaction()).unwrap().logs, large.logs);
+}
+
+#[test]
+fn unsupported_encodings_are_reported_instead_of_failing_to_parse() {
+    let mock: MockPte = mock("x-snappy");
+    let client: PteClient = mock.client();
+
+    assert!(matches!(
+        client.submit_transaction(&transaction()),
+        Err(TransactionSubmissionError::UnsupportedEncoding(encoding)) if encoding == "x-snappy"
+    ));
+    assert!(matches!(
+        client.get_component(account()),
+        Err(PteApiError::UnsupportedEncoding(encoding)) if encoding == "x-snappy"
+    ));
+}
+
+#[cfg(feature = "async-http")]
+#[tokio::test]
+async fn the_async_client_decodes_gzip_responses() {
+    use programmatic_pte_interactions::async_client::AsyncPteClient;
+
+    let mock: MockPte = mock("gzip");
+    let client: AsyncPteClient = AsyncPteClient::new(&mock.url());
+
+    let receipt: Receipt = client.submit_transaction(&transaction()).await.unwrap();
+    assert_eq!(receipt.transaction_hash, account_creation_receipt().transaction_hash);
+    assert_eq!(client.get_component(account()).await.unwrap().address, ACCOUNT);
+}
@@ -0,0 +1,127 @@
+//! Probes of the optional endpoints of the PTE, which must only remember an endpoint as missing
+//! when the server says so.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::circuit::{CircuitBreaker, CircuitConfig};
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, ACCOUNT};
+use programmatic_pte_interactions::hooks::Hooks;
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::readonly::ReadOnlyError;
+use programmatic_pte_interactions::PteApiError;
+
+use scrypto::prelude::*;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn a_server_error_is_not_remembered_as_a_missing_epoch_endpoint() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/epoch", EndpointConfig::new(500, "overloaded"));
+    let client: PteClient = mock.client();
+
+    assert!(matches!(client.supports_epoch(), Err(PteApiError::UnexpectedStatus { status: 500, .. })));
+    assert!(matches!(client.current_epoch(), Err(PteApiError::UnexpectedStatus { status: 500, .. })));
+
+    mock.set_endpoint("GET", "/epoch", EndpointConfig::new(200, "{\"epoch\":5}"));
+    assert!(client.supports_epoch().unwrap());
+    assert_eq!(client.current_epoch().unwrap(), Some(5));
+}
+
+#[test]
+fn a_missing_epoch_endpoint_is_probed_and_reported_once() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let warnings: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+    let counted: Arc<AtomicUsize> = warnings.clone();
+    let client: PteClient = mock.client().with_hooks(Hooks::new().on_warning(move |_| {
+        counted.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    assert_eq!(client.current_epoch().unwrap(), None);
+    assert_eq!(client.current_epoch().unwrap(), None);
+    assert_eq!(mock.request_count("GET", "/epoch"), 1);
+    assert_eq!(warnings.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn a_method_not_allowed_means_the_endpoint_is_missing() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/epoch", EndpointConfig::new(405, "method not allowed"));
+    let client: PteClient = mock.client();
+
+    assert!(!client.supports_epoch().unwrap());
+    assert!(!client.supports_epoch().unwrap());
+    assert_eq!(mock.request_count("GET", "/epoch"), 1);
+}
+
+#[test]
+fn epoch_probes_go_through_the_circuit_breaker() {
+    let circuit_breaker: Arc<CircuitBreaker> = Arc::new(CircuitBreaker::new(CircuitConfig {
+        failure_threshold: 1,
+        cool_down: Duration::from_secs(60),
+    }));
+    let client: PteClient = PteClient::new("http://127.0.0.1:1").with_circuit_breaker(circuit_breaker.clone());
+
+    assert!(matches!(client.supports_epoch(), Err(PteApiError::HttpRequestError(_))));
+    assert_eq!(circuit_breaker.metrics().consecutive_failures, 1);
+    assert!(matches!(client.supports_epoch(), Err(PteApiError::CircuitOpen(_))));
+    assert_eq!(circuit_breaker.metrics().rejected, 1);
+}
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+#[test]
+fn a_server_error_is_not_remembered_as_a_missing_preview_endpoint() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction/preview", EndpointConfig::new(503, "overloaded"));
+    let client: PteClient = mock.client();
+
+    assert!(matches!(
+        client.call_method_readonly(account(), "balance", vec![], None),
+        Err(ReadOnlyError::ApiError(PteApiError::UnexpectedStatus { status: 503, .. }))
+    ));
+
+    mock.set_endpoint(
+        "POST",
+        "/transaction/preview",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap()),
+    );
+    assert!(client.supports_preview().unwrap());
+}
+
+#[test]
+fn a_missing_preview_endpoint_is_probed_once() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client();
+
+    for _ in 0..2 {
+        assert!(matches!(
+            client.call_method_readonly(account(), "balance", vec![], None),
+            Err(ReadOnlyError::ReadOnlyUnsupported)
+        ));
+    }
+    assert_eq!(mock.request_count("POST", "/transaction/preview"), 1);
+}
+
+#[test]
+fn a_rejected_preview_is_an_error_rather_than_a_receipt() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction/preview", EndpointConfig::new(400, "invalid manifest"));
+    let client: PteClient = mock.client();
+
+    match client.call_method_readonly(account(), "balance", vec![], None) {
+        Err(ReadOnlyError::ApiError(PteApiError::UnexpectedStatus { status, body })) => {
+            assert_eq!((status, body.as_str()), (400, "invalid manifest"))
+        }
+        other => panic!("expected the status of the preview, got {:?}", other),
+    }
+    assert_eq!(mock.request_count("POST", "/transaction/preview"), 2);
+}
@@ -0,0 +1,160 @@
+//! The classification, exit code and HTTP status of each variant of `TransactionSubmissionError`.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::circuit::CircuitOpen;
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::compatibility::{compatibility_info, ManifestDialectMismatch};
+use programmatic_pte_interactions::fixtures::{private_key, ACCOUNT};
+use programmatic_pte_interactions::mock::{EndpointConfig, LatencyDistribution, MockPte};
+use programmatic_pte_interactions::queue::QueueFull;
+use programmatic_pte_interactions::scope::{ScopeViolation, ScopeViolationKind};
+use programmatic_pte_interactions::utils::decompile;
+use programmatic_pte_interactions::{ErrorClass, TransactionSubmissionError};
+
+use radix_engine::model::{Instruction, SignedTransaction, Transaction};
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::time::Duration;
+
+/// The class, exit code and HTTP status each error is expected to map to. The match is exhaustive,
+/// so a new variant fails to compile here until its mappings are decided, and it must then be
+/// given an example in `errors`.
+fn expected(error: &TransactionSubmissionError) -> (ErrorClass, i32, u16) {
+    match error {
+        TransactionSubmissionError::NoNonceFound => (ErrorClass::UserError, 65, 400),
+        TransactionSubmissionError::MultipleNonceFound => (ErrorClass::UserError, 65, 400),
+        TransactionSubmissionError::DecompileError(_) => (ErrorClass::InternalBug, 70, 500),
+        TransactionSubmissionError::HttpRequestError(error) if error.is_timeout() => {
+            (ErrorClass::TransientServerError, 69, 504)
+        }
+        TransactionSubmissionError::HttpRequestError(_) => (ErrorClass::TransientServerError, 69, 502),
+        TransactionSubmissionError::QueueFull(_) => (ErrorClass::TransientServerError, 75, 503),
+        TransactionSubmissionError::ManifestDialectMismatch(_) => (ErrorClass::PermanentServerError, 76, 502),
+        TransactionSubmissionError::Rejected { status: 429, .. } => (ErrorClass::TransientServerError, 75, 503),
+        TransactionSubmissionError::Rejected { status: 404, .. } => (ErrorClass::UserError, 65, 404),
+        TransactionSubmissionError::Rejected { status: 400, .. } => (ErrorClass::UserError, 65, 400),
+        TransactionSubmissionError::Rejected { status, .. } => {
+            assert!(*status >= 500, "no expectation for a rejection with status {}", status);
+            (ErrorClass::TransientServerError, 69, 502)
+        }
+        TransactionSubmissionError::IdempotencyNotConfigured => (ErrorClass::InternalBug, 78, 500),
+        TransactionSubmissionError::IdempotencyStoreError(_) => (ErrorClass::TransientServerError, 74, 503),
+        TransactionSubmissionError::CircuitOpen(_) => (ErrorClass::TransientServerError, 75, 503),
+        TransactionSubmissionError::UnsupportedEncoding(_) => (ErrorClass::PermanentServerError, 76, 502),
+        TransactionSubmissionError::NoDefaultSigner => (ErrorClass::InternalBug, 78, 500),
+        TransactionSubmissionError::ScopeViolation(_) => (ErrorClass::UserError, 65, 403),
+        TransactionSubmissionError::NoServers => (ErrorClass::InternalBug, 78, 500),
+    }
+}
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+fn rejected(status: u16) -> TransactionSubmissionError {
+    TransactionSubmissionError::Rejected {
+        status,
+        body: "rejected".to_string(),
+    }
+}
+
+/// A submission to a mock PTE which answers slower than the client waits.
+fn timed_out() -> TransactionSubmissionError {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, "{}").latency(LatencyDistribution::Fixed(Duration::from_secs(2))),
+    );
+    reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap()
+        .post(format!("{}/transaction", mock.url()))
+        .send()
+        .unwrap_err()
+        .into()
+}
+
+/// An error of each variant, and of each range of statuses of `Rejected`.
+fn errors() -> Vec<TransactionSubmissionError> {
+    let unknown_bucket: Transaction = Transaction {
+        instructions: vec![Instruction::ReturnToWorktop { bucket_id: 512 }],
+    };
+    vec![
+        TransactionSubmissionError::NoNonceFound,
+        TransactionSubmissionError::MultipleNonceFound,
+        decompile(&unknown_bucket).unwrap_err().into(),
+        reqwest::blocking::get("http://127.0.0.1:1").unwrap_err().into(),
+        timed_out(),
+        QueueFull { max_depth: 8 }.into(),
+        TransactionSubmissionError::ManifestDialectMismatch(ManifestDialectMismatch {
+            rejected_line: Some("CALL_METHOD".to_string()),
+            server_message: "CompileError".to_string(),
+            compatibility: compatibility_info(),
+        }),
+        rejected(400),
+        rejected(404),
+        rejected(429),
+        rejected(500),
+        rejected(503),
+        TransactionSubmissionError::IdempotencyNotConfigured,
+        TransactionSubmissionError::IdempotencyStoreError(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "read-only",
+        )),
+        TransactionSubmissionError::CircuitOpen(CircuitOpen {
+            retry_after: Duration::from_secs(5),
+        }),
+        TransactionSubmissionError::UnsupportedEncoding("br".to_string()),
+        TransactionSubmissionError::NoDefaultSigner,
+        TransactionSubmissionError::ScopeViolation(Box::new(ScopeViolation {
+            index: 0,
+            instruction: "CALL_METHOD".to_string(),
+            kind: ScopeViolationKind::WithdrawalNotAllowed(ComponentAddress::from_str(ACCOUNT).unwrap()),
+            label: None,
+        })),
+        TransactionSubmissionError::NoServers,
+    ]
+}
+
+#[test]
+fn every_variant_maps_to_its_class_exit_code_and_status() {
+    for error in errors() {
+        let (class, exit_code, status) = expected(&error);
+        assert_eq!(error.classification(), class, "classification of {:?}", error);
+        assert_eq!(error.exit_code(), exit_code, "exit code of {:?}", error);
+        assert_eq!(error.http_status(), status, "HTTP status of {:?}", error);
+    }
+}
+
+#[test]
+fn timeouts_are_gateway_timeouts() {
+    let error: TransactionSubmissionError = timed_out();
+    assert!(matches!(&error, TransactionSubmissionError::HttpRequestError(error) if error.is_timeout()));
+    assert_eq!(error.http_status(), 504);
+}
+
+#[test]
+fn exit_codes_follow_sysexits() {
+    for error in errors() {
+        assert!((64..=78).contains(&error.exit_code()), "exit code of {:?}", error);
+    }
+}
+
+#[test]
+fn a_client_without_servers_fails_with_no_servers() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client().with_write_urls(&[]);
+
+    let error: TransactionSubmissionError = client.submit_transaction(&transaction()).unwrap_err();
+    assert!(matches!(error, TransactionSubmissionError::NoServers));
+    assert_eq!(error.classification(), ErrorClass::InternalBug);
+    assert_eq!(error.exit_code(), 78);
+    assert_eq!(error.http_status(), 500);
+    assert_eq!(mock.request_count("POST", "/transaction"), 0);
+}
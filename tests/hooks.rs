@@ -0,0 +1,207 @@
+//! Ordering and isolation of the hooks of the client, and the built-in hooks, against a mock PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::clock::TestClock;
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, failed_transfer_receipt, private_key};
+use programmatic_pte_interactions::hooks::{ClientWarning, Hooks, SubmissionFailure};
+use programmatic_pte_interactions::mirror::Mirror;
+use programmatic_pte_interactions::mock::{EndpointConfig, LatencyDistribution, MockPte};
+use programmatic_pte_interactions::{Receipt, TransactionSubmissionError};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+/// Hooks appending the events they see to the log, in the order they ran.
+fn logging_hooks(log: &Arc<Mutex<Vec<String>>>) -> Hooks {
+    let (submitted, committed, failed, completed) = (log.clone(), log.clone(), log.clone(), log.clone());
+    Hooks::new()
+        .on_submitted(move |_, _| submitted.lock().unwrap().push("submitted".to_string()))
+        .on_committed(move |_| committed.lock().unwrap().push("committed".to_string()))
+        .on_failed(move |failure| {
+            let event: &str = match failure {
+                SubmissionFailure::Receipt(_) => "failed receipt",
+                SubmissionFailure::Error(_) => "failed error",
+            };
+            failed.lock().unwrap().push(event.to_string())
+        })
+        .on_completed(move |_| completed.lock().unwrap().push("completed".to_string()))
+}
+
+fn log() -> Arc<Mutex<Vec<String>>> {
+    Arc::new(Mutex::new(Vec::new()))
+}
+
+fn events(log: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+    log.lock().unwrap().clone()
+}
+
+#[test]
+fn committed_submissions_run_the_hooks_in_order() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let log: Arc<Mutex<Vec<String>>> = log();
+    let client: PteClient = mock.client().with_hooks(logging_hooks(&log));
+
+    client.submit_transaction(&transaction()).unwrap();
+    assert_eq!(events(&log), vec!["submitted", "committed", "completed"]);
+}
+
+#[test]
+fn failed_receipts_and_errors_run_the_failed_hooks() {
+    let mock: MockPte = MockPte::start(0).unwrap().with_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&failed_transfer_receipt()).unwrap()),
+    );
+    let log: Arc<Mutex<Vec<String>>> = log();
+    let client: PteClient = mock.client().with_hooks(logging_hooks(&log));
+
+    client.submit_transaction(&transaction()).unwrap();
+    mock.set_endpoint("POST", "/transaction", EndpointConfig::new(400, "bad request"));
+    assert!(matches!(
+        client.submit_transaction(&transaction()),
+        Err(TransactionSubmissionError::Rejected { status: 400, .. })
+    ));
+
+    assert_eq!(
+        events(&log),
+        vec!["submitted", "failed receipt", "completed", "submitted", "failed error", "completed"]
+    );
+}
+
+#[test]
+fn hooks_of_an_event_run_in_registration_order_across_registrations() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let log: Arc<Mutex<Vec<String>>> = log();
+    let (first, second, third) = (log.clone(), log.clone(), log.clone());
+    let client: PteClient = mock
+        .client()
+        .with_hooks(Hooks::new().on_committed(move |_| first.lock().unwrap().push("first".to_string())))
+        .with_hooks(
+            Hooks::new()
+                .on_committed(move |_| second.lock().unwrap().push("second".to_string()))
+                .on_committed(move |_| third.lock().unwrap().push("third".to_string())),
+        );
+
+    client.submit_transaction(&transaction()).unwrap();
+    assert_eq!(events(&log), vec!["first", "second", "third"]);
+}
+
+#[test]
+fn a_panicking_hook_affects_neither_the_submission_nor_the_other_hooks() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let log: Arc<Mutex<Vec<String>>> = log();
+    let warnings: Arc<Mutex<Vec<ClientWarning>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorded: Arc<Mutex<Vec<ClientWarning>>> = warnings.clone();
+    let client: PteClient = mock.client().with_hooks(
+        Hooks::new()
+            .on_submitted(|_, _| panic!("the submitted hook failed"))
+            .on_committed(|_| panic!("the committed hook failed"))
+            .on_warning(|_| panic!("the warning hook failed"))
+            .on_warning(move |warning| recorded.lock().unwrap().push(warning.clone()))
+            .extend(logging_hooks(&log)),
+    );
+
+    assert!(client.submit_transaction(&transaction()).unwrap().is_success());
+    assert_eq!(events(&log), vec!["submitted", "committed", "completed"]);
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        vec![
+            ClientWarning::HookPanicked {
+                event: "on_submitted".to_string()
+            },
+            ClientWarning::HookPanicked {
+                event: "on_committed".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn completed_hooks_see_the_duration_on_the_clock_of_the_client() {
+    let clock: Arc<TestClock> = Arc::new(TestClock::new());
+    let mock: MockPte = MockPte::start(0).unwrap().with_clock(clock.clone());
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap())
+            .latency(LatencyDistribution::Fixed(Duration::from_secs(3))),
+    );
+    let durations: Arc<Mutex<Vec<Duration>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorded: Arc<Mutex<Vec<Duration>>> = durations.clone();
+    let client: PteClient = mock
+        .client()
+        .with_clock(clock)
+        .with_hooks(Hooks::new().on_completed(move |completion| recorded.lock().unwrap().push(completion.duration)));
+
+    client.submit_transaction(&transaction()).unwrap();
+    assert_eq!(*durations.lock().unwrap(), vec![Duration::from_secs(3)]);
+}
+
+#[test]
+fn the_mirror_records_receipts_through_its_built_in_hooks() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let mirror: Arc<Mirror> = Arc::new(Mirror::new(Duration::from_secs(60)));
+    let client: PteClient = mock
+        .client()
+        .with_mirror(mirror.clone())
+        .with_hooks(Hooks::new().on_committed(|_| panic!("a user hook failed")));
+
+    let receipt: Receipt = client.submit_transaction(&transaction()).unwrap();
+    assert_eq!(mirror.get_receipt(&receipt.transaction_hash).unwrap().transaction_hash, receipt.transaction_hash);
+
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&failed_transfer_receipt()).unwrap()),
+    );
+    let failed: Receipt = client.submit_transaction(&transaction()).unwrap();
+    assert!(mirror.get_receipt(&failed.transaction_hash).is_some());
+}
+
+#[test]
+fn the_counters_are_built_in_hooks() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let (hooks, counters) = Hooks::new().with_counters();
+    let client: PteClient = mock.client().with_hooks(hooks);
+
+    client.submit_transaction(&transaction()).unwrap();
+    mock.set_endpoint("POST", "/transaction", EndpointConfig::new(503, "overloaded"));
+    client.submit_transaction(&transaction()).unwrap_err();
+
+    assert_eq!(counters.submitted.load(Ordering::SeqCst), 2);
+    assert_eq!(counters.committed.load(Ordering::SeqCst), 1);
+    assert_eq!(counters.failed.load(Ordering::SeqCst), 1);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn reports_are_recorded_by_a_built_in_hook() {
+    use programmatic_pte_interactions::report::BatchReport;
+
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let report: Arc<Mutex<BatchReport>> = Arc::new(Mutex::new(BatchReport::new()));
+    let client: PteClient = mock.client().with_hooks(Hooks::new().with_report("faucet", report.clone()));
+
+    client.submit_transaction(&transaction()).unwrap();
+    mock.set_endpoint("POST", "/transaction", EndpointConfig::new(400, "bad request"));
+    client.submit_transaction(&transaction()).unwrap_err();
+
+    let report = report.lock().unwrap();
+    assert_eq!(report.entries.len(), 2);
+    assert!(report.entries.iter().all(|entry| entry.label == "faucet" && entry.faucet_calls == 1));
+    assert!(report.entries[0].outcome.is_ok());
+    assert!(report.entries[1].outcome.is_err());
+}
@@ -0,0 +1,137 @@
+//! Idempotency keys against a mock PTE, with concurrent calls sharing a key.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, failed_transfer_receipt, private_key};
+use programmatic_pte_interactions::idempotency::{Idempotency, IdempotencyStore, MemoryIdempotencyStore};
+use programmatic_pte_interactions::mock::{EndpointConfig, LatencyDistribution, MockPte};
+use programmatic_pte_interactions::queue::ExecuteOptions;
+use programmatic_pte_interactions::{Receipt, TransactionSubmissionError};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn transaction(nonce: u64) -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(nonce)
+        .sign([&private_key()])
+}
+
+fn client(mock: &MockPte) -> Arc<PteClient> {
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap())
+            .latency(LatencyDistribution::Fixed(Duration::from_millis(50))),
+    );
+    Arc::new(mock.client().with_idempotency(Idempotency::new(MemoryIdempotencyStore::default())))
+}
+
+#[test]
+fn concurrent_calls_with_the_same_key_submit_once() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: Arc<PteClient> = client(&mock);
+
+    let handles: Vec<thread::JoinHandle<Receipt>> = (0..8)
+        .map(|nonce| {
+            let client: Arc<PteClient> = client.clone();
+            thread::spawn(move || {
+                client
+                    .execute_idempotent("payout-1", &transaction(nonce), &ExecuteOptions::new())
+                    .unwrap()
+            })
+        })
+        .collect();
+    let receipts: Vec<Receipt> = handles.into_iter().map(|x| x.join().unwrap()).collect();
+
+    assert_eq!(mock.request_count("POST", "/transaction"), 1);
+    assert!(receipts.iter().all(|x| x.transaction_hash == receipts[0].transaction_hash));
+    assert_eq!(client.idempotency().unwrap().locked_keys(), 0);
+}
+
+#[test]
+fn the_locks_of_many_keys_are_not_kept() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: Arc<PteClient> = client(&mock);
+
+    for index in 0..20 {
+        client
+            .execute_idempotent(&format!("payout-{}", index), &transaction(index), &ExecuteOptions::new())
+            .unwrap();
+    }
+    assert_eq!(mock.request_count("POST", "/transaction"), 20);
+    assert_eq!(client.idempotency().unwrap().locked_keys(), 0);
+}
+
+#[test]
+fn a_key_whose_submission_failed_is_retried_and_then_replayed() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: Arc<PteClient> = client(&mock);
+    let execute = || client.execute_idempotent("payout-1", &transaction(1), &ExecuteOptions::new());
+
+    mock.set_endpoint("POST", "/transaction", EndpointConfig::new(503, "overloaded"));
+    assert!(matches!(execute(), Err(TransactionSubmissionError::Rejected { status: 503, .. })));
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&failed_transfer_receipt()).unwrap()),
+    );
+    assert!(!execute().unwrap().is_success());
+
+    // Neither failure recorded the key, so the retry is submitted and then replayed.
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap()),
+    );
+    let committed: Receipt = execute().unwrap();
+    assert_eq!(committed.transaction_hash, account_creation_receipt().transaction_hash);
+    assert_eq!(mock.request_count("POST", "/transaction"), 3);
+
+    assert_eq!(execute().unwrap().transaction_hash, committed.transaction_hash);
+    assert_eq!(mock.request_count("POST", "/transaction"), 3);
+}
+
+/// A store whose reads or writes fail.
+struct FailingStore {
+    fail_reads: bool,
+}
+
+impl IdempotencyStore for FailingStore {
+    fn get(&self, _: &str) -> std::io::Result<Option<Receipt>> {
+        match self.fail_reads {
+            true => Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "unreadable")),
+            false => Ok(None),
+        }
+    }
+
+    fn put(&self, _: &str, _: &Receipt) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "read-only"))
+    }
+}
+
+#[test]
+fn store_failures_are_store_errors() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    client(&mock);
+
+    let unreadable: PteClient = mock.client().with_idempotency(Idempotency::new(FailingStore { fail_reads: true }));
+    assert!(matches!(
+        unreadable.execute_idempotent("payout-1", &transaction(1), &ExecuteOptions::new()),
+        Err(TransactionSubmissionError::IdempotencyStoreError(_))
+    ));
+    assert_eq!(mock.request_count("POST", "/transaction"), 0);
+
+    let read_only: PteClient = mock.client().with_idempotency(Idempotency::new(FailingStore { fail_reads: false }));
+    assert!(matches!(
+        read_only.execute_idempotent("payout-1", &transaction(1), &ExecuteOptions::new()),
+        Err(TransactionSubmissionError::IdempotencyStoreError(_))
+    ));
+    assert_eq!(mock.request_count("POST", "/transaction"), 1);
+}
@@ -0,0 +1,77 @@
+//! Labels given to addresses show up in the errors of the lookups they are passed to.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::{ACCOUNT, GOLD_TOKEN};
+use programmatic_pte_interactions::labeled::{AddressSource, LabeledAddress};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::readonly::{ReadOnlyError, StateHint};
+use programmatic_pte_interactions::vaults::PageOptions;
+use programmatic_pte_interactions::PteApiError;
+
+use scrypto::prelude::*;
+
+const NOT_FOUND: &str = "treasury (02c1d7…)";
+
+fn treasury() -> LabeledAddress<ComponentAddress> {
+    LabeledAddress::new(
+        ComponentAddress::from_str(ACCOUNT).unwrap(),
+        "treasury",
+        AddressSource::AddressBook { path: None },
+    )
+}
+
+fn client(mock: &MockPte) -> PteClient {
+    mock.set_endpoint("GET", "/component/", EndpointConfig::new(404, "not found"));
+    mock.client()
+}
+
+fn not_found<T: std::fmt::Debug>(outcome: Result<T, PteApiError>) -> String {
+    match outcome {
+        Err(PteApiError::NotFound(address)) => address,
+        other => panic!("expected the component not to be found, got {:?}", other),
+    }
+}
+
+#[test]
+fn vault_and_balance_lookups_report_the_label() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = client(&mock);
+
+    assert_eq!(not_found(client.get_vaults(treasury())), NOT_FOUND);
+    assert_eq!(not_found(client.get_balances(treasury())), NOT_FOUND);
+    assert_eq!(not_found(client.get_vaults_all(treasury(), 10)), NOT_FOUND);
+    assert_eq!(not_found(client.get_vaults_page(treasury(), &PageOptions::new(10))), NOT_FOUND);
+}
+
+#[test]
+fn registry_and_read_only_lookups_report_the_label() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = client(&mock);
+
+    assert_eq!(not_found(client.resolve_name(treasury(), "alice")), NOT_FOUND);
+    let state_hint: StateHint = &|_| Ok(Vec::new());
+    match client.call_method_readonly(treasury(), "balance", vec![], Some(state_hint)) {
+        Err(ReadOnlyError::ApiError(PteApiError::NotFound(address))) => assert_eq!(address, NOT_FOUND),
+        other => panic!("expected the component not to be found, got {:?}", other),
+    }
+}
+
+#[test]
+fn resource_lookups_report_the_label() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/resource/", EndpointConfig::new(404, "not found"));
+    let gold: LabeledAddress<ResourceAddress> =
+        LabeledAddress::new(ResourceAddress::from_str(GOLD_TOKEN).unwrap(), "gold", AddressSource::CliArg);
+
+    assert_eq!(not_found(mock.client().get_resource(gold)), "gold (03ca97…)");
+}
+
+#[test]
+fn plain_addresses_are_still_accepted_and_reported_raw() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = client(&mock);
+
+    assert_eq!(not_found(client.get_balances(ComponentAddress::from_str(ACCOUNT).unwrap())), ACCOUNT);
+}
@@ -0,0 +1,170 @@
+//! The outbox worker against a mock PTE, including a worker which dies part way through its entries
+//! and is restarted on the same stores.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::clock::TestClock;
+use programmatic_pte_interactions::fixtures::private_key;
+use programmatic_pte_interactions::hooks::{ClientWarning, Hooks};
+use programmatic_pte_interactions::idempotency::{FileIdempotencyStore, Idempotency, MemoryIdempotencyStore};
+use programmatic_pte_interactions::manifest_spec::ManifestSpec;
+use programmatic_pte_interactions::mock::MockPte;
+use programmatic_pte_interactions::outbox::{
+    FileOutboxStore, MemoryOutboxStore, Outbox, OutboxEntry, OutboxState, OutboxStore, OutboxWorker, WorkerStatus,
+};
+use programmatic_pte_interactions::TransactionSubmissionError;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const ENTRIES: u64 = 6;
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+fn temporary_file(name: &str) -> PathBuf {
+    let path: PathBuf = std::env::temp_dir().join(format!("pte-outbox-{}-{}.json", name, std::process::id()));
+    std::fs::remove_file(&path).ok();
+    path
+}
+
+/// An outbox store which stops saving once `saves_left` is used up, like a process which dies after
+/// submitting an entry but before recording its outcome.
+struct CrashingStore {
+    inner: FileOutboxStore,
+    saves_left: AtomicUsize,
+}
+
+impl OutboxStore for CrashingStore {
+    fn load(&self) -> std::io::Result<Vec<OutboxEntry>> {
+        self.inner.load()
+    }
+
+    fn save(&self, entries: &[OutboxEntry]) -> std::io::Result<()> {
+        if self.saves_left.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |x| x.checked_sub(1)).is_err() {
+            return Err(std::io::Error::new(std::io::ErrorKind::Other, "the process died"));
+        }
+        self.inner.save(entries)
+    }
+}
+
+/// A client persisting its idempotency keys to the given file, as a restarted process would.
+fn client(mock: &MockPte, idempotency: &Path, warnings: &Arc<Mutex<Vec<ClientWarning>>>) -> Arc<PteClient> {
+    let warnings: Arc<Mutex<Vec<ClientWarning>>> = warnings.clone();
+    Arc::new(
+        mock.client()
+            .with_idempotency(Idempotency::new(FileIdempotencyStore::new(idempotency)))
+            .with_hooks(Hooks::new().on_warning(move |warning| warnings.lock().unwrap().push(warning.clone()))),
+    )
+}
+
+fn wait_for(condition: impl Fn() -> bool) {
+    let started_at: Instant = Instant::now();
+    while !condition() {
+        assert!(started_at.elapsed() < Duration::from_secs(30), "timed out waiting for the worker");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn no_entry_is_lost_or_committed_twice_when_the_worker_dies() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let (outbox_file, idempotency_file): (PathBuf, PathBuf) = (temporary_file("entries"), temporary_file("idempotency"));
+    let warnings: Arc<Mutex<Vec<ClientWarning>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let outbox: Arc<Outbox> = Arc::new(Outbox::new(FileOutboxStore::new(&outbox_file), 3));
+    for index in 0..ENTRIES {
+        outbox.enqueue(ManifestSpec::NewAccount, BTreeMap::new(), &format!("signup-{}", index)).unwrap();
+    }
+
+    // The first worker records the outcomes of two entries and dies while recording the third.
+    let dying: Arc<Outbox> = Arc::new(Outbox::new(
+        CrashingStore {
+            inner: FileOutboxStore::new(&outbox_file),
+            saves_left: AtomicUsize::new(2),
+        },
+        3,
+    ));
+    let worker: OutboxWorker = OutboxWorker::spawn(dying, client(&mock, &idempotency_file, &warnings), private_key(), POLL_INTERVAL).unwrap();
+    wait_for(|| worker.status().store_errors > 0);
+    let status: WorkerStatus = worker.shutdown(false);
+    assert_eq!(status.committed, 2);
+    assert!(!status.running);
+    assert_eq!(outbox.pending().unwrap().len() as u64, ENTRIES - 2);
+    assert!(mock.request_count("POST", "/transaction") > 2);
+    assert!(matches!(warnings.lock().unwrap()[0], ClientWarning::OutboxStoreFailed { entry: Some(2), .. }));
+
+    // The restarted worker finds the committed keys of the entries submitted before the crash.
+    let worker: OutboxWorker = OutboxWorker::spawn(outbox.clone(), client(&mock, &idempotency_file, &warnings), private_key(), POLL_INTERVAL).unwrap();
+    wait_for(|| outbox.pending().unwrap().is_empty());
+    let status: WorkerStatus = worker.shutdown(true);
+    assert_eq!(status.committed, ENTRIES - 2);
+    assert_eq!(status.store_errors, 0);
+
+    let entries: Vec<OutboxEntry> = outbox.entries().unwrap();
+    assert_eq!(entries.len() as u64, ENTRIES);
+    assert!(entries.iter().all(|x| x.state == OutboxState::Committed && x.receipt.is_some()));
+    assert_eq!(mock.request_count("POST", "/transaction"), ENTRIES);
+
+    std::fs::remove_file(&outbox_file).ok();
+    std::fs::remove_file(&idempotency_file).ok();
+}
+
+#[test]
+fn the_worker_requires_idempotency() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let outbox: Arc<Outbox> = Arc::new(Outbox::new(MemoryOutboxStore::default(), 3));
+    assert!(matches!(
+        OutboxWorker::spawn(outbox, Arc::new(mock.client()), private_key(), POLL_INTERVAL),
+        Err(TransactionSubmissionError::IdempotencyNotConfigured)
+    ));
+}
+
+#[test]
+fn entries_which_can_not_be_built_are_dead_lettered() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let outbox: Arc<Outbox> = Arc::new(Outbox::new(MemoryOutboxStore::default(), 3));
+    let spec: ManifestSpec = ManifestSpec::NewToken {
+        account: "not an account".to_string(),
+        symbol: "TKN".to_string(),
+        supply: "100".to_string(),
+    };
+    let id: u64 = outbox.enqueue(spec, BTreeMap::new(), "token").unwrap();
+
+    let client: Arc<PteClient> = Arc::new(mock.client().with_idempotency(Idempotency::new(MemoryIdempotencyStore::default())));
+    let worker: OutboxWorker = OutboxWorker::spawn(outbox.clone(), client, private_key(), POLL_INTERVAL).unwrap();
+    wait_for(|| worker.status().dead_lettered == 1);
+    worker.shutdown(false);
+
+    let dead_letters: Vec<OutboxEntry> = outbox.dead_letters().unwrap();
+    assert_eq!((dead_letters.len(), dead_letters[0].attempts), (1, 1));
+    assert_eq!(dead_letters[0].last_error.as_deref(), Some("invalid account"));
+    assert_eq!(mock.request_count("POST", "/transaction"), 0);
+    assert!(outbox.requeue_dead_letter(id).unwrap());
+    assert_eq!(outbox.pending().unwrap().len(), 1);
+}
+
+#[test]
+fn an_idle_worker_waits_for_a_test_clock_instead_of_advancing_it() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let clock: Arc<TestClock> = Arc::new(TestClock::new());
+    let outbox: Arc<Outbox> = Arc::new(Outbox::new(MemoryOutboxStore::default(), 3));
+    let client: Arc<PteClient> = Arc::new(
+        mock.client()
+            .with_clock(clock.clone())
+            .with_idempotency(Idempotency::new(MemoryIdempotencyStore::default())),
+    );
+    let worker: OutboxWorker = OutboxWorker::spawn(outbox.clone(), client, private_key(), Duration::from_secs(60)).unwrap();
+
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(clock.elapsed(), Duration::ZERO);
+
+    outbox.enqueue(ManifestSpec::NewAccount, BTreeMap::new(), "signup").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(worker.status().committed, 0);
+
+    clock.advance(Duration::from_secs(60));
+    wait_for(|| worker.status().committed == 1);
+    assert!(!worker.shutdown(false).running);
+}
@@ -0,0 +1,158 @@
+//! Ordering of submissions in the submission queue, with the single in-flight slot of the queue held
+//! so that the waiting submissions pile up before any of them is dispatched.
+
+use programmatic_pte_interactions::queue::{ExecuteOptions, Priority, QueueFull, SubmissionQueue};
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Five submissions from one account followed by two from another.
+const SKEWED_LOAD: [&str; 7] = ["whale", "whale", "whale", "whale", "whale", "minnow", "minnow"];
+const FAIR_ORDER: [&str; 7] = ["whale", "minnow", "whale", "minnow", "whale", "whale", "whale"];
+
+/// Occupies the in-flight slot of the queue until something is sent on the returned channel.
+fn hold_slot(queue: &Arc<SubmissionQueue>) -> (JoinHandle<()>, Sender<()>) {
+    let (release, released): (Sender<()>, Receiver<()>) = channel();
+    let (started, has_started): (Sender<()>, Receiver<()>) = channel();
+    let queue: Arc<SubmissionQueue> = queue.clone();
+    let holder: JoinHandle<()> = std::thread::spawn(move || {
+        queue
+            .run(&ExecuteOptions::new().fairness_key("holder"), || {
+                started.send(()).unwrap();
+                released.recv().unwrap();
+            })
+            .unwrap();
+    });
+    has_started.recv().unwrap();
+    (holder, release)
+}
+
+fn wait_for_depth(queue: &SubmissionQueue, depth: usize) {
+    while queue.metrics().depth < depth {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn submit(queue: &Arc<SubmissionQueue>, options: ExecuteOptions, order: &Arc<Mutex<Vec<String>>>, label: &str) -> JoinHandle<()> {
+    let (queue, order, label): (Arc<SubmissionQueue>, Arc<Mutex<Vec<String>>>, String) = (queue.clone(), order.clone(), label.to_string());
+    std::thread::spawn(move || queue.run(&options, || order.lock().unwrap().push(label)).unwrap())
+}
+
+#[test]
+fn a_burst_from_one_key_does_not_starve_the_others() {
+    let queue: Arc<SubmissionQueue> = Arc::new(SubmissionQueue::new(100, 1));
+    let order: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let (holder, release) = hold_slot(&queue);
+
+    let mut submissions: Vec<JoinHandle<()>> = Vec::new();
+    for (index, key) in SKEWED_LOAD.into_iter().enumerate() {
+        submissions.push(submit(&queue, ExecuteOptions::new().fairness_key(key), &order, key));
+        wait_for_depth(&queue, index + 1);
+    }
+    release.send(()).unwrap();
+    holder.join().unwrap();
+    submissions.into_iter().for_each(|x| x.join().unwrap());
+
+    assert_eq!(*order.lock().unwrap(), FAIR_ORDER);
+    assert_eq!(queue.metrics().dispatched, 8);
+    assert_eq!(queue.metrics().max_depth_observed, 7);
+}
+
+#[test]
+fn higher_priorities_are_dispatched_first() {
+    let queue: Arc<SubmissionQueue> = Arc::new(SubmissionQueue::new(100, 1));
+    let order: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let (holder, release) = hold_slot(&queue);
+
+    let mut submissions: Vec<JoinHandle<()>> = Vec::new();
+    for (index, priority) in [Priority::Low, Priority::Normal, Priority::High].into_iter().enumerate() {
+        submissions.push(submit(&queue, ExecuteOptions::new().priority(priority), &order, &format!("{:?}", priority)));
+        wait_for_depth(&queue, index + 1);
+    }
+    release.send(()).unwrap();
+    holder.join().unwrap();
+    submissions.into_iter().for_each(|x| x.join().unwrap());
+
+    assert_eq!(*order.lock().unwrap(), ["High", "Normal", "Low"]);
+}
+
+#[test]
+fn submissions_beyond_the_depth_are_rejected() {
+    let queue: Arc<SubmissionQueue> = Arc::new(SubmissionQueue::new(1, 1));
+    let order: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let (holder, release) = hold_slot(&queue);
+
+    let waiting: JoinHandle<()> = submit(&queue, ExecuteOptions::new(), &order, "waiting");
+    wait_for_depth(&queue, 1);
+    assert_eq!(queue.run(&ExecuteOptions::new(), || ()), Err(QueueFull { max_depth: 1 }));
+
+    release.send(()).unwrap();
+    holder.join().unwrap();
+    waiting.join().unwrap();
+    assert_eq!(queue.metrics().rejected, 1);
+    assert_eq!(queue.metrics().dispatched, 2);
+}
+
+#[test]
+fn a_panicking_submission_frees_its_slot() {
+    let queue: Arc<SubmissionQueue> = Arc::new(SubmissionQueue::new(100, 1));
+    let panicking: Arc<SubmissionQueue> = queue.clone();
+    assert!(std::thread::spawn(move || panicking.run(&ExecuteOptions::new(), || panic!("dispatch failed")))
+        .join()
+        .is_err());
+
+    assert_eq!(queue.run(&ExecuteOptions::new(), || 1), Ok(1));
+    assert_eq!(queue.metrics().depth, 0);
+}
+
+#[cfg(feature = "async-http")]
+#[tokio::test]
+async fn async_submissions_are_dispatched_fairly() {
+    let queue: Arc<SubmissionQueue> = Arc::new(SubmissionQueue::new(100, 1));
+    let order: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let (holder, release) = hold_slot(&queue);
+
+    let mut submissions: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+    for (index, key) in SKEWED_LOAD.into_iter().enumerate() {
+        let (queue, order): (Arc<SubmissionQueue>, Arc<Mutex<Vec<String>>>) = (queue.clone(), order.clone());
+        submissions.push(tokio::spawn(async move {
+            queue
+                .run_async(&ExecuteOptions::new().fairness_key(key), move || async move {
+                    order.lock().unwrap().push(key.to_string())
+                })
+                .await
+                .unwrap()
+        }));
+        while queue.metrics().depth < index + 1 {
+            tokio::task::yield_now().await;
+        }
+    }
+    release.send(()).unwrap();
+    for submission in submissions {
+        submission.await.unwrap();
+    }
+    holder.join().unwrap();
+
+    assert_eq!(*order.lock().unwrap(), FAIR_ORDER);
+}
+
+#[cfg(feature = "async-http")]
+#[tokio::test]
+async fn async_submissions_given_up_on_leave_the_queue() {
+    let queue: Arc<SubmissionQueue> = Arc::new(SubmissionQueue::new(100, 1));
+    let (holder, release) = hold_slot(&queue);
+
+    tokio::select! {
+        biased;
+        _ = queue.run_async(&ExecuteOptions::new(), || async {}) => panic!("dispatched while the slot was held"),
+        _ = async {} => {}
+    }
+    assert_eq!(queue.metrics().depth, 0);
+
+    release.send(()).unwrap();
+    holder.join().unwrap();
+    queue.run_async(&ExecuteOptions::new(), || async {}).await.unwrap();
+    assert_eq!(queue.metrics().dispatched, 2);
+}
@@ -0,0 +1,97 @@
+//! The string-only helpers for scripts, against a mock PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::fixtures::{
+    account_creation_receipt, account_envelope, failed_transfer_receipt, ACCOUNT, OTHER_ACCOUNT,
+};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::quick::{self, QuickError};
+use programmatic_pte_interactions::{PteApiError, Receipt};
+
+use scrypto::prelude::*;
+
+fn receipt_endpoint(receipt: &Receipt) -> EndpointConfig {
+    EndpointConfig::new(200, &serde_json::to_string(receipt).unwrap())
+}
+
+fn key_hex() -> String {
+    "01".repeat(32)
+}
+
+#[test]
+fn accounts_are_created_with_a_new_key() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction", receipt_endpoint(&account_creation_receipt()));
+
+    let (key, address) = quick::create_account(&mock.url()).unwrap();
+
+    assert_eq!(address, ACCOUNT);
+    let key_bytes: Vec<u8> = hex::decode(&key).unwrap();
+    assert!(EcdsaPrivateKey::from_bytes(&key_bytes).is_ok());
+    assert_eq!(mock.request_count("POST", "/transaction"), 1);
+}
+
+#[test]
+fn transfers_return_the_hash_of_the_transaction() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction", receipt_endpoint(&account_creation_receipt()));
+
+    let hash: String = quick::transfer(&mock.url(), &format!("0x{}", key_hex()), ACCOUNT, OTHER_ACCOUNT, "100").unwrap();
+
+    assert_eq!(hash, account_creation_receipt().transaction_hash);
+    let bodies: Vec<String> = mock.request_bodies("POST", "/transaction");
+    assert_eq!(bodies.len(), 1);
+    assert!(bodies[0].contains("withdraw_by_amount"), "{}", bodies[0]);
+    assert!(bodies[0].contains(ACCOUNT) && bodies[0].contains(OTHER_ACCOUNT), "{}", bodies[0]);
+}
+
+#[test]
+fn failed_transfers_return_the_receipt() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction", receipt_endpoint(&failed_transfer_receipt()));
+
+    match quick::transfer(&mock.url(), &key_hex(), ACCOUNT, OTHER_ACCOUNT, "100") {
+        Err(QuickError::TransactionFailed(receipt)) => assert_eq!(receipt.transaction_hash, failed_transfer_receipt().transaction_hash),
+        other => panic!("expected a failed transaction, got {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_arguments_are_rejected_before_submitting() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let key: String = key_hex();
+
+    for (key, from, amount) in [("not a key", ACCOUNT, "100"), (key.as_str(), "alice", "100"), (key.as_str(), ACCOUNT, "lots")] {
+        assert!(matches!(
+            quick::transfer(&mock.url(), key, from, OTHER_ACCOUNT, amount),
+            Err(QuickError::InvalidArgument(_))
+        ));
+    }
+    assert!(matches!(quick::balance(&mock.url(), "alice"), Err(QuickError::InvalidArgument(_))));
+    assert_eq!(mock.connections(), 0);
+}
+
+#[test]
+fn balances_are_listed_as_strings() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/component", EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap()));
+
+    assert_eq!(
+        quick::balance(&mock.url(), ACCOUNT).unwrap(),
+        vec![(RADIX_TOKEN.to_string(), "1000000".to_string())]
+    );
+}
+
+#[test]
+fn missing_accounts_are_reported() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+
+    assert!(matches!(
+        quick::balance(&mock.url(), ACCOUNT),
+        Err(QuickError::Api(PteApiError::NotFound(_)))
+    ));
+}
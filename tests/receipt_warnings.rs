@@ -0,0 +1,143 @@
+//! The warnings of receipts, and their delivery to the warning hooks of the client.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::extensions::ReceiptExtension;
+use programmatic_pte_interactions::fixtures::{
+    account_creation_receipt, duplicate_components_receipt, inconsistent_lists_receipt, private_key, GOLD_TOKEN,
+    SILVER_TOKEN, VENDOR,
+};
+use programmatic_pte_interactions::hooks::{ClientWarning, Hooks};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::{Receipt, ReceiptWarning};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::sync::{Arc, Mutex};
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+/// A client against a mock PTE answering submissions with the receipt, and the warnings its hooks
+/// received.
+fn client_returning(mock: &MockPte, receipt: &Receipt) -> (PteClient, Arc<Mutex<Vec<ClientWarning>>>) {
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(receipt).unwrap()),
+    );
+    let warnings: Arc<Mutex<Vec<ClientWarning>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorded: Arc<Mutex<Vec<ClientWarning>>> = warnings.clone();
+    let client: PteClient = mock
+        .client()
+        .with_hooks(Hooks::new().on_warning(move |warning| recorded.lock().unwrap().push(warning.clone())));
+    (client, warnings)
+}
+
+#[derive(Debug)]
+struct StateRoot(String);
+
+fn decode_state_root(extra: &serde_json::Map<String, serde_json::Value>) -> Result<StateRoot, String> {
+    extra
+        .get("state_root")
+        .and_then(|value| value.as_str())
+        .map(|value| StateRoot(value.to_string()))
+        .ok_or_else(|| "no state_root".to_string())
+}
+
+#[test]
+fn consistent_receipts_have_no_warnings() {
+    assert_eq!(account_creation_receipt().warnings(), Vec::new());
+}
+
+#[test]
+fn duplicate_entries_are_reported_and_removed_by_the_accessors() {
+    let receipt: Receipt = duplicate_components_receipt();
+
+    assert_eq!(
+        receipt.warnings(),
+        vec![ReceiptWarning::DuplicateEntry {
+            list: "new_components".to_string(),
+            address: VENDOR.to_string(),
+            occurrences: 2,
+        }]
+    );
+    assert_eq!(receipt.new_components(), vec![ComponentAddress::from_str(VENDOR).unwrap()]);
+    assert_eq!(receipt.new_components, vec![VENDOR.to_string(), VENDOR.to_string()]);
+    assert_eq!(
+        receipt.new_resources(),
+        vec![
+            ResourceAddress::from_str(GOLD_TOKEN).unwrap(),
+            ResourceAddress::from_str(SILVER_TOKEN).unwrap()
+        ]
+    );
+}
+
+#[test]
+fn addresses_in_more_than_one_list_are_reported() {
+    assert_eq!(
+        inconsistent_lists_receipt().warnings(),
+        vec![ReceiptWarning::InconsistentLists {
+            address: GOLD_TOKEN.to_string(),
+            lists: vec!["new_components".to_string(), "new_resources".to_string()],
+        }]
+    );
+}
+
+#[test]
+fn extensions_failing_to_decode_are_reported() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let (client, warnings) = client_returning(&mock, &account_creation_receipt());
+    let client: PteClient = client.with_receipt_extension(ReceiptExtension::new("state_root", decode_state_root));
+
+    let receipt: Receipt = client.submit_transaction(&transaction()).unwrap();
+    let warning: ReceiptWarning = ReceiptWarning::ExtensionDecodeFailed {
+        extension: "state_root".to_string(),
+        error: "\"no state_root\"".to_string(),
+    };
+    assert!(receipt.extension::<StateRoot>().is_none());
+    assert_eq!(receipt.warnings(), vec![warning.clone()]);
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        vec![ClientWarning::InconsistentReceipt {
+            transaction_hash: receipt.transaction_hash.clone(),
+            warning,
+        }]
+    );
+}
+
+#[test]
+fn the_warnings_of_received_receipts_reach_the_warning_hooks() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+
+    for receipt in [duplicate_components_receipt(), inconsistent_lists_receipt()] {
+        let (client, warnings) = client_returning(&mock, &receipt);
+        client.submit_transaction(&transaction()).unwrap();
+
+        let expected: Vec<ClientWarning> = receipt
+            .warnings()
+            .into_iter()
+            .map(|warning| ClientWarning::InconsistentReceipt {
+                transaction_hash: receipt.transaction_hash.clone(),
+                warning,
+            })
+            .collect();
+        assert_eq!(expected.len(), 1);
+        assert_eq!(*warnings.lock().unwrap(), expected);
+    }
+}
+
+#[test]
+fn consistent_receipts_raise_no_warnings() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let (client, warnings) = client_returning(&mock, &account_creation_receipt());
+
+    client.submit_transaction(&transaction()).unwrap();
+    assert!(warnings.lock().unwrap().is_empty());
+}
@@ -0,0 +1,64 @@
+//! Parse-only checks of manifests against the validation endpoint of a mock PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::compatibility::{compatibility_info, RecompileCheckError};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::utils::decompile;
+use programmatic_pte_interactions::PteApiError;
+
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+fn manifest() -> String {
+    decompile(
+        &TransactionBuilder::new()
+            .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+            .build(1),
+    )
+    .unwrap()
+}
+
+#[test]
+fn decompiled_manifests_pass_the_check() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client();
+
+    client.recompile_check(&manifest()).unwrap();
+    assert_eq!(mock.request_count("POST", "/transaction/validate"), 1);
+    assert_eq!(mock.request_count("POST", "/transaction"), 0);
+}
+
+#[test]
+fn manifests_the_compiler_refuses_are_rejected() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client();
+
+    match client.recompile_check(&format!("{}\nNOT_AN_INSTRUCTION;", manifest())) {
+        Err(RecompileCheckError::Rejected(mismatch)) => assert_eq!(mismatch.compatibility, compatibility_info()),
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+}
+
+#[test]
+fn servers_without_the_endpoint_are_unsupported() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction/validate", EndpointConfig::new(404, "not found"));
+    let client: PteClient = mock.client();
+
+    assert!(matches!(client.recompile_check(&manifest()), Err(RecompileCheckError::Unsupported)));
+}
+
+#[test]
+fn server_errors_are_not_taken_for_a_missing_endpoint() {
+    let mock: MockPte = MockPte::start(0)
+        .unwrap()
+        .with_endpoint("POST", "/transaction/validate", EndpointConfig::new(500, "overloaded"));
+    let client: PteClient = mock.client();
+
+    assert!(matches!(
+        client.recompile_check(&manifest()),
+        Err(RecompileCheckError::HttpRequestError(PteApiError::UnexpectedStatus { status: 500, .. }))
+    ));
+}
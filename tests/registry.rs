@@ -0,0 +1,108 @@
+//! Registering and resolving names against a mock PTE serving the state of a registry component.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::component::ComponentEnvelope;
+use programmatic_pte_interactions::fixtures::{account_envelope, private_key, ACCOUNT, GOLD_TOKEN, OTHER_ACCOUNT, VENDOR};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::PteApiError;
+
+use sbor::{Encode, TypeId};
+use scrypto::buffer::scrypto_encode;
+use scrypto::prelude::*;
+
+/// The state of the registry blueprint, as it is encoded on ledger.
+#[derive(TypeId, Encode)]
+struct NameRegistry {
+    names: HashMap<String, (ComponentAddress, ResourceAddress)>,
+}
+
+fn component(address: &str) -> ComponentAddress {
+    ComponentAddress::from_str(address).unwrap()
+}
+
+fn registry_envelope(state: &str) -> String {
+    let envelope: ComponentEnvelope = ComponentEnvelope {
+        state: state.to_string(),
+        ..account_envelope()
+    };
+    serde_json::to_string(&envelope).unwrap()
+}
+
+/// A mock PTE whose components are all the registry, with `vendor` registered.
+fn mock() -> MockPte {
+    let mut names: HashMap<String, (ComponentAddress, ResourceAddress)> = HashMap::new();
+    names.insert(
+        "vendor".to_string(),
+        (component(VENDOR), ResourceAddress::from_str(GOLD_TOKEN).unwrap()),
+    );
+    let state: String = hex::encode(scrypto_encode(&NameRegistry { names }));
+    MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/component/", EndpointConfig::new(200, &registry_envelope(&state)))
+}
+
+#[test]
+fn registered_names_resolve_to_their_target() {
+    let mock: MockPte = mock();
+    let client: PteClient = mock.client();
+
+    assert_eq!(client.resolve_name(component(ACCOUNT), "vendor").unwrap(), Some(component(VENDOR)));
+    assert_eq!(client.resolve_name(component(ACCOUNT), "nobody").unwrap(), None);
+    assert_eq!(mock.request_count("GET", "/component/"), 2);
+}
+
+#[test]
+fn missing_registries_are_not_found() {
+    let mock: MockPte = mock();
+    mock.set_endpoint("GET", "/component/", EndpointConfig::new(404, "not found"));
+
+    assert!(matches!(
+        mock.client().resolve_name(component(ACCOUNT), "vendor"),
+        Err(PteApiError::NotFound(_))
+    ));
+}
+
+#[test]
+fn components_which_are_not_registries_have_an_invalid_state() {
+    let mock: MockPte = mock();
+    mock.set_endpoint(
+        "GET",
+        "/component/",
+        EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap()),
+    );
+
+    assert!(matches!(
+        mock.client().resolve_name(component(ACCOUNT), "vendor"),
+        Err(PteApiError::InvalidState(_))
+    ));
+}
+
+#[test]
+fn registering_submits_a_call_with_a_proof_of_the_owner_badge() {
+    let mock: MockPte = mock();
+
+    let receipt = mock
+        .client()
+        .register_name(
+            component(ACCOUNT),
+            "vendor",
+            component(VENDOR),
+            ResourceAddress::from_str(GOLD_TOKEN).unwrap(),
+            component(OTHER_ACCOUNT),
+            &private_key(),
+        )
+        .unwrap();
+    assert!(receipt.is_success());
+
+    let bodies: Vec<String> = mock.request_bodies("POST", "/transaction");
+    assert_eq!(bodies.len(), 1);
+    let manifest: String = serde_json::from_str::<serde_json::Value>(&bodies[0]).unwrap()["manifest"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(manifest.contains("\"create_proof\""));
+    assert!(manifest.contains("\"register\""));
+    assert!(manifest.contains("\"vendor\""));
+    assert!(manifest.contains(ACCOUNT) && manifest.contains(OTHER_ACCOUNT) && manifest.contains(GOLD_TOKEN));
+}
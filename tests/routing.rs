@@ -0,0 +1,121 @@
+//! Routing of reads and submissions to their configured servers, against mock PTEs.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::{PoolConfig, PteClient};
+use programmatic_pte_interactions::fixtures::{private_key, ACCOUNT};
+use programmatic_pte_interactions::mock::{EndpointConfig, LatencyDistribution, MockPte};
+use programmatic_pte_interactions::{PteApiError, TransactionSubmissionError};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::time::Duration;
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+fn mock() -> MockPte {
+    MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/component/", EndpointConfig::new(404, "not found"))
+}
+
+#[test]
+fn reads_and_submissions_go_to_their_own_servers() {
+    let (reads, writes) = (mock(), mock());
+    let client: PteClient = PteClient::new(&writes.url()).with_read_urls(&[&reads.url()]);
+
+    assert!(matches!(client.get_component(account()), Err(PteApiError::NotFound(_))));
+    client.submit_transaction(&transaction()).unwrap();
+
+    assert_eq!(reads.request_count("GET", "/component/"), 1);
+    assert_eq!(reads.request_count("POST", "/transaction"), 0);
+    assert_eq!(writes.request_count("GET", "/component/"), 0);
+    assert_eq!(writes.request_count("POST", "/transaction"), 1);
+}
+
+#[test]
+fn reads_fail_over_to_the_next_server() {
+    let reads = mock();
+    let client: PteClient = PteClient::new(&reads.url()).with_read_urls(&["http://127.0.0.1:1", &reads.url()]);
+
+    assert!(matches!(client.get_component(account()), Err(PteApiError::NotFound(_))));
+    assert_eq!(reads.request_count("GET", "/component/"), 1);
+}
+
+#[test]
+fn an_empty_list_of_read_servers_is_an_error_rather_than_a_panic() {
+    let client: PteClient = PteClient::new("http://127.0.0.1:1").with_read_urls(&[]);
+    assert!(matches!(client.get_component(account()), Err(PteApiError::NoServers)));
+    assert!(matches!(client.supports_epoch(), Err(PteApiError::NoServers)));
+}
+
+#[test]
+fn an_empty_list_of_write_servers_is_an_error_rather_than_a_panic() {
+    let client: PteClient = PteClient::new("http://127.0.0.1:1").with_write_urls(&[]);
+    assert_eq!(client.base_url(), "");
+    assert!(matches!(
+        client.submit_transaction(&transaction()),
+        Err(TransactionSubmissionError::NoServers)
+    ));
+}
+
+#[test]
+fn submissions_fail_over_when_the_server_can_not_be_reached() {
+    let writes = mock();
+    let client: PteClient = PteClient::new(&writes.url()).with_write_urls(&["http://127.0.0.1:1", &writes.url()]);
+
+    client.submit_transaction(&transaction()).unwrap();
+    assert_eq!(writes.request_count("POST", "/transaction"), 1);
+}
+
+#[test]
+fn submissions_which_may_have_been_received_are_not_sent_again() {
+    let (slow, other) = (mock(), mock());
+    slow.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, "{}").latency(LatencyDistribution::Fixed(Duration::from_secs(2))),
+    );
+    let client: PteClient = PteClient::new(&slow.url())
+        .with_write_urls(&[&slow.url(), &other.url()])
+        .with_pool_config(PoolConfig {
+            request_timeout: Some(Duration::from_millis(200)),
+            ..PoolConfig::default()
+        });
+
+    assert!(matches!(
+        client.submit_transaction(&transaction()),
+        Err(TransactionSubmissionError::HttpRequestError(error)) if error.is_timeout()
+    ));
+    assert_eq!(slow.request_count("POST", "/transaction"), 1);
+    assert_eq!(other.request_count("POST", "/transaction"), 0);
+}
+
+#[test]
+fn reads_fail_over_after_a_timeout() {
+    let (slow, other) = (mock(), mock());
+    slow.set_endpoint(
+        "GET",
+        "/component/",
+        EndpointConfig::new(404, "not found").latency(LatencyDistribution::Fixed(Duration::from_secs(2))),
+    );
+    let client: PteClient = PteClient::new(&slow.url())
+        .with_read_urls(&[&slow.url(), &other.url()])
+        .with_pool_config(PoolConfig {
+            request_timeout: Some(Duration::from_millis(200)),
+            ..PoolConfig::default()
+        });
+
+    assert!(matches!(client.get_component(account()), Err(PteApiError::NotFound(_))));
+    assert_eq!(other.request_count("GET", "/component/"), 1);
+}
@@ -0,0 +1,173 @@
+//! Selection of the first healthy server and its stickiness across runs, against mock PTEs.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::{PteClient, ServerSelection};
+use programmatic_pte_interactions::fixtures::{private_key, ACCOUNT};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::{PteApiError, TransactionSubmissionError};
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::path::{Path, PathBuf};
+
+/// A server which is down.
+const DOWN: &str = "http://127.0.0.1:1";
+
+fn mock() -> MockPte {
+    MockPte::start(0)
+        .unwrap()
+        .with_endpoint("GET", "/component/", EndpointConfig::new(404, "not found"))
+}
+
+fn session_file(name: &str) -> PathBuf {
+    let path: PathBuf = std::env::temp_dir().join(format!("pte-server-selection-{}-{}.json", name, std::process::id()));
+    std::fs::remove_file(&path).ok();
+    path
+}
+
+fn recorded(session: &Path) -> Option<String> {
+    ServerSelection::load(session).unwrap().map(|x| x.server)
+}
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+#[test]
+fn the_first_healthy_server_is_selected_and_recorded() {
+    let up: MockPte = mock();
+    let session: PathBuf = session_file("selected");
+
+    let client: PteClient = PteClient::first_healthy_in_session(&[DOWN, &up.url()], false, &session)
+        .unwrap()
+        .unwrap();
+    assert_eq!(client.base_url(), up.url());
+    assert_eq!(recorded(&session), Some(up.url()));
+}
+
+#[test]
+fn later_runs_stay_on_the_recorded_server() {
+    let (first, second) = (mock(), mock());
+    let session: PathBuf = session_file("sticky");
+    ServerSelection { server: second.url() }.save(&session).unwrap();
+
+    let client: PteClient = PteClient::first_healthy_in_session(&[&first.url(), &second.url()], false, &session)
+        .unwrap()
+        .unwrap();
+    assert_eq!(client.base_url(), second.url());
+    assert_eq!(recorded(&session), Some(second.url()));
+}
+
+#[test]
+fn a_recorded_server_which_is_down_is_not_replaced_without_failover() {
+    let up: MockPte = mock();
+    let session: PathBuf = session_file("no-failover");
+    ServerSelection { server: DOWN.to_string() }.save(&session).unwrap();
+
+    assert!(PteClient::first_healthy_in_session(&[DOWN, &up.url()], false, &session)
+        .unwrap()
+        .is_none());
+    assert_eq!(recorded(&session), Some(DOWN.to_string()));
+}
+
+#[test]
+fn a_recorded_server_which_is_down_stays_the_write_server_with_failover() {
+    let up: MockPte = mock();
+    let session: PathBuf = session_file("failover");
+    ServerSelection { server: DOWN.to_string() }.save(&session).unwrap();
+
+    let client: PteClient = PteClient::first_healthy_in_session(&[DOWN, &up.url()], true, &session)
+        .unwrap()
+        .unwrap();
+    assert_eq!(client.base_url(), DOWN);
+    assert_eq!(recorded(&session), Some(DOWN.to_string()));
+
+    // Reads fail over to the healthy candidate, while submissions stay on the server of the session.
+    assert!(matches!(client.get_component(account()), Err(PteApiError::NotFound(_))));
+    assert!(matches!(
+        client.submit_transaction(&transaction()),
+        Err(TransactionSubmissionError::HttpRequestError(_))
+    ));
+    assert_eq!(up.request_count("GET", "/component/"), 1);
+    assert_eq!(up.request_count("POST", "/transaction"), 0);
+}
+
+#[test]
+fn no_client_is_returned_when_no_candidate_is_healthy() {
+    let session: PathBuf = session_file("all-down");
+    ServerSelection { server: DOWN.to_string() }.save(&session).unwrap();
+
+    assert!(PteClient::first_healthy_in_session(&[DOWN], true, &session).unwrap().is_none());
+}
+
+#[test]
+fn servers_answering_with_an_error_are_not_healthy() {
+    let (failing, up) = (mock(), mock());
+    failing.set_endpoint("GET", "/", EndpointConfig::new(503, "overloaded"));
+    let session: PathBuf = session_file("unhealthy");
+
+    let client: PteClient = PteClient::first_healthy_in_session(&[&failing.url(), &up.url()], false, &session)
+        .unwrap()
+        .unwrap();
+    assert_eq!(client.base_url(), up.url());
+    assert_eq!(failing.request_count("GET", "/"), 1);
+    assert_eq!(
+        client.health_check().into_iter().map(|(_, _, healthy)| healthy).collect::<Vec<bool>>(),
+        vec![true, true]
+    );
+    assert!(PteClient::new(&failing.url()).health_check().iter().all(|(_, _, healthy)| !healthy));
+}
+
+#[test]
+fn reads_only_fail_over_mid_run_when_allowed() {
+    let (first, second) = (mock(), mock());
+    let candidates: Vec<String> = vec![first.url(), second.url()];
+    let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+    let sticky: PteClient = PteClient::first_healthy(&candidates, false).unwrap();
+    let failing_over: PteClient = PteClient::first_healthy(&candidates, true).unwrap();
+    assert_eq!(sticky.base_url(), first.url());
+    assert_eq!(failing_over.base_url(), first.url());
+
+    drop(first);
+    assert!(matches!(sticky.get_component(account()), Err(PteApiError::HttpRequestError(_))));
+    assert!(matches!(failing_over.get_component(account()), Err(PteApiError::NotFound(_))));
+    assert_eq!(second.request_count("GET", "/component/"), 1);
+}
+
+#[test]
+fn warming_up_connects_to_every_server_once_despite_failures() {
+    let (first, second, writer): (MockPte, MockPte, MockPte) = (mock(), mock(), mock());
+    let client: PteClient = PteClient::new(&writer.url())
+        .with_read_urls(&[&first.url(), DOWN, &second.url(), &writer.url()])
+        .with_write_urls(&[&writer.url(), DOWN]);
+
+    let failures: Vec<(String, PteApiError)> = client.warm_up();
+    assert_eq!(failures.iter().map(|(url, _)| url.as_str()).collect::<Vec<&str>>(), vec![DOWN]);
+    assert_eq!(
+        (first.connections(), second.connections(), writer.connections()),
+        (1, 1, 1)
+    );
+}
+
+#[test]
+fn servers_whose_domain_does_not_resolve_are_reported_when_warming_up() {
+    const UNRESOLVABLE: &str = "http://pte.invalid";
+    let server: MockPte = mock();
+    let client: PteClient = PteClient::new(&server.url()).with_read_urls(&[UNRESOLVABLE, &server.url()]);
+
+    let failures: Vec<(String, PteApiError)> = client.warm_up();
+    assert_eq!(failures.iter().map(|(url, _)| url.as_str()).collect::<Vec<&str>>(), vec![UNRESOLVABLE]);
+    assert_eq!(server.connections(), 1);
+
+    // The client still reaches the server it warmed up.
+    assert!(client.submit_transaction(&transaction()).is_ok());
+}
@@ -0,0 +1,173 @@
+//! Resolving `DepositTarget::SignerAccount` into the only known account the signer controls, against
+//! a mock PTE serving the accounts.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::access_rules::virtual_badge;
+use programmatic_pte_interactions::address_book::AddressBook;
+use programmatic_pte_interactions::bootstrap::{BootstrapAccount, BootstrapSession};
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::component::ComponentEnvelope;
+use programmatic_pte_interactions::deterministic::Randomness;
+use programmatic_pte_interactions::fixtures::{account_envelope, private_key, ACCOUNT, OTHER_ACCOUNT, VENDOR};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::signer_account::{DepositTarget, SignerAccountError, SignerAccountResolver};
+use programmatic_pte_interactions::test_vectors::TEST_VECTORS_MODE;
+
+use scrypto::prelude::*;
+use serde_json::json;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A key other than `private_key`.
+fn other_key() -> EcdsaPrivateKey {
+    let mut randomness: Randomness = Randomness::new(TEST_VECTORS_MODE);
+    randomness.new_private_key();
+    randomness.new_private_key()
+}
+
+/// The envelope of an account whose withdraw rule requires the virtual badge of the key.
+fn account_of(key: &EcdsaPrivateKey) -> ComponentEnvelope {
+    let badge: String = virtual_badge(&key.public_key()).to_string();
+    let mut envelope: ComponentEnvelope = account_envelope();
+    envelope.authorization[0]["default_auth"] = json!({ "Protected": { "ProofRule": { "Require": badge } } });
+    envelope
+}
+
+/// A mock PTE serving each account with the envelope of the key controlling it.
+fn mock(accounts: &[(&str, &EcdsaPrivateKey)]) -> MockPte {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    for (address, key) in accounts {
+        mock.set_endpoint(
+            "GET",
+            &format!("/component/{}", address),
+            EndpointConfig::new(200, &serde_json::to_string(&account_of(key)).unwrap()),
+        );
+    }
+    mock
+}
+
+fn address(address: &str) -> ComponentAddress {
+    ComponentAddress::from_str(address).unwrap()
+}
+
+fn resolver(accounts: &[&str]) -> SignerAccountResolver {
+    accounts
+        .iter()
+        .fold(SignerAccountResolver::new(), |resolver, account| resolver.with_account(address(account)))
+}
+
+#[test]
+fn the_only_account_of_the_signer_is_resolved_and_cached() {
+    let other_key: EcdsaPrivateKey = other_key();
+    let mock: MockPte = mock(&[(ACCOUNT, &private_key()), (OTHER_ACCOUNT, &other_key)]);
+    let client: PteClient = mock
+        .client()
+        .with_signer_account_resolver(Arc::new(resolver(&[ACCOUNT, OTHER_ACCOUNT])));
+
+    for _ in 0..3 {
+        assert_eq!(
+            client.resolve_deposit_target(&DepositTarget::SignerAccount, &private_key().public_key()).unwrap(),
+            address(ACCOUNT)
+        );
+    }
+    assert_eq!(
+        client.resolve_deposit_target(&DepositTarget::SignerAccount, &other_key.public_key()).unwrap(),
+        address(OTHER_ACCOUNT)
+    );
+
+    // The accounts are checked once for each of the two keys.
+    assert_eq!(mock.request_count("GET", &format!("/component/{}", ACCOUNT)), 2);
+    assert_eq!(mock.request_count("GET", &format!("/component/{}", OTHER_ACCOUNT)), 2);
+}
+
+#[test]
+fn a_signer_controlling_several_accounts_is_ambiguous() {
+    let mock: MockPte = mock(&[(ACCOUNT, &private_key()), (OTHER_ACCOUNT, &private_key()), (VENDOR, &other_key())]);
+    let client: PteClient = mock
+        .client()
+        .with_signer_account_resolver(Arc::new(resolver(&[ACCOUNT, OTHER_ACCOUNT, VENDOR])));
+
+    match client.resolve_deposit_target(&DepositTarget::SignerAccount, &private_key().public_key()) {
+        Err(SignerAccountError::Ambiguous { public_key, candidates }) => {
+            assert_eq!(public_key, hex::encode(private_key().public_key().to_vec()));
+            assert_eq!(candidates, vec![ACCOUNT.to_string(), OTHER_ACCOUNT.to_string()]);
+        }
+        other => panic!("expected the signer to be ambiguous, got {:?}", other),
+    }
+
+    // Ambiguity isn't cached, so the accounts are checked again.
+    client
+        .resolve_deposit_target(&DepositTarget::SignerAccount, &private_key().public_key())
+        .unwrap_err();
+    assert_eq!(mock.request_count("GET", &format!("/component/{}", ACCOUNT)), 2);
+}
+
+#[test]
+fn a_signer_controlling_none_of_the_accounts_names_those_checked() {
+    let mock: MockPte = mock(&[(ACCOUNT, &other_key()), (OTHER_ACCOUNT, &other_key())]);
+    let client: PteClient = mock
+        .client()
+        .with_signer_account_resolver(Arc::new(resolver(&[ACCOUNT, OTHER_ACCOUNT])));
+
+    match client.resolve_deposit_target(&DepositTarget::SignerAccount, &private_key().public_key()) {
+        Err(SignerAccountError::NoAccount { checked, .. }) => {
+            assert_eq!(checked, vec![ACCOUNT.to_string(), OTHER_ACCOUNT.to_string()]);
+        }
+        other => panic!("expected no account, got {:?}", other),
+    }
+
+    let empty: PteClient = mock.client().with_signer_account_resolver(Arc::new(SignerAccountResolver::new()));
+    assert!(matches!(
+        empty.resolve_deposit_target(&DepositTarget::SignerAccount, &private_key().public_key()),
+        Err(SignerAccountError::NoAccount { checked, .. }) if checked.is_empty()
+    ));
+}
+
+#[test]
+fn explicit_targets_need_no_resolver() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client();
+
+    assert_eq!(
+        client
+            .resolve_deposit_target(&address(OTHER_ACCOUNT).into(), &private_key().public_key())
+            .unwrap(),
+        address(OTHER_ACCOUNT)
+    );
+    assert!(matches!(
+        client.resolve_deposit_target(&DepositTarget::SignerAccount, &private_key().public_key()),
+        Err(SignerAccountError::NoResolver)
+    ));
+    assert_eq!(mock.request_count("GET", "/component/"), 0);
+}
+
+#[test]
+fn resolvers_are_built_from_address_books_and_sessions() {
+    let mut book: AddressBook = AddressBook::new();
+    book.insert("alice", ACCOUNT);
+    book.insert("bob", OTHER_ACCOUNT);
+    book.insert("alice again", ACCOUNT);
+    book.insert("package", "01bda8686d6c2fa45dce04fac71a09b54efbc8028c23aac74bc00e");
+    let from_book: SignerAccountResolver = SignerAccountResolver::from_address_book(&book);
+    assert_eq!(from_book.accounts().len(), 2);
+    assert_eq!(
+        from_book.accounts().iter().copied().collect::<HashSet<ComponentAddress>>(),
+        HashSet::from([address(ACCOUNT), address(OTHER_ACCOUNT)])
+    );
+
+    let account = |label: &str, address: Option<&str>| BootstrapAccount {
+        label: label.to_string(),
+        key_file: format!("keys/{}.key", label),
+        address: address.map(str::to_string),
+    };
+    let session: BootstrapSession = BootstrapSession {
+        token_symbol: "DEMO".to_string(),
+        token_supply: "1000000".to_string(),
+        accounts: vec![account("account-1", Some(ACCOUNT)), account("account-2", None)],
+        token: None,
+        distributed: false,
+        last_error: None,
+    };
+    assert_eq!(SignerAccountResolver::from_session(&session).accounts(), [address(ACCOUNT)]);
+}
@@ -0,0 +1,63 @@
+//! Client-side tags, which are recorded in reports but never sent to the PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::private_key;
+use programmatic_pte_interactions::mock::MockPte;
+use programmatic_pte_interactions::queue::ExecuteOptions;
+
+use radix_engine::model::SignedTransaction;
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+fn transaction() -> SignedTransaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(1)
+        .sign([&private_key()])
+}
+
+fn onboarding() -> ExecuteOptions {
+    ExecuteOptions::new().tag("feature", "onboarding").tag("ticket", "ABC-123")
+}
+
+#[test]
+fn tags_are_never_sent_to_the_pte() {
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client();
+
+    client.execute(&transaction(), &ExecuteOptions::new()).unwrap();
+    client.execute(&transaction(), &onboarding()).unwrap();
+
+    let bodies: Vec<String> = mock.request_bodies("POST", "/transaction");
+    assert_eq!(bodies.len(), 2);
+    assert_eq!(bodies[0], bodies[1]);
+    assert!(!bodies[1].contains("onboarding"));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn tags_group_the_transactions_of_reports() {
+    use programmatic_pte_interactions::report::{timed, BatchReport, RunReport, TagSummary};
+    use std::collections::BTreeMap;
+
+    let mock: MockPte = MockPte::start(0).unwrap();
+    let client: PteClient = mock.client();
+    let mut batch: BatchReport = BatchReport::new();
+
+    for (label, options) in [("signup", onboarding()), ("login", ExecuteOptions::new().tag("feature", "onboarding"))] {
+        timed(&mut batch, label, &options, &transaction(), |transaction, options| client.execute(transaction, options)).unwrap();
+    }
+    timed(&mut batch, "untagged", &ExecuteOptions::new(), &transaction(), |transaction, options| {
+        client.execute(transaction, options)
+    })
+    .unwrap();
+
+    let report: RunReport = RunReport::from_json(&RunReport::from_batch(&batch).to_json()).unwrap();
+    assert_eq!(report.by_tag["feature=onboarding"], TagSummary { transactions: 2, failures: 0, cost_units: 0 });
+    assert_eq!(report.by_tag["ticket=ABC-123"].transactions, 1);
+
+    let tags: BTreeMap<String, String> = [("ticket".to_string(), "ABC-123".to_string())].into_iter().collect();
+    let tagged: Vec<&str> = report.transactions_tagged(&tags).iter().map(|x| x.label.as_str()).collect();
+    assert_eq!(tagged, ["signup"]);
+}
@@ -0,0 +1,141 @@
+//! Test contexts running side by side against the same mock PTE, and the reporting of their
+//! cleanup failures.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::address_book::AddressBook;
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, ACCOUNT, GOLD_TOKEN, OTHER_ACCOUNT};
+use programmatic_pte_interactions::hooks::{ClientWarning, Hooks};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::test_context::TestContext;
+
+use scrypto::prelude::*;
+
+use std::path::PathBuf;
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+
+/// A mock PTE answering every submission with the receipt of an account creation.
+fn mock() -> MockPte {
+    MockPte::start(0).unwrap().with_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap()),
+    )
+}
+
+/// A client of the mock, and the warnings its hooks received.
+fn client(mock: &MockPte) -> (PteClient, Arc<Mutex<Vec<ClientWarning>>>) {
+    let warnings: Arc<Mutex<Vec<ClientWarning>>> = Arc::new(Mutex::new(Vec::new()));
+    let recorded: Arc<Mutex<Vec<ClientWarning>>> = warnings.clone();
+    let client: PteClient = mock
+        .client()
+        .with_hooks(Hooks::new().on_warning(move |warning| recorded.lock().unwrap().push(warning.clone())));
+    (client, warnings)
+}
+
+/// What a context saw of itself while the other context was alive.
+struct Seen {
+    prefix: String,
+    directory: PathBuf,
+    labels: Vec<String>,
+    keys: Vec<String>,
+}
+
+#[test]
+fn two_contexts_running_at_once_only_see_their_own_entities() {
+    let mock: MockPte = mock();
+    let url: String = mock.url();
+    let barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+
+    let tests: Vec<thread::JoinHandle<Seen>> = (0..2)
+        .map(|_| {
+            let (url, barrier) = (url.clone(), barrier.clone());
+            thread::spawn(move || {
+                let context: TestContext = TestContext::new(PteClient::new(&url), "parallel").with_accounts(2).unwrap();
+                context.register("token", GOLD_TOKEN);
+                // Both contexts exist and have created their entities before either looks at its own.
+                barrier.wait();
+
+                let book: AddressBook = AddressBook::load(&context.address_book_path()).unwrap();
+                let seen: Seen = Seen {
+                    prefix: context.prefix().to_string(),
+                    directory: context.directory().to_path_buf(),
+                    labels: book.entries().map(|(label, _)| label.to_string()).collect(),
+                    keys: context.keystore().labels().unwrap(),
+                };
+                barrier.wait();
+                seen
+            })
+        })
+        .collect();
+    let seen: Vec<Seen> = tests.into_iter().map(|x| x.join().unwrap()).collect();
+
+    assert_ne!(seen[0].prefix, seen[1].prefix);
+    assert_ne!(seen[0].directory, seen[1].directory);
+    for seen in seen.iter() {
+        let mut expected: Vec<String> = ["account-1", "account-2", "token"]
+            .iter()
+            .map(|x| format!("{}-{}", seen.prefix, x))
+            .collect();
+        let mut labels: Vec<String> = seen.labels.clone();
+        labels.sort();
+        assert_eq!(labels, expected);
+
+        expected.pop();
+        let mut keys: Vec<String> = seen.keys.clone();
+        keys.sort();
+        assert_eq!(keys, expected);
+
+        // The directory went away with the context.
+        assert!(!seen.directory.exists());
+    }
+    assert_eq!(mock.request_count("POST", "/transaction"), 4);
+}
+
+#[test]
+fn accounts_get_their_own_keys() {
+    let mock: MockPte = mock();
+    let context: TestContext = TestContext::new(mock.client(), "keys").with_accounts(2).unwrap();
+
+    assert_ne!(
+        context.account(0).private_key.public_key().to_vec(),
+        context.account(1).private_key.public_key().to_vec()
+    );
+    assert_eq!(context.account(0).label, context.label("account-1"));
+    assert_eq!(context.account(0).address, ComponentAddress::from_str(ACCOUNT).unwrap());
+}
+
+#[test]
+fn cleanup_failures_are_reported_as_warnings() {
+    let mock: MockPte = mock();
+    let (client, warnings) = client(&mock);
+    let context: TestContext = TestContext::new(client, "cleanup")
+        .with_accounts(1)
+        .unwrap()
+        .sweep_to(ComponentAddress::from_str(OTHER_ACCOUNT).unwrap());
+    let prefix: String = context.prefix().to_string();
+    let directory: PathBuf = context.directory().to_path_buf();
+
+    // The mock has no components to read the vaults of the account from, and the directory is
+    // already gone when the context is dropped.
+    std::fs::remove_dir_all(&directory).unwrap();
+    drop(context);
+
+    let warnings: Vec<ClientWarning> = warnings.lock().unwrap().clone();
+    assert_eq!(warnings.len(), 2);
+    match &warnings[0] {
+        ClientWarning::TestContextCleanupFailed { context, resource, .. } => {
+            assert_eq!(*context, prefix);
+            assert_eq!(*resource, format!("{}-account-1", prefix));
+        }
+        other => panic!("expected a failed sweep, got {:?}", other),
+    }
+    match &warnings[1] {
+        ClientWarning::TestContextCleanupFailed { context, resource, .. } => {
+            assert_eq!(*context, prefix);
+            assert_eq!(*resource, directory.display().to_string());
+        }
+        other => panic!("expected a failed removal, got {:?}", other),
+    }
+}
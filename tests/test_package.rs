@@ -0,0 +1,32 @@
+//! Guards the checked-in test package against accidental modification.
+#![cfg(feature = "testing")]
+
+use programmatic_pte_interactions::test_package::{abi, check_test_package, wasm, wasm_hash, TEST_PACKAGE_BLUEPRINT};
+
+#[test]
+fn test_package_wasm_matches_the_recorded_hash() {
+    let code: Vec<u8> = wasm().unwrap();
+    let recorded: String = std::fs::read_to_string(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/assets/test_package/test_package.wasm.hash"),
+    )
+    .unwrap();
+    assert_eq!(wasm_hash(&code), recorded.trim());
+}
+
+#[test]
+fn test_package_wasm_is_a_wasm_module() {
+    assert_eq!(&wasm().unwrap()[..4], b"\0asm");
+}
+
+#[test]
+fn test_package_abi_describes_the_blueprint() {
+    let abi: serde_json::Value = abi().unwrap();
+    assert_eq!(abi["blueprint_name"], TEST_PACKAGE_BLUEPRINT);
+    let functions: Vec<&str> = abi["functions"].as_array().unwrap().iter().map(|x| x["name"].as_str().unwrap()).collect();
+    assert_eq!(functions, vec!["new"]);
+}
+
+#[test]
+fn test_package_check_finds_no_problems() {
+    assert_eq!(check_test_package(), Vec::<String>::new());
+}
@@ -0,0 +1,55 @@
+//! Regression tests of the checked-in test vectors of the canonical flows. Running the tests with
+//! `PTE_UPDATE_GOLDEN=1` rewrites the vectors under `test-vectors`.
+#![cfg(feature = "testing")]
+
+use programmatic_pte_interactions::deterministic::{DeterministicMode, Randomness};
+use programmatic_pte_interactions::test_vectors::{
+    check_test_vectors, generate_test_vectors, write_test_vectors, TestVector, TEST_VECTORS_MODE,
+};
+
+use std::path::PathBuf;
+
+fn test_vectors_directory() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-vectors")
+}
+
+fn contents(vectors: &[TestVector]) -> Vec<(String, String, String)> {
+    vectors
+        .iter()
+        .map(|x| (x.name.clone(), x.transaction_body_json(), x.compiled_transaction.clone()))
+        .collect()
+}
+
+#[test]
+fn checked_in_test_vectors_match_the_generated_ones() {
+    let vectors: Vec<TestVector> = generate_test_vectors(TEST_VECTORS_MODE).unwrap();
+    if std::env::var_os("PTE_UPDATE_GOLDEN").is_some() {
+        write_test_vectors(&vectors, &test_vectors_directory()).unwrap();
+    }
+    assert_eq!(check_test_vectors(&vectors, &test_vectors_directory()), Vec::<String>::new());
+}
+
+#[test]
+fn test_vectors_are_reproducible() {
+    let first: Vec<TestVector> = generate_test_vectors(TEST_VECTORS_MODE).unwrap();
+    let second: Vec<TestVector> = generate_test_vectors(TEST_VECTORS_MODE).unwrap();
+    assert_eq!(contents(&first), contents(&second));
+}
+
+#[test]
+fn other_seeds_give_other_test_vectors() {
+    let vectors: Vec<TestVector> = generate_test_vectors(TEST_VECTORS_MODE).unwrap();
+    let other: Vec<TestVector> = generate_test_vectors(DeterministicMode::Seeded {
+        seed: 1,
+        frozen_timestamp: 1_650_000_000,
+    })
+    .unwrap();
+    assert_ne!(contents(&vectors), contents(&other));
+}
+
+#[test]
+fn nonces_span_the_whole_u64_range() {
+    let mut randomness: Randomness = Randomness::new(TEST_VECTORS_MODE);
+    let nonces: Vec<u64> = (0..64).map(|_| randomness.next_nonce()).collect();
+    assert!(nonces.iter().any(|x| *x > u64::from(u32::MAX)));
+}
@@ -0,0 +1,105 @@
+//! Timing measured on a `TestClock`, so that latencies are exact and nothing actually waits.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::bench::{compare_rebuilds, run, BenchReport, RebuildComparison};
+use programmatic_pte_interactions::client::PteClient;
+use programmatic_pte_interactions::clock::{Clock, TestClock};
+use programmatic_pte_interactions::deterministic::{DeterministicMode, Randomness};
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, private_key};
+use programmatic_pte_interactions::mock::{EndpointConfig, LatencyDistribution, MockPte};
+use programmatic_pte_interactions::queue::ExecuteOptions;
+
+use radix_engine::model::{SignedTransaction, Transaction};
+use radix_engine::transaction::TransactionBuilder;
+use scrypto::prelude::*;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const LATENCY: Duration = Duration::from_secs(60);
+
+fn transaction(nonce: u64) -> Transaction {
+    TransactionBuilder::new()
+        .call_method(SYSTEM_COMPONENT, "free_xrd", vec![])
+        .build(nonce)
+}
+
+#[test]
+fn bench_latencies_are_measured_on_the_clock_of_the_client() {
+    let clock: Arc<TestClock> = Arc::new(TestClock::new());
+    let mock: MockPte = MockPte::start(0).unwrap().with_clock(clock.clone());
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap())
+            .latency(LatencyDistribution::Fixed(LATENCY)),
+    );
+    let client: PteClient = mock.client().with_clock(clock.clone());
+    let transactions: Vec<SignedTransaction> = (0..5).map(|nonce| transaction(nonce).sign([&private_key()])).collect();
+
+    let started_at: Instant = Instant::now();
+    let report: BenchReport = run(&client, &transactions, 1, &ExecuteOptions::default());
+    assert!(started_at.elapsed() < LATENCY);
+
+    assert_eq!(report.latencies, vec![LATENCY; 5]);
+    assert_eq!(report.elapsed, LATENCY * 5);
+    assert_eq!(report.p99(), LATENCY);
+    assert_eq!(report.failures, 0);
+    assert_eq!(clock.elapsed(), LATENCY * 5);
+}
+
+#[test]
+fn bench_tails_match_the_latency_distribution_of_the_mock() {
+    let distribution: LatencyDistribution = LatencyDistribution::LogNormal {
+        median: Duration::from_millis(100),
+        sigma: 0.5,
+    };
+    let clock: Arc<TestClock> = Arc::new(TestClock::new());
+    let mock: MockPte = MockPte::start(11).unwrap().with_clock(clock.clone());
+    mock.set_endpoint(
+        "POST",
+        "/transaction",
+        EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap()).latency(distribution),
+    );
+    let client: PteClient = mock.client().with_clock(clock);
+    let transactions: Vec<SignedTransaction> = (0..2_000).map(|nonce| transaction(nonce).sign([&private_key()])).collect();
+
+    // One submission at a time, so that each latency is exactly the one the mock slept on the clock.
+    let report: BenchReport = run(&client, &transactions, 1, &ExecuteOptions::default());
+    assert_eq!(report.failures, 0);
+    assert_eq!(report.latencies.len(), 2_000);
+    for (measured, expected) in [
+        (report.p50(), distribution.quantile(0.5)),
+        (report.p99(), distribution.quantile(0.99)),
+    ] {
+        let ratio: f64 = measured.as_secs_f64() / expected.as_secs_f64();
+        assert!((0.8..=1.25).contains(&ratio), "measured {:?}, expected about {:?}", measured, expected);
+    }
+    assert!(report.p99() > report.p50() * 2);
+}
+
+#[test]
+fn rebuilds_are_timed_on_the_given_clock() {
+    let clock: TestClock = TestClock::new();
+    let comparison: RebuildComparison = compare_rebuilds(&transaction(0), &private_key(), 3, &clock).unwrap();
+    assert_eq!(comparison.rounds, 3);
+    assert_eq!(comparison.decompiling, Duration::ZERO);
+    assert_eq!(comparison.cached, Duration::ZERO);
+}
+
+#[test]
+fn timestamps_come_from_the_clock_unless_seeded() {
+    let clock: Arc<TestClock> = Arc::new(TestClock::new());
+    let randomness: Randomness = Randomness::new(DeterministicMode::Disabled).with_clock(clock.clone());
+    assert_eq!(randomness.now(), clock.system_time());
+
+    clock.advance(LATENCY);
+    assert_eq!(randomness.now(), clock.system_time());
+
+    let seeded: Randomness = Randomness::new(DeterministicMode::Seeded {
+        seed: 0,
+        frozen_timestamp: 1_650_000_000,
+    })
+    .with_clock(clock.clone());
+    assert_eq!(seeded.now(), std::time::UNIX_EPOCH + Duration::from_secs(1_650_000_000));
+}
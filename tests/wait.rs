@@ -0,0 +1,133 @@
+//! Waiting for components which only become visible some time after their instantiation, against a
+//! mock PTE.
+#![cfg(all(feature = "testing", feature = "blocking-http"))]
+
+use programmatic_pte_interactions::chain::{ChainError, ChainedCalls, Instantiation};
+use programmatic_pte_interactions::client::{ComponentWait, PteClient};
+use programmatic_pte_interactions::clock::TestClock;
+use programmatic_pte_interactions::fixtures::{account_creation_receipt, account_envelope, private_key, ACCOUNT, PACKAGE};
+use programmatic_pte_interactions::mock::{EndpointConfig, MockPte};
+use programmatic_pte_interactions::PteApiError;
+
+use scrypto::prelude::*;
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn account() -> ComponentAddress {
+    ComponentAddress::from_str(ACCOUNT).unwrap()
+}
+
+/// A mock PTE which does not know the component yet, answering submissions with the receipt of its
+/// creation.
+fn mock() -> MockPte {
+    MockPte::start(0)
+        .unwrap()
+        .with_endpoint(
+            "POST",
+            "/transaction",
+            EndpointConfig::new(200, &serde_json::to_string(&account_creation_receipt()).unwrap()),
+        )
+        .with_endpoint("GET", "/component/", EndpointConfig::new(404, "not found"))
+}
+
+/// Makes the component visible once the mock was asked for it twice.
+fn propagate_after_two_reads(mock: &MockPte) {
+    while mock.request_count("GET", "/component/") < 2 {
+        thread::sleep(Duration::from_millis(5));
+    }
+    mock.set_endpoint(
+        "GET",
+        "/component/",
+        EndpointConfig::new(200, &serde_json::to_string(&account_envelope()).unwrap()),
+    );
+}
+
+fn instantiation() -> Instantiation {
+    Instantiation::new(PackageAddress::from_str(PACKAGE).unwrap(), "Vendor", "new", vec![])
+}
+
+#[test]
+fn a_delayed_component_is_waited_for() {
+    let mock: MockPte = mock();
+    let client: PteClient = mock.client();
+
+    let info = thread::scope(|scope| {
+        scope.spawn(|| propagate_after_two_reads(&mock));
+        client.wait_for_component(account(), Duration::from_secs(10), Duration::from_millis(20))
+    });
+    assert_eq!(info.unwrap().address, ACCOUNT);
+    assert!(mock.request_count("GET", "/component/") >= 3);
+}
+
+#[test]
+fn timeouts_report_the_attempts_and_the_last_error() {
+    let mock: MockPte = mock();
+    let client: PteClient = mock.client().with_clock(Arc::new(TestClock::new()));
+
+    match client.wait_for_component(account(), Duration::from_secs(2), Duration::from_millis(500)) {
+        Err(PteApiError::WaitTimeout { attempts, last_error }) => {
+            assert_eq!(attempts, 5);
+            assert!(matches!(*last_error, PteApiError::NotFound(_)));
+        }
+        other => panic!("expected the wait to time out, got {:?}", other),
+    }
+    assert_eq!(mock.request_count("GET", "/component/"), 5);
+}
+
+#[test]
+fn errors_other_than_not_found_are_returned_right_away() {
+    let mock: MockPte = mock();
+    mock.set_endpoint("GET", "/component/", EndpointConfig::new(500, "internal error"));
+    let client: PteClient = mock.client().with_clock(Arc::new(TestClock::new()));
+
+    assert!(matches!(
+        client.wait_for_component(account(), Duration::from_secs(2), Duration::from_millis(500)),
+        Err(PteApiError::UnexpectedStatus { status: 500, .. })
+    ));
+    assert_eq!(mock.request_count("GET", "/component/"), 1);
+}
+
+#[test]
+fn followups_are_only_submitted_once_the_component_is_visible() {
+    let mock: MockPte = mock();
+    let client: PteClient = mock.client().with_component_wait(Some(ComponentWait {
+        timeout: Duration::from_secs(10),
+        poll: Duration::from_millis(20),
+    }));
+
+    let chained: ChainedCalls = thread::scope(|scope| {
+        scope.spawn(|| propagate_after_two_reads(&mock));
+        client.instantiate_then_call(&instantiation(), vec![("restock".to_string(), vec![])], &private_key())
+    })
+    .unwrap();
+    assert_eq!(chained.component, account());
+    assert_eq!(chained.receipts.len(), 2);
+    assert!(mock.request_count("GET", "/component/") >= 3);
+    assert_eq!(mock.request_count("POST", "/transaction"), 2);
+}
+
+#[test]
+fn components_which_never_become_visible_stop_the_chain() {
+    let mock: MockPte = mock();
+    let client: PteClient = mock.client().with_clock(Arc::new(TestClock::new()));
+
+    match client.instantiate_then_call(&instantiation(), vec![("restock".to_string(), vec![])], &private_key()) {
+        Err(ChainError::ComponentNotVisible { component, error, .. }) => {
+            assert_eq!(component, account());
+            assert!(matches!(error, PteApiError::WaitTimeout { .. }));
+        }
+        other => panic!("expected the component not to become visible, got {:?}", other),
+    }
+    assert_eq!(mock.request_count("POST", "/transaction"), 1);
+}
+
+#[test]
+fn components_are_read_right_away_without_a_wait() {
+    let mock: MockPte = mock();
+    let client: PteClient = mock.client().with_component_wait(None);
+
+    assert!(matches!(client.get_created_component(account()), Err(PteApiError::NotFound(_))));
+    assert_eq!(mock.request_count("GET", "/component/"), 1);
+}